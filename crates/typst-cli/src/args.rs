@@ -56,6 +56,22 @@ pub enum Command {
     /// Processes an input file to extract provided metadata
     Query(QueryCommand),
 
+    /// Serves compile and query requests, keeping caches alive between them
+    Serve(ServeCommand),
+
+    /// Starts a language server that communicates over stdio
+    Lsp(LspCommand),
+
+    /// Formats Typst source files
+    Fmt(FmtCommand),
+
+    /// Copies the packages a document uses into the project and locks them
+    Vendor(VendorCommand),
+
+    /// Checks and bundles packages for publishing
+    #[command(subcommand)]
+    Package(PackageCommand),
+
     /// Lists all discovered fonts in system and custom font paths
     Fonts(FontsCommand),
 
@@ -71,7 +87,7 @@ pub struct CompileCommand {
     #[clap(flatten)]
     pub common: SharedArgs,
 
-    /// Path to output file (PDF, PNG, or SVG).
+    /// Path to output file (PDF, PNG, SVG, or HTML).
     /// Use `-` to write output to stdout; For output formats emitting one file per page,
     /// a page number template must be present if the source document renders to multiple pages.
     /// Use `{p}` for page numbers, `{0p}` for zero padded page numbers, `{t}` for page count.
@@ -99,14 +115,33 @@ pub struct CompileCommand {
     #[arg(long = "format", short = 'f')]
     pub format: Option<OutputFormat>,
 
+    /// Writes all exported pages as PNG or SVG images into a single archive,
+    /// together with a `manifest.json` describing the pages.
+    ///
+    /// The archive is a tarball, compressed with gzip if the path ends in
+    /// `.tar.gz` or `.tgz`. When given, no page number template is needed and
+    /// the format defaults to PNG.
+    #[arg(long = "archive", value_name = "PATH", conflicts_with = "output")]
+    pub archive: Option<PathBuf>,
+
     /// Opens the output file using the default viewer after compilation.
     /// Ignored if output is stdout
     #[arg(long = "open")]
     pub open: Option<Option<String>>,
 
-    /// The PPI (pixels per inch) to use for PNG export
-    #[arg(long = "ppi", default_value_t = 144.0)]
-    pub ppi: f32,
+    /// The PDF standard the output should conform to, defaults to 1.7
+    #[arg(long = "pdf-standard", value_name = "STANDARD")]
+    pub pdf_standard: Option<PdfStandard>,
+
+    /// Writes the document's logical structure (paragraphs, lists, tables,
+    /// and so on) into the PDF, which makes it accessible to assistive
+    /// technology
+    #[arg(long = "pdf-tags")]
+    pub pdf_tags: bool,
+
+    /// The PPI (pixels per inch) to use for PNG export, defaults to 144
+    #[arg(long = "ppi")]
+    pub ppi: Option<f32>,
 
     /// Produces performance timings of the compilation process (experimental)
     ///
@@ -115,6 +150,15 @@ pub struct CompileCommand {
     /// apart from file names and line numbers.
     #[arg(long = "timings", value_name = "OUTPUT_JSON")]
     pub timings: Option<Option<PathBuf>>,
+
+    /// Warns about unused bindings and imports, shadowed parameters, and
+    /// unreachable code
+    ///
+    /// A `// lint-allow: <lint>` comment suppresses a lint on its own and the
+    /// following line. The lints are `unused-binding`, `unused-import`,
+    /// `shadowed-parameter`, and `unreachable-code`.
+    #[arg(long = "lint")]
+    pub lint: bool,
 }
 
 /// Initializes a new project from a template
@@ -130,6 +174,10 @@ pub struct InitCommand {
 
     /// The project directory, defaults to the template's name
     pub dir: Option<String>,
+
+    /// Arguments related to storage of packages in the system
+    #[clap(flatten)]
+    pub package: PackageArgs,
 }
 
 /// Processes an input file to extract provided metadata
@@ -155,6 +203,44 @@ pub struct QueryCommand {
     pub format: SerializationFormat,
 }
 
+/// Serves compile and query requests, keeping caches alive between them
+///
+/// Requests are JSON-RPC 2.0 messages, one per line. Supported methods are
+/// `compile`, `query`, and `shutdown`. Each response is written as a single
+/// line as well.
+#[derive(Debug, Clone, Parser)]
+pub struct ServeCommand {
+    /// Shared arguments
+    #[clap(flatten)]
+    pub common: SharedArgs,
+
+    /// Listens for connections on a Unix socket at this path instead of
+    /// reading requests from stdin
+    #[clap(long = "socket", value_name = "PATH")]
+    pub socket: Option<PathBuf>,
+}
+
+/// Starts a language server that communicates over stdio
+///
+/// The server speaks the Language Server Protocol and provides diagnostics,
+/// completions, quick fixes, hover tooltips, signature help, inlay hints,
+/// semantic highlighting, go-to-definition, references, renaming, document
+/// outlines, folding, selection ranges, and formatting. An input file is
+/// compiled to check all open documents. Without one, each open document is
+/// compiled on its own. The root defaults to the workspace root reported by
+/// the editor.
+#[derive(Debug, Clone, Parser)]
+pub struct LspCommand {
+    /// Shared arguments
+    #[clap(flatten)]
+    pub common: SharedArgs,
+
+    /// Warns about unused bindings and imports, shadowed parameters, and
+    /// unreachable code
+    #[arg(long = "lint")]
+    pub lint: bool,
+}
+
 // Output file format for query command
 #[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum)]
 pub enum SerializationFormat {
@@ -166,8 +252,19 @@ pub enum SerializationFormat {
 #[derive(Debug, Clone, Args)]
 pub struct SharedArgs {
     /// Path to input Typst file, use `-` to read input from stdin
+    ///
+    /// Defaults to the entrypoint of the project manifest, a `typst.toml`
+    /// with a `[project]` section in the current directory or one of its
+    /// ancestors. The manifest also provides defaults for the root, font
+    /// paths, inputs, and output settings. Command line arguments take
+    /// precedence over it.
     #[clap(value_parser = input_value_parser)]
-    pub input: Input,
+    pub input: Option<Input>,
+
+    /// Selects a named target of the project manifest instead of its default
+    /// target
+    #[clap(long = "target", value_name = "NAME")]
+    pub target: Option<String>,
 
     /// Configures the project root (for absolute paths)
     #[clap(long = "root", env = "TYPST_ROOT", value_name = "DIR")]
@@ -209,6 +306,47 @@ pub struct SharedArgs {
         value_parser = clap::value_parser!(DiagnosticFormat)
     )]
     pub diagnostic_format: DiagnosticFormat,
+
+    /// Arguments related to storage of packages in the system
+    #[clap(flatten)]
+    pub package: PackageArgs,
+}
+
+/// Arguments related to where packages are stored in the system.
+#[derive(Debug, Clone, Args)]
+pub struct PackageArgs {
+    /// Custom path to local packages, defaults to system-dependent location
+    #[clap(long = "package-path", env = "TYPST_PACKAGE_PATH", value_name = "DIR")]
+    pub package_path: Option<PathBuf>,
+
+    /// Custom path to package cache, defaults to system-dependent location
+    #[clap(
+        long = "package-cache-path",
+        env = "TYPST_PACKAGE_CACHE_PATH",
+        value_name = "DIR"
+    )]
+    pub package_cache_path: Option<PathBuf>,
+
+    /// Fetches the packages of a namespace from a registry, which is either an
+    /// HTTP(S) URL or a local directory
+    ///
+    /// An HTTP registry serves `{name}-{version}.tar.gz` archives and an
+    /// `index.json` below its URL, like `https://packages.typst.org/preview`
+    /// does for `@preview`. A local directory contains the packages as
+    /// `{name}/{version}`.
+    #[clap(
+        long = "registry",
+        env = "TYPST_REGISTRIES",
+        value_name = "namespace=location",
+        action = ArgAction::Append,
+        value_delimiter = ',',
+        value_parser = ValueParser::new(parse_registry),
+    )]
+    pub registries: Vec<(String, String)>,
+
+    /// Fails instead of downloading packages or package indices
+    #[clap(long = "offline")]
+    pub offline: bool,
 }
 
 /// Parses a UNIX timestamp according to <https://reproducible-builds.org/specs/source-date-epoch/>
@@ -286,6 +424,22 @@ fn parse_input_pair(raw: &str) -> Result<(String, String), String> {
     Ok((key, val))
 }
 
+/// Parses a namespace and the location of its registry.
+fn parse_registry(raw: &str) -> Result<(String, String), String> {
+    let (namespace, location) = raw.split_once('=').ok_or(
+        "registry must be a namespace and a location separated by an equal sign",
+    )?;
+    let namespace = namespace.trim().trim_start_matches('@').to_owned();
+    if namespace.is_empty() {
+        return Err("the namespace was missing or empty".to_owned());
+    }
+    let location = location.trim().to_owned();
+    if location.is_empty() {
+        return Err("the location was missing or empty".to_owned());
+    }
+    Ok((namespace, location))
+}
+
 /// Implements parsing of page ranges (`1-3`, `4`, `5-`, `-2`), used by the
 /// `CompileCommand.pages` argument, through the `FromStr` trait instead of
 /// a value parser, in order to generate better errors.
@@ -335,6 +489,90 @@ fn parse_page_number(value: &str) -> Result<NonZeroUsize, &'static str> {
     }
 }
 
+/// Formats Typst source files
+///
+/// Only code is formatted, markup stays exactly as written.
+#[derive(Debug, Clone, Parser)]
+pub struct FmtCommand {
+    /// Paths to the files to format. Directories are searched recursively for
+    /// `.typ` files. Use `-` to read from stdin and write to stdout
+    #[clap(value_name = "PATH", default_value = ".")]
+    pub paths: Vec<PathBuf>,
+
+    /// Checks whether the files are formatted instead of formatting them
+    #[arg(long)]
+    pub check: bool,
+}
+
+/// Copies the packages a document uses into the project and locks them
+///
+/// The packages are copied into the `.typst/vendor` directory of the project
+/// root and their hashes are recorded in `typst.lock`. Afterwards, packages
+/// are resolved from the vendor directory first and checked against the
+/// lockfile, so that the project compiles without network access.
+#[derive(Debug, Clone, Parser)]
+pub struct VendorCommand {
+    /// Shared arguments
+    #[clap(flatten)]
+    pub common: SharedArgs,
+}
+
+/// Checks and bundles packages for publishing
+#[derive(Debug, Clone, Subcommand)]
+pub enum PackageCommand {
+    /// Validates a package's manifest and compiles its entrypoint and template
+    Check(PackageCheckCommand),
+
+    /// Bundles a package into an archive in the format registries serve
+    Bundle(PackageBundleCommand),
+}
+
+/// Validates a package's manifest and compiles its entrypoint and template
+#[derive(Debug, Clone, Parser)]
+pub struct PackageCheckCommand {
+    /// The directory of the package
+    #[clap(default_value = ".")]
+    pub dir: PathBuf,
+
+    /// The namespace the package is going to be published in
+    #[clap(long = "namespace", default_value = "preview")]
+    pub namespace: String,
+
+    /// Adds additional directories to search for fonts
+    #[clap(
+        long = "font-path",
+        env = "TYPST_FONT_PATHS",
+        value_name = "DIR",
+        value_delimiter = ENV_PATH_SEP,
+    )]
+    pub font_paths: Vec<PathBuf>,
+
+    /// The format to emit diagnostics in
+    #[clap(
+        long,
+        default_value_t = DiagnosticFormat::Human,
+        value_parser = clap::value_parser!(DiagnosticFormat)
+    )]
+    pub diagnostic_format: DiagnosticFormat,
+
+    /// Arguments related to storage of packages in the system
+    #[clap(flatten)]
+    pub package: PackageArgs,
+}
+
+/// Bundles a package into an archive in the format registries serve
+#[derive(Debug, Clone, Parser)]
+pub struct PackageBundleCommand {
+    /// The directory of the package
+    #[clap(default_value = ".")]
+    pub dir: PathBuf,
+
+    /// Path of the archive, defaults to `{name}-{version}.tar.gz` in the
+    /// working directory
+    #[clap(long = "output", short = 'o', value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
 /// Lists all discovered fonts in system and custom font paths
 #[derive(Debug, Clone, Parser)]
 pub struct FontsCommand {
@@ -390,6 +628,7 @@ pub enum OutputFormat {
     Pdf,
     Png,
     Svg,
+    Html,
 }
 
 impl Display for OutputFormat {
@@ -400,3 +639,27 @@ impl Display for OutputFormat {
             .fmt(f)
     }
 }
+
+/// A PDF standard that the output can conform to.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum)]
+#[allow(non_camel_case_types)]
+pub enum PdfStandard {
+    /// PDF 1.7.
+    #[value(name = "1.7")]
+    V_1_7,
+    /// PDF/A-2b.
+    #[value(name = "a-2b")]
+    A_2b,
+    /// PDF/A-3b.
+    #[value(name = "a-3b")]
+    A_3b,
+}
+
+impl Display for PdfStandard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.to_possible_value()
+            .expect("no values are skipped")
+            .get_name()
+            .fmt(f)
+    }
+}
@@ -5,20 +5,27 @@ use std::path::{Path, PathBuf};
 use chrono::{Datelike, Timelike};
 use codespan_reporting::diagnostic::{Diagnostic, Label};
 use codespan_reporting::term;
+use comemo::Track;
 use ecow::{eco_format, EcoString};
+use flate2::write::GzEncoder;
 use parking_lot::RwLock;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
-use typst::diag::{bail, At, Severity, SourceDiagnostic, StrResult};
+use serde::Serialize;
+use typst::diag::{bail, At, Severity, SourceDiagnostic, SourceResult, StrResult};
+use typst::engine::{Engine, Route};
 use typst::eval::Tracer;
-use typst::foundations::{Datetime, Smart};
+use typst::foundations::{Context, Datetime, Smart};
+use typst::introspection::Locator;
 use typst::layout::{Frame, PageRanges};
 use typst::model::Document;
 use typst::syntax::{FileId, Source, Span};
-use typst::visualize::Color;
+use typst::visualize::{check_pdf_images, Color};
 use typst::{World, WorldExt};
+use typst_pdf::PdfOptions;
 
 use crate::args::{
     CompileCommand, DiagnosticFormat, Input, Output, OutputFormat, PageRangeArgument,
+    PdfStandard,
 };
 use crate::timings::Timer;
 use crate::watch::Status;
@@ -31,8 +38,12 @@ type CodespanError = codespan_reporting::files::Error;
 impl CompileCommand {
     /// The output path.
     pub fn output(&self) -> Output {
+        if let Some(archive) = &self.archive {
+            return Output::Path(archive.clone());
+        }
+
         self.output.clone().unwrap_or_else(|| {
-            let Input::Path(path) = &self.common.input else {
+            let Some(Input::Path(path)) = &self.common.input else {
                 panic!("output must be specified when input is from stdin, as guarded by the CLI");
             };
             Output::Path(path.with_extension(
@@ -40,11 +51,17 @@ impl CompileCommand {
                     OutputFormat::Pdf => "pdf",
                     OutputFormat::Png => "png",
                     OutputFormat::Svg => "svg",
+                    OutputFormat::Html => "html",
                 },
             ))
         })
     }
 
+    /// The pixels per inch of PNG output.
+    pub fn ppi(&self) -> f32 {
+        self.ppi.unwrap_or(144.0)
+    }
+
     /// The format to use for generated output, either specified by the user or inferred from the extension.
     ///
     /// Will return `Err` if the format was not specified and could not be inferred.
@@ -56,13 +73,42 @@ impl CompileCommand {
                 Some(ext) if ext.eq_ignore_ascii_case("pdf") => OutputFormat::Pdf,
                 Some(ext) if ext.eq_ignore_ascii_case("png") => OutputFormat::Png,
                 Some(ext) if ext.eq_ignore_ascii_case("svg") => OutputFormat::Svg,
+                Some(ext) if ext.eq_ignore_ascii_case("html") => OutputFormat::Html,
                 _ => bail!("could not infer output format for path {}.\nconsider providing the format manually with `--format/-f`", output.display()),
             }
+        } else if self.archive.is_some() {
+            OutputFormat::Png
         } else {
             OutputFormat::Pdf
         })
     }
 
+    /// The paths of the files that exporting the document writes to.
+    ///
+    /// This is empty if the output is written to stdout.
+    pub fn output_paths(&self, document: &Document) -> StrResult<Vec<PathBuf>> {
+        let Output::Path(path) = self.output() else { return Ok(vec![]) };
+        let template = path.to_str().unwrap_or_default();
+        let multiple = self.archive.is_none()
+            && matches!(self.output_format()?, OutputFormat::Png | OutputFormat::Svg)
+            && output_template::has_indexable_template(template);
+        if !multiple {
+            return Ok(vec![path]);
+        }
+
+        let exported_page_ranges = self.exported_page_ranges();
+        Ok((0..document.pages.len())
+            .filter(|i| {
+                exported_page_ranges.as_ref().map_or(true, |exported_page_ranges| {
+                    exported_page_ranges.includes_page_index(*i)
+                })
+            })
+            .map(|i| {
+                output_template::format(template, i + 1, document.pages.len()).into()
+            })
+            .collect())
+    }
+
     /// The ranges of the pages to be exported as specified by the user.
     ///
     /// This returns `None` if all pages should be exported.
@@ -77,8 +123,10 @@ impl CompileCommand {
 
 /// Execute a compilation command.
 pub fn compile(mut timer: Timer, mut command: CompileCommand) -> StrResult<()> {
+    crate::project::configure_compile(&mut command)?;
     let mut world =
         SystemWorld::new(&command.common).map_err(|err| eco_format!("{err}"))?;
+    world.set_tagging(command.pdf_tags);
     timer.record(&mut world, |world| compile_once(world, &mut command, false))??;
     Ok(())
 }
@@ -111,13 +159,16 @@ pub fn compile_once(
     }
 
     let mut tracer = Tracer::new();
-    let result = typst::compile(world, &mut tracer);
+    let result = typst::compile(world, &mut tracer)
+        .and_then(|document| export(world, &mut tracer, &document, command, watching));
+    if command.lint {
+        typst_ide::lint(world, &mut tracer);
+    }
     let warnings = tracer.warnings();
 
     match result {
         // Export the PDF / PNG.
-        Ok(document) => {
-            export(world, &document, command, watching)?;
+        Ok(()) => {
             let duration = start.elapsed();
 
             if watching {
@@ -162,34 +213,65 @@ pub fn compile_once(
 }
 
 /// Export into the target format.
-fn export(
+pub fn export(
     world: &mut SystemWorld,
+    tracer: &mut Tracer,
     document: &Document,
     command: &CompileCommand,
     watching: bool,
-) -> StrResult<()> {
-    match command.output_format()? {
-        OutputFormat::Png => {
-            export_image(world, document, command, watching, ImageExportFormat::Png)
-        }
-        OutputFormat::Svg => {
-            export_image(world, document, command, watching, ImageExportFormat::Svg)
-        }
-        OutputFormat::Pdf => export_pdf(document, command),
+) -> SourceResult<()> {
+    if let Some(archive) = &command.archive {
+        let labels = page_labels(world, tracer, document)?;
+        return export_archive(document, &labels, command, archive).at(Span::detached());
     }
+
+    let fmt = match command.output_format().at(Span::detached())? {
+        OutputFormat::Png => ImageExportFormat::Png,
+        OutputFormat::Svg => ImageExportFormat::Svg,
+        OutputFormat::Pdf => return export_pdf(document, command),
+        OutputFormat::Html => return export_html(world, tracer, document, command),
+    };
+
+    export_image(world, tracer, document, command, watching, fmt).at(Span::detached())
 }
 
 /// Export to a PDF.
-fn export_pdf(document: &Document, command: &CompileCommand) -> StrResult<()> {
-    let timestamp = convert_datetime(
-        command.common.creation_timestamp.unwrap_or_else(chrono::Utc::now),
-    );
-    let exported_page_ranges = command.exported_page_ranges();
-    let buffer = typst_pdf::pdf(document, Smart::Auto, timestamp, exported_page_ranges);
+fn export_pdf(document: &Document, command: &CompileCommand) -> SourceResult<()> {
+    let options = PdfOptions {
+        ident: Smart::Auto,
+        timestamp: convert_datetime(
+            command.common.creation_timestamp.unwrap_or_else(chrono::Utc::now),
+        ),
+        page_ranges: command.exported_page_ranges(),
+        standard: match command.pdf_standard.unwrap_or(PdfStandard::V_1_7) {
+            PdfStandard::V_1_7 => typst_pdf::PdfStandard::V_1_7,
+            PdfStandard::A_2b => typst_pdf::PdfStandard::A_2b,
+            PdfStandard::A_3b => typst_pdf::PdfStandard::A_3b,
+        },
+        tagged: command.pdf_tags,
+    };
+    let buffer = typst_pdf::pdf(document, &options)?;
     command
         .output()
         .write(&buffer)
-        .map_err(|err| eco_format!("failed to write PDF file ({err})"))?;
+        .map_err(|err| eco_format!("failed to write PDF file ({err})"))
+        .at(Span::detached())?;
+    Ok(())
+}
+
+/// Export to an HTML file.
+fn export_html(
+    world: &SystemWorld,
+    tracer: &mut Tracer,
+    document: &Document,
+    command: &CompileCommand,
+) -> SourceResult<()> {
+    let html = typst_html::html(world, tracer, document)?;
+    command
+        .output()
+        .write(html.as_bytes())
+        .map_err(|err| eco_format!("failed to write HTML file ({err})"))
+        .at(Span::detached())?;
     Ok(())
 }
 
@@ -212,9 +294,20 @@ enum ImageExportFormat {
     Svg,
 }
 
+impl ImageExportFormat {
+    /// The file extension of the format.
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Svg => "svg",
+        }
+    }
+}
+
 /// Export to one or multiple images.
 fn export_image(
     world: &mut SystemWorld,
+    tracer: &mut Tracer,
     document: &Document,
     command: &CompileCommand,
     watching: bool,
@@ -252,6 +345,16 @@ fn export_image(
         bail!("cannot export multiple images {err}");
     }
 
+    let name = match fmt {
+        ImageExportFormat::Png => "PNG",
+        ImageExportFormat::Svg => "SVG",
+    };
+    for (_, page) in &exported_pages {
+        for warning in check_pdf_images(&page.frame, name) {
+            tracer.warn(warning);
+        }
+    }
+
     let cache = world.export_cache();
 
     // The results are collected in a `Vec<()>` which does not allocate.
@@ -329,26 +432,218 @@ fn export_image_page(
     output: &Output,
     fmt: ImageExportFormat,
 ) -> StrResult<()> {
+    let buf = encode_image_page(command, frame, fmt)?;
+    match fmt {
+        ImageExportFormat::Png => output
+            .write(&buf)
+            .map_err(|err| eco_format!("failed to write PNG file ({err})")),
+        ImageExportFormat::Svg => output
+            .write(&buf)
+            .map_err(|err| eco_format!("failed to write SVG file ({err})")),
+    }
+}
+
+/// Encode a single page as an image.
+fn encode_image_page(
+    command: &CompileCommand,
+    frame: &Frame,
+    fmt: ImageExportFormat,
+) -> StrResult<Vec<u8>> {
     match fmt {
         ImageExportFormat::Png => {
-            let pixmap = typst_render::render(frame, command.ppi / 72.0, Color::WHITE);
-            let buf = pixmap
+            let pixmap = typst_render::render(frame, command.ppi() / 72.0, Color::WHITE);
+            pixmap
                 .encode_png()
-                .map_err(|err| eco_format!("failed to encode PNG file ({err})"))?;
-            output
-                .write(&buf)
-                .map_err(|err| eco_format!("failed to write PNG file ({err})"))?;
+                .map_err(|err| eco_format!("failed to encode PNG file ({err})"))
         }
-        ImageExportFormat::Svg => {
-            let svg = typst_svg::svg(frame);
-            output
-                .write(svg.as_bytes())
-                .map_err(|err| eco_format!("failed to write SVG file ({err})"))?;
+        ImageExportFormat::Svg => Ok(typst_svg::svg(frame).into_bytes()),
+    }
+}
+
+/// Format the page numbers of all pages with their numbering, if any.
+///
+/// Numbering functions are called with the logical page number, so that
+/// updates of the page counter are respected.
+fn page_labels(
+    world: &dyn World,
+    tracer: &mut Tracer,
+    document: &Document,
+) -> SourceResult<Vec<Option<EcoString>>> {
+    let mut locator = Locator::new();
+    let mut engine = Engine {
+        world: world.track(),
+        introspector: document.introspector.track(),
+        route: Route::default(),
+        locator: &mut locator,
+        tracer: tracer.track_mut(),
+    };
+
+    document
+        .pages
+        .iter()
+        .map(|page| {
+            let Some(numbering) = &page.numbering else { return Ok(None) };
+            let value =
+                numbering.apply(&mut engine, Context::none().track(), &[page.number])?;
+            Ok(Some(value.display().plain_text()))
+        })
+        .collect()
+}
+
+/// Export all selected pages as images into a single archive, along with a
+/// manifest describing them.
+fn export_archive(
+    document: &Document,
+    labels: &[Option<EcoString>],
+    command: &CompileCommand,
+    path: &Path,
+) -> StrResult<()> {
+    let fmt = match command.output_format()? {
+        OutputFormat::Png => ImageExportFormat::Png,
+        OutputFormat::Svg => ImageExportFormat::Svg,
+        _ => bail!("archives can only contain PNG or SVG images"),
+    };
+
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let compress = if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        true
+    } else if name.ends_with(".tar") {
+        false
+    } else {
+        bail!(
+            "could not infer archive format for path {}\n\
+             consider using a `.tar`, `.tar.gz`, or `.tgz` extension",
+            path.display()
+        );
+    };
+
+    let exported_page_ranges = command.exported_page_ranges();
+    let exported_pages = document
+        .pages
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| {
+            exported_page_ranges.as_ref().map_or(true, |exported_page_ranges| {
+                exported_page_ranges.includes_page_index(*i)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let files = exported_pages
+        .par_iter()
+        .map(|(i, page)| {
+            let file = output_template::format(
+                &format!("page-{{0p}}.{}", fmt.extension()),
+                i + 1,
+                document.pages.len(),
+            );
+            let data = encode_image_page(command, &page.frame, fmt)?;
+            Ok((file, data))
+        })
+        .collect::<StrResult<Vec<_>>>()?;
+
+    let manifest = ArchiveManifest {
+        format: fmt.extension(),
+        ppi: matches!(fmt, ImageExportFormat::Png).then_some(command.ppi()),
+        pages: exported_pages
+            .iter()
+            .zip(&files)
+            .map(|((i, page), (file, _))| {
+                let size = page.frame.size();
+                ArchivePage {
+                    page: i + 1,
+                    number: page.number,
+                    label: labels[*i].clone(),
+                    width: size.x.to_pt(),
+                    height: size.y.to_pt(),
+                    file: file.clone(),
+                }
+            })
+            .collect(),
+    };
+    let manifest = serde_json::to_vec_pretty(&manifest)
+        .map_err(|err| eco_format!("failed to serialize manifest ({err})"))?;
+
+    // Use a fixed modification time so that the archive is reproducible.
+    let mtime = command
+        .common
+        .creation_timestamp
+        .map_or(0, |timestamp| timestamp.timestamp().max(0) as u64);
+
+    let entries = files
+        .iter()
+        .map(|(file, data)| (file.as_str(), data.as_slice()))
+        .chain([("manifest.json", manifest.as_slice())])
+        .collect::<Vec<_>>();
+
+    write_archive(path, &entries, mtime, compress)
+        .map_err(|err| eco_format!("failed to write archive ({err})"))
+}
+
+/// Write files into a tar archive at the given path, optionally compressing
+/// it with gzip.
+fn write_archive(
+    path: &Path,
+    entries: &[(&str, &[u8])],
+    mtime: u64,
+    compress: bool,
+) -> io::Result<()> {
+    fn write_tar<W: Write>(
+        writer: W,
+        entries: &[(&str, &[u8])],
+        mtime: u64,
+    ) -> io::Result<W> {
+        let mut builder = tar::Builder::new(writer);
+        for &(name, data) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(mtime);
+            builder.append_data(&mut header, name, data)?;
         }
+        builder.into_inner()
     }
+
+    let file = File::create(path)?;
+    if compress {
+        let encoder = GzEncoder::new(file, flate2::Compression::default());
+        write_tar(encoder, entries, mtime)?.finish()?;
+    } else {
+        write_tar(file, entries, mtime)?.flush()?;
+    }
+
     Ok(())
 }
 
+/// Describes the contents of an archive written with `--archive`.
+#[derive(Serialize)]
+struct ArchiveManifest {
+    /// The image format of the pages.
+    format: &'static str,
+    /// The pixels per inch of PNG images.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ppi: Option<f32>,
+    /// The exported pages.
+    pages: Vec<ArchivePage>,
+}
+
+/// A page in an archive written with `--archive`.
+#[derive(Serialize)]
+struct ArchivePage {
+    /// The physical, one-based page number.
+    page: usize,
+    /// The logical page number, as controlled by the page counter.
+    number: usize,
+    /// The page number formatted with the page's numbering pattern, if any.
+    label: Option<EcoString>,
+    /// The width of the page in points.
+    width: f64,
+    /// The height of the page in points.
+    height: f64,
+    /// The name of the page's image file in the archive.
+    file: String,
+}
+
 impl Output {
     fn write(&self, buffer: &[u8]) -> StrResult<()> {
         match self {
@@ -594,3 +889,48 @@ impl<'a> codespan_reporting::files::Files<'a> for SystemWorld {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+
+    /// Read the names, modification times and contents of an archive's files.
+    fn read_archive(reader: impl Read) -> Vec<(String, u64, Vec<u8>)> {
+        tar::Archive::new(reader)
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let name = entry.path().unwrap().to_string_lossy().into_owned();
+                let mtime = entry.header().mtime().unwrap();
+                let mut data = vec![];
+                entry.read_to_end(&mut data).unwrap();
+                (name, mtime, data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_write_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let entries: [(&str, &[u8]); 2] =
+            [("page-1.svg", b"<svg></svg>"), ("manifest.json", b"{}")];
+        let expected: Vec<_> = entries
+            .iter()
+            .map(|(name, data)| (name.to_string(), 42, data.to_vec()))
+            .collect();
+
+        let path = dir.path().join("pages.tar");
+        write_archive(&path, &entries, 42, false).unwrap();
+        assert_eq!(read_archive(File::open(&path).unwrap()), expected);
+
+        let path = dir.path().join("pages.tar.gz");
+        write_archive(&path, &entries, 42, true).unwrap();
+        let decoder = GzDecoder::new(File::open(&path).unwrap());
+        assert_eq!(read_archive(decoder), expected);
+    }
+}
@@ -0,0 +1,115 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use ecow::eco_format;
+use typst::diag::{bail, StrResult};
+
+use crate::args::FmtCommand;
+
+/// Execute a formatting command.
+pub fn fmt(command: &FmtCommand) -> StrResult<()> {
+    let mut files = vec![];
+    for path in &command.paths {
+        if path.as_os_str() == "-" {
+            return fmt_stdin(command);
+        }
+        collect(path, &mut files)?;
+    }
+
+    let mut unformatted = 0;
+    for path in &files {
+        let text = fs::read_to_string(path)
+            .map_err(|err| eco_format!("failed to read {} ({err})", path.display()))?;
+
+        let formatted = match typst_ide::format(&text) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                crate::set_failed();
+                crate::print_error(&format!("{}: {err}", path.display()))
+                    .map_err(|err| eco_format!("failed to print error ({err})"))?;
+                continue;
+            }
+        };
+
+        if formatted == text {
+            continue;
+        }
+
+        if command.check {
+            println!("would reformat {}", path.display());
+            unformatted += 1;
+        } else {
+            fs::write(path, formatted).map_err(|err| {
+                eco_format!("failed to write {} ({err})", path.display())
+            })?;
+        }
+    }
+
+    if unformatted > 0 {
+        let s = if unformatted == 1 { "" } else { "s" };
+        bail!("{unformatted} file{s} would be reformatted");
+    }
+
+    Ok(())
+}
+
+/// Format the source code from stdin and write it to stdout.
+fn fmt_stdin(command: &FmtCommand) -> StrResult<()> {
+    if command.paths.len() > 1 {
+        bail!("cannot format stdin together with other files");
+    }
+
+    let mut text = String::new();
+    io::stdin()
+        .read_to_string(&mut text)
+        .map_err(|err| eco_format!("failed to read from stdin ({err})"))?;
+
+    let formatted = typst_ide::format(&text)?;
+    if command.check {
+        if formatted != text {
+            bail!("stdin would be reformatted");
+        }
+        return Ok(());
+    }
+
+    io::stdout()
+        .write_all(formatted.as_bytes())
+        .map_err(|err| eco_format!("failed to write to stdout ({err})"))
+}
+
+/// Collect the Typst files at a path, searching directories recursively and
+/// skipping hidden entries.
+fn collect(path: &Path, files: &mut Vec<PathBuf>) -> StrResult<()> {
+    if !path.is_dir() {
+        if !path.exists() {
+            bail!("{} does not exist", path.display());
+        }
+        files.push(path.into());
+        return Ok(());
+    }
+
+    let entries = fs::read_dir(path)
+        .map_err(|err| eco_format!("failed to read {} ({err})", path.display()))?;
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            !path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        })
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            collect(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "typ") {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,1511 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+use ecow::{eco_format, EcoString};
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use typst::diag::{
+    bail, FileResult, PackageResult, Severity, SourceDiagnostic, StrResult,
+};
+use typst::eval::Tracer;
+use typst::foundations::{Bytes, Datetime};
+use typst::model::Document;
+use typst::syntax::package::{PackageRequirement, PackageSpec};
+use typst::syntax::{FileId, Side, Source, Span, Tag, VirtualPath};
+use typst::text::{Font, FontBook};
+use typst::utils::LazyHash;
+use typst::{Library, World};
+use typst_ide::{
+    CompletionKind, DocumentSymbol, FoldingKind, InlayHintKind, SemanticTokens,
+    SymbolKind, TokenModifier, Tooltip,
+};
+use url::Url;
+
+use crate::args::{Input, LspCommand};
+use crate::world::SystemWorld;
+
+/// The JSON-RPC error code for messages that aren't valid JSON.
+const PARSE_ERROR: i64 = -32700;
+/// The JSON-RPC error code for messages that aren't valid requests.
+const INVALID_REQUEST: i64 = -32600;
+/// The JSON-RPC error code for unknown methods.
+const METHOD_NOT_FOUND: i64 = -32601;
+/// The JSON-RPC error code for invalid method parameters.
+const INVALID_PARAMS: i64 = -32602;
+/// The LSP error code for requests that arrive before `initialize`.
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+/// The LSP error code for requests that arrive after `shutdown`.
+const INVALID_AFTER_SHUTDOWN: i64 = -32600;
+/// The LSP error code for valid requests that failed nonetheless.
+const REQUEST_FAILED: i64 = -32803;
+
+/// The characters after which the editor should ask for completions.
+const TRIGGER_CHARACTERS: &[&str] = &["#", ".", "@", "(", ",", ":", "\"", "<", "/"];
+
+/// Execute a language server command.
+pub fn lsp(command: &LspCommand) -> StrResult<()> {
+    if matches!(command.common.input, Some(Input::Stdin)) {
+        bail!("cannot read input from stdin while running the language server");
+    }
+
+    // Read messages on a separate thread so that we can batch up edits that
+    // arrive in quick succession before recompiling.
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(io::stdin());
+        while let Ok(Some(message)) = read_message(&mut reader) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut server = Server::new(command.clone(), io::stdout());
+
+    while let Ok(first) = receiver.recv() {
+        for message in std::iter::once(first).chain(receiver.try_iter()) {
+            if server
+                .handle_message(&message)
+                .map_err(|err| eco_format!("failed to communicate over stdio ({err})"))?
+            {
+                if !server.shutdown {
+                    bail!("received exit notification before shutdown request");
+                }
+                return Ok(());
+            }
+        }
+
+        if server.dirty {
+            server
+                .check()
+                .map_err(|err| eco_format!("failed to communicate over stdio ({err})"))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Read a single message with its `Content-Length` header.
+///
+/// Returns `None` once the stream has ended.
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let length = length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing content length")
+    })?;
+
+    let mut buf = vec![0; length];
+    reader.read_exact(&mut buf)?;
+    String::from_utf8(buf)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Write a single message with its `Content-Length` header.
+fn write_message(writer: &mut impl Write, message: &JsonValue) -> io::Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    writer.flush()
+}
+
+/// The state of the language server.
+struct Server<W> {
+    /// The command the server was started with.
+    command: LspCommand,
+    /// Where responses and notifications are written to.
+    output: W,
+    /// The world that serves files and fonts, created upon initialization.
+    world: Option<SystemWorld>,
+    /// The pinned main file, if any.
+    main: Option<FileId>,
+    /// The document that was opened or edited most recently.
+    focus: Option<FileId>,
+    /// The contents of all open documents.
+    sources: HashMap<FileId, Source>,
+    /// The semantic tokens of all open documents.
+    tokens: HashMap<FileId, SemanticTokens>,
+    /// The last encoded semantic tokens sent for each document, together with
+    /// their result ID.
+    encoded: HashMap<FileId, (String, Vec<u32>)>,
+    /// The ID of the next semantic tokens result.
+    next_result: u64,
+    /// The document produced by the last successful compilation.
+    document: Option<Document>,
+    /// The errors and warnings of the last compilation.
+    diagnostics: Vec<SourceDiagnostic>,
+    /// The files that we've published diagnostics for.
+    published: HashSet<Url>,
+    /// Whether the open documents changed since the last compilation.
+    dirty: bool,
+    /// Whether a shutdown was requested.
+    shutdown: bool,
+}
+
+impl<W: Write> Server<W> {
+    /// Create a server that isn't initialized yet.
+    fn new(command: LspCommand, output: W) -> Self {
+        Self {
+            command,
+            output,
+            world: None,
+            main: None,
+            focus: None,
+            sources: HashMap::new(),
+            tokens: HashMap::new(),
+            encoded: HashMap::new(),
+            next_result: 0,
+            document: None,
+            diagnostics: vec![],
+            published: HashSet::new(),
+            dirty: false,
+            shutdown: false,
+        }
+    }
+
+    /// Handle a single message. Returns whether the server should exit.
+    fn handle_message(&mut self, message: &str) -> io::Result<bool> {
+        let message: Message = match serde_json::from_str(message) {
+            Ok(message) => message,
+            Err(err) => {
+                let code = if err.is_syntax() || err.is_eof() {
+                    PARSE_ERROR
+                } else {
+                    INVALID_REQUEST
+                };
+                let response = error(JsonValue::Null, code, eco_format!("{err}"));
+                write_message(&mut self.output, &response)?;
+                return Ok(false);
+            }
+        };
+
+        // Responses to requests from the server don't have a method. We
+        // don't send any requests, so there's nothing to do.
+        let Some(method) = message.method else { return Ok(false) };
+        let params = message.params.unwrap_or(JsonValue::Null);
+
+        let Some(id) = message.id else {
+            if method == "exit" {
+                return Ok(true);
+            }
+            if self.world.is_some() && !self.shutdown {
+                // Malformed notifications can't be answered, so we just
+                // ignore them.
+                self.handle_notification(&method, params).ok();
+            }
+            return Ok(false);
+        };
+
+        let result = if method == "initialize" {
+            if self.world.is_some() {
+                Err((INVALID_REQUEST, "server is already initialized".into()))
+            } else {
+                parse_params(params).and_then(|params| self.initialize(params))
+            }
+        } else if self.world.is_none() {
+            Err((SERVER_NOT_INITIALIZED, "server is not initialized yet".into()))
+        } else if self.shutdown {
+            Err((INVALID_AFTER_SHUTDOWN, "server is shutting down".into()))
+        } else {
+            // Answer requests based on the latest edits.
+            if self.dirty {
+                self.check()?;
+            }
+            self.handle_request(&method, params)
+        };
+
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error(id, code, message),
+        };
+
+        write_message(&mut self.output, &response)?;
+        Ok(false)
+    }
+
+    /// Handle a request after initialization.
+    fn handle_request(
+        &mut self,
+        method: &str,
+        params: JsonValue,
+    ) -> Result<JsonValue, (i64, EcoString)> {
+        match method {
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(JsonValue::Null)
+            }
+            "textDocument/completion" => Ok(self.completion(parse_params(params)?)),
+            "textDocument/hover" => Ok(self.hover(parse_params(params)?)),
+            "textDocument/definition" => Ok(self.definition(parse_params(params)?)),
+            "textDocument/references" => Ok(self.references(parse_params(params)?)),
+            "textDocument/signatureHelp" => {
+                Ok(self.signature_help(parse_params(params)?))
+            }
+            "textDocument/inlayHint" => Ok(self.inlay_hints(parse_params(params)?)),
+            "textDocument/documentSymbol" => {
+                Ok(self.document_symbols(parse_params(params)?))
+            }
+            "textDocument/codeAction" => Ok(self.code_actions(parse_params(params)?)),
+            "textDocument/foldingRange" => Ok(self.folding_ranges(parse_params(params)?)),
+            "textDocument/selectionRange" => {
+                Ok(self.selection_ranges(parse_params(params)?))
+            }
+            "textDocument/semanticTokens/full" => {
+                Ok(self.semantic_tokens_full(parse_params(params)?))
+            }
+            "textDocument/semanticTokens/full/delta" => {
+                Ok(self.semantic_tokens_delta(parse_params(params)?))
+            }
+            "textDocument/semanticTokens/range" => {
+                Ok(self.semantic_tokens_range(parse_params(params)?))
+            }
+            "textDocument/formatting" => Ok(self.formatting(parse_params(params)?)),
+            "textDocument/rename" => self
+                .rename(parse_params(params)?)
+                .map_err(|message| (REQUEST_FAILED, message)),
+            method => Err((METHOD_NOT_FOUND, eco_format!("unknown method `{method}`"))),
+        }
+    }
+
+    /// Handle a notification after initialization.
+    fn handle_notification(
+        &mut self,
+        method: &str,
+        params: JsonValue,
+    ) -> Result<(), (i64, EcoString)> {
+        match method {
+            "textDocument/didOpen" => self.did_open(parse_params(params)?),
+            "textDocument/didChange" => self.did_change(parse_params(params)?),
+            "textDocument/didClose" => self.did_close(parse_params(params)?),
+            "textDocument/didSave" => self.dirty = true,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Set up the world and announce our capabilities.
+    fn initialize(
+        &mut self,
+        params: InitializeParams,
+    ) -> Result<JsonValue, (i64, EcoString)> {
+        let mut args = self.command.common.clone();
+        let main = match args.input.replace(Input::Stdin) {
+            Some(Input::Path(path)) => Some(path),
+            _ => None,
+        };
+
+        if args.root.is_none() {
+            args.root = params
+                .root_uri
+                .and_then(|uri| uri.to_file_path().ok())
+                .or_else(|| params.root_path.map(PathBuf::from));
+        }
+
+        let world = SystemWorld::new(&args)
+            .map_err(|err| (INVALID_PARAMS, EcoString::from(err)))?;
+
+        if let Some(path) = &main {
+            let id = resolve(world.root(), path).ok_or_else(|| {
+                (INVALID_PARAMS, "main file must be within the project root".into())
+            })?;
+            self.main = Some(id);
+        }
+
+        self.world = Some(world);
+
+        Ok(json!({
+            "capabilities": {
+                "positionEncoding": "utf-16",
+                "textDocumentSync": {
+                    "openClose": true,
+                    "change": 2,
+                    "save": true,
+                },
+                "completionProvider": {
+                    "triggerCharacters": TRIGGER_CHARACTERS,
+                },
+                "hoverProvider": true,
+                "signatureHelpProvider": {
+                    "triggerCharacters": ["(", ","],
+                },
+                "definitionProvider": true,
+                "referencesProvider": true,
+                "renameProvider": true,
+                "documentSymbolProvider": true,
+                "inlayHintProvider": true,
+                "documentFormattingProvider": true,
+                "foldingRangeProvider": true,
+                "codeActionProvider": {
+                    "codeActionKinds": ["quickfix"],
+                },
+                "selectionRangeProvider": true,
+                "semanticTokensProvider": {
+                    "legend": {
+                        "tokenTypes": Tag::LIST
+                            .iter()
+                            .map(|&tag| token_type(tag))
+                            .collect::<Vec<_>>(),
+                        "tokenModifiers": TokenModifier::LIST
+                            .iter()
+                            .map(|&modifier| token_modifier(modifier))
+                            .collect::<Vec<_>>(),
+                    },
+                    "full": { "delta": true },
+                    "range": true,
+                },
+            },
+            "serverInfo": {
+                "name": "typst",
+                "version": crate::typst_version(),
+            },
+        }))
+    }
+
+    /// Start tracking a document.
+    fn did_open(&mut self, params: DidOpenParams) {
+        let Some(id) = self.id(&params.text_document.uri) else { return };
+        let source = Source::new(id, params.text_document.text);
+        self.tokens.insert(id, SemanticTokens::new(&source));
+        self.sources.insert(id, source);
+        self.focus = Some(id);
+        self.dirty = true;
+    }
+
+    /// Apply edits to a document.
+    fn did_change(&mut self, params: DidChangeParams) {
+        let Some(id) = self.id(&params.text_document.uri) else { return };
+        let Some(source) = self.sources.get_mut(&id) else { return };
+        let Some(tokens) = self.tokens.get_mut(&id) else { return };
+
+        for change in params.content_changes {
+            match change.range.and_then(|range| to_range(source, range)) {
+                Some(range) => {
+                    tokens.edit(source, range, &change.text);
+                }
+                None => {
+                    source.replace(&change.text);
+                    *tokens = SemanticTokens::new(source);
+                }
+            }
+        }
+
+        self.focus = Some(id);
+        self.dirty = true;
+    }
+
+    /// Stop tracking a document. From now on, it's read from disk.
+    fn did_close(&mut self, params: DidCloseParams) {
+        let Some(id) = self.id(&params.text_document.uri) else { return };
+        self.sources.remove(&id);
+        self.tokens.remove(&id);
+        self.encoded.remove(&id);
+        if self.focus == Some(id) {
+            self.focus = None;
+        }
+        self.dirty = true;
+    }
+
+    /// Compile the current main file and publish its diagnostics.
+    fn check(&mut self) -> io::Result<()> {
+        self.dirty = false;
+
+        let Some(main) = self.main.or(self.focus) else {
+            self.diagnostics.clear();
+            return self.publish(HashMap::new());
+        };
+
+        self.world.as_mut().unwrap().reset();
+
+        let world = self.world(main);
+        let mut tracer = Tracer::new();
+        let result = match World::source(&world, main) {
+            Ok(_) => typst::compile(&world, &mut tracer),
+            Err(err) => {
+                eprintln!("failed to load main file ({err})");
+                Ok(Document::default())
+            }
+        };
+
+        if self.command.lint {
+            typst_ide::lint(&world, &mut tracer);
+        }
+
+        comemo::evict(10);
+
+        let warnings = tracer.warnings();
+        let errors = match result {
+            Ok(document) => {
+                self.document = Some(document);
+                vec![]
+            }
+            Err(errors) => errors.to_vec(),
+        };
+
+        let world = self.world(main);
+        let mut diagnostics = HashMap::<Url, Vec<JsonValue>>::new();
+        for diagnostic in warnings.iter().chain(&errors) {
+            let Some((uri, range)) = world.locate(diagnostic.span) else { continue };
+            diagnostics
+                .entry(uri)
+                .or_default()
+                .push(to_diagnostic(&world, diagnostic, range));
+        }
+
+        self.diagnostics = warnings.iter().chain(&errors).cloned().collect();
+        self.publish(diagnostics)
+    }
+
+    /// Publish diagnostics, clearing those of files that don't have any
+    /// anymore.
+    fn publish(
+        &mut self,
+        mut diagnostics: HashMap<Url, Vec<JsonValue>>,
+    ) -> io::Result<()> {
+        for uri in self.published.drain() {
+            diagnostics.entry(uri).or_default();
+        }
+
+        for (uri, items) in diagnostics {
+            if !items.is_empty() {
+                self.published.insert(uri.clone());
+            }
+
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/publishDiagnostics",
+                "params": { "uri": uri, "diagnostics": items },
+            });
+            write_message(&mut self.output, &notification)?;
+        }
+
+        Ok(())
+    }
+
+    /// Provide completions at a position.
+    fn completion(&self, params: CompletionParams) -> JsonValue {
+        let Some((world, source, cursor)) = self.locate(&params.position) else {
+            return JsonValue::Null;
+        };
+
+        let explicit = params.context.map_or(true, |context| context.trigger_kind == 1);
+        let Some((from, completions)) = typst_ide::autocomplete(
+            &world,
+            self.document.as_ref(),
+            &source,
+            cursor,
+            explicit,
+        ) else {
+            return JsonValue::Null;
+        };
+
+        let range = to_lsp_range(&source, from..cursor);
+        let items: Vec<_> = completions
+            .into_iter()
+            .map(|completion| {
+                let apply = completion.apply.as_ref().unwrap_or(&completion.label);
+                let snippet = apply.contains("${");
+                let detail = match completion.kind {
+                    CompletionKind::Symbol(c) if completion.detail.is_none() => {
+                        Some(eco_format!("{c}"))
+                    }
+                    _ => completion.detail.clone(),
+                };
+
+                json!({
+                    "label": completion.label,
+                    "kind": completion_kind(&completion.kind),
+                    "detail": detail,
+                    "insertTextFormat": if snippet { 2 } else { 1 },
+                    "textEdit": {
+                        "range": range,
+                        "newText": if snippet { to_snippet(apply) } else { apply.to_string() },
+                    },
+                })
+            })
+            .collect();
+
+        json!({ "isIncomplete": false, "items": items })
+    }
+
+    /// Provide a tooltip for a position.
+    fn hover(&self, params: PositionParams) -> JsonValue {
+        let Some((world, source, cursor)) = self.locate(&params) else {
+            return JsonValue::Null;
+        };
+
+        let Some(tooltip) = typst_ide::tooltip(
+            &world,
+            self.document.as_ref(),
+            &source,
+            cursor,
+            Side::Before,
+        ) else {
+            return JsonValue::Null;
+        };
+
+        let value = match tooltip {
+            Tooltip::Text(text) => text.to_string(),
+            Tooltip::Code(code) => format!("```typc\n{code}\n```"),
+        };
+
+        json!({ "contents": { "kind": "markdown", "value": value } })
+    }
+
+    /// Describe the signature of the function call at a position.
+    fn signature_help(&self, params: PositionParams) -> JsonValue {
+        let Some((world, source, cursor)) = self.locate(&params) else {
+            return JsonValue::Null;
+        };
+
+        let Some(help) =
+            typst_ide::signature_help(&world, self.document.as_ref(), &source, cursor)
+        else {
+            return JsonValue::Null;
+        };
+
+        // Parameter labels are given as UTF-16 offsets into the signature.
+        let utf16 = |offset: usize| help.label[..offset].encode_utf16().count();
+        let parameters: Vec<_> = help
+            .params
+            .iter()
+            .map(|param| {
+                json!({
+                    "label": [utf16(param.label.start), utf16(param.label.end)],
+                    "documentation": param.docs,
+                })
+            })
+            .collect();
+
+        json!({
+            "signatures": [{
+                "label": help.label,
+                "documentation": help.docs,
+                "parameters": parameters,
+                "activeParameter": help.active,
+            }],
+            "activeSignature": 0,
+        })
+    }
+
+    /// Find the definition of the item at a position.
+    fn definition(&self, params: PositionParams) -> JsonValue {
+        let Some((world, source, cursor)) = self.locate(&params) else {
+            return JsonValue::Null;
+        };
+
+        let Some(span) =
+            typst_ide::definition(&world, self.document.as_ref(), &source, cursor)
+        else {
+            return JsonValue::Null;
+        };
+
+        match world.locate(span) {
+            Some((uri, range)) => json!({ "uri": uri, "range": range }),
+            None => JsonValue::Null,
+        }
+    }
+
+    /// Find all references to the item at a position.
+    fn references(&self, params: PositionParams) -> JsonValue {
+        let Some((world, source, cursor)) = self.locate(&params) else {
+            return JsonValue::Null;
+        };
+
+        let locations: Vec<_> = typst_ide::references(&world, &source, cursor)
+            .into_iter()
+            .flat_map(|(id, ranges)| ranges.into_iter().map(move |range| (id, range)))
+            .filter_map(|(id, range)| {
+                let (uri, range) = world.locate_range(id, range)?;
+                Some(json!({ "uri": uri, "range": range }))
+            })
+            .collect();
+
+        json!(locations)
+    }
+
+    /// Rename the item at a position across all files.
+    fn rename(&self, params: RenameParams) -> StrResult<JsonValue> {
+        let Some((world, source, cursor)) = self.locate(&params.position) else {
+            bail!("there is nothing to rename here");
+        };
+
+        let mut changes = serde_json::Map::new();
+        for (id, ranges) in typst_ide::rename(&world, &source, cursor, &params.new_name)?
+        {
+            let edits: Vec<_> = ranges
+                .into_iter()
+                .filter_map(|range| {
+                    let (_, range) = world.locate_range(id, range)?;
+                    Some(json!({ "range": range, "newText": params.new_name }))
+                })
+                .collect();
+            let Some((uri, _)) = world.locate_range(id, 0..0) else { continue };
+            changes.insert(uri.to_string(), json!(edits));
+        }
+
+        Ok(json!({ "changes": changes }))
+    }
+
+    /// Provide the outline of a document.
+    fn document_symbols(&self, params: DocumentParams) -> JsonValue {
+        let Some(id) = self.id(&params.text_document.uri) else {
+            return JsonValue::Null;
+        };
+        let world = self.world(self.main.unwrap_or(id));
+        let Ok(source) = World::source(&world, id) else {
+            return JsonValue::Null;
+        };
+
+        json!(to_document_symbols(&source, typst_ide::document_symbols(&source)))
+    }
+
+    /// Provide quick fixes for the diagnostics in a range of a document.
+    fn code_actions(&self, params: RangeParams) -> JsonValue {
+        let Some(id) = self.id(&params.text_document.uri) else {
+            return JsonValue::Null;
+        };
+        let world = self.world(self.main.unwrap_or(id));
+        let Ok(source) = World::source(&world, id) else {
+            return JsonValue::Null;
+        };
+        let Some(range) = to_range(&source, params.range) else {
+            return JsonValue::Null;
+        };
+
+        let actions: Vec<_> =
+            typst_ide::code_actions(&world, &source, range, &self.diagnostics)
+                .into_iter()
+                .map(|action| {
+                    let edits: Vec<_> = action
+                        .edits
+                        .into_iter()
+                        .map(|(range, text)| {
+                            json!({
+                                "range": to_lsp_range(&source, range),
+                                "newText": text,
+                            })
+                        })
+                        .collect();
+
+                    let mut changes = serde_json::Map::new();
+                    changes.insert(params.text_document.uri.to_string(), json!(edits));
+
+                    let diagnostic = &self.diagnostics[action.diagnostic];
+                    let diagnostics: Vec<_> = world
+                        .locate(diagnostic.span)
+                        .map(|(_, range)| to_diagnostic(&world, diagnostic, range))
+                        .into_iter()
+                        .collect();
+
+                    json!({
+                        "title": action.title,
+                        "kind": "quickfix",
+                        "diagnostics": diagnostics,
+                        "edit": { "changes": changes },
+                    })
+                })
+                .collect();
+
+        json!(actions)
+    }
+
+    /// Provide the foldable regions of a document.
+    fn folding_ranges(&self, params: DocumentParams) -> JsonValue {
+        let Some(id) = self.id(&params.text_document.uri) else {
+            return JsonValue::Null;
+        };
+        let world = self.world(self.main.unwrap_or(id));
+        let Ok(source) = World::source(&world, id) else {
+            return JsonValue::Null;
+        };
+
+        let ranges: Vec<_> = typst_ide::folding_ranges(&source)
+            .into_iter()
+            .map(|folding| {
+                let start = to_position(&source, folding.range.start);
+                let end = to_position(&source, folding.range.end);
+                let mut range = json!({
+                    "startLine": start["line"],
+                    "startCharacter": start["character"],
+                    "endLine": end["line"],
+                    "endCharacter": end["character"],
+                });
+                match folding.kind {
+                    FoldingKind::Block => {}
+                    FoldingKind::Heading => range["kind"] = json!("region"),
+                    FoldingKind::Comment => range["kind"] = json!("comment"),
+                }
+                range
+            })
+            .collect();
+
+        json!(ranges)
+    }
+
+    /// Provide the ranges the selection can be expanded to from each cursor.
+    fn selection_ranges(&self, params: SelectionRangeParams) -> JsonValue {
+        let Some(id) = self.id(&params.text_document.uri) else {
+            return JsonValue::Null;
+        };
+        let world = self.world(self.main.unwrap_or(id));
+        let Ok(source) = World::source(&world, id) else {
+            return JsonValue::Null;
+        };
+
+        let mut cursors = vec![];
+        for position in params.positions {
+            let Some(cursor) = to_offset(&source, position) else {
+                return JsonValue::Null;
+            };
+            cursors.push(cursor);
+        }
+
+        let selections: Vec<_> = typst_ide::selection_ranges(&source, &cursors)
+            .into_iter()
+            .zip(cursors)
+            .map(|(mut ranges, cursor)| {
+                // Editors expect a result for every position.
+                if ranges.is_empty() {
+                    ranges.push(cursor..cursor);
+                }
+
+                // Each range points to the next larger one as its parent.
+                ranges.into_iter().rev().fold(JsonValue::Null, |parent, range| {
+                    let mut selection = json!({ "range": to_lsp_range(&source, range) });
+                    if !parent.is_null() {
+                        selection["parent"] = parent;
+                    }
+                    selection
+                })
+            })
+            .collect();
+
+        json!(selections)
+    }
+
+    /// Provide the semantic tokens of a whole document.
+    fn semantic_tokens_full(&mut self, params: DocumentParams) -> JsonValue {
+        let Some((id, data)) = self.encode_tokens(&params.text_document.uri) else {
+            return JsonValue::Null;
+        };
+
+        let result_id = self.remember_tokens(id, data.clone());
+        json!({ "resultId": result_id, "data": data })
+    }
+
+    /// Provide the changes to a document's semantic tokens since a previous
+    /// result.
+    fn semantic_tokens_delta(&mut self, params: SemanticTokensDeltaParams) -> JsonValue {
+        let Some((id, data)) = self.encode_tokens(&params.text_document.uri) else {
+            return JsonValue::Null;
+        };
+
+        let edits = match self.encoded.get(&id) {
+            Some((prev_id, prev)) if *prev_id == params.previous_result_id => {
+                typst_ide::diff_tokens(prev, &data)
+                    .map(|edit| {
+                        json!({
+                            "start": edit.start,
+                            "deleteCount": edit.delete,
+                            "data": edit.data,
+                        })
+                    })
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            }
+            _ => {
+                let result_id = self.remember_tokens(id, data.clone());
+                return json!({ "resultId": result_id, "data": data });
+            }
+        };
+
+        let result_id = self.remember_tokens(id, data);
+        json!({ "resultId": result_id, "edits": edits })
+    }
+
+    /// Provide the semantic tokens on the lines in a range of a document.
+    fn semantic_tokens_range(&self, params: RangeParams) -> JsonValue {
+        let Some(id) = self.id(&params.text_document.uri) else {
+            return JsonValue::Null;
+        };
+        let world = self.world(self.main.unwrap_or(id));
+        let Ok(source) = World::source(&world, id) else {
+            return JsonValue::Null;
+        };
+        let Some(range) = to_range(&source, params.range) else {
+            return JsonValue::Null;
+        };
+
+        let tokens = typst_ide::semantic_tokens(&source, range);
+        json!({ "data": typst_ide::encode_tokens(&tokens) })
+    }
+
+    /// Encode the semantic tokens of a document, reusing the incrementally
+    /// updated ones of open documents.
+    fn encode_tokens(&self, uri: &Url) -> Option<(FileId, Vec<u32>)> {
+        let id = self.id(uri)?;
+        if let Some(tokens) = self.tokens.get(&id) {
+            return Some((id, typst_ide::encode_tokens(tokens.tokens())));
+        }
+
+        let world = self.world(self.main.unwrap_or(id));
+        let source = World::source(&world, id).ok()?;
+        let tokens = SemanticTokens::new(&source);
+        Some((id, typst_ide::encode_tokens(tokens.tokens())))
+    }
+
+    /// Store the encoded semantic tokens sent for a document so that later
+    /// requests can be answered with a delta. Returns the new result ID.
+    fn remember_tokens(&mut self, id: FileId, data: Vec<u32>) -> String {
+        let result_id = self.next_result.to_string();
+        self.next_result += 1;
+        self.encoded.insert(id, (result_id.clone(), data));
+        result_id
+    }
+
+    /// Format a document, replacing all of its text.
+    fn formatting(&self, params: DocumentParams) -> JsonValue {
+        let Some(id) = self.id(&params.text_document.uri) else {
+            return JsonValue::Null;
+        };
+        let world = self.world(self.main.unwrap_or(id));
+        let Ok(source) = World::source(&world, id) else {
+            return JsonValue::Null;
+        };
+        let Ok(formatted) = typst_ide::format(source.text()) else {
+            return JsonValue::Null;
+        };
+
+        if formatted == source.text() {
+            return json!([]);
+        }
+
+        json!([{
+            "range": to_lsp_range(&source, 0..source.len_bytes()),
+            "newText": formatted,
+        }])
+    }
+
+    /// Provide inlay hints for a range of a document.
+    fn inlay_hints(&self, params: RangeParams) -> JsonValue {
+        let Some(id) = self.id(&params.text_document.uri) else {
+            return JsonValue::Null;
+        };
+        let world = self.world(self.main.unwrap_or(id));
+        let Ok(source) = World::source(&world, id) else {
+            return JsonValue::Null;
+        };
+        let Some(range) = to_range(&source, params.range) else {
+            return JsonValue::Null;
+        };
+
+        let document = self.document.as_ref();
+        let hints: Vec<_> = typst_ide::inlay_hints(&world, document, &source, range)
+            .into_iter()
+            .map(|hint| {
+                let position = to_position(&source, hint.offset);
+                match hint.kind {
+                    InlayHintKind::Param => json!({
+                        "position": position,
+                        "label": eco_format!("{}:", hint.label),
+                        "kind": 2,
+                        "paddingRight": true,
+                    }),
+                    InlayHintKind::Value => json!({
+                        "position": position,
+                        "label": eco_format!("= {}", hint.label),
+                        "paddingLeft": true,
+                    }),
+                }
+            })
+            .collect();
+
+        json!(hints)
+    }
+
+    /// Resolve a text document position to a world, the document's source,
+    /// and a byte offset.
+    fn locate(&self, params: &PositionParams) -> Option<(LspWorld<'_>, Source, usize)> {
+        let id = self.id(&params.text_document.uri)?;
+        let world = self.world(self.main.unwrap_or(id));
+        let source = World::source(&world, id).ok()?;
+        let cursor = to_offset(&source, params.position)?;
+        Some((world, source, cursor))
+    }
+
+    /// Create a world with the given main file.
+    fn world(&self, main: FileId) -> LspWorld<'_> {
+        LspWorld {
+            base: self.world.as_ref().unwrap(),
+            main,
+            sources: &self.sources,
+        }
+    }
+
+    /// Resolve a document URI to a file id in the project.
+    fn id(&self, uri: &Url) -> Option<FileId> {
+        let path = uri.to_file_path().ok()?;
+        resolve(self.world.as_ref()?.root(), &path)
+    }
+}
+
+/// Resolve a path to a file id in the project.
+fn resolve(root: &Path, path: &Path) -> Option<FileId> {
+    let vpath = VirtualPath::within_root(path, root).or_else(|| {
+        let path = path.canonicalize().ok()?;
+        VirtualPath::within_root(&path, root)
+    })?;
+    Some(FileId::new(None, vpath))
+}
+
+/// A world that overlays the open documents onto the file system.
+struct LspWorld<'a> {
+    /// The world that serves everything that isn't open.
+    base: &'a SystemWorld,
+    /// The file to compile.
+    main: FileId,
+    /// The contents of all open documents.
+    sources: &'a HashMap<FileId, Source>,
+}
+
+impl LspWorld<'_> {
+    /// Find the document URI and range of a span.
+    fn locate(&self, span: Span) -> Option<(Url, JsonValue)> {
+        let id = span.id()?;
+        let range = self.source(id).ok()?.range(span)?;
+        self.locate_range(id, range)
+    }
+
+    /// Find the document URI and LSP range of a byte range in a file.
+    fn locate_range(&self, id: FileId, range: Range<usize>) -> Option<(Url, JsonValue)> {
+        let source = self.source(id).ok()?;
+        let uri = Url::from_file_path(self.base.path(id).ok()?).ok()?;
+        Some((uri, to_lsp_range(&source, range)))
+    }
+}
+
+impl World for LspWorld<'_> {
+    fn library(&self) -> &LazyHash<Library> {
+        World::library(self.base)
+    }
+
+    fn book(&self) -> &LazyHash<FontBook> {
+        self.base.book()
+    }
+
+    fn main(&self) -> Source {
+        self.source(self.main).unwrap()
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        match self.sources.get(&id) {
+            Some(source) => Ok(source.clone()),
+            None => World::source(self.base, id),
+        }
+    }
+
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        match self.sources.get(&id) {
+            Some(source) => Ok(Bytes::from(source.text().as_bytes())),
+            None => self.base.file(id),
+        }
+    }
+
+    fn font(&self, index: usize) -> Option<Font> {
+        self.base.font(index)
+    }
+
+    fn today(&self, offset: Option<i64>) -> Option<Datetime> {
+        self.base.today(offset)
+    }
+
+    fn resolve_package(
+        &self,
+        requirement: &PackageRequirement,
+    ) -> PackageResult<PackageSpec> {
+        self.base.resolve_package(requirement)
+    }
+}
+
+/// Convert a diagnostic into its LSP representation.
+fn to_diagnostic(
+    world: &LspWorld,
+    diagnostic: &SourceDiagnostic,
+    range: JsonValue,
+) -> JsonValue {
+    let mut message = diagnostic.message.to_string();
+    for hint in &diagnostic.hints {
+        write!(message, "\nhint: {hint}").unwrap();
+    }
+
+    let related: Vec<_> = diagnostic
+        .trace
+        .iter()
+        .filter_map(|point| {
+            let (uri, range) = world.locate(point.span)?;
+            Some(json!({
+                "location": { "uri": uri, "range": range },
+                "message": point.v.to_string(),
+            }))
+        })
+        .collect();
+
+    json!({
+        "range": range,
+        "severity": match diagnostic.severity {
+            Severity::Error => 1,
+            Severity::Warning => 2,
+        },
+        "source": "typst",
+        "message": message,
+        "relatedInformation": related,
+    })
+}
+
+/// Convert an LSP position into a byte offset.
+fn to_offset(source: &Source, position: Position) -> Option<usize> {
+    let Some(line) = source.line_to_range(position.line) else {
+        // Editors may place the cursor after the final newline.
+        return (position.line == source.len_lines()).then(|| source.len_bytes());
+    };
+
+    let start = source.byte_to_utf16(line.start)?;
+    let end = source.byte_to_utf16(line.end)?;
+    source.utf16_to_byte((start + position.character).min(end))
+}
+
+/// Convert an LSP range into a byte range.
+fn to_range(source: &Source, range: LspRange) -> Option<Range<usize>> {
+    let start = to_offset(source, range.start)?;
+    let end = to_offset(source, range.end)?;
+    (start <= end).then_some(start..end)
+}
+
+/// Convert a byte offset into an LSP position.
+fn to_position(source: &Source, offset: usize) -> JsonValue {
+    let line = source.byte_to_line(offset).unwrap_or(0);
+    let start = source
+        .line_to_byte(line)
+        .and_then(|start| source.byte_to_utf16(start));
+    let character = source
+        .byte_to_utf16(offset)
+        .zip(start)
+        .map_or(0, |(offset, start)| offset - start);
+    json!({ "line": line, "character": character })
+}
+
+/// Convert a byte range into an LSP range.
+fn to_lsp_range(source: &Source, range: Range<usize>) -> JsonValue {
+    json!({
+        "start": to_position(source, range.start),
+        "end": to_position(source, range.end),
+    })
+}
+
+/// Convert a completion's snippet from Typst's `${name}` syntax to the
+/// numbered `${1:name}` syntax of LSP.
+fn to_snippet(apply: &str) -> String {
+    let mut out = String::new();
+    let mut index = 1;
+    let mut rest = apply;
+
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else { break };
+        escape_snippet(&mut out, &rest[..start]);
+        let name = &rest[start + 2..end];
+        if name.is_empty() {
+            write!(out, "${index}").unwrap();
+        } else {
+            write!(out, "${{{index}:").unwrap();
+            escape_snippet(&mut out, name);
+            out.push('}');
+        }
+        index += 1;
+        rest = &rest[end + 1..];
+    }
+
+    escape_snippet(&mut out, rest);
+    out
+}
+
+/// Escape text that should appear verbatim in an LSP snippet.
+fn escape_snippet(out: &mut String, text: &str) {
+    for c in text.chars() {
+        if matches!(c, '$' | '}' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+/// The LSP completion item kind for a kind of completion.
+fn completion_kind(kind: &CompletionKind) -> u8 {
+    match kind {
+        CompletionKind::Syntax => 15,
+        CompletionKind::Func => 3,
+        CompletionKind::Type => 7,
+        CompletionKind::Param => 6,
+        CompletionKind::Constant => 21,
+        CompletionKind::Symbol(_) => 1,
+    }
+}
+
+/// Convert document symbols into their LSP representation.
+fn to_document_symbols(source: &Source, symbols: Vec<DocumentSymbol>) -> Vec<JsonValue> {
+    symbols
+        .into_iter()
+        .map(|symbol| {
+            // Editors reject symbols with empty names.
+            let name = if symbol.name.is_empty() { "…".into() } else { symbol.name };
+            json!({
+                "name": name,
+                "kind": symbol_kind(symbol.kind),
+                "range": to_lsp_range(source, symbol.range),
+                "selectionRange": to_lsp_range(source, symbol.selection_range),
+                "children": to_document_symbols(source, symbol.children),
+            })
+        })
+        .collect()
+}
+
+/// The LSP symbol kind for a kind of document symbol.
+fn symbol_kind(kind: SymbolKind) -> u8 {
+    match kind {
+        SymbolKind::Heading => 3,
+        SymbolKind::Func => 12,
+        SymbolKind::Variable => 13,
+        SymbolKind::Label => 14,
+        SymbolKind::Figure => 19,
+    }
+}
+
+/// The name of a semantic token type in the legend.
+fn token_type(tag: Tag) -> &'static str {
+    match tag {
+        Tag::Comment => "comment",
+        Tag::Punctuation => "punctuation",
+        Tag::Escape => "escape",
+        Tag::Strong => "strong",
+        Tag::Emph => "emph",
+        Tag::Link => "link",
+        Tag::Raw => "raw",
+        Tag::Label => "label",
+        Tag::Ref => "ref",
+        Tag::Heading => "heading",
+        Tag::ListMarker => "marker",
+        Tag::ListTerm => "term",
+        Tag::MathDelimiter => "delimiter",
+        Tag::MathOperator => "mathOperator",
+        Tag::Keyword => "keyword",
+        Tag::Operator => "operator",
+        Tag::Number => "number",
+        Tag::String => "string",
+        Tag::Function => "function",
+        Tag::Interpolated => "variable",
+        Tag::Error => "error",
+    }
+}
+
+/// The name of a semantic token modifier in the legend.
+fn token_modifier(modifier: TokenModifier) -> &'static str {
+    match modifier {
+        TokenModifier::Strong => "strong",
+        TokenModifier::Emph => "emph",
+        TokenModifier::Math => "math",
+    }
+}
+
+/// Create a JSON-RPC error response.
+fn error(id: JsonValue, code: i64, message: EcoString) -> JsonValue {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+/// Parse the parameters of a request or notification.
+fn parse_params<T: for<'de> Deserialize<'de>>(
+    params: JsonValue,
+) -> Result<T, (i64, EcoString)> {
+    serde_json::from_value(params)
+        .map_err(|err| (INVALID_PARAMS, eco_format!("invalid parameters ({err})")))
+}
+
+/// A JSON-RPC request, notification, or response.
+#[derive(Deserialize)]
+struct Message {
+    /// The message's ID. Notifications don't have one.
+    id: Option<JsonValue>,
+    /// The name of the method to call. Responses don't have one.
+    method: Option<String>,
+    /// The method's parameters.
+    params: Option<JsonValue>,
+}
+
+/// Parameters of the `initialize` request.
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct InitializeParams {
+    root_uri: Option<Url>,
+    root_path: Option<String>,
+}
+
+/// Parameters of the `textDocument/didOpen` notification.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DidOpenParams {
+    text_document: TextDocumentItem,
+}
+
+/// A document that was opened.
+#[derive(Deserialize)]
+struct TextDocumentItem {
+    uri: Url,
+    text: String,
+}
+
+/// Parameters of the `textDocument/didChange` notification.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DidChangeParams {
+    text_document: TextDocumentIdentifier,
+    content_changes: Vec<ContentChange>,
+}
+
+/// A change to a document. Without a range, it replaces the whole text.
+#[derive(Deserialize)]
+struct ContentChange {
+    range: Option<LspRange>,
+    text: String,
+}
+
+/// Parameters of the `textDocument/didClose` notification.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DidCloseParams {
+    text_document: TextDocumentIdentifier,
+}
+
+/// Parameters of requests that refer to a whole document.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DocumentParams {
+    text_document: TextDocumentIdentifier,
+}
+
+/// Parameters of requests that refer to a range of a document.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RangeParams {
+    text_document: TextDocumentIdentifier,
+    range: LspRange,
+}
+
+/// Parameters of the `textDocument/selectionRange` request.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SelectionRangeParams {
+    text_document: TextDocumentIdentifier,
+    positions: Vec<Position>,
+}
+
+/// Parameters of the `textDocument/semanticTokens/full/delta` request.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SemanticTokensDeltaParams {
+    text_document: TextDocumentIdentifier,
+    previous_result_id: String,
+}
+
+/// Parameters of the `textDocument/completion` request.
+#[derive(Deserialize)]
+struct CompletionParams {
+    #[serde(flatten)]
+    position: PositionParams,
+    context: Option<CompletionContext>,
+}
+
+/// How a completion was triggered.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompletionContext {
+    trigger_kind: u8,
+}
+
+/// Parameters of the `textDocument/rename` request.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RenameParams {
+    #[serde(flatten)]
+    position: PositionParams,
+    new_name: String,
+}
+
+/// Parameters of requests that refer to a position in a document.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PositionParams {
+    text_document: TextDocumentIdentifier,
+    position: Position,
+}
+
+/// Identifies a document.
+#[derive(Deserialize)]
+struct TextDocumentIdentifier {
+    uri: Url,
+}
+
+/// A range in a document.
+#[derive(Copy, Clone, Deserialize)]
+struct LspRange {
+    start: Position,
+    end: Position,
+}
+
+/// A zero-based line and UTF-16 column in a document.
+#[derive(Copy, Clone, Deserialize)]
+struct Position {
+    line: usize,
+    character: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    /// Create a server that was started with the given arguments and
+    /// initialize it for a workspace.
+    fn server(args: &[&str], workspace: &Path) -> Server<Vec<u8>> {
+        let args = std::iter::once("lsp").chain(args.iter().copied());
+        let command = LspCommand::try_parse_from(args).unwrap();
+        let mut server = Server::new(command, vec![]);
+
+        let responses = send(
+            &mut server,
+            &[json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": { "rootUri": Url::from_directory_path(workspace).unwrap() },
+            })],
+        );
+
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["result"]["serverInfo"]["name"], "typst");
+        server
+    }
+
+    /// Send messages to a server, compile if necessary, and return the parsed
+    /// messages it wrote back.
+    fn send(server: &mut Server<Vec<u8>>, messages: &[JsonValue]) -> Vec<JsonValue> {
+        for message in messages {
+            assert!(!server.handle_message(&message.to_string()).unwrap());
+        }
+        if server.dirty {
+            server.check().unwrap();
+        }
+
+        let output = std::mem::take(&mut server.output);
+        let mut reader = output.as_slice();
+        let mut messages = vec![];
+        while let Some(message) = read_message(&mut reader).unwrap() {
+            messages.push(serde_json::from_str(&message).unwrap());
+        }
+        messages
+    }
+
+    /// A notification that a document was opened.
+    fn did_open(path: &Path, text: &str) -> JsonValue {
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": {
+                "textDocument": {
+                    "uri": Url::from_file_path(path).unwrap(),
+                    "languageId": "typst",
+                    "version": 1,
+                    "text": text,
+                },
+            },
+        })
+    }
+
+    #[test]
+    fn test_lsp_diagnostics() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("main.typ");
+        let uri = Url::from_file_path(&path).unwrap();
+        let mut server = server(&[], dir.path());
+
+        let messages = send(&mut server, &[did_open(&path, "#foo")]);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["method"], "textDocument/publishDiagnostics");
+
+        let params = &messages[0]["params"];
+        assert_eq!(params["uri"], uri.as_str());
+        assert_eq!(params["diagnostics"].as_array().unwrap().len(), 1);
+
+        let diagnostic = &params["diagnostics"][0];
+        assert_eq!(diagnostic["severity"], 1);
+        assert_eq!(diagnostic["message"], "unknown variable: foo");
+        assert_eq!(diagnostic["range"]["start"], json!({ "line": 0, "character": 1 }));
+        assert_eq!(diagnostic["range"]["end"], json!({ "line": 0, "character": 4 }));
+
+        // Fixing the error clears the diagnostics.
+        let change = json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didChange",
+            "params": {
+                "textDocument": { "uri": uri },
+                "contentChanges": [{ "text": "Hello" }],
+            },
+        });
+        let messages = send(&mut server, &[change]);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["params"]["uri"], uri.as_str());
+        assert_eq!(messages[0]["params"]["diagnostics"], json!([]));
+    }
+
+    #[test]
+    fn test_lsp_main() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("main.typ");
+        let chapter = dir.path().join("chapter.typ");
+        std::fs::write(&main, "#include \"chapter.typ\"\n#sys.inputs.x #foo").unwrap();
+
+        // The open document is checked by compiling the pinned main file with
+        // the inputs given on the command line.
+        let mut server = server(&[main.to_str().unwrap(), "--input", "x=1"], dir.path());
+        let messages = send(&mut server, &[did_open(&chapter, "Hello")]);
+        assert_eq!(messages.len(), 1);
+
+        let params = &messages[0]["params"];
+        assert_eq!(params["uri"], Url::from_file_path(&main).unwrap().as_str());
+        assert_eq!(params["diagnostics"][0]["message"], "unknown variable: foo");
+    }
+}
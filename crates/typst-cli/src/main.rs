@@ -1,14 +1,20 @@
 mod args;
 mod compile;
 mod download;
+mod fmt;
 mod fonts;
 mod init;
+mod lsp;
 mod package;
+mod project;
+mod publish;
 mod query;
+mod serve;
 mod terminal;
 mod timings;
 #[cfg(feature = "self-update")]
 mod update;
+mod vendor;
 mod watch;
 mod world;
 
@@ -41,6 +47,11 @@ fn main() -> ExitCode {
         Command::Watch(command) => crate::watch::watch(timer, command.clone()),
         Command::Init(command) => crate::init::init(command),
         Command::Query(command) => crate::query::query(command),
+        Command::Serve(command) => crate::serve::serve(command),
+        Command::Lsp(command) => crate::lsp::lsp(command),
+        Command::Fmt(command) => crate::fmt::fmt(command),
+        Command::Vendor(command) => crate::vendor::vendor(command),
+        Command::Package(command) => crate::publish::package(command),
         Command::Fonts(command) => crate::fonts::fonts(command),
         Command::Update(command) => crate::update::update(command),
     };
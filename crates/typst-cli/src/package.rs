@@ -1,87 +1,561 @@
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 
 use codespan_reporting::term::{self, termcolor};
-use ecow::eco_format;
+use ecow::{eco_format, EcoString};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use termcolor::WriteColor;
 use typst::diag::{bail, PackageError, PackageResult, StrResult};
 use typst::syntax::package::{
-    PackageInfo, PackageSpec, PackageVersion, VersionlessPackageSpec,
+    PackageInfo, PackageRequirement, PackageSpec, PackageVersion, VersionlessPackageSpec,
 };
 
+use crate::args::PackageArgs;
 use crate::download::{download, download_with_progress};
 use crate::terminal;
 
-const HOST: &str = "https://packages.typst.org";
+/// The default registry of the `@preview` namespace.
+const DEFAULT_REGISTRY: &str = "https://packages.typst.org/preview";
 
-/// Make a package available in the on-disk cache.
-pub fn prepare_package(spec: &PackageSpec) -> PackageResult<PathBuf> {
-    let subdir =
-        format!("typst/packages/{}/{}/{}", spec.namespace, spec.name, spec.version);
+/// The subdirectory of the data and cache directories that holds packages.
+const DEFAULT_PACKAGES_SUBDIR: &str = "typst/packages";
 
-    if let Some(data_dir) = dirs::data_dir() {
-        let dir = data_dir.join(&subdir);
-        if dir.exists() {
-            return Ok(dir);
+/// The name of the lockfile in the project root.
+pub const LOCKFILE_NAME: &str = "typst.lock";
+
+/// The directory in the project root that holds vendored packages, organized
+/// by namespace. It is hidden so that it doesn't collide with the project's
+/// own folders.
+pub const VENDOR_DIR: &str = ".typst/vendor";
+
+/// The prefix of package hashes, naming the hash algorithm.
+const HASH_PREFIX: &str = "sha256:";
+
+/// Holds information about where packages are stored and where they are
+/// downloaded from.
+#[derive(Debug)]
+pub struct PackageStorage {
+    /// The directory of local packages, organized by namespace.
+    package_path: Option<PathBuf>,
+    /// The directory that downloaded packages are cached in.
+    package_cache_path: Option<PathBuf>,
+    /// The registries of the namespaces, in the order they were configured.
+    registries: Vec<(EcoString, Registry)>,
+    /// Whether to fail instead of downloading packages.
+    offline: bool,
+    /// The directory of the project's vendored packages, if any.
+    vendor_path: Option<PathBuf>,
+    /// The hashes of the packages in the project's lockfile.
+    locked: HashMap<PackageSpec, EcoString>,
+    /// The packages whose hashes were already checked against the lockfile.
+    verified: Mutex<HashSet<PackageSpec>>,
+    /// Packages that are served from a fixed directory instead.
+    provided: HashMap<PackageSpec, PathBuf>,
+    /// The versions that requirements were resolved to so far.
+    resolved: Mutex<HashMap<PackageRequirement, PackageSpec>>,
+    /// The package names and versions listed in the indices of HTTP
+    /// registries, by URL.
+    indices: Mutex<HashMap<EcoString, Vec<(EcoString, PackageVersion)>>>,
+}
+
+/// Where the packages of a namespace come from.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Registry {
+    /// An HTTP(S) server that serves `{name}-{version}.tar.gz` archives and an
+    /// `index.json` at the given base URL.
+    Http(EcoString),
+    /// A directory that contains the packages as `{name}/{version}`.
+    Local(PathBuf),
+}
+
+impl Registry {
+    /// Parse a registry location. URLs with an `http` or `https` scheme denote
+    /// HTTP registries, everything else is a local directory.
+    pub fn parse(location: &str) -> Self {
+        if location.starts_with("http://") || location.starts_with("https://") {
+            Self::Http(location.trim_end_matches('/').into())
+        } else {
+            Self::Local(location.into())
+        }
+    }
+}
+
+impl PackageStorage {
+    /// Create package storage from the command line arguments, falling back
+    /// to the system-dependent default locations.
+    pub fn from_args(args: &PackageArgs) -> Self {
+        let package_path = args
+            .package_path
+            .clone()
+            .or_else(|| Some(dirs::data_dir()?.join(DEFAULT_PACKAGES_SUBDIR)));
+        let package_cache_path = args
+            .package_cache_path
+            .clone()
+            .or_else(|| Some(dirs::cache_dir()?.join(DEFAULT_PACKAGES_SUBDIR)));
+
+        let mut registries: Vec<(EcoString, Registry)> = args
+            .registries
+            .iter()
+            .map(|(namespace, location)| {
+                (namespace.as_str().into(), Registry::parse(location))
+            })
+            .collect();
+        if !registries.iter().any(|(namespace, _)| namespace == "preview") {
+            registries.push(("preview".into(), Registry::parse(DEFAULT_REGISTRY)));
+        }
+
+        Self {
+            package_path,
+            package_cache_path,
+            registries,
+            offline: args.offline,
+            vendor_path: None,
+            locked: HashMap::new(),
+            verified: Mutex::new(HashSet::new()),
+            provided: HashMap::new(),
+            resolved: Mutex::new(HashMap::new()),
+            indices: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Resolve packages from the vendor directory of a project first and
+    /// verify them against its lockfile, if there is one.
+    pub fn with_project(mut self, root: &Path) -> StrResult<Self> {
+        if let Some(lockfile) = Lockfile::read(root)? {
+            self.locked = lockfile.hashes()?;
         }
+        self.vendor_path = Some(root.join(VENDOR_DIR));
+        Ok(self)
+    }
+
+    /// Stop resolving packages from the project's vendor directory and
+    /// verifying them against its lockfile.
+    pub fn without_project(&mut self) {
+        self.vendor_path = None;
+        self.locked.clear();
+        self.verified.get_mut().clear();
+        self.resolved.get_mut().clear();
+    }
+
+    /// Serve a package from a directory, regardless of where it would
+    /// otherwise be found. This is useful to compile against a package that
+    /// isn't published yet.
+    pub fn provide(&mut self, spec: PackageSpec, dir: PathBuf) {
+        self.provided.insert(spec, dir);
+    }
+
+    /// The registry of a namespace, if any. If a namespace is configured
+    /// multiple times, the last registry wins.
+    pub fn registry(&self, namespace: &str) -> Option<&Registry> {
+        self.registries
+            .iter()
+            .rev()
+            .find(|(configured, _)| configured == namespace)
+            .map(|(_, registry)| registry)
     }
 
-    if let Some(cache_dir) = dirs::cache_dir() {
-        let dir = cache_dir.join(&subdir);
-        if dir.exists() {
+    /// Make a package available on disk and return its directory.
+    ///
+    /// Vendored packages take precedence over local ones, which take
+    /// precedence over the namespace's registry. Packages from HTTP registries
+    /// are downloaded into the cache. If the package is locked, the hash of
+    /// its files must match the lockfile. Vendored packages must be locked.
+    pub fn prepare_package(&self, spec: &PackageSpec) -> PackageResult<PathBuf> {
+        let dir = self.locate_package(spec)?;
+        let Some(expected) = self.locked.get(spec) else {
+            let vendored =
+                self.vendor_path.as_ref().is_some_and(|path| dir.starts_with(path));
+            if vendored {
+                return Err(PackageError::Other(Some(eco_format!(
+                    "{spec} is vendored, but not locked in {LOCKFILE_NAME}"
+                ))));
+            }
             return Ok(dir);
+        };
+
+        if !self.verified.lock().contains(spec) {
+            let found = hash_package(&dir).map_err(|err| {
+                PackageError::Other(Some(eco_format!("failed to hash package ({err})")))
+            })?;
+            if found != *expected {
+                return Err(PackageError::Other(Some(eco_format!(
+                    "files of {spec} do not match the hash in {LOCKFILE_NAME}"
+                ))));
+            }
+            self.verified.lock().insert(spec.clone());
+        }
+
+        Ok(dir)
+    }
+
+    /// Find the directory of a package, downloading it if necessary.
+    fn locate_package(&self, spec: &PackageSpec) -> PackageResult<PathBuf> {
+        if let Some(dir) = self.provided.get(spec) {
+            return Ok(dir.clone());
         }
 
-        // Download from network if it doesn't exist yet.
-        if spec.namespace == "preview" {
-            download_package(spec, &dir)?;
+        let subdir = format!("{}/{}/{}", spec.namespace, spec.name, spec.version);
+
+        if let Some(vendor_path) = &self.vendor_path {
+            let dir = vendor_path.join(&subdir);
             if dir.exists() {
                 return Ok(dir);
             }
         }
+
+        if let Some(package_path) = &self.package_path {
+            let dir = package_path.join(&subdir);
+            if dir.exists() {
+                return Ok(dir);
+            }
+        }
+
+        match self.registry(&spec.namespace) {
+            Some(Registry::Local(registry)) => {
+                let dir =
+                    registry.join(spec.name.as_str()).join(spec.version.to_string());
+                if dir.exists() {
+                    return Ok(dir);
+                }
+            }
+            Some(Registry::Http(url)) => {
+                if let Some(cache_path) = &self.package_cache_path {
+                    let dir = cache_path.join(&subdir);
+                    if dir.exists() {
+                        return Ok(dir);
+                    }
+
+                    // Download from the network if it doesn't exist yet.
+                    if self.offline {
+                        return Err(PackageError::NetworkFailed(Some(
+                            "downloads are disabled in offline mode".into(),
+                        )));
+                    }
+                    download_package(url, spec, &dir)?;
+                    if dir.exists() {
+                        return Ok(dir);
+                    }
+                }
+            }
+            None => {
+                // Packages may also have been placed in the cache by hand.
+                if let Some(cache_path) = &self.package_cache_path {
+                    let dir = cache_path.join(&subdir);
+                    if dir.exists() {
+                        return Ok(dir);
+                    }
+                }
+            }
+        }
+
+        Err(PackageError::NotFound(spec.clone()))
     }
 
-    Err(PackageError::NotFound(spec.clone()))
-}
+    /// Choose the version of a package that satisfies a requirement.
+    ///
+    /// Versions in the lockfile take precedence over versions that are
+    /// already on disk, which take precedence over versions from the
+    /// registry's index. Within each of these, the latest matching version is
+    /// chosen. Once resolved, a requirement keeps its version, so that all
+    /// compilations with this storage agree on it.
+    pub fn resolve_package(
+        &self,
+        requirement: &PackageRequirement,
+    ) -> PackageResult<PackageSpec> {
+        if let Some(spec) = self.resolved.lock().get(requirement) {
+            return Ok(spec.clone());
+        }
 
-/// Try to determine the latest version of a package.
-pub fn determine_latest_version(
-    spec: &VersionlessPackageSpec,
-) -> StrResult<PackageVersion> {
-    if spec.namespace == "preview" {
-        // For `@preview`, download the package index and find the latest
-        // version.
-        download_index()?
-            .iter()
-            .filter(|package| package.name == spec.name)
-            .map(|package| package.version)
-            .max()
-            .ok_or_else(|| eco_format!("failed to find package {spec}"))
-    } else {
-        // For other namespaces, search locally. We only search in the data
-        // directory and not the cache directory, because the latter is not
-        // intended for storage of local packages.
-        let subdir = format!("typst/packages/{}/{}", spec.namespace, spec.name);
-        dirs::data_dir()
+        let spec = self.find_version(requirement)?;
+        self.resolved.lock().insert(requirement.clone(), spec.clone());
+        Ok(spec)
+    }
+
+    /// Find the best version of a package that satisfies a requirement.
+    fn find_version(
+        &self,
+        requirement: &PackageRequirement,
+    ) -> PackageResult<PackageSpec> {
+        let package = requirement.versionless();
+        let best = |versions: Vec<PackageVersion>| {
+            versions
+                .into_iter()
+                .filter(|&version| requirement.version.matches(version))
+                .max()
+                .map(|version| package.clone().at(version))
+        };
+
+        let locked = self
+            .locked
+            .keys()
+            .filter(|spec| {
+                spec.namespace == package.namespace && spec.name == package.name
+            })
+            .map(|spec| spec.version)
+            .collect();
+        if let Some(spec) = best(locked) {
+            return Ok(spec);
+        }
+
+        if let Some(spec) = best(self.local_versions(&package)) {
+            return Ok(spec);
+        }
+
+        if let Some(Registry::Http(url)) = self.registry(&package.namespace) {
+            if self.offline {
+                return Err(PackageError::NetworkFailed(Some(
+                    "cannot download package index in offline mode".into(),
+                )));
+            }
+            let versions = self
+                .index_versions(url, &package)
+                .map_err(|err| PackageError::NetworkFailed(Some(err)))?;
+            if let Some(spec) = best(versions) {
+                return Ok(spec);
+            }
+        }
+
+        Err(PackageError::VersionNotFound(requirement.clone()))
+    }
+
+    /// The versions of a package that are already available on disk.
+    fn local_versions(&self, package: &VersionlessPackageSpec) -> Vec<PackageVersion> {
+        let subdir = Path::new(package.namespace.as_str()).join(package.name.as_str());
+        let mut versions: Vec<PackageVersion> = self
+            .provided
+            .keys()
+            .filter(|spec| {
+                spec.namespace == package.namespace && spec.name == package.name
+            })
+            .map(|spec| spec.version)
+            .collect();
+
+        for dir in [&self.vendor_path, &self.package_path, &self.package_cache_path]
             .into_iter()
-            .flat_map(|dir| std::fs::read_dir(dir.join(&subdir)).ok())
             .flatten()
-            .filter_map(|entry| entry.ok())
-            .map(|entry| entry.path())
-            .filter_map(|path| path.file_name()?.to_string_lossy().parse().ok())
-            .max()
-            .ok_or_else(|| eco_format!("please specify the desired version"))
+        {
+            versions.extend(versions_in(&dir.join(&subdir)));
+        }
+
+        if let Some(Registry::Local(registry)) = self.registry(&package.namespace) {
+            versions.extend(versions_in(&registry.join(package.name.as_str())));
+        }
+
+        versions
+    }
+
+    /// The versions of a package listed in the index of an HTTP registry.
+    ///
+    /// The index is downloaded at most once.
+    fn index_versions(
+        &self,
+        registry: &EcoString,
+        package: &VersionlessPackageSpec,
+    ) -> StrResult<Vec<PackageVersion>> {
+        let mut indices = self.indices.lock();
+        if !indices.contains_key(registry) {
+            let index = download_index(registry)?
+                .into_iter()
+                .map(|info| (info.name, info.version))
+                .collect();
+            indices.insert(registry.clone(), index);
+        }
+
+        Ok(indices[registry]
+            .iter()
+            .filter(|(name, _)| *name == package.name)
+            .map(|&(_, version)| version)
+            .collect())
+    }
+
+    /// Try to determine the latest version of a package.
+    pub fn determine_latest_version(
+        &self,
+        spec: &VersionlessPackageSpec,
+    ) -> StrResult<PackageVersion> {
+        match self.registry(&spec.namespace) {
+            // For HTTP registries, download the package index and find the
+            // latest version.
+            Some(Registry::Http(_)) if self.offline => {
+                bail!("cannot download package index in offline mode")
+            }
+            Some(Registry::Http(url)) => self
+                .index_versions(url, spec)?
+                .into_iter()
+                .max()
+                .ok_or_else(|| eco_format!("failed to find package {spec}")),
+
+            // For local registries, search their directory.
+            Some(Registry::Local(registry)) => {
+                versions_in(&registry.join(spec.name.as_str()))
+                    .max()
+                    .ok_or_else(|| eco_format!("failed to find package {spec}"))
+            }
+
+            // For other namespaces, search the local packages. We don't search
+            // the cache directory, because it is not intended for storage of
+            // local packages.
+            None => self
+                .package_path
+                .as_ref()
+                .and_then(|dir| {
+                    versions_in(
+                        &dir.join(spec.namespace.as_str()).join(spec.name.as_str()),
+                    )
+                    .max()
+                })
+                .ok_or_else(|| eco_format!("please specify the desired version")),
+        }
     }
 }
 
-/// Download a package over the network.
-fn download_package(spec: &PackageSpec, package_dir: &Path) -> PackageResult<()> {
-    // The `@preview` namespace is the only namespace that supports on-demand
-    // fetching.
-    assert_eq!(spec.namespace, "preview");
+/// The hashes of the packages a project depends on, as stored in its
+/// `typst.lock`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// The version of the lockfile format.
+    pub version: u32,
+    /// The locked packages.
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+}
+
+/// A package in a lockfile.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockedPackage {
+    /// The package specification, e.g. `@preview/example:0.1.0`.
+    pub spec: String,
+    /// The hash of the package's files.
+    pub hash: EcoString,
+}
+
+impl Lockfile {
+    /// The current version of the lockfile format.
+    pub const VERSION: u32 = 1;
 
-    let url = format!("{HOST}/preview/{}-{}.tar.gz", spec.name, spec.version);
+    /// Read the lockfile of a project, if it has one.
+    pub fn read(root: &Path) -> StrResult<Option<Self>> {
+        let path = root.join(LOCKFILE_NAME);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => bail!("failed to read {} ({err})", path.display()),
+        };
+
+        let lockfile: Self = toml::from_str(&text)
+            .map_err(|err| eco_format!("failed to parse {} ({err})", path.display()))?;
+        if lockfile.version != Self::VERSION {
+            bail!("unsupported version {} of {}", lockfile.version, path.display());
+        }
+
+        Ok(Some(lockfile))
+    }
+
+    /// Write the lockfile into a project.
+    pub fn write(&self, root: &Path) -> StrResult<()> {
+        let path = root.join(LOCKFILE_NAME);
+        let toml = toml::to_string(self)
+            .map_err(|err| eco_format!("failed to serialize lockfile ({err})"))?;
+        let mut text = String::from("# This file is generated by `typst vendor`.\n");
+        text.push_str("# Do not edit it by hand.\n\n");
+        text.push_str(&toml);
+        fs::write(&path, text)
+            .map_err(|err| eco_format!("failed to write {} ({err})", path.display()))
+    }
+
+    /// The hashes of the locked packages.
+    pub fn hashes(&self) -> StrResult<HashMap<PackageSpec, EcoString>> {
+        self.packages
+            .iter()
+            .map(|package| {
+                let spec: PackageSpec = package.spec.parse().map_err(|err| {
+                    eco_format!(
+                        "invalid package {} in {LOCKFILE_NAME} ({err})",
+                        package.spec
+                    )
+                })?;
+                if !package.hash.starts_with(HASH_PREFIX) {
+                    bail!(
+                        "unsupported hash of {} in {LOCKFILE_NAME}, expected a \
+                         {HASH_PREFIX} hash",
+                        package.spec
+                    );
+                }
+                Ok((spec, package.hash.clone()))
+            })
+            .collect()
+    }
+}
+
+/// Hash the files of a package directory.
+///
+/// The hash is a SHA-256 digest of all files, ordered by their relative paths.
+/// Each file contributes the length of its `/`-separated relative path as a
+/// 64-bit little-endian integer, the path, the length of its contents in the
+/// same way, and the contents. The hash thus doesn't depend on where the
+/// package is stored. It is prefixed with the name of the algorithm.
+pub fn hash_package(dir: &Path) -> io::Result<EcoString> {
+    let mut files = vec![];
+    collect_files(dir, dir, &mut files)?;
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for (path, data) in &files {
+        hasher.update((path.len() as u64).to_le_bytes());
+        hasher.update(path.as_bytes());
+        hasher.update((data.len() as u64).to_le_bytes());
+        hasher.update(data);
+    }
+
+    let digest: String =
+        hasher.finalize().iter().map(|byte| format!("{byte:02x}")).collect();
+    Ok(eco_format!("{HASH_PREFIX}{digest}"))
+}
+
+/// Collect the relative paths and contents of all files in a directory.
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    files: &mut Vec<(String, Vec<u8>)>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            let name = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push((name, fs::read(&path)?));
+        }
+    }
+    Ok(())
+}
+
+/// Find the versions among the subdirectories of a package directory.
+fn versions_in(dir: &Path) -> impl Iterator<Item = PackageVersion> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter_map(|path| path.file_name()?.to_string_lossy().parse().ok())
+}
+
+/// Download a package over the network.
+fn download_package(
+    registry: &str,
+    spec: &PackageSpec,
+    package_dir: &Path,
+) -> PackageResult<()> {
+    let url = format!("{registry}/{}-{}.tar.gz", spec.name, spec.version);
 
     print_downloading(spec).unwrap();
 
@@ -100,9 +574,9 @@ fn download_package(spec: &PackageSpec, package_dir: &Path) -> PackageResult<()>
     })
 }
 
-/// Download the `@preview` package index.
-fn download_index() -> StrResult<Vec<PackageInfo>> {
-    let url = format!("{HOST}/preview/index.json");
+/// Download the package index of a registry.
+fn download_index(registry: &str) -> StrResult<Vec<PackageInfo>> {
+    let url = format!("{registry}/index.json");
     match download(&url) {
         Ok(response) => response
             .into_json()
@@ -125,3 +599,133 @@ fn print_downloading(spec: &PackageSpec) -> io::Result<()> {
     out.reset()?;
     writeln!(out, " {spec}")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The package that the tests vendor.
+    fn spec() -> PackageSpec {
+        "@preview/example:0.1.0".parse().unwrap()
+    }
+
+    /// Vendor the example package into a project and return its directory.
+    fn vendor(root: &Path) -> PathBuf {
+        let dir = root.join(VENDOR_DIR).join("preview/example/0.1.0");
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("typst.toml"), "[package]").unwrap();
+        fs::write(dir.join("src/lib.typ"), "#let x = 1").unwrap();
+        dir
+    }
+
+    /// Lock the vendored example package of a project.
+    fn lock(root: &Path) {
+        let hash = hash_package(&vendor(root)).unwrap();
+        let package = LockedPackage { spec: spec().to_string(), hash };
+        Lockfile {
+            version: Lockfile::VERSION,
+            packages: vec![package],
+        }
+        .write(root)
+        .unwrap();
+    }
+
+    /// Create offline package storage for a project. The default registry of
+    /// the `@preview` namespace is an HTTP registry.
+    fn storage(root: &Path) -> PackageStorage {
+        let args = PackageArgs {
+            package_path: Some(root.join("data")),
+            package_cache_path: Some(root.join("cache")),
+            registries: vec![],
+            offline: true,
+        };
+        PackageStorage::from_args(&args).with_project(root).unwrap()
+    }
+
+    #[test]
+    fn test_hash_package() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = vendor(root.path());
+        let hash = hash_package(&dir).unwrap();
+        assert!(hash.starts_with("sha256:"));
+        assert_eq!(hash.len(), "sha256:".len() + 64);
+
+        // The hash doesn't depend on where the package is.
+        let other = root.path().join("other");
+        fs::create_dir_all(&other).unwrap();
+        fs_extra::dir::copy(&dir, &other, &fs_extra::dir::CopyOptions::new()).unwrap();
+        assert_eq!(hash_package(&other.join("0.1.0")).unwrap(), hash);
+
+        // Moving bytes between the path and the contents changes the hash.
+        fs::remove_file(dir.join("typst.toml")).unwrap();
+        fs::write(dir.join("typst.tom"), "l[package]").unwrap();
+        assert_ne!(hash_package(&dir).unwrap(), hash);
+    }
+
+    #[test]
+    fn test_lockfile_round_trip() {
+        let root = tempfile::tempdir().unwrap();
+        assert!(Lockfile::read(root.path()).unwrap().is_none());
+
+        lock(root.path());
+        let lockfile = Lockfile::read(root.path()).unwrap().unwrap();
+        let hashes = lockfile.hashes().unwrap();
+        assert_eq!(hashes.len(), 1);
+        assert_eq!(hashes[&spec()], hash_package(&vendor(root.path())).unwrap());
+
+        let text = "version = 1\n[[package]]\nspec = \"@preview/example:0.1.0\"\n\
+                    hash = \"0123456789abcdef\"";
+        fs::write(root.path().join(LOCKFILE_NAME), text).unwrap();
+        let lockfile = Lockfile::read(root.path()).unwrap().unwrap();
+        assert_eq!(
+            lockfile.hashes().unwrap_err(),
+            "unsupported hash of @preview/example:0.1.0 in typst.lock, \
+             expected a sha256: hash"
+        );
+    }
+
+    #[test]
+    fn test_offline() {
+        let root = tempfile::tempdir().unwrap();
+        let packages = storage(root.path());
+        assert!(matches!(
+            packages.prepare_package(&spec()),
+            Err(PackageError::NetworkFailed(_))
+        ));
+
+        let requirement = "@preview/example:0.1".parse().unwrap();
+        assert!(matches!(
+            packages.resolve_package(&requirement),
+            Err(PackageError::NetworkFailed(_))
+        ));
+
+        // Locked packages are resolved from the vendor directory.
+        lock(root.path());
+        let packages = storage(root.path());
+        assert_eq!(packages.resolve_package(&requirement).unwrap(), spec());
+        assert_eq!(packages.prepare_package(&spec()).unwrap(), vendor(root.path()));
+    }
+
+    #[test]
+    fn test_hash_mismatch() {
+        let root = tempfile::tempdir().unwrap();
+        lock(root.path());
+        fs::write(vendor(root.path()).join("src/lib.typ"), "#let x = 2").unwrap();
+        assert_eq!(
+            storage(root.path()).prepare_package(&spec()).unwrap_err().to_string(),
+            "failed to load package (files of @preview/example:0.1.0 do not match \
+             the hash in typst.lock)"
+        );
+    }
+
+    #[test]
+    fn test_vendored_without_lockfile() {
+        let root = tempfile::tempdir().unwrap();
+        vendor(root.path());
+        assert_eq!(
+            storage(root.path()).prepare_package(&spec()).unwrap_err().to_string(),
+            "failed to load package (@preview/example:0.1.0 is vendored, but not \
+             locked in typst.lock)"
+        );
+    }
+}
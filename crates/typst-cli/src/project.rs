@@ -0,0 +1,303 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use ecow::eco_format;
+use typst::diag::{bail, StrResult};
+use typst::syntax::package::{ProjectManifest, TargetInfo};
+
+use crate::args::{CompileCommand, Input, Output, OutputFormat, PdfStandard, SharedArgs};
+
+/// The file name of project manifests.
+const MANIFEST_NAME: &str = "typst.toml";
+
+/// Fill in the shared arguments that weren't given on the command line from
+/// the project manifest.
+///
+/// Returns the settings of the selected target and the directory relative to
+/// which its paths are resolved, if there is a manifest.
+pub fn configure(args: &mut SharedArgs) -> StrResult<Option<(TargetInfo, PathBuf)>> {
+    // The manifest that applies to an input file is the closest one above it.
+    let workdir = std::env::current_dir()
+        .map_err(|err| eco_format!("failed to determine working directory ({err})"))?;
+    let start = match &args.input {
+        Some(Input::Path(path)) => {
+            workdir.join(path).parent().map_or(workdir.clone(), Path::to_path_buf)
+        }
+        _ => workdir,
+    };
+
+    let Some((manifest, dir)) = find_manifest(&start)? else {
+        if args.target.is_some() {
+            bail!("cannot select a target without a project manifest");
+        }
+        if args.input.is_none() {
+            bail!("input file is missing and there is no project manifest");
+        }
+        return Ok(None);
+    };
+
+    let target = manifest
+        .target(args.target.as_deref())
+        .map_err(|err| eco_format!("{err} (at {})", dir.join(MANIFEST_NAME).display()))?;
+
+    if args.input.is_none() {
+        let Some(entrypoint) = &target.entrypoint else {
+            bail!(
+                "input file is missing and the project manifest specifies no \
+                 entrypoint (at {})",
+                dir.join(MANIFEST_NAME).display()
+            );
+        };
+        args.input = Some(Input::Path(dir.join(entrypoint.as_str())));
+    }
+
+    if args.root.is_none() {
+        args.root = Some(match &manifest.project.root {
+            Some(root) => dir.join(root.as_str()),
+            None => dir.clone(),
+        });
+    }
+
+    // Font paths given on the command line replace those of the manifest.
+    if args.font_paths.is_empty() {
+        args.font_paths = manifest
+            .project
+            .font_paths
+            .iter()
+            .map(|path| dir.join(path.as_str()))
+            .collect();
+    }
+
+    for (key, value) in &target.inputs {
+        if !args.inputs.iter().any(|(given, _)| given == key.as_str()) {
+            args.inputs.push((key.to_string(), value.to_string()));
+        }
+    }
+
+    Ok(Some((target, dir)))
+}
+
+/// Fill in the arguments of a compilation that weren't given on the command
+/// line from the project manifest.
+pub fn configure_compile(command: &mut CompileCommand) -> StrResult<()> {
+    let Some((target, dir)) = configure(&mut command.common)? else {
+        return Ok(());
+    };
+
+    if command.output.is_none() && command.archive.is_none() {
+        if let Some(output) = &target.output {
+            command.output = Some(Output::Path(dir.join(output.as_str())));
+        }
+    }
+
+    if command.format.is_none() {
+        if let Some(format) = &target.format {
+            let format = OutputFormat::from_str(format, true).map_err(|err| {
+                eco_format!("invalid format in project manifest ({err})")
+            })?;
+            command.format = Some(format);
+        }
+    }
+
+    if command.pdf_standard.is_none() {
+        if let Some(standard) = &target.pdf_standard {
+            let standard = PdfStandard::from_str(standard, true).map_err(|err| {
+                eco_format!("invalid PDF standard in project manifest ({err})")
+            })?;
+            command.pdf_standard = Some(standard);
+        }
+    }
+
+    command.ppi = command.ppi.or(target.ppi);
+
+    Ok(())
+}
+
+/// Find the closest project manifest in a directory or one of its ancestors.
+///
+/// Manifests without a `[project]` section, like those of packages, are
+/// skipped. This includes manifests that can't be parsed, unless they appear
+/// to declare a project.
+fn find_manifest(start: &Path) -> StrResult<Option<(ProjectManifest, PathBuf)>> {
+    for dir in start.ancestors() {
+        let path = dir.join(MANIFEST_NAME);
+        let Ok(text) = fs::read_to_string(&path) else { continue };
+
+        let table: toml::Table = match toml::from_str(&text) {
+            Ok(table) => table,
+            Err(_) if !declares_project(&text) => continue,
+            Err(err) => {
+                bail!("failed to parse project manifest ({err}) (at {})", path.display())
+            }
+        };
+        if !table.contains_key("project") {
+            continue;
+        }
+
+        let manifest: ProjectManifest = toml::from_str(&text).map_err(|err| {
+            eco_format!(
+                "failed to parse project manifest ({err}) (at {})",
+                path.display()
+            )
+        })?;
+        return Ok(Some((manifest, dir.to_path_buf())));
+    }
+
+    Ok(None)
+}
+
+/// Whether the text of a manifest has a `[project]` table header, judging by
+/// its lines alone.
+fn declares_project(text: &str) -> bool {
+    text.lines().map(str::trim_start).any(|line| {
+        line.strip_prefix('[').map(str::trim_start).is_some_and(|rest| {
+            rest.starts_with("project]") || rest.starts_with("project.")
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// The manifest of the tests' project.
+    const MANIFEST: &str = r#"
+[project]
+root = ".."
+font-paths = ["fonts"]
+default-target = "print"
+output = "out.pdf"
+inputs = { mode = "screen", lang = "en" }
+
+[targets.print]
+pdf-standard = "a-2b"
+inputs = { mode = "print" }
+
+[targets.web]
+output = "out-{p}.png"
+ppi = 72
+"#;
+
+    /// Create a project with a main file and the given manifest.
+    fn project(manifest: &str) -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(MANIFEST_NAME), manifest).unwrap();
+        fs::write(dir.path().join("main.typ"), "Hello").unwrap();
+        dir
+    }
+
+    /// Configure a compilation of the project's main file with additional
+    /// command line arguments.
+    fn compile(dir: &TempDir, args: &[&str]) -> StrResult<CompileCommand> {
+        let input = dir.path().join("main.typ");
+        let args = ["compile", input.to_str().unwrap()]
+            .into_iter()
+            .chain(args.iter().copied());
+        let mut command = CompileCommand::try_parse_from(args).unwrap();
+        configure_compile(&mut command)?;
+        Ok(command)
+    }
+
+    /// The path of the command's output file, if any.
+    fn output(command: &CompileCommand) -> Option<PathBuf> {
+        match &command.output {
+            Some(Output::Path(path)) => Some(path.clone()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_manifest_parse() {
+        let dir = project(MANIFEST);
+        let (manifest, found) =
+            find_manifest(&dir.path().join("chapters")).unwrap().unwrap();
+        assert_eq!(found, dir.path());
+        assert_eq!(manifest.project.root.as_deref(), Some(".."));
+        assert_eq!(manifest.project.font_paths, ["fonts"]);
+        assert_eq!(manifest.project.default_target.as_deref(), Some("print"));
+        assert_eq!(manifest.project.settings.output.as_deref(), Some("out.pdf"));
+        assert_eq!(manifest.targets.len(), 2);
+        assert_eq!(manifest.targets["web"].ppi, Some(72.0));
+
+        let dir = project("[project]\nroot = 1");
+        let err = find_manifest(dir.path()).unwrap_err();
+        assert!(err.starts_with("failed to parse project manifest"), "{err}");
+    }
+
+    #[test]
+    fn test_manifest_skipped() {
+        // Package manifests and manifests that can't be parsed, but don't
+        // declare a project, are skipped.
+        let dir = project(MANIFEST);
+        let nested = dir.path().join("nested");
+        fs::create_dir(&nested).unwrap();
+        fs::write(nested.join(MANIFEST_NAME), "[package]\nname = \"example\"").unwrap();
+        let (_, found) = find_manifest(&nested).unwrap().unwrap();
+        assert_eq!(found, dir.path());
+
+        fs::write(nested.join(MANIFEST_NAME), "[package\nname =").unwrap();
+        let (_, found) = find_manifest(&nested).unwrap().unwrap();
+        assert_eq!(found, dir.path());
+
+        fs::write(nested.join(MANIFEST_NAME), "[project]\nroot =").unwrap();
+        assert!(find_manifest(&nested).is_err());
+    }
+
+    #[test]
+    fn test_manifest_targets() {
+        let dir = project(MANIFEST);
+
+        // The default target inherits the project's settings.
+        let command = compile(&dir, &[]).unwrap();
+        assert_eq!(output(&command), Some(dir.path().join("out.pdf")));
+        assert_eq!(command.pdf_standard, Some(PdfStandard::A_2b));
+        assert_eq!(command.common.root, Some(dir.path().join("..")));
+        assert_eq!(command.common.font_paths, [dir.path().join("fonts")]);
+        let mut inputs = command.common.inputs.clone();
+        inputs.sort();
+        assert_eq!(
+            inputs,
+            [("lang".into(), "en".into()), ("mode".into(), "print".into())]
+        );
+
+        // A named target overrides the project's settings.
+        let command = compile(&dir, &["--target", "web"]).unwrap();
+        assert_eq!(output(&command), Some(dir.path().join("out-{p}.png")));
+        assert_eq!(command.pdf_standard, None);
+        assert_eq!(command.ppi, Some(72.0));
+
+        let err = compile(&dir, &["--target", "draft"]).unwrap_err();
+        assert!(err.starts_with("project manifest contains no target `draft`"), "{err}");
+    }
+
+    #[test]
+    fn test_manifest_command_line() {
+        let dir = project(MANIFEST);
+        let command = compile(
+            &dir,
+            &[
+                "out.svg",
+                "--root",
+                "/",
+                "--font-path",
+                "/fonts",
+                "--input",
+                "mode=draft",
+                "--pdf-standard",
+                "1.7",
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(output(&command), Some(PathBuf::from("out.svg")));
+        assert_eq!(command.pdf_standard, Some(PdfStandard::V_1_7));
+        assert_eq!(command.common.root, Some(PathBuf::from("/")));
+        assert_eq!(command.common.font_paths, [PathBuf::from("/fonts")]);
+        assert!(command.common.inputs.contains(&("mode".into(), "draft".into())));
+        assert!(!command.common.inputs.contains(&("mode".into(), "print".into())));
+    }
+}
@@ -0,0 +1,611 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use codespan_reporting::term::{self, termcolor};
+use ecow::{eco_format, EcoString};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use glob::{MatchOptions, Pattern};
+use termcolor::WriteColor;
+use typst::diag::StrResult;
+use typst::eval::Tracer;
+use typst::syntax::is_ident;
+use typst::syntax::package::{PackageManifest, PackageSpec, PackageVersion};
+
+use crate::args::{
+    Input, PackageBundleCommand, PackageCheckCommand, PackageCommand, SharedArgs,
+};
+use crate::compile::print_diagnostics;
+use crate::init::parse_manifest;
+use crate::world::SystemWorld;
+use crate::{print_error, set_failed};
+
+/// The categories packages can be listed in.
+const CATEGORIES: &[&str] = &[
+    "components",
+    "visualization",
+    "model",
+    "layout",
+    "text",
+    "languages",
+    "scripting",
+    "integration",
+    "utility",
+    "fun",
+    "book",
+    "report",
+    "paper",
+    "thesis",
+    "poster",
+    "flyer",
+    "presentation",
+    "cv",
+    "office",
+];
+
+/// The maximum number of categories a package can be listed in.
+const MAX_CATEGORIES: usize = 3;
+
+/// The file formats accepted for template thumbnails.
+const THUMBNAIL_FORMATS: &[&str] = &["png", "webp"];
+
+/// Execute a package command.
+pub fn package(command: &PackageCommand) -> StrResult<()> {
+    match command {
+        PackageCommand::Check(command) => check(command),
+        PackageCommand::Bundle(command) => bundle(command),
+    }
+}
+
+/// Validate a package and compile its entrypoint and template.
+fn check(command: &PackageCheckCommand) -> StrResult<()> {
+    let dir = &command.dir;
+    let manifest = parse_manifest(dir)?;
+    let spec = PackageSpec {
+        namespace: command.namespace.as_str().into(),
+        name: manifest.package.name.clone(),
+        version: manifest.package.version,
+    };
+
+    let problems = validate(&manifest, dir);
+    for problem in &problems {
+        print_error(problem)
+            .map_err(|err| eco_format!("failed to print error ({err})"))?;
+    }
+
+    let mut compiled = true;
+    let entrypoint = dir.join(manifest.package.entrypoint.as_str());
+    if entrypoint.is_file() {
+        compiled &= compile(command, &spec, entrypoint, dir.clone())?;
+    }
+
+    if let Some(template) = &manifest.template {
+        let template_dir = dir.join(template.path.as_str());
+        let entrypoint = template_dir.join(template.entrypoint.as_str());
+        if entrypoint.is_file() {
+            compiled &= compile(command, &spec, entrypoint, template_dir)?;
+        }
+    }
+
+    if !problems.is_empty() || !compiled {
+        set_failed();
+        return Ok(());
+    }
+
+    print_status("checked", &spec.to_string())
+        .map_err(|err| eco_format!("failed to print status ({err})"))
+}
+
+/// Compile a file of the package against the current compiler and print its
+/// diagnostics. Imports of the package itself resolve to its directory.
+///
+/// Returns whether the compilation succeeded.
+fn compile(
+    command: &PackageCheckCommand,
+    spec: &PackageSpec,
+    input: PathBuf,
+    root: PathBuf,
+) -> StrResult<bool> {
+    let args = SharedArgs {
+        input: Some(Input::Path(input)),
+        target: None,
+        root: Some(root),
+        inputs: vec![],
+        font_paths: command.font_paths.clone(),
+        creation_timestamp: None,
+        diagnostic_format: command.diagnostic_format,
+        package: command.package.clone(),
+    };
+
+    let mut world = SystemWorld::new(&args)?;
+    world.provide_package(spec.clone(), command.dir.clone());
+
+    let mut tracer = Tracer::new();
+    let result = typst::compile(&world, &mut tracer);
+    let warnings = tracer.warnings();
+    let errors = match &result {
+        Ok(_) => &[][..],
+        Err(errors) => errors.as_slice(),
+    };
+
+    print_diagnostics(&world, errors, &warnings, command.diagnostic_format)
+        .map_err(|err| eco_format!("failed to print diagnostics ({err})"))?;
+
+    Ok(result.is_ok())
+}
+
+/// Check the manifest against the requirements for published packages.
+fn validate(manifest: &PackageManifest, dir: &Path) -> Vec<EcoString> {
+    let mut problems = vec![];
+    let package = &manifest.package;
+
+    if !is_ident(&package.name)
+        || package.name.chars().any(|c| c.is_ascii_uppercase() || c == '_')
+    {
+        problems.push(eco_format!(
+            "package name `{}` must be lowercase and may only contain letters, \
+             digits, and hyphens",
+            package.name
+        ));
+    }
+
+    if !dir.join(package.entrypoint.as_str()).is_file() {
+        problems.push(eco_format!("entrypoint `{}` does not exist", package.entrypoint));
+    }
+
+    if let Some(required) = package.compiler {
+        let current = PackageVersion::compiler();
+        if current < required {
+            problems.push(eco_format!(
+                "package requires typst {required}, but the current version is \
+                 {current}"
+            ));
+        }
+    }
+
+    if package.authors.is_empty() {
+        problems.push("package has no authors".into());
+    }
+    for author in &package.authors {
+        if let Err(message) = validate_author(author) {
+            problems.push(eco_format!("invalid author `{author}` ({message})"));
+        }
+    }
+
+    match &package.license {
+        Some(license) => {
+            if let Err(message) = validate_license(license) {
+                problems.push(eco_format!("invalid license `{license}` ({message})"));
+            }
+        }
+        None => problems.push("package has no license".into()),
+    }
+
+    if package.description.as_ref().map_or(true, |d| d.trim().is_empty()) {
+        problems.push("package has no description".into());
+    }
+
+    for (key, link) in
+        [("repository", &package.repository), ("homepage", &package.homepage)]
+    {
+        if let Some(link) = link {
+            if !link.starts_with("https://") && !link.starts_with("http://") {
+                problems.push(eco_format!("{key} `{link}` is not an HTTP(S) link"));
+            }
+        }
+    }
+
+    for category in &package.categories {
+        if !CATEGORIES.contains(&category.as_str()) {
+            problems.push(eco_format!("unknown category `{category}`"));
+        }
+    }
+    if package.categories.len() > MAX_CATEGORIES {
+        problems.push(eco_format!(
+            "package is listed in {} categories, but at most {MAX_CATEGORIES} are \
+             allowed",
+            package.categories.len()
+        ));
+    }
+
+    for pattern in &package.exclude {
+        if let Err(message) = validate_exclude(pattern) {
+            problems.push(eco_format!("invalid exclude pattern `{pattern}` ({message})"));
+        }
+    }
+
+    let mut required = vec![EcoString::from("typst.toml"), package.entrypoint.clone()];
+
+    if let Some(template) = &manifest.template {
+        let template_dir = dir.join(template.path.as_str());
+        if !template_dir.is_dir() {
+            problems.push(eco_format!(
+                "template directory `{}` does not exist",
+                template.path
+            ));
+        } else if !template_dir.join(template.entrypoint.as_str()).is_file() {
+            problems.push(eco_format!(
+                "template entrypoint `{}` does not exist in `{}`",
+                template.entrypoint,
+                template.path
+            ));
+        }
+        required.push(eco_format!("{}/{}", template.path, template.entrypoint));
+
+        match &template.thumbnail {
+            Some(thumbnail) => {
+                let extension = Path::new(thumbnail.as_str())
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().to_lowercase());
+                if !extension.is_some_and(|ext| THUMBNAIL_FORMATS.contains(&ext.as_str()))
+                {
+                    problems.push(eco_format!(
+                        "thumbnail `{thumbnail}` must be a PNG or WebP image"
+                    ));
+                }
+                if !dir.join(thumbnail.as_str()).is_file() {
+                    problems.push(eco_format!("thumbnail `{thumbnail}` does not exist"));
+                }
+            }
+            None => problems.push("template has no thumbnail".into()),
+        }
+    }
+
+    for path in &required {
+        let path = normalize(path);
+        if is_excluded(&package.exclude, &path) {
+            problems.push(eco_format!("`{path}` is required, but excluded"));
+        }
+    }
+
+    problems
+}
+
+/// Check that an author is a name, optionally followed by a contact in angle
+/// brackets.
+fn validate_author(author: &str) -> Result<(), &'static str> {
+    let (name, contact) = match author.split_once('<') {
+        Some((name, rest)) => match rest.strip_suffix('>') {
+            Some(contact) => (name, Some(contact)),
+            None => return Err("contact must be enclosed in angle brackets"),
+        },
+        None => (author, None),
+    };
+
+    if name.trim().is_empty() {
+        return Err("name is empty");
+    }
+    if name.contains('>') {
+        return Err("unbalanced angle brackets");
+    }
+    if let Some(contact) = contact {
+        if contact.trim().is_empty() || contact.contains(['<', '>']) {
+            return Err("contact is malformed");
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that a license is a valid SPDX expression.
+fn validate_license(expression: &str) -> StrResult<()> {
+    spdx::Expression::parse(expression)
+        .map(|_| ())
+        .map_err(|err| eco_format!("{}", err.reason))
+}
+
+/// Check that an exclude pattern is a valid glob that stays within the
+/// package.
+fn validate_exclude(pattern: &str) -> Result<(), &'static str> {
+    if pattern.trim().is_empty() {
+        return Err("pattern is empty");
+    }
+    if pattern.contains('\\') {
+        return Err("paths must be separated by forward slashes");
+    }
+    if pattern.split('/').any(|segment| segment == "..") {
+        return Err("pattern must not leave the package");
+    }
+    Pattern::new(&normalize(pattern)).map_err(|err| err.msg)?;
+    Ok(())
+}
+
+/// Turn a relative path from the manifest into the form that exclude
+/// patterns are matched against.
+fn normalize(path: &str) -> String {
+    path.split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Whether a path relative to the package root is excluded.
+///
+/// Patterns starting with or containing a slash are matched against the full
+/// path, other patterns against each file and directory name. Patterns that
+/// match a directory exclude everything in it. Patterns are globs: `*` and `?`
+/// match within a name and `**` matches any number of directories.
+fn is_excluded(patterns: &[EcoString], path: &str) -> bool {
+    let options = MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+
+    let segments: Vec<&str> = path.split('/').collect();
+    patterns.iter().any(|pattern| {
+        let anchored = pattern.trim_end_matches('/').contains('/');
+        let Ok(pattern) = Pattern::new(&normalize(pattern)) else { return false };
+        (1..=segments.len()).any(|end| {
+            if anchored {
+                pattern.matches_with(&segments[..end].join("/"), options)
+            } else {
+                pattern.matches_with(segments[end - 1], options)
+            }
+        })
+    })
+}
+
+/// Bundle a package into a gzipped tarball.
+fn bundle(command: &PackageBundleCommand) -> StrResult<()> {
+    let dir = &command.dir;
+    let manifest = parse_manifest(dir)?;
+    let package = &manifest.package;
+
+    let output = command.output.clone().unwrap_or_else(|| {
+        PathBuf::from(format!("{}-{}.tar.gz", package.name, package.version))
+    });
+
+    // Don't include a previous bundle that lies within the package.
+    let previous = output.canonicalize().ok();
+
+    let mut files = vec![];
+    collect_files(dir, "", &package.exclude, previous.as_deref(), &mut files)
+        .map_err(|err| eco_format!("failed to read package ({err})"))?;
+    files.sort();
+
+    let data = archive(&files)?;
+    fs::write(&output, data)
+        .map_err(|err| eco_format!("failed to write {} ({err})", output.display()))?;
+
+    let s = if files.len() == 1 { "" } else { "s" };
+    print_status("bundled", &format!("{} file{s} into {}", files.len(), output.display()))
+        .map_err(|err| eco_format!("failed to print status ({err})"))
+}
+
+/// Pack files into a gzipped tarball.
+///
+/// The headers are normalized, so that bundling the same files always yields
+/// the same archive.
+fn archive(files: &[(String, PathBuf)]) -> StrResult<Vec<u8>> {
+    let encoder = GzEncoder::new(vec![], Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    for (name, path) in files {
+        let data =
+            fs::read(path).map_err(|err| eco_format!("failed to read {name} ({err})"))?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(0);
+        header.set_uid(0);
+        header.set_gid(0);
+        builder
+            .append_data(&mut header, name, data.as_slice())
+            .map_err(|err| eco_format!("failed to add {name} to archive ({err})"))?;
+    }
+
+    builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(|err| eco_format!("failed to create archive ({err})"))
+}
+
+/// Collect the files of a package that are not excluded or hidden, with
+/// their paths relative to the package root.
+fn collect_files(
+    dir: &Path,
+    prefix: &str,
+    exclude: &[EcoString],
+    skip: Option<&Path>,
+    files: &mut Vec<(String, PathBuf)>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().map(|name| name.to_string_lossy()) else {
+            continue;
+        };
+
+        let relative =
+            if prefix.is_empty() { name.to_string() } else { format!("{prefix}/{name}") };
+        if name.starts_with('.') || is_excluded(exclude, &relative) {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect_files(&path, &relative, exclude, skip, files)?;
+        } else if skip
+            .map_or(true, |skip| path.canonicalize().ok().as_deref() != Some(skip))
+        {
+            files.push((relative, path));
+        }
+    }
+    Ok(())
+}
+
+/// Print a status line with a highlighted verb.
+fn print_status(verb: &str, message: &str) -> io::Result<()> {
+    let styles = term::Styles::default();
+
+    let mut out = crate::terminal::out();
+    out.set_color(&styles.header_help)?;
+    write!(out, "{verb}")?;
+    out.reset()?;
+    writeln!(out, " {message}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    /// Create an example package with files that are hidden or excluded.
+    fn package(root: &Path) -> PathBuf {
+        let dir = root.join("example");
+        for sub in ["src", "docs", "tests", ".git"] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        let manifest = "[package]\n\
+            name = \"example\"\n\
+            version = \"0.1.0\"\n\
+            entrypoint = \"src/lib.typ\"\n\
+            exclude = [\"docs/*.pdf\", \"tests\", \"*.bak\"]\n";
+        fs::write(dir.join("typst.toml"), manifest).unwrap();
+        for file in [
+            "src/lib.typ",
+            "src/old.bak",
+            "docs/guide.md",
+            "docs/manual.pdf",
+            "tests/test.typ",
+            ".git/HEAD",
+            ".hidden",
+        ] {
+            fs::write(dir.join(file), file).unwrap();
+        }
+        dir
+    }
+
+    /// The files that would be bundled, in order.
+    fn files(dir: &Path, skip: Option<&Path>) -> Vec<(String, PathBuf)> {
+        let manifest = parse_manifest(dir).unwrap();
+        let mut files = vec![];
+        collect_files(dir, "", &manifest.package.exclude, skip, &mut files).unwrap();
+        files.sort();
+        files
+    }
+
+    /// The names of the files that would be bundled, in order.
+    fn names(dir: &Path, skip: Option<&Path>) -> Vec<String> {
+        files(dir, skip).into_iter().map(|(name, _)| name).collect()
+    }
+
+    /// The entries of an archive with their modification time, owner and data.
+    fn entries(data: &[u8]) -> Vec<(String, u64, u64, u64, String)> {
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(data));
+        let mut entries = vec![];
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let header = entry.header().clone();
+            let mut data = String::new();
+            entry.read_to_string(&mut data).unwrap();
+            entries.push((
+                entry.path().unwrap().to_string_lossy().into_owned(),
+                header.mtime().unwrap(),
+                header.uid().unwrap(),
+                header.gid().unwrap(),
+                data,
+            ));
+        }
+        entries
+    }
+
+    #[test]
+    fn test_validate_license() {
+        for valid in [
+            "MIT",
+            "WTFPL",
+            "MIT OR Apache-2.0",
+            "(MIT OR Apache-2.0) AND BSD-3-Clause",
+            "Apache-2.0 WITH LLVM-exception",
+            "GPL-3.0-or-later",
+            "LicenseRef-Custom",
+        ] {
+            assert_eq!(validate_license(valid), Ok(()), "{valid}");
+        }
+
+        assert_eq!(validate_license("Unknown-1.0").unwrap_err(), "unknown term");
+        for invalid in ["", "MIT AND", "(MIT", "MIT OR OR Apache-2.0", "Foo WITH"] {
+            assert!(validate_license(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_validate_exclude() {
+        for valid in ["*.pdf", "docs/", "/tests", "src/**/*.bak", "./a/b", "[ab].typ"] {
+            assert_eq!(validate_exclude(valid), Ok(()), "{valid}");
+        }
+
+        assert_eq!(validate_exclude(" "), Err("pattern is empty"));
+        assert_eq!(
+            validate_exclude("docs\\*.pdf"),
+            Err("paths must be separated by forward slashes")
+        );
+        assert_eq!(validate_exclude("../x"), Err("pattern must not leave the package"));
+        assert!(validate_exclude("a**/b").is_err());
+        assert!(validate_exclude("[ab").is_err());
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        let excluded = |pattern: &str, path: &str| is_excluded(&[pattern.into()], path);
+
+        // Names match anywhere.
+        assert!(excluded("*.pdf", "manual.pdf"));
+        assert!(excluded("*.pdf", "docs/manual.pdf"));
+        assert!(!excluded("*.pdf", "docs/manual.pdf.md"));
+        assert!(excluded("tests", "tests/a/b.typ"));
+        assert!(excluded("tests/", "src/tests/b.typ"));
+        assert!(!excluded("test", "tests/b.typ"));
+        assert!(!excluded("*.PDF", "manual.pdf"));
+
+        // Paths are anchored at the package root.
+        assert!(excluded("docs/*.pdf", "docs/manual.pdf"));
+        assert!(excluded("/docs", "docs/manual.pdf"));
+        assert!(excluded("./docs", "docs/images/a.png"));
+        assert!(!excluded("docs/*.pdf", "src/docs/manual.pdf"));
+        assert!(!excluded("docs/*.pdf", "docs/old/manual.pdf"));
+        assert!(!excluded("/docs", "src/docs/manual.pdf"));
+
+        // Double stars match any number of directories.
+        assert!(excluded("docs/**/*.pdf", "docs/manual.pdf"));
+        assert!(excluded("docs/**/*.pdf", "docs/old/v1/manual.pdf"));
+        assert!(excluded("**/build", "src/build/out.typ"));
+        assert!(!excluded("docs/**/*.pdf", "src/manual.pdf"));
+    }
+
+    #[test]
+    fn test_collect_files() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = package(root.path());
+        assert_eq!(names(&dir, None), ["docs/guide.md", "src/lib.typ", "typst.toml"]);
+
+        // A previous bundle within the package is skipped.
+        let bundle = dir.join("example-0.1.0.tar.gz");
+        fs::write(&bundle, "").unwrap();
+        assert_eq!(names(&dir, None).len(), 4);
+        assert_eq!(names(&dir, Some(&bundle.canonicalize().unwrap())).len(), 3);
+    }
+
+    #[test]
+    fn test_archive_reproducible() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = package(root.path());
+        let first = archive(&files(&dir, None)).unwrap();
+        let entry = |name: &str, data: &str| (name.into(), 0, 0, 0, data.into());
+        let manifest = fs::read_to_string(dir.join("typst.toml")).unwrap();
+        assert_eq!(
+            entries(&first),
+            [
+                entry("docs/guide.md", "docs/guide.md"),
+                entry("src/lib.typ", "src/lib.typ"),
+                entry("typst.toml", &manifest),
+            ]
+        );
+
+        // Rewriting a file changes its modification time, but not the archive.
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        fs::write(dir.join("src/lib.typ"), "src/lib.typ").unwrap();
+        assert_eq!(archive(&files(&dir, None)).unwrap(), first);
+    }
+}
@@ -3,7 +3,7 @@ use ecow::{eco_format, EcoString};
 use serde::Serialize;
 use typst::diag::{bail, StrResult};
 use typst::eval::{eval_string, EvalMode, Tracer};
-use typst::foundations::{Content, IntoValue, LocatableSelector, Scope};
+use typst::foundations::{Content, IntoValue, LocatableSelector, Scope, Value};
 use typst::model::Document;
 use typst::syntax::Span;
 use typst::World;
@@ -15,6 +15,10 @@ use crate::world::SystemWorld;
 
 /// Execute a query command.
 pub fn query(command: &QueryCommand) -> StrResult<()> {
+    let mut command = command.clone();
+    crate::project::configure(&mut command.common)?;
+    let command = &command;
+
     let mut world = SystemWorld::new(&command.common)?;
 
     // Reset everything and ensure that the main file is present.
@@ -52,7 +56,7 @@ pub fn query(command: &QueryCommand) -> StrResult<()> {
 }
 
 /// Retrieve the matches for the selector.
-fn retrieve(
+pub fn retrieve(
     world: &dyn World,
     command: &QueryCommand,
     document: &Document,
@@ -83,6 +87,11 @@ fn retrieve(
 
 /// Format the query result in the output format.
 fn format(elements: Vec<Content>, command: &QueryCommand) -> StrResult<String> {
+    serialize(&extract(elements, command)?, command.format)
+}
+
+/// Extract the requested fields from the matched elements.
+pub fn extract(elements: Vec<Content>, command: &QueryCommand) -> StrResult<Value> {
     if command.one && elements.len() != 1 {
         bail!("expected exactly one element, found {}", elements.len());
     }
@@ -96,12 +105,12 @@ fn format(elements: Vec<Content>, command: &QueryCommand) -> StrResult<String> {
         .collect();
 
     if command.one {
-        let Some(value) = mapped.first() else {
+        let Some(value) = mapped.into_iter().next() else {
             bail!("no such field found for element");
         };
-        serialize(value, command.format)
+        Ok(value)
     } else {
-        serialize(&mapped, command.format)
+        Ok(mapped.into_value())
     }
 }
 
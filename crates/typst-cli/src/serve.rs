@@ -0,0 +1,660 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Instant;
+
+use clap::ValueEnum;
+use codespan_reporting::files::Files;
+use ecow::{eco_format, EcoString};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use typst::diag::{bail, At, Severity, SourceDiagnostic, StrResult};
+use typst::eval::Tracer;
+use typst::syntax::Span;
+use typst::{World, WorldExt};
+
+use crate::args::{
+    CompileCommand, Input, Output, OutputFormat, PageRangeArgument, PdfStandard,
+    QueryCommand, SerializationFormat, ServeCommand,
+};
+use crate::compile::export;
+use crate::query;
+use crate::world::SystemWorld;
+
+/// The JSON-RPC error code for messages that aren't valid JSON.
+const PARSE_ERROR: i64 = -32700;
+/// The JSON-RPC error code for messages that aren't valid requests.
+const INVALID_REQUEST: i64 = -32600;
+/// The JSON-RPC error code for unknown methods.
+const METHOD_NOT_FOUND: i64 = -32601;
+/// The JSON-RPC error code for invalid method parameters.
+const INVALID_PARAMS: i64 = -32602;
+/// The JSON-RPC error code for requests that failed on the server.
+const SERVER_ERROR: i64 = -32000;
+
+/// Execute a serve command.
+pub fn serve(command: &ServeCommand) -> StrResult<()> {
+    let mut command = command.clone();
+    crate::project::configure(&mut command.common)?;
+    let command = &command;
+
+    if command.socket.is_none() && matches!(command.common.input, Some(Input::Stdin)) {
+        bail!("cannot read the input from stdin while serving requests over stdin");
+    }
+
+    let mut server = Server {
+        world: SystemWorld::new(&command.common)?,
+        command: command.clone(),
+        shutdown: false,
+    };
+
+    match &command.socket {
+        Some(path) => serve_socket(&mut server, path),
+        None => {
+            let stdin = io::stdin();
+            server
+                .handle_stream(stdin.lock(), io::stdout())
+                .map_err(|err| eco_format!("failed to communicate over stdio ({err})"))
+        }
+    }
+}
+
+/// Accept connections on a Unix socket, one after another.
+#[cfg(unix)]
+fn serve_socket(server: &mut Server, path: &Path) -> StrResult<()> {
+    use std::os::unix::net::UnixListener;
+
+    remove_stale_socket(path)?;
+    let listener = UnixListener::bind(path)
+        .map_err(|err| eco_format!("failed to bind socket ({err})"))?;
+    let socket = socket_id(path);
+
+    let result = (|| {
+        for stream in listener.incoming() {
+            let stream = stream
+                .map_err(|err| eco_format!("failed to accept connection ({err})"))?;
+            let reader = stream
+                .try_clone()
+                .map_err(|err| eco_format!("failed to accept connection ({err})"))?;
+
+            // A broken connection should not bring down the server.
+            server.handle_stream(BufReader::new(reader), stream).ok();
+            if server.shutdown {
+                break;
+            }
+        }
+        Ok(())
+    })();
+
+    // Only remove the socket if it wasn't replaced in the meantime.
+    if socket.is_some() && socket_id(path) == socket {
+        std::fs::remove_file(path).ok();
+    }
+
+    result
+}
+
+/// Remove a socket left behind by an earlier run.
+///
+/// Fails if something other than a socket exists at the path, so that we
+/// don't delete a user's file, or if a server is still listening on the
+/// socket, so that we don't take over its path.
+#[cfg(unix)]
+fn remove_stale_socket(path: &Path) -> StrResult<()> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixStream;
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            match UnixStream::connect(path) {
+                Ok(_) => bail!("a server is already listening on {}", path.display()),
+                Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => {}
+                Err(err) => bail!("failed to check existing socket ({err})"),
+            }
+            std::fs::remove_file(path)
+                .map_err(|err| eco_format!("failed to remove existing socket ({err})"))
+        }
+        Ok(_) => bail!("{} already exists and is not a socket", path.display()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => bail!("failed to access socket path ({err})"),
+    }
+}
+
+/// Identify the socket at a path by its device and inode numbers.
+///
+/// Returns `None` if there is no socket at the path.
+#[cfg(unix)]
+fn socket_id(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+    let metadata = std::fs::symlink_metadata(path).ok()?;
+    metadata
+        .file_type()
+        .is_socket()
+        .then(|| (metadata.dev(), metadata.ino()))
+}
+
+/// Unix sockets are not available on this platform.
+#[cfg(not(unix))]
+fn serve_socket(_: &mut Server, _: &Path) -> StrResult<()> {
+    bail!("serving over a socket is only supported on Unix")
+}
+
+/// Keeps the world and, implicitly, the memoization cache alive between
+/// requests.
+struct Server {
+    /// The world that serves sources, files, and fonts.
+    world: SystemWorld,
+    /// The command the server was started with.
+    command: ServeCommand,
+    /// Whether a shutdown was requested.
+    shutdown: bool,
+}
+
+impl Server {
+    /// Handle newline-delimited requests from a stream until it ends or a
+    /// shutdown is requested.
+    fn handle_stream(
+        &mut self,
+        reader: impl BufRead,
+        mut writer: impl Write,
+    ) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if let Some(response) = self.handle_message(&line) {
+                serde_json::to_writer(&mut writer, &response)?;
+                writeln!(writer)?;
+                writer.flush()?;
+            }
+
+            if self.shutdown {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle a single JSON-RPC message, returning the response if the
+    /// message was not a notification.
+    fn handle_message(&mut self, message: &str) -> Option<JsonValue> {
+        let request: Request = match serde_json::from_str(message) {
+            Ok(request) => request,
+            Err(err) if err.is_syntax() || err.is_eof() => {
+                return Some(error(JsonValue::Null, PARSE_ERROR, eco_format!("{err}")));
+            }
+            Err(err) => {
+                return Some(error(
+                    JsonValue::Null,
+                    INVALID_REQUEST,
+                    eco_format!("{err}"),
+                ));
+            }
+        };
+
+        let result = match request.method.as_str() {
+            "compile" => parse_params(request.params).and_then(|params| {
+                self.compile(params).map_err(|msg| (SERVER_ERROR, msg))
+            }),
+            "query" => parse_params(request.params)
+                .and_then(|params| self.query(params).map_err(|msg| (SERVER_ERROR, msg))),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(JsonValue::Null)
+            }
+            method => Err((METHOD_NOT_FOUND, eco_format!("unknown method `{method}`"))),
+        };
+
+        let id = request.id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error(id, code, message),
+        })
+    }
+
+    /// Compile the document and export it.
+    fn compile(&mut self, params: CompileParams) -> StrResult<JsonValue> {
+        let command = params.to_command(&self.command)?;
+        if matches!(command.output(), Output::Stdout) {
+            bail!("cannot write output to stdout while serving requests");
+        }
+
+        let start = Instant::now();
+        self.world.reset();
+        self.world.set_tagging(command.pdf_tags);
+
+        let mut tracer = Tracer::new();
+        let mut outputs = vec![];
+        let result = World::source(&self.world, self.world.main())
+            .at(Span::detached())
+            .and_then(|_| typst::compile(&self.world, &mut tracer))
+            .and_then(|document| {
+                export(&mut self.world, &mut tracer, &document, &command, false)?;
+                outputs = command.output_paths(&document).at(Span::detached())?;
+                Ok(())
+            });
+
+        if command.lint {
+            typst_ide::lint(&self.world, &mut tracer);
+        }
+
+        comemo::evict(10);
+
+        let warnings = tracer.warnings();
+        let errors = result.err().unwrap_or_default();
+        Ok(json!({
+            "success": errors.is_empty(),
+            "duration": start.elapsed().as_secs_f64(),
+            "outputs": outputs,
+            "diagnostics": self.diagnostics(&errors, &warnings),
+        }))
+    }
+
+    /// Compile the document and query it for elements.
+    fn query(&mut self, params: QueryParams) -> StrResult<JsonValue> {
+        let command = QueryCommand {
+            common: self.command.common.clone(),
+            selector: params.selector,
+            field: params.field,
+            one: params.one,
+            format: SerializationFormat::Json,
+        };
+
+        self.world.reset();
+
+        let mut tracer = Tracer::new();
+        let result = World::source(&self.world, self.world.main())
+            .at(Span::detached())
+            .and_then(|_| typst::compile(&self.world, &mut tracer));
+
+        comemo::evict(10);
+
+        let warnings = tracer.warnings();
+        let (value, errors) = match result {
+            Ok(document) => {
+                let elements = query::retrieve(&self.world, &command, &document)?;
+                let value = query::extract(elements, &command)?;
+                (serde_json::to_value(value).map_err(|err| eco_format!("{err}"))?, vec![])
+            }
+            Err(errors) => (JsonValue::Null, errors.to_vec()),
+        };
+
+        Ok(json!({
+            "success": errors.is_empty(),
+            "value": value,
+            "diagnostics": self.diagnostics(&errors, &warnings),
+        }))
+    }
+
+    /// Convert diagnostics into a serializable form.
+    fn diagnostics(
+        &self,
+        errors: &[SourceDiagnostic],
+        warnings: &[SourceDiagnostic],
+    ) -> Vec<DiagnosticInfo> {
+        warnings
+            .iter()
+            .chain(errors)
+            .map(|diagnostic| DiagnosticInfo {
+                severity: match diagnostic.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                },
+                message: diagnostic.message.clone(),
+                hints: diagnostic.hints.to_vec(),
+                location: self.locate(diagnostic.span),
+                trace: diagnostic
+                    .trace
+                    .iter()
+                    .map(|point| TracepointInfo {
+                        message: point.v.to_string(),
+                        location: self.locate(point.span),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Find the file and range of a span.
+    fn locate(&self, span: Span) -> Option<LocationInfo> {
+        let id = span.id()?;
+        let range = self.world.range(span)?;
+        let source = World::source(&self.world, id).ok()?;
+        let position = |offset| {
+            Some(PositionInfo {
+                line: source.byte_to_line(offset)?,
+                column: source.byte_to_column(offset)?,
+            })
+        };
+
+        Some(LocationInfo {
+            file: Files::name(&self.world, id).ok()?,
+            start: position(range.start)?,
+            end: position(range.end)?,
+        })
+    }
+}
+
+/// Create a JSON-RPC error response.
+fn error(id: JsonValue, code: i64, message: EcoString) -> JsonValue {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+/// Parse the parameters of a request.
+fn parse_params<T: for<'de> Deserialize<'de>>(
+    params: Option<JsonValue>,
+) -> Result<T, (i64, EcoString)> {
+    serde_json::from_value(params.unwrap_or_else(|| json!({})))
+        .map_err(|err| (INVALID_PARAMS, eco_format!("invalid parameters ({err})")))
+}
+
+/// A JSON-RPC request or notification.
+#[derive(Deserialize)]
+struct Request {
+    /// The request's ID. Notifications don't have one.
+    id: Option<JsonValue>,
+    /// The name of the method to call.
+    method: String,
+    /// The method's parameters.
+    params: Option<JsonValue>,
+}
+
+/// Parameters of the `compile` method.
+///
+/// These mirror the arguments of `typst compile`.
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+struct CompileParams {
+    output: Option<PathBuf>,
+    format: Option<String>,
+    pages: Option<String>,
+    ppi: Option<f32>,
+    pdf_standard: Option<String>,
+    pdf_tags: bool,
+    archive: Option<PathBuf>,
+    lint: bool,
+}
+
+impl CompileParams {
+    /// Create the compile command that corresponds to the parameters.
+    fn to_command(&self, serve: &ServeCommand) -> StrResult<CompileCommand> {
+        let format = self
+            .format
+            .as_deref()
+            .map(|format| OutputFormat::from_str(format, true))
+            .transpose()
+            .map_err(|err| eco_format!("invalid format ({err})"))?;
+
+        let pdf_standard = self
+            .pdf_standard
+            .as_deref()
+            .map(|standard| PdfStandard::from_str(standard, true))
+            .transpose()
+            .map_err(|err| eco_format!("invalid PDF standard ({err})"))?;
+
+        let pages = self
+            .pages
+            .as_deref()
+            .map(|pages| pages.split(',').map(PageRangeArgument::from_str).collect())
+            .transpose()
+            .map_err(|err| eco_format!("invalid page range ({err})"))?;
+
+        if self.output.is_some() && self.archive.is_some() {
+            bail!("`output` and `archive` cannot be used together");
+        }
+
+        Ok(CompileCommand {
+            common: serve.common.clone(),
+            output: self.output.clone().map(Output::Path),
+            pages,
+            make_deps: None,
+            format,
+            archive: self.archive.clone(),
+            open: None,
+            pdf_standard,
+            pdf_tags: self.pdf_tags,
+            ppi: self.ppi,
+            timings: None,
+            lint: self.lint,
+        })
+    }
+}
+
+/// Parameters of the `query` method.
+///
+/// These mirror the arguments of `typst query`.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct QueryParams {
+    selector: String,
+    #[serde(default)]
+    field: Option<String>,
+    #[serde(default)]
+    one: bool,
+}
+
+/// A diagnostic in a response.
+#[derive(Serialize)]
+struct DiagnosticInfo {
+    severity: &'static str,
+    message: EcoString,
+    hints: Vec<EcoString>,
+    location: Option<LocationInfo>,
+    trace: Vec<TracepointInfo>,
+}
+
+/// A step in the trace of a diagnostic.
+#[derive(Serialize)]
+struct TracepointInfo {
+    message: String,
+    location: Option<LocationInfo>,
+}
+
+/// The file and range a diagnostic refers to.
+#[derive(Serialize)]
+struct LocationInfo {
+    file: String,
+    start: PositionInfo,
+    end: PositionInfo,
+}
+
+/// A zero-based line and column in a file.
+#[derive(Serialize)]
+struct PositionInfo {
+    line: usize,
+    column: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    /// Create a server for a document in the given directory.
+    fn server(dir: &Path, text: &str) -> Server {
+        let input = dir.join("main.typ");
+        std::fs::write(&input, text).unwrap();
+        let args =
+            ["serve".as_ref(), input.as_os_str(), "--root".as_ref(), dir.as_os_str()];
+        let command = ServeCommand::try_parse_from(args).unwrap();
+        let world = SystemWorld::new(&command.common).unwrap();
+        Server { world, command, shutdown: false }
+    }
+
+    /// Send messages to a server and return the parsed responses.
+    fn send(server: &mut Server, messages: &[&str]) -> Vec<JsonValue> {
+        let input: String =
+            messages.iter().map(|message| format!("{message}\n")).collect();
+        let mut output = vec![];
+        server.handle_stream(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_serve_compile() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut server = server(dir.path(), "Hello");
+        let output = dir.path().join("main.pdf");
+        let compile = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "compile",
+            "params": { "output": output },
+        });
+
+        let responses = send(
+            &mut server,
+            &[
+                &compile.to_string(),
+                r#"{"jsonrpc": "2.0", "id": 2, "method": "shutdown"}"#,
+                r#"{"jsonrpc": "2.0", "id": 3, "method": "shutdown"}"#,
+            ],
+        );
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["result"]["success"], true);
+        assert_eq!(responses[0]["result"]["outputs"], json!([output]));
+        assert!(output.exists());
+        assert_eq!(responses[1], json!({ "jsonrpc": "2.0", "id": 2, "result": null }));
+        assert!(server.shutdown);
+    }
+
+    #[test]
+    fn test_serve_diagnostics() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut server = server(dir.path(), "#foo");
+        let output = dir.path().join("main.pdf");
+        let compile = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "compile",
+            "params": { "output": output },
+        });
+
+        let responses = send(&mut server, &[&compile.to_string()]);
+        let result = &responses[0]["result"];
+        assert_eq!(result["success"], false);
+        assert_eq!(result["outputs"], json!([]));
+
+        let diagnostic = &result["diagnostics"][0];
+        assert_eq!(diagnostic["severity"], "error");
+        assert_eq!(diagnostic["message"], "unknown variable: foo");
+        assert_eq!(diagnostic["location"]["start"], json!({ "line": 0, "column": 1 }));
+        assert_eq!(diagnostic["location"]["end"], json!({ "line": 0, "column": 4 }));
+        assert!(!output.exists());
+    }
+
+    #[test]
+    fn test_serve_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut server = server(dir.path(), "Hello");
+        let responses = send(
+            &mut server,
+            &[
+                "{",
+                r#"{"jsonrpc": "2.0", "id": 1}"#,
+                r#"{"jsonrpc": "2.0", "id": 2, "method": "frobnicate"}"#,
+                r#"{"jsonrpc": "2.0", "id": 3, "method": "compile", "params": {"x": 1}}"#,
+                r#"{"jsonrpc": "2.0", "method": "frobnicate"}"#,
+            ],
+        );
+
+        let codes: Vec<_> = responses
+            .iter()
+            .map(|response| (response["id"].clone(), response["error"]["code"].clone()))
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                (JsonValue::Null, json!(PARSE_ERROR)),
+                (JsonValue::Null, json!(INVALID_REQUEST)),
+                (json!(2), json!(METHOD_NOT_FOUND)),
+                (json!(3), json!(INVALID_PARAMS)),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remove_stale_socket() {
+        use std::os::unix::net::UnixListener;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("typst.sock");
+
+        // Nothing to remove.
+        remove_stale_socket(&path).unwrap();
+
+        // A socket from an earlier run is removed.
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(socket_id(&path).is_some());
+        remove_stale_socket(&path).unwrap();
+        assert!(!path.exists());
+
+        // A socket that a server is still listening on is kept.
+        let listener = UnixListener::bind(&path).unwrap();
+        let err = remove_stale_socket(&path).unwrap_err();
+        assert!(err.starts_with("a server is already listening on"), "{err}");
+        assert!(socket_id(&path).is_some());
+        drop(listener);
+        std::fs::remove_file(&path).unwrap();
+
+        // Other files are kept.
+        std::fs::write(&path, "data").unwrap();
+        assert_eq!(socket_id(&path), None);
+        let err = remove_stale_socket(&path).unwrap_err();
+        assert!(err.ends_with("already exists and is not a socket"), "{err}");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "data");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_serve_socket() {
+        use std::os::unix::net::UnixStream;
+        use std::time::Duration;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("typst.sock");
+        let handle = std::thread::spawn({
+            let dir = dir.path().to_path_buf();
+            let path = path.clone();
+            move || serve_socket(&mut server(&dir, "Hello"), &path)
+        });
+
+        let stream = loop {
+            match UnixStream::connect(&path) {
+                Ok(stream) => break stream,
+                Err(_) if !handle.is_finished() => {
+                    std::thread::sleep(Duration::from_millis(10))
+                }
+                Err(err) => panic!("failed to connect ({err})"),
+            }
+        };
+
+        let mut writer = stream.try_clone().unwrap();
+        writeln!(writer, r#"{{"jsonrpc": "2.0", "id": 1, "method": "shutdown"}}"#)
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        assert_eq!(
+            serde_json::from_str::<JsonValue>(&line).unwrap(),
+            json!({ "jsonrpc": "2.0", "id": 1, "result": null })
+        );
+
+        handle.join().unwrap().unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,108 @@
+use std::fs;
+use std::io::{self, Write};
+
+use codespan_reporting::term::{self, termcolor};
+use ecow::eco_format;
+use fs_extra::dir::CopyOptions;
+use termcolor::WriteColor;
+use typst::diag::StrResult;
+use typst::eval::Tracer;
+use typst::syntax::package::PackageSpec;
+use typst::World;
+
+use crate::args::VendorCommand;
+use crate::compile::print_diagnostics;
+use crate::package::{hash_package, LockedPackage, Lockfile, LOCKFILE_NAME, VENDOR_DIR};
+use crate::set_failed;
+use crate::world::SystemWorld;
+
+/// Execute a vendoring command.
+pub fn vendor(command: &VendorCommand) -> StrResult<()> {
+    let mut command = command.clone();
+    crate::project::configure(&mut command.common)?;
+    let command = &command;
+
+    let mut world = SystemWorld::new(&command.common)?;
+
+    // Resolve packages from their usual locations, so that outdated vendored
+    // copies are replaced.
+    world.unlock_packages();
+
+    // Compile the document to find all packages it depends on.
+    world.reset();
+    world.source(world.main()).map_err(|err| err.to_string())?;
+
+    let mut tracer = Tracer::new();
+    let result = typst::compile(&world, &mut tracer);
+    let warnings = tracer.warnings();
+
+    if let Err(errors) = result {
+        set_failed();
+        print_diagnostics(&world, &errors, &warnings, command.common.diagnostic_format)
+            .map_err(|err| eco_format!("failed to print diagnostics ({err})"))?;
+        return Ok(());
+    }
+
+    let mut specs: Vec<PackageSpec> = world.used_packages().cloned().collect();
+    specs.sort_by(|a, b| {
+        (&a.namespace, &a.name, a.version).cmp(&(&b.namespace, &b.name, b.version))
+    });
+    specs.dedup();
+
+    // Remove the packages vendored previously.
+    let root = world.root().to_path_buf();
+    let vendor_dir = root.join(VENDOR_DIR);
+    if let Some(previous) = Lockfile::read(&root)? {
+        for spec in previous.hashes()?.keys() {
+            let dir = vendor_dir
+                .join(spec.namespace.as_str())
+                .join(spec.name.as_str())
+                .join(spec.version.to_string());
+            if dir.exists() {
+                fs::remove_dir_all(&dir).map_err(|err| {
+                    eco_format!("failed to remove {} ({err})", dir.display())
+                })?;
+            }
+        }
+    }
+
+    let mut lockfile = Lockfile { version: Lockfile::VERSION, packages: vec![] };
+    for spec in &specs {
+        let source = world.package_storage().prepare_package(spec)?;
+        let dir = vendor_dir
+            .join(spec.namespace.as_str())
+            .join(spec.name.as_str())
+            .join(spec.version.to_string());
+
+        fs::create_dir_all(&dir)
+            .map_err(|err| eco_format!("failed to create {} ({err})", dir.display()))?;
+        fs_extra::dir::copy(&source, &dir, &CopyOptions::new().content_only(true))
+            .map_err(|err| eco_format!("failed to copy {spec} ({err})"))?;
+
+        let hash = hash_package(&dir)
+            .map_err(|err| eco_format!("failed to hash {spec} ({err})"))?;
+        lockfile.packages.push(LockedPackage { spec: spec.to_string(), hash });
+    }
+
+    lockfile.write(&root)?;
+
+    print_diagnostics(&world, &[], &warnings, command.common.diagnostic_format)
+        .map_err(|err| eco_format!("failed to print diagnostics ({err})"))?;
+    print_summary(&specs).map_err(|err| eco_format!("failed to print summary ({err})"))
+}
+
+/// Print the vendored packages.
+fn print_summary(specs: &[PackageSpec]) -> io::Result<()> {
+    let styles = term::Styles::default();
+
+    let mut out = crate::terminal::out();
+    for spec in specs {
+        out.set_color(&styles.header_help)?;
+        write!(out, "vendored")?;
+        out.reset()?;
+        writeln!(out, " {spec}")?;
+    }
+
+    let s = if specs.len() == 1 { "" } else { "s" };
+    writeln!(out, "Locked {} package{s} in {}", specs.len(), LOCKFILE_NAME)
+}
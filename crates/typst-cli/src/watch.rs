@@ -20,6 +20,8 @@ use crate::{print_error, terminal};
 
 /// Execute a watching compilation command.
 pub fn watch(mut timer: Timer, mut command: CompileCommand) -> StrResult<()> {
+    crate::project::configure_compile(&mut command)?;
+
     let Output::Path(output) = command.output() else {
         bail!("cannot write document to stdout in watch mode");
     };
@@ -45,6 +47,8 @@ pub fn watch(mut timer: Timer, mut command: CompileCommand) -> StrResult<()> {
         }
     };
 
+    world.set_tagging(command.pdf_tags);
+
     // Perform initial compilation.
     timer.record(&mut world, |world| compile_once(world, &mut command, true))??;
 
@@ -278,8 +282,8 @@ impl Status {
         write!(out, "watching")?;
         out.reset()?;
         match &command.common.input {
-            Input::Stdin => writeln!(out, " <stdin>"),
-            Input::Path(path) => writeln!(out, " {}", path.display()),
+            Some(Input::Path(path)) => writeln!(out, " {}", path.display()),
+            _ => writeln!(out, " <stdin>"),
         }?;
 
         out.set_color(&color)?;
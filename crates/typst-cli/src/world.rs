@@ -8,8 +8,9 @@ use chrono::{DateTime, Datelike, FixedOffset, Local, Utc};
 use ecow::{eco_format, EcoString};
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
-use typst::diag::{FileError, FileResult};
+use typst::diag::{FileError, FileResult, PackageResult};
 use typst::foundations::{Bytes, Datetime, Dict, IntoValue};
+use typst::syntax::package::{PackageRequirement, PackageSpec};
 use typst::syntax::{FileId, Source, VirtualPath};
 use typst::text::{Font, FontBook};
 use typst::utils::LazyHash;
@@ -19,6 +20,7 @@ use typst_timing::{timed, TimingScope};
 use crate::args::{Input, SharedArgs};
 use crate::compile::ExportCache;
 use crate::fonts::{FontSearcher, FontSlot};
+use crate::package::PackageStorage;
 
 /// Static `FileId` allocated for stdin.
 /// This is to ensure that a file is read in the correct way.
@@ -48,6 +50,8 @@ pub struct SystemWorld {
     /// The export cache, used for caching output files in `typst watch`
     /// sessions.
     export_cache: ExportCache,
+    /// Where packages are stored and downloaded from.
+    package_storage: PackageStorage,
 }
 
 impl SystemWorld {
@@ -55,8 +59,9 @@ impl SystemWorld {
     pub fn new(command: &SharedArgs) -> Result<Self, WorldCreationError> {
         // Resolve the system-global input path.
         let input = match &command.input {
-            Input::Stdin => None,
-            Input::Path(path) => {
+            None => return Err(WorldCreationError::InputMissing),
+            Some(Input::Stdin) => None,
+            Some(Input::Path(path)) => {
                 Some(path.canonicalize().map_err(|err| match err.kind() {
                     io::ErrorKind::NotFound => {
                         WorldCreationError::InputNotFound(path.clone())
@@ -102,6 +107,10 @@ impl SystemWorld {
             Library::builder().with_inputs(inputs).build()
         };
 
+        let package_storage = PackageStorage::from_args(&command.package)
+            .with_project(&root)
+            .map_err(WorldCreationError::Lockfile)?;
+
         let mut searcher = FontSearcher::new();
         searcher.search(&command.font_paths);
 
@@ -120,6 +129,7 @@ impl SystemWorld {
             slots: Mutex::new(HashMap::new()),
             now,
             export_cache: ExportCache::new(),
+            package_storage,
         })
     }
 
@@ -138,13 +148,51 @@ impl SystemWorld {
         self.workdir.as_deref().unwrap_or(Path::new("."))
     }
 
+    /// The system path of a file, downloading its package if necessary.
+    pub fn path(&self, id: FileId) -> FileResult<PathBuf> {
+        system_path(&self.root, id, &self.package_storage)
+    }
+
     /// Return all paths the last compilation depended on.
     pub fn dependencies(&mut self) -> impl Iterator<Item = PathBuf> + '_ {
         self.slots
             .get_mut()
             .values()
             .filter(|slot| slot.accessed())
-            .filter_map(|slot| system_path(&self.root, slot.id).ok())
+            .filter_map(|slot| {
+                system_path(&self.root, slot.id, &self.package_storage).ok()
+            })
+    }
+
+    /// Return all packages the last compilation depended on.
+    pub fn used_packages(&mut self) -> impl Iterator<Item = &'static PackageSpec> + '_ {
+        self.slots
+            .get_mut()
+            .values()
+            .filter(|slot| slot.accessed())
+            .filter_map(|slot| slot.id.package())
+    }
+
+    /// Where packages are stored and downloaded from.
+    pub fn package_storage(&self) -> &PackageStorage {
+        &self.package_storage
+    }
+
+    /// Resolve packages without the project's vendor directory and lockfile.
+    pub fn unlock_packages(&mut self) {
+        self.package_storage.without_project();
+    }
+
+    /// Serve a package from a directory instead of its usual location.
+    pub fn provide_package(&mut self, spec: PackageSpec, dir: PathBuf) {
+        self.package_storage.provide(spec, dir);
+    }
+
+    /// Configure whether elements are tagged for tagged PDF export.
+    pub fn set_tagging(&mut self, tagged: bool) {
+        if self.library.tagged != tagged {
+            self.library.tagged = tagged;
+        }
     }
 
     /// Reset the compilation state in preparation of a new compilation.
@@ -183,17 +231,24 @@ impl World for SystemWorld {
     }
 
     fn source(&self, id: FileId) -> FileResult<Source> {
-        self.slot(id, |slot| slot.source(&self.root))
+        self.slot(id, |slot| slot.source(&self.root, &self.package_storage))
     }
 
     fn file(&self, id: FileId) -> FileResult<Bytes> {
-        self.slot(id, |slot| slot.file(&self.root))
+        self.slot(id, |slot| slot.file(&self.root, &self.package_storage))
     }
 
     fn font(&self, index: usize) -> Option<Font> {
         self.fonts[index].get()
     }
 
+    fn resolve_package(
+        &self,
+        requirement: &PackageRequirement,
+    ) -> PackageResult<PackageSpec> {
+        self.package_storage.resolve_package(requirement)
+    }
+
     fn today(&self, offset: Option<i64>) -> Option<Datetime> {
         let now = match &self.now {
             Now::Fixed(time) => time,
@@ -259,9 +314,13 @@ impl FileSlot {
     }
 
     /// Retrieve the source for this file.
-    fn source(&mut self, project_root: &Path) -> FileResult<Source> {
+    fn source(
+        &mut self,
+        project_root: &Path,
+        package_storage: &PackageStorage,
+    ) -> FileResult<Source> {
         self.source.get_or_init(
-            || read(self.id, project_root),
+            || read(self.id, project_root, package_storage),
             |data, prev| {
                 let name = if prev.is_some() { "reparsing file" } else { "parsing file" };
                 let _scope = TimingScope::new(name, None);
@@ -277,9 +336,15 @@ impl FileSlot {
     }
 
     /// Retrieve the file's bytes.
-    fn file(&mut self, project_root: &Path) -> FileResult<Bytes> {
-        self.file
-            .get_or_init(|| read(self.id, project_root), |data, _| Ok(data.into()))
+    fn file(
+        &mut self,
+        project_root: &Path,
+        package_storage: &PackageStorage,
+    ) -> FileResult<Bytes> {
+        self.file.get_or_init(
+            || read(self.id, project_root, package_storage),
+            |data, _| Ok(data.into()),
+        )
     }
 }
 
@@ -344,13 +409,17 @@ impl<T: Clone> SlotCell<T> {
 
 /// Resolves the path of a file id on the system, downloading a package if
 /// necessary.
-fn system_path(project_root: &Path, id: FileId) -> FileResult<PathBuf> {
+fn system_path(
+    project_root: &Path,
+    id: FileId,
+    package_storage: &PackageStorage,
+) -> FileResult<PathBuf> {
     // Determine the root path relative to which the file path
     // will be resolved.
     let buf;
     let mut root = project_root;
     if let Some(spec) = id.package() {
-        buf = crate::package::prepare_package(spec)?;
+        buf = package_storage.prepare_package(spec)?;
         root = &buf;
     }
 
@@ -363,11 +432,15 @@ fn system_path(project_root: &Path, id: FileId) -> FileResult<PathBuf> {
 ///
 /// If the ID represents stdin it will read from standard input,
 /// otherwise it gets the file path of the ID and reads the file from disk.
-fn read(id: FileId, project_root: &Path) -> FileResult<Vec<u8>> {
+fn read(
+    id: FileId,
+    project_root: &Path,
+    package_storage: &PackageStorage,
+) -> FileResult<Vec<u8>> {
     if id == *STDIN_ID {
         read_from_stdin()
     } else {
-        read_from_disk(&system_path(project_root, id)?)
+        read_from_disk(&system_path(project_root, id, package_storage)?)
     }
 }
 
@@ -411,12 +484,16 @@ enum Now {
 /// An error that occurs during world construction.
 #[derive(Debug)]
 pub enum WorldCreationError {
+    /// No input file was given.
+    InputMissing,
     /// The input file does not appear to exist.
     InputNotFound(PathBuf),
     /// The input file is not contained within the root folder.
     InputOutsideRoot,
     /// The root directory does not appear to exist.
     RootNotFound(PathBuf),
+    /// The project's lockfile could not be loaded.
+    Lockfile(EcoString),
     /// Another type of I/O error.
     Io(io::Error),
 }
@@ -424,6 +501,7 @@ pub enum WorldCreationError {
 impl fmt::Display for WorldCreationError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            WorldCreationError::InputMissing => write!(f, "input file is missing"),
             WorldCreationError::InputNotFound(path) => {
                 write!(f, "input file not found (searched at {})", path.display())
             }
@@ -433,6 +511,7 @@ impl fmt::Display for WorldCreationError {
             WorldCreationError::RootNotFound(path) => {
                 write!(f, "root directory not found (searched at {})", path.display())
             }
+            WorldCreationError::Lockfile(err) => write!(f, "{err}"),
             WorldCreationError::Io(err) => write!(f, "{err}"),
         }
     }
@@ -0,0 +1,987 @@
+//! Exporting of Typst documents into HTML.
+
+mod writer;
+
+use comemo::Track;
+use ecow::{eco_format, EcoString};
+use typst::diag::{bail, At, Hint, HintedStrResult, SourceResult};
+use typst::engine::{Engine, Route};
+use typst::eval::Tracer;
+use typst::foundations::{
+    Content, NativeElement, Packed, SequenceElem, Smart, StyleChain, StyledElem,
+};
+use typst::introspection::{Counter, Locator, TagElem};
+use typst::layout::{
+    Abs, Axes, BlockElem, BoxElem, ColbreakElem, Dir, Frame, HElem, LayoutMultiple,
+    LayoutSingle, OuterVAlignment, PageElem, PagebreakElem, Point, Regions, Size, VElem,
+};
+use typst::math::{EquationElem, MathParItem};
+use typst::model::{
+    Destination, Document, EmphElem, EnumElem, EnumItem, FigureCaption, FigureElem,
+    HeadingElem, LinkElem, LinkTarget, ListElem, ListItem, ParbreakElem, StrongElem,
+    TableCell, TableChild, TableElem, TableItem, TermItem, TermsElem,
+};
+use typst::text::{
+    Lang, LinebreakElem, RawElem, SmartQuoteElem, SmartQuoter, SmartQuotes, SpaceElem,
+    TextElem,
+};
+use typst::visualize::ImageElem;
+use typst::World;
+
+use crate::writer::HtmlWriter;
+
+/// Export a document into an HTML file.
+///
+/// In contrast to the other exporters, this one does not work on the laid-out
+/// frames of the document, but walks the content the document was laid out
+/// from and maps it to semantic HTML elements. The `document` must stem from
+/// compiling the given `world`, whose main file is evaluated again to obtain
+/// that content. As evaluation is memoized, this is cheap after compilation.
+///
+/// Content is realized in the same order and with a fresh locator, just like
+/// in the final layout iteration, so that elements resolve to the same
+/// locations as in the document's introspector. Warnings raised during export
+/// are reported to the `tracer`.
+///
+/// Content that cannot be expressed in HTML, like equations or shapes, is laid
+/// out on its own and embedded as an inline SVG.
+#[typst_macros::time(name = "html")]
+pub fn html(
+    world: &dyn World,
+    tracer: &mut Tracer,
+    document: &Document,
+) -> SourceResult<String> {
+    let world = world.track();
+    let module = typst::eval::eval(
+        world,
+        Route::default().track(),
+        tracer.track_mut(),
+        &world.main(),
+    )?;
+
+    let library = world.library();
+    let styles = StyleChain::new(&library.styles);
+
+    let mut locator = Locator::new();
+    let mut engine = Engine {
+        world,
+        introspector: document.introspector.track(),
+        route: Route::default(),
+        locator: &mut locator,
+        tracer: tracer.track_mut(),
+    };
+
+    let mut exporter = HtmlExporter::new(&mut engine);
+    exporter.accept(&module.content(), styles)?;
+    exporter.start_block();
+
+    Ok(exporter.finish(document.title.as_ref()))
+}
+
+/// Exports realized content into an HTML body.
+struct HtmlExporter<'a, 'e> {
+    /// The engine used for realization and fallback layout.
+    engine: &'a mut Engine<'e>,
+    /// The body of the HTML file.
+    body: HtmlWriter,
+    /// How inline content is treated in the current container.
+    mode: Mode,
+    /// Whether a `<p>` element is currently open.
+    par: bool,
+    /// The tag of the list that consecutive list items are currently
+    /// collected into, if any.
+    list: Option<&'static str>,
+    /// The smart quote state of the current paragraph.
+    quoter: SmartQuoter,
+    /// The language of the first text in the document.
+    lang: Option<Lang>,
+}
+
+/// How inline content is treated in a container.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Mode {
+    /// Inline content is wrapped in paragraphs, which are separated by
+    /// paragraph breaks.
+    Block,
+    /// Inline content is written as-is, as for tight list items or table cells.
+    Flow,
+    /// Only phrasing content is allowed, as in headings or emphasis.
+    Inline,
+}
+
+impl<'a, 'e> HtmlExporter<'a, 'e> {
+    /// Create a new exporter.
+    fn new(engine: &'a mut Engine<'e>) -> Self {
+        Self {
+            engine,
+            body: HtmlWriter::new(),
+            mode: Mode::Block,
+            par: false,
+            list: None,
+            quoter: SmartQuoter::new(),
+            lang: None,
+        }
+    }
+
+    /// Wrap the body into a full HTML file.
+    fn finish(self, title: Option<&EcoString>) -> String {
+        let mut html = HtmlWriter::new();
+        html.raw("<!DOCTYPE html>\n");
+        match self.lang {
+            Some(lang) => html.open("html", &[("lang", lang.as_str())]),
+            None => html.open("html", &[]),
+        }
+        html.raw("\n");
+        html.open("head", &[]);
+        html.raw("\n");
+        html.open("meta", &[("charset", "utf-8")]);
+        html.raw("\n");
+        html.open(
+            "meta",
+            &[("name", "viewport"), ("content", "width=device-width, initial-scale=1")],
+        );
+        html.raw("\n");
+        // Inline math may overflow its frame into the surrounding leading.
+        html.raw("<style>.typst-frame svg { overflow: visible; }</style>\n");
+        if let Some(title) = title {
+            html.open("title", &[]);
+            html.text(title);
+            html.close("title");
+            html.raw("\n");
+        }
+        html.close("head");
+        html.raw("\n");
+        html.open("body", &[]);
+        html.raw("\n");
+        html.raw(&self.body.finish());
+        html.close("body");
+        html.raw("\n");
+        html.close("html");
+        html.raw("\n");
+        html.finish()
+    }
+
+    /// Export a piece of realized content.
+    fn accept(&mut self, content: &Content, styles: StyleChain) -> SourceResult<()> {
+        if let Some(styled) = content.to_packed::<StyledElem>() {
+            return self.accept(&styled.child, styles.chain(&styled.styles));
+        }
+
+        if let Some(sequence) = content.to_packed::<SequenceElem>() {
+            for child in &sequence.children {
+                self.accept(child, styles)?;
+            }
+            return Ok(());
+        }
+
+        // Apply user-defined show rules and prepare the element. The built-in
+        // show rules are skipped so that we see the semantic elements.
+        if let Some(realized) =
+            typst::realize::process_without_builtin(self.engine, content, styles)?
+        {
+            return self.accept(&realized, styles);
+        }
+
+        self.element(content, styles)
+    }
+
+    /// Export a single prepared element.
+    fn element(&mut self, content: &Content, styles: StyleChain) -> SourceResult<()> {
+        if content.is::<TagElem>() {
+            return Ok(());
+        }
+
+        if let Some(elem) = content.to_packed::<TextElem>() {
+            self.start_inline(styles);
+            self.lang.get_or_insert(TextElem::lang_in(styles));
+            self.body.text(elem.text());
+            if let Some(c) = elem.text().chars().last() {
+                self.quoter.last(c, false);
+            }
+        } else if content.is::<SpaceElem>() {
+            // Spaces at the start of a paragraph are insignificant.
+            if self.par || self.mode != Mode::Block {
+                self.body.text(" ");
+                self.quoter.last(' ', false);
+            }
+        } else if content.is::<LinebreakElem>() {
+            self.start_inline(styles);
+            self.body.open("br", &[]);
+            self.quoter.last('\n', false);
+        } else if let Some(elem) = content.to_packed::<SmartQuoteElem>() {
+            self.start_inline(styles);
+            let double = elem.double(styles);
+            if elem.enabled(styles) {
+                let quotes = SmartQuoteElem::quotes_in(styles);
+                let quotes = SmartQuotes::new(
+                    quotes,
+                    TextElem::lang_in(styles),
+                    TextElem::region_in(styles),
+                    SmartQuoteElem::alternative_in(styles),
+                );
+                let quote = self.quoter.quote(&quotes, double, None);
+                self.body.text(quote);
+            } else {
+                self.body.text(if double { "\"" } else { "'" });
+            }
+            self.quoter.last('"', true);
+        } else if content.is::<ParbreakElem>() {
+            self.end_par();
+        } else if content.is::<HElem>() {
+            // Horizontal spacing has no semantic meaning.
+        } else if content.is::<VElem>()
+            || content.is::<ColbreakElem>()
+            || content.is::<PagebreakElem>()
+        {
+            self.start_block();
+        } else if let Some(elem) = content.to_packed::<StrongElem>() {
+            self.inline_container(&elem.body, "strong", &[], styles)?;
+        } else if let Some(elem) = content.to_packed::<EmphElem>() {
+            self.inline_container(&elem.body, "em", &[], styles)?;
+        } else if let Some(elem) = content.to_packed::<LinkElem>() {
+            self.link(elem, styles)?;
+        } else if let Some(elem) = content.to_packed::<RawElem>() {
+            self.raw(elem, styles);
+        } else if let Some(elem) = content.to_packed::<HeadingElem>() {
+            self.heading(elem, styles)?;
+        } else if let Some(elem) = content.to_packed::<ListElem>() {
+            self.start_block();
+            self.body.open("ul", &[]);
+            for item in &elem.children {
+                self.list_item(item, styles)?;
+            }
+            self.body.close("ul");
+        } else if let Some(elem) = content.to_packed::<ListItem>() {
+            self.start_list("ul");
+            self.list_item(elem, styles)?;
+        } else if let Some(elem) = content.to_packed::<EnumElem>() {
+            self.enumeration(elem, styles)?;
+        } else if let Some(elem) = content.to_packed::<EnumItem>() {
+            self.start_list("ol");
+            self.enum_item(elem, styles)?;
+        } else if let Some(elem) = content.to_packed::<TermsElem>() {
+            self.start_block();
+            self.body.open("dl", &[]);
+            for item in &elem.children {
+                self.term_item(item, styles)?;
+            }
+            self.body.close("dl");
+        } else if let Some(elem) = content.to_packed::<TermItem>() {
+            self.start_list("dl");
+            self.term_item(elem, styles)?;
+        } else if let Some(elem) = content.to_packed::<TableElem>() {
+            self.table(elem, styles)?;
+        } else if let Some(elem) = content.to_packed::<FigureElem>() {
+            self.figure(elem, styles)?;
+        } else if let Some(elem) = content.to_packed::<EquationElem>() {
+            if elem.block(styles) {
+                let frame = elem.layout(self.engine, styles, self.regions(styles))?;
+                self.svg(&frame, content, false, styles);
+            } else {
+                self.start_inline(styles);
+                let items =
+                    elem.layout_inline(self.engine, styles, self.regions(styles))?;
+                self.svg(&inline_frame(items), content, true, styles);
+            }
+        } else if let Some(elem) = content.to_packed::<BoxElem>() {
+            match elem.body(styles) {
+                Some(body) => self.inline_container(&body, "span", &[], styles)?,
+                None => self.fallback(content, styles)?,
+            }
+        } else if let Some(elem) = content.to_packed::<BlockElem>() {
+            match elem.body(styles) {
+                Some(body) => {
+                    self.start_block();
+                    self.body.open("div", &[]);
+                    self.container(&body, Mode::Block, styles)?;
+                    self.body.close("div");
+                }
+                None => self.fallback(content, styles)?,
+            }
+        } else if let Some(show) = content.with::<dyn typst::foundations::Show>() {
+            // Elements that we do not know are exported through their built-in
+            // show rule.
+            let realized = show.show(self.engine, styles)?;
+            self.accept(&realized, styles)?;
+        } else {
+            self.fallback(content, styles)?;
+        }
+
+        Ok(())
+    }
+
+    /// Export a link.
+    fn link(&mut self, elem: &Packed<LinkElem>, styles: StyleChain) -> SourceResult<()> {
+        let href = match &elem.dest {
+            LinkTarget::Dest(Destination::Url(url)) => Some(url.clone()),
+            LinkTarget::Label(label) => Some(eco_format!("#{}", label.as_str())),
+            LinkTarget::Dest(Destination::Location(loc)) => self
+                .engine
+                .introspector
+                .query_first(&typst::foundations::Selector::Location(*loc))
+                .and_then(|elem| elem.label())
+                .map(|label| eco_format!("#{}", label.as_str())),
+            LinkTarget::Dest(Destination::Position(_)) => None,
+        };
+
+        match href {
+            Some(href) => {
+                self.inline_container(&elem.body, "a", &[("href", &href)], styles)
+            }
+            None => self.inline_container(&elem.body, "span", &[], styles),
+        }
+    }
+
+    /// Export raw text, either as an inline `<code>` or as a `<pre>` block.
+    fn raw(&mut self, elem: &Packed<RawElem>, styles: StyleChain) {
+        let text = elem
+            .lines()
+            .map(|lines| {
+                lines
+                    .iter()
+                    .map(|line| line.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .unwrap_or_default();
+
+        let class = elem.lang(styles).as_ref().map(|lang| eco_format!("language-{lang}"));
+        let attrs = match &class {
+            Some(class) => vec![("class", class.as_str())],
+            None => vec![],
+        };
+
+        if elem.block(styles) {
+            self.start_block();
+            self.body.open("pre", &[]);
+            self.body.open("code", &attrs);
+            self.body.text(&text);
+            self.body.close("code");
+            self.body.close("pre");
+        } else {
+            self.start_inline(styles);
+            self.body.open("code", &attrs);
+            self.body.text(&text);
+            self.body.close("code");
+        }
+    }
+
+    /// Export a heading, including its numbering.
+    fn heading(
+        &mut self,
+        elem: &Packed<HeadingElem>,
+        styles: StyleChain,
+    ) -> SourceResult<()> {
+        self.start_block();
+
+        let level = elem.resolve_level(styles).get().min(6);
+        let tag = eco_format!("h{level}");
+        let id = elem.label().map(|label| label.as_str());
+        self.body.open(&tag, &attrs([("id", id)]));
+
+        let prev = std::mem::replace(&mut self.mode, Mode::Inline);
+        if let (Some(numbering), Some(location)) =
+            ((**elem).numbering(styles).as_ref(), elem.location())
+        {
+            let numbering = Counter::of(HeadingElem::elem()).display_at_loc(
+                self.engine,
+                location,
+                styles,
+                numbering,
+            )?;
+            self.accept(&numbering, styles)?;
+            self.body.text(" ");
+        }
+        self.accept(&elem.body, styles)?;
+        self.mode = prev;
+
+        self.body.close(&tag);
+        Ok(())
+    }
+
+    /// Export a numbered list.
+    fn enumeration(
+        &mut self,
+        elem: &Packed<EnumElem>,
+        styles: StyleChain,
+    ) -> SourceResult<()> {
+        self.start_block();
+
+        let start = elem.start(styles);
+        let start = (start != 1).then(|| start.to_string());
+        self.body.open("ol", &attrs([("start", start.as_deref())]));
+        for item in &elem.children {
+            self.enum_item(item, styles)?;
+        }
+        self.body.close("ol");
+
+        Ok(())
+    }
+
+    /// Export a bullet list item.
+    fn list_item(
+        &mut self,
+        item: &Packed<ListItem>,
+        styles: StyleChain,
+    ) -> SourceResult<()> {
+        self.body.open("li", &[]);
+        self.container(&item.body, Mode::Flow, styles)?;
+        self.body.close("li");
+        Ok(())
+    }
+
+    /// Export a numbered list item.
+    fn enum_item(
+        &mut self,
+        item: &Packed<EnumItem>,
+        styles: StyleChain,
+    ) -> SourceResult<()> {
+        let value = item.number(styles).map(|number| number.to_string());
+        self.body.open("li", &attrs([("value", value.as_deref())]));
+        self.container(&item.body, Mode::Flow, styles)?;
+        self.body.close("li");
+        Ok(())
+    }
+
+    /// Export a term list item.
+    fn term_item(
+        &mut self,
+        item: &Packed<TermItem>,
+        styles: StyleChain,
+    ) -> SourceResult<()> {
+        self.body.open("dt", &[]);
+        self.container(&item.term, Mode::Inline, styles)?;
+        self.body.close("dt");
+        self.body.open("dd", &[]);
+        self.container(&item.description, Mode::Flow, styles)?;
+        self.body.close("dd");
+        Ok(())
+    }
+
+    /// Export a table with its header and footer rows.
+    fn table(
+        &mut self,
+        elem: &Packed<TableElem>,
+        styles: StyleChain,
+    ) -> SourceResult<()> {
+        self.start_block();
+
+        let columns = elem.columns(styles).0.len().max(1);
+        let mut grid = CellGrid::new(columns);
+        for child in &elem.children {
+            // The first automatically positioned cell of a header or footer
+            // starts a new row, like in table layout.
+            grid.new_row = !matches!(child, TableChild::Item(_));
+            let (items, section) = match child {
+                TableChild::Header(header) => {
+                    (header.children.as_slice(), Section::Header)
+                }
+                TableChild::Footer(footer) => {
+                    (footer.children.as_slice(), Section::Footer)
+                }
+                TableChild::Item(item) => (std::slice::from_ref(item), Section::Body),
+            };
+            for item in items {
+                if let TableItem::Cell(cell) = item {
+                    grid.place(cell, section, styles)?;
+                }
+            }
+        }
+
+        self.body.open("table", &[]);
+        for (section, tag) in [
+            (Section::Header, "thead"),
+            (Section::Body, "tbody"),
+            (Section::Footer, "tfoot"),
+        ] {
+            let rows = grid.rows(section);
+            if rows.is_empty() && section != Section::Body {
+                continue;
+            }
+
+            let cell_tag = if section == Section::Header { "th" } else { "td" };
+            self.body.open(tag, &[]);
+            for y in rows {
+                self.table_row(&grid, y, cell_tag, styles)?;
+            }
+            self.body.close(tag);
+        }
+        self.body.close("table");
+
+        Ok(())
+    }
+
+    /// Export a single row of a table's resolved cell grid.
+    fn table_row(
+        &mut self,
+        grid: &CellGrid,
+        y: usize,
+        tag: &str,
+        styles: StyleChain,
+    ) -> SourceResult<()> {
+        self.body.open("tr", &[]);
+        for x in 0..grid.columns {
+            match grid.slots[y][x] {
+                // Slots spanned by a cell from an earlier row or column are
+                // covered by that cell's `colspan` and `rowspan`.
+                Some(i) if grid.cells[i].x != x || grid.cells[i].y != y => {}
+                Some(i) => {
+                    let placed = &grid.cells[i];
+                    let colspan = placed.colspan.to_string();
+                    let rowspan = placed.rowspan.to_string();
+                    let mut attrs = vec![];
+                    if placed.colspan != 1 {
+                        attrs.push(("colspan", colspan.as_str()));
+                    }
+                    if placed.rowspan != 1 {
+                        attrs.push(("rowspan", rowspan.as_str()));
+                    }
+
+                    self.body.open(tag, &attrs);
+                    self.container(&placed.cell.body, Mode::Flow, styles)?;
+                    self.body.close(tag);
+                }
+                // Keep the columns aligned when a slot is left empty.
+                None => {
+                    self.body.open(tag, &[]);
+                    self.body.close(tag);
+                }
+            }
+        }
+        self.body.close("tr");
+        Ok(())
+    }
+
+    /// Export a figure with its caption.
+    fn figure(
+        &mut self,
+        elem: &Packed<FigureElem>,
+        styles: StyleChain,
+    ) -> SourceResult<()> {
+        self.start_block();
+
+        let id = elem.label().map(|label| label.as_str());
+        self.body.open("figure", &attrs([("id", id)]));
+
+        let caption = elem.caption(styles);
+        let top = caption
+            .as_ref()
+            .is_some_and(|caption| caption.position(styles) == OuterVAlignment::Top);
+
+        if top {
+            self.caption(caption.as_ref().unwrap(), styles)?;
+        }
+
+        self.container(&elem.body, Mode::Flow, styles)?;
+
+        if !top {
+            if let Some(caption) = &caption {
+                self.caption(caption, styles)?;
+            }
+        }
+
+        self.body.close("figure");
+        Ok(())
+    }
+
+    /// Export a figure caption.
+    fn caption(
+        &mut self,
+        caption: &Packed<FigureCaption>,
+        styles: StyleChain,
+    ) -> SourceResult<()> {
+        self.body.open("figcaption", &[]);
+        self.container(&caption.clone().pack(), Mode::Flow, styles)?;
+        self.body.close("figcaption");
+        Ok(())
+    }
+
+    /// Lay out content that has no HTML equivalent and embed it as an SVG.
+    fn fallback(&mut self, content: &Content, styles: StyleChain) -> SourceResult<()> {
+        let regions = self.regions(styles);
+        let frame = if let Some(elem) = content.with::<dyn LayoutSingle>() {
+            elem.layout(self.engine, styles, regions)?
+        } else {
+            content.layout(self.engine, styles, regions)?.into_frame()
+        };
+        self.svg(&frame, content, self.mode == Mode::Inline, styles);
+        Ok(())
+    }
+
+    /// Write a frame as an inline SVG.
+    fn svg(
+        &mut self,
+        frame: &Frame,
+        content: &Content,
+        inline: bool,
+        styles: StyleChain,
+    ) {
+        let alt = content.to_packed::<ImageElem>().and_then(|image| image.alt(styles));
+
+        let mut attrs = vec![("class", "typst-frame")];
+        if let Some(alt) = &alt {
+            attrs.push(("role", "img"));
+            attrs.push(("aria-label", alt.as_str()));
+        }
+
+        let style;
+        if inline {
+            style = eco_format!("vertical-align: -{}pt", frame.descent().to_pt());
+            attrs.push(("style", &style));
+            self.body.open("span", &attrs);
+            self.body.raw(&typst_svg::svg(frame));
+            self.body.close("span");
+        } else {
+            self.start_block();
+            self.body.open("div", &attrs);
+            self.body.raw(&typst_svg::svg(frame));
+            self.body.close("div");
+        }
+    }
+
+    /// The regions to lay out fallback content in.
+    fn regions(&self, styles: StyleChain) -> Regions<'static> {
+        let width = match PageElem::width_in(styles) {
+            Smart::Custom(width) => width,
+            Smart::Auto => Abs::inf(),
+        };
+        Regions::one(Size::new(width, Abs::inf()), Axes::splat(false))
+    }
+
+    /// Export content into the current container with an inline element.
+    fn inline_container(
+        &mut self,
+        body: &Content,
+        tag: &str,
+        attrs: &[(&str, &str)],
+        styles: StyleChain,
+    ) -> SourceResult<()> {
+        self.start_inline(styles);
+        self.body.open(tag, attrs);
+        let prev = std::mem::replace(&mut self.mode, Mode::Inline);
+        self.accept(body, styles)?;
+        self.mode = prev;
+        self.body.close(tag);
+        Ok(())
+    }
+
+    /// Export content into a fresh container in the given mode.
+    ///
+    /// In flow mode, the container switches to block mode if the content
+    /// consists of multiple paragraphs.
+    fn container(
+        &mut self,
+        body: &Content,
+        mode: Mode,
+        styles: StyleChain,
+    ) -> SourceResult<()> {
+        let mode =
+            if mode == Mode::Flow && has_parbreak(body) { Mode::Block } else { mode };
+        let prev_mode = std::mem::replace(&mut self.mode, mode);
+        let prev_par = std::mem::replace(&mut self.par, false);
+        let prev_list = self.list.take();
+        self.accept(body, styles)?;
+        self.start_block();
+        self.mode = prev_mode;
+        self.par = prev_par;
+        self.list = prev_list;
+        Ok(())
+    }
+
+    /// Prepare for writing inline content, opening a paragraph if necessary.
+    fn start_inline(&mut self, styles: StyleChain) {
+        self.end_list();
+        if self.mode == Mode::Block && !self.par {
+            if TextElem::dir_in(styles) == Dir::RTL {
+                self.body.open("p", &[("dir", "rtl")]);
+            } else {
+                self.body.open("p", &[]);
+            }
+            self.par = true;
+            self.quoter = SmartQuoter::new();
+        }
+    }
+
+    /// Prepare for writing a block-level element.
+    fn start_block(&mut self) {
+        self.end_par();
+        self.end_list();
+    }
+
+    /// Prepare for writing an item into a list with the given tag, opening the
+    /// list if necessary.
+    fn start_list(&mut self, tag: &'static str) {
+        if self.list != Some(tag) {
+            self.start_block();
+            self.body.open(tag, &[]);
+            self.list = Some(tag);
+        }
+    }
+
+    /// Close the list that items are currently collected into, if any.
+    fn end_list(&mut self) {
+        if let Some(tag) = self.list.take() {
+            self.body.close(tag);
+        }
+    }
+
+    /// Close the current paragraph, if any.
+    fn end_par(&mut self) {
+        if self.par {
+            self.body.trim_end();
+            self.body.close("p");
+            self.par = false;
+        }
+    }
+}
+
+/// The part of a table a row belongs to.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Section {
+    Header,
+    Body,
+    Footer,
+}
+
+/// A table cell with its resolved position in the grid.
+struct PlacedCell<'a> {
+    cell: &'a Packed<TableCell>,
+    section: Section,
+    x: usize,
+    y: usize,
+    colspan: usize,
+    rowspan: usize,
+}
+
+/// The cells of a table, placed in the grid the same way table layout places
+/// them: Cells with an explicit `x` and `y` go exactly there and the others
+/// fill the next free slots. Cells that don't fit fail with the same errors
+/// as in table layout.
+struct CellGrid<'a> {
+    /// The number of columns.
+    columns: usize,
+    /// The placed cells.
+    cells: Vec<PlacedCell<'a>>,
+    /// For each row and column, the index of the cell occupying the slot.
+    slots: Vec<Vec<Option<usize>>>,
+    /// The slot at which the search for the next automatically positioned
+    /// cell starts, counted in row-major order.
+    cursor: usize,
+    /// Whether the next automatically positioned cell starts a new row, as
+    /// the first one in a header or footer does.
+    new_row: bool,
+}
+
+impl<'a> CellGrid<'a> {
+    /// Create an empty grid with the given number of columns.
+    fn new(columns: usize) -> Self {
+        Self {
+            columns,
+            cells: vec![],
+            slots: vec![],
+            cursor: 0,
+            new_row: false,
+        }
+    }
+
+    /// Place a cell in the grid.
+    fn place(
+        &mut self,
+        cell: &'a Packed<TableCell>,
+        section: Section,
+        styles: StyleChain,
+    ) -> SourceResult<()> {
+        let span = cell.span();
+        let columns = self.columns;
+        let colspan = cell.colspan(styles).get();
+        let rowspan = cell.rowspan(styles).get();
+        let (x, y) = self
+            .position(cell.x(styles), cell.y(styles), colspan, rowspan)
+            .at(span)?;
+
+        if colspan > columns - x {
+            bail!(
+                span,
+                "cell's colspan would cause it to exceed the available column(s)";
+                hint: "try placing the cell in another position or reducing its colspan"
+            );
+        }
+
+        let Some(end) = y.checked_add(rowspan) else {
+            bail!(
+                span,
+                "cell would span an exceedingly large position";
+                hint: "try reducing the cell's rowspan or colspan"
+            );
+        };
+
+        if self.slots.len() < end {
+            self.slots.resize(end, vec![None; columns]);
+        }
+
+        if self.slots[y][x].is_some() {
+            bail!(
+                span,
+                "attempted to place a second cell at column {x}, row {y}";
+                hint: "try specifying your cells in a different order"
+            );
+        }
+
+        let index = self.cells.len();
+        for (dy, row) in self.slots[y..end].iter_mut().enumerate() {
+            for (dx, slot) in row[x..x + colspan].iter_mut().enumerate() {
+                if slot.is_some() {
+                    bail!(
+                        span,
+                        "cell would span a previously placed cell at column {}, row {}",
+                        x + dx,
+                        y + dy;
+                        hint: "try specifying your cells in a different order or reducing the cell's rowspan or colspan"
+                    );
+                }
+                *slot = Some(index);
+            }
+        }
+
+        // Like in table layout, a header or footer doesn't need to start a
+        // new row if one of its cells already lies in the row of the next
+        // automatically positioned cell.
+        if self.new_row && y <= self.cursor.div_ceil(columns) {
+            self.new_row = false;
+        }
+
+        self.cells.push(PlacedCell { cell, section, x, y, colspan, rowspan });
+        Ok(())
+    }
+
+    /// Resolve the position of a cell the same way table layout does.
+    fn position(
+        &mut self,
+        x: Smart<usize>,
+        y: Smart<usize>,
+        colspan: usize,
+        rowspan: usize,
+    ) -> HintedStrResult<(usize, usize)> {
+        let columns = self.columns;
+        match (x, y) {
+            (Smart::Auto, Smart::Auto) => {
+                let mut index = self.cursor;
+                if self.new_row {
+                    index = index.next_multiple_of(columns);
+                    while self
+                        .slots
+                        .get(index / columns)
+                        .is_some_and(|row| row.iter().any(Option::is_some))
+                    {
+                        index += columns;
+                    }
+                    self.new_row = false;
+                } else {
+                    while self.occupied(index % columns, index / columns) {
+                        index += 1;
+                    }
+                }
+
+                // A cell that spans all columns blocks automatic placement
+                // until all of its rows are done.
+                self.cursor = if colspan == columns {
+                    index + colspan * rowspan
+                } else {
+                    index + colspan
+                };
+
+                Ok((index % columns, index / columns))
+            }
+            (Smart::Custom(x), _) if x >= columns => {
+                Err(eco_format!("cell could not be placed at invalid column {x}").into())
+            }
+            (Smart::Custom(x), Smart::Custom(y)) => Ok((x, y)),
+            (Smart::Custom(x), Smart::Auto) => {
+                let y = (0..).find(|&y| !self.occupied(x, y)).unwrap_or_default();
+                Ok((x, y))
+            }
+            (Smart::Auto, Smart::Custom(y)) => (0..columns)
+                .find(|&x| !self.occupied(x, y))
+                .map(|x| (x, y))
+                .ok_or_else(|| {
+                    eco_format!("cell could not be placed in row {y} because it was full")
+                })
+                .hint("try specifying your cells in a different order"),
+        }
+    }
+
+    /// Whether a slot is occupied by a cell.
+    fn occupied(&self, x: usize, y: usize) -> bool {
+        self.slots.get(y).is_some_and(|row| row[x].is_some())
+    }
+
+    /// The indices of the rows that belong to the given section.
+    ///
+    /// A row belongs to the section of the cells in it. Rows without any
+    /// cells belong to the body.
+    fn rows(&self, section: Section) -> Vec<usize> {
+        (0..self.slots.len())
+            .filter(|&y| {
+                let first = self.slots[y].iter().find_map(|&slot| slot);
+                first.map_or(Section::Body, |i| self.cells[i].section) == section
+            })
+            .collect()
+    }
+}
+
+/// Collect the attributes that have a value.
+fn attrs<'a, const N: usize>(
+    attrs: [(&'a str, Option<&'a str>); N],
+) -> Vec<(&'a str, &'a str)> {
+    attrs
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect()
+}
+
+/// Whether the content contains a paragraph break at the top level.
+fn has_parbreak(content: &Content) -> bool {
+    let mut found = false;
+    content.sequence_recursive_for_each(&mut |child| {
+        let child = match child.to_packed::<StyledElem>() {
+            Some(styled) => &styled.child,
+            None => child,
+        };
+        found |= child.is::<ParbreakElem>();
+    });
+    found
+}
+
+/// Combine the items of an inline equation into a single frame.
+fn inline_frame(items: Vec<MathParItem>) -> Frame {
+    let mut ascent = Abs::zero();
+    let mut descent = Abs::zero();
+    let mut width = Abs::zero();
+    for item in &items {
+        match item {
+            MathParItem::Space(amount) => width += *amount,
+            MathParItem::Frame(frame) => {
+                ascent.set_max(frame.baseline());
+                descent.set_max(frame.height() - frame.baseline());
+                width += frame.width();
+            }
+        }
+    }
+
+    let mut output = Frame::soft(Size::new(width, ascent + descent));
+    output.set_baseline(ascent);
+
+    let mut x = Abs::zero();
+    for item in items {
+        match item {
+            MathParItem::Space(amount) => x += amount,
+            MathParItem::Frame(frame) => {
+                let width = frame.width();
+                output.push_frame(Point::new(x, ascent - frame.baseline()), frame);
+                x += width;
+            }
+        }
+    }
+
+    output
+}
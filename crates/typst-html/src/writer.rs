@@ -0,0 +1,99 @@
+use std::fmt::Write;
+
+/// Writes HTML markup into a string.
+pub struct HtmlWriter {
+    buf: String,
+}
+
+impl HtmlWriter {
+    /// Create a new, empty writer.
+    pub fn new() -> Self {
+        Self { buf: String::new() }
+    }
+
+    /// Write an opening tag with attributes.
+    ///
+    /// Void elements like `<br>` are opened, but never closed.
+    pub fn open(&mut self, tag: &str, attrs: &[(&str, &str)]) {
+        self.buf.push('<');
+        self.buf.push_str(tag);
+        for (name, value) in attrs {
+            write!(self.buf, " {name}=\"").unwrap();
+            escape(&mut self.buf, value, true);
+            self.buf.push('"');
+        }
+        self.buf.push('>');
+    }
+
+    /// Write a closing tag.
+    pub fn close(&mut self, tag: &str) {
+        write!(self.buf, "</{tag}>").unwrap();
+        if is_block(tag) {
+            self.buf.push('\n');
+        }
+    }
+
+    /// Write escaped text.
+    pub fn text(&mut self, text: &str) {
+        escape(&mut self.buf, text, false);
+    }
+
+    /// Remove trailing spaces from the written markup.
+    pub fn trim_end(&mut self) {
+        let len = self.buf.trim_end_matches(' ').len();
+        self.buf.truncate(len);
+    }
+
+    /// Write raw markup without escaping.
+    pub fn raw(&mut self, markup: &str) {
+        self.buf.push_str(markup);
+    }
+
+    /// Return the written markup.
+    pub fn finish(self) -> String {
+        self.buf
+    }
+}
+
+/// Escape text for use in HTML content or attribute values.
+fn escape(buf: &mut String, text: &str, attr: bool) {
+    for c in text.chars() {
+        match c {
+            '&' => buf.push_str("&amp;"),
+            '<' => buf.push_str("&lt;"),
+            '>' => buf.push_str("&gt;"),
+            '"' if attr => buf.push_str("&quot;"),
+            '\u{a0}' => buf.push_str("&nbsp;"),
+            _ => buf.push(c),
+        }
+    }
+}
+
+/// Whether the tag is a block-level element, after which we insert a newline
+/// for readability.
+fn is_block(tag: &str) -> bool {
+    matches!(
+        tag,
+        "p" | "div"
+            | "pre"
+            | "ul"
+            | "ol"
+            | "li"
+            | "dl"
+            | "dt"
+            | "dd"
+            | "table"
+            | "thead"
+            | "tbody"
+            | "tfoot"
+            | "tr"
+            | "figure"
+            | "figcaption"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+    )
+}
@@ -0,0 +1,341 @@
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+use ecow::{eco_format, EcoString};
+use serde::{Deserialize, Serialize};
+use typst::diag::SourceDiagnostic;
+use typst::foundations::Value;
+use typst::syntax::ast::AstNode;
+use typst::syntax::{ast, LinkedNode, Source, SyntaxKind};
+use typst::World;
+
+use crate::analyze::analyze_import;
+
+/// The maximum number of spelling corrections offered for an unknown name.
+const MAX_CORRECTIONS: usize = 3;
+
+/// Find quick fixes for the diagnostics that overlap with a byte range of a
+/// source file.
+///
+/// Besides the [fixes](typst::diag::Fix) that diagnostics carry themselves,
+/// this suggests names in scope for misspelled identifiers and imports of
+/// items from modules that are already imported without them.
+pub fn code_actions(
+    world: &dyn World,
+    source: &Source,
+    range: Range<usize>,
+    diagnostics: &[SourceDiagnostic],
+) -> Vec<CodeAction> {
+    let mut actions = vec![];
+    for (i, diagnostic) in diagnostics.iter().enumerate() {
+        if diagnostic.span.id() != Some(source.id()) {
+            continue;
+        }
+
+        let Some(node) = source.find(diagnostic.span) else { continue };
+        let node_range = node.range();
+        if node_range.end < range.start || range.end < node_range.start {
+            continue;
+        }
+
+        for fix in &diagnostic.fixes {
+            let Some(fix_range) = source.range(fix.span) else { continue };
+            actions.push(CodeAction {
+                title: fix.title.clone(),
+                edits: vec![(fix_range, fix.replacement.clone())],
+                diagnostic: i,
+            });
+        }
+
+        let unknown = diagnostic.message.strip_prefix("unknown variable: ");
+        let is_ident = matches!(node.kind(), SyntaxKind::Ident | SyntaxKind::MathIdent);
+        if let Some(name) = unknown.filter(|_| is_ident) {
+            import_actions(world, source, name, i, &mut actions);
+            spelling_actions(world, &node, name, i, &mut actions);
+        }
+    }
+    actions
+}
+
+/// A set of edits that fixes a diagnostic.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct CodeAction {
+    /// A short description of the action.
+    pub title: EcoString,
+    /// The byte ranges to replace in the source file and their replacements.
+    pub edits: Vec<(Range<usize>, EcoString)>,
+    /// The index of the fixed diagnostic in the diagnostics passed to
+    /// [`code_actions`].
+    pub diagnostic: usize,
+}
+
+/// Add actions that import an unknown name from modules which are imported
+/// without it.
+fn import_actions(
+    world: &dyn World,
+    source: &Source,
+    name: &str,
+    diagnostic: usize,
+    actions: &mut Vec<CodeAction>,
+) {
+    let mut imports = vec![];
+    visit(&LinkedNode::new(source.root()), &mut |node| {
+        if node.kind() == SyntaxKind::ModuleImport {
+            imports.push(node.clone());
+        }
+    });
+
+    for node in imports {
+        let Some(import) = node.cast::<ast::ModuleImport>() else { continue };
+        let ast::Expr::Str(path) = import.source() else { continue };
+
+        let (offset, text) = match import.imports() {
+            None => {
+                let last = match import.new_name() {
+                    Some(new_name) => new_name.to_untyped(),
+                    None => path.to_untyped(),
+                };
+                let Some(last) = node.find(last.span()) else { continue };
+                (last.range().end, eco_format!(": {name}"))
+            }
+            Some(ast::Imports::Items(items)) => {
+                let Some(last) = items.iter().last() else { continue };
+                let Some(last) = node.find(last.bound_name().span()) else { continue };
+                (last.range().end, eco_format!(", {name}"))
+            }
+            Some(ast::Imports::Wildcard) => continue,
+        };
+
+        let Some(path_node) = node.find(path.span()) else { continue };
+        let Some(Value::Module(module)) = analyze_import(world, &path_node) else {
+            continue;
+        };
+        if module.scope().get(name).is_none() {
+            continue;
+        }
+
+        actions.push(CodeAction {
+            title: eco_format!("import `{name}` from {}", path.to_untyped().text()),
+            edits: vec![(offset..offset, text)],
+            diagnostic,
+        });
+    }
+}
+
+/// Add actions that replace an unknown name with similar names that are in
+/// scope.
+fn spelling_actions(
+    world: &dyn World,
+    node: &LinkedNode,
+    name: &str,
+    diagnostic: usize,
+    actions: &mut Vec<CodeAction>,
+) {
+    let limit = name.chars().count().div_ceil(3);
+    let mut candidates: Vec<_> = names_in_scope(world, node)
+        .into_iter()
+        .filter(|candidate| candidate != name)
+        .filter_map(|candidate| {
+            let distance = distance(name, &candidate);
+            (distance <= limit).then_some((distance, candidate))
+        })
+        .collect();
+    candidates.sort();
+
+    for (_, candidate) in candidates.into_iter().take(MAX_CORRECTIONS) {
+        actions.push(CodeAction {
+            title: eco_format!("change to `{candidate}`"),
+            edits: vec![(node.range(), candidate)],
+            diagnostic,
+        });
+    }
+}
+
+/// Collect the names that are visible at a node.
+fn names_in_scope(world: &dyn World, node: &LinkedNode) -> BTreeSet<EcoString> {
+    let mut names = BTreeSet::new();
+    let mut ancestor = node.clone();
+    loop {
+        // Bindings that precede the node in the same block.
+        let mut sibling = ancestor.prev_sibling();
+        while let Some(prev) = sibling {
+            if let Some(binding) = prev.cast::<ast::LetBinding>() {
+                for ident in binding.kind().bindings() {
+                    names.insert(ident.get().clone());
+                }
+            } else if let Some(import) = prev.cast::<ast::ModuleImport>() {
+                import_names(world, &prev, import, &mut names);
+            }
+            sibling = prev.prev_sibling();
+        }
+
+        let Some(parent) = ancestor.parent().cloned() else { break };
+
+        // Parameters are visible in the body of a closure.
+        if let Some(closure) = parent.cast::<ast::Closure>() {
+            if closure.body().span() == ancestor.span() {
+                for param in closure.params().children() {
+                    let idents = match param {
+                        ast::Param::Pos(pattern) => pattern.bindings(),
+                        ast::Param::Named(named) => vec![named.name()],
+                        ast::Param::Spread(spread) => {
+                            spread.sink_ident().into_iter().collect()
+                        }
+                    };
+                    names.extend(idents.into_iter().map(|ident| ident.get().clone()));
+                }
+            }
+        }
+
+        // The pattern is visible in the body of a for loop.
+        if let Some(for_loop) = parent.cast::<ast::ForLoop>() {
+            if for_loop.body().span() == ancestor.span() {
+                for ident in for_loop.pattern().bindings() {
+                    names.insert(ident.get().clone());
+                }
+            }
+        }
+
+        ancestor = parent;
+    }
+
+    let library = world.library();
+    let scope = if node.kind() == SyntaxKind::MathIdent {
+        library.math.scope()
+    } else {
+        library.global.scope()
+    };
+    names.extend(scope.iter().map(|(name, _)| name.clone()));
+    names
+}
+
+/// Collect the names bound by a module import.
+fn import_names(
+    world: &dyn World,
+    node: &LinkedNode,
+    import: ast::ModuleImport,
+    names: &mut BTreeSet<EcoString>,
+) {
+    if let Some(new_name) = import.new_name() {
+        names.insert(new_name.get().clone());
+    }
+
+    let module = || {
+        let source = node.find(import.source().span())?;
+        analyze_import(world, &source)
+    };
+
+    match import.imports() {
+        None => {
+            if import.new_name().is_none() {
+                names.extend(
+                    module().and_then(|module| module.name().map(EcoString::from)),
+                );
+            }
+        }
+        Some(ast::Imports::Wildcard) => {
+            if let Some(scope) = module().as_ref().and_then(Value::scope) {
+                names.extend(scope.iter().map(|(name, _)| name.clone()));
+            }
+        }
+        Some(ast::Imports::Items(items)) => {
+            names.extend(items.iter().map(|item| item.bound_name().get().clone()));
+        }
+    }
+}
+
+/// The Levenshtein distance between two strings.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, &y) in b.iter().enumerate() {
+            let cost = usize::from(x != y);
+            row.push((prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+/// Call a function for a node and all of its descendants.
+fn visit<'a>(node: &LinkedNode<'a>, f: &mut impl FnMut(&LinkedNode<'a>)) {
+    f(node);
+    for child in node.children() {
+        visit(&child, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use typst::eval::Tracer;
+
+    use super::{code_actions, distance};
+    use crate::tests::TestWorld;
+
+    /// The title and edits of an action.
+    type Action = (String, Vec<(Range<usize>, String)>);
+
+    /// Compile the world and find the actions for all of its errors.
+    fn actions(world: &TestWorld) -> Vec<Action> {
+        let errors = typst::compile(world, &mut Tracer::new()).unwrap_err();
+        code_actions(world, &world.main, 0..world.main.len_bytes(), &errors)
+            .into_iter()
+            .map(|action| {
+                let edits = action
+                    .edits
+                    .into_iter()
+                    .map(|(range, text)| (range, text.into()))
+                    .collect();
+                (action.title.into(), edits)
+            })
+            .collect()
+    }
+
+    #[track_caller]
+    fn test(world: &TestWorld, title: &str, edit: (Range<usize>, &str)) {
+        let expected = (title.to_string(), vec![(edit.0, edit.1.to_string())]);
+        let found = actions(world);
+        assert!(found.contains(&expected), "{found:?}");
+    }
+
+    #[test]
+    fn test_actions_from_fixes() {
+        test(&TestWorld::new("$foo$"), "wrap in quotes", (1..4, "\"foo\""));
+        test(
+            &TestWorld::new("#let (a, b) = (1, 2)\n#(a-b)"),
+            "add spaces around the minus sign",
+            (23..26, "a - b"),
+        );
+    }
+
+    #[test]
+    fn test_actions_spelling() {
+        let world = TestWorld::new("#let value = 1\n#valeu");
+        test(&world, "change to `value`", (16..21, "value"));
+        let world = TestWorld::new("#let f(count) = cuont\n#f(1)");
+        test(&world, "change to `count`", (16..21, "count"));
+        assert_eq!(actions(&TestWorld::new("#let f() = qqqqqq\n#f()")), vec![]);
+    }
+
+    #[test]
+    fn test_actions_import() {
+        let world = TestWorld::new("#import \"lib.typ\"\n#foo")
+            .with_source("lib.typ", "#let foo = 1");
+        test(&world, "import `foo` from \"lib.typ\"", (17..17, ": foo"));
+
+        let world = TestWorld::new("#import \"lib.typ\": bar\n#foo")
+            .with_source("lib.typ", "#let foo = 1\n#let bar = 2");
+        test(&world, "import `foo` from \"lib.typ\"", (22..22, ", foo"));
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "ab"), 2);
+        assert_eq!(distance("ab", "ab"), 0);
+    }
+}
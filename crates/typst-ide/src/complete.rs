@@ -724,7 +724,7 @@ fn param_completions<'a>(
     set: bool,
     args: ast::Args<'a>,
 ) {
-    let Some(func) = resolve_global_callee(ctx, callee) else { return };
+    let Some(func) = resolve_global_callee(ctx.global, callee) else { return };
     let Some(params) = func.params() else { return };
 
     // Exclude named arguments which are already present.
@@ -770,7 +770,7 @@ fn named_param_value_completions<'a>(
     callee: ast::Expr<'a>,
     name: &str,
 ) {
-    let Some(func) = resolve_global_callee(ctx, callee) else { return };
+    let Some(func) = resolve_global_callee(ctx.global, callee) else { return };
     let Some(param) = func.param(name) else { return };
     if !param.named {
         return;
@@ -786,15 +786,15 @@ fn named_param_value_completions<'a>(
     }
 }
 
-/// Resolve a callee expression to a global function.
-fn resolve_global_callee<'a>(
-    ctx: &CompletionContext<'a>,
+/// Resolve a callee expression to a function in the global scope.
+pub(crate) fn resolve_global_callee<'a>(
+    global: &'a Scope,
     callee: ast::Expr<'a>,
 ) -> Option<&'a Func> {
     let value = match callee {
-        ast::Expr::Ident(ident) => ctx.global.get(&ident)?,
+        ast::Expr::Ident(ident) => global.get(&ident)?,
         ast::Expr::FieldAccess(access) => match access.target() {
-            ast::Expr::Ident(target) => match ctx.global.get(&target)? {
+            ast::Expr::Ident(target) => match global.get(&target)? {
                 Value::Module(module) => module.field(&access.field()).ok()?,
                 Value::Func(func) => func.field(&access.field()).ok()?,
                 _ => return None,
@@ -0,0 +1,429 @@
+use typst::foundations::{Label, Module, Value};
+use typst::model::Document;
+use typst::syntax::ast::AstNode;
+use typst::syntax::{ast, FileId, LinkedNode, Side, Source, Span, SyntaxKind};
+use typst::World;
+
+use crate::analyze::{analyze_expr, analyze_import};
+
+/// Find the definition of the item under the cursor.
+///
+/// Returns the span of the identifier that binds the item. Imports of files
+/// resolve to the root node of the imported file.
+///
+/// Passing a `document` (from a previous compilation) is optional, but
+/// enables jumping from label references to the labelled elements.
+pub fn definition(
+    world: &dyn World,
+    document: Option<&Document>,
+    source: &Source,
+    cursor: usize,
+) -> Option<Span> {
+    let leaf = leaf_at(source, cursor)?;
+    find_definition(world, document, &leaf)
+}
+
+/// Find the leaf at the cursor that may refer to a definition, preferring the
+/// one before the cursor.
+pub(crate) fn leaf_at(source: &Source, cursor: usize) -> Option<LinkedNode<'_>> {
+    fn is_target(node: &LinkedNode) -> bool {
+        matches!(
+            node.kind(),
+            SyntaxKind::Ident
+                | SyntaxKind::MathIdent
+                | SyntaxKind::RefMarker
+                | SyntaxKind::Label
+                | SyntaxKind::Str
+        )
+    }
+
+    let root = LinkedNode::new(source.root());
+    root.leaf_at(cursor, Side::Before)
+        .filter(is_target)
+        .or_else(|| root.leaf_at(cursor, Side::After).filter(is_target))
+}
+
+/// Find the definition of the item a leaf refers to.
+pub(crate) fn find_definition(
+    world: &dyn World,
+    document: Option<&Document>,
+    leaf: &LinkedNode,
+) -> Option<Span> {
+    match leaf.kind() {
+        SyntaxKind::Ident | SyntaxKind::MathIdent => ident_definition(world, leaf),
+        SyntaxKind::RefMarker | SyntaxKind::Label => {
+            let name = leaf.text().trim_start_matches(['@', '<']).trim_end_matches('>');
+            let elem = document?.introspector.query_label(Label::new(name)).ok()?;
+            Some(elem.span()).filter(|span| !span.is_detached())
+        }
+        SyntaxKind::Str => {
+            if !matches!(
+                leaf.parent_kind(),
+                Some(SyntaxKind::ModuleImport | SyntaxKind::ModuleInclude)
+            ) {
+                return None;
+            }
+            let Value::Module(module) = analyze_import(world, leaf)? else {
+                return None;
+            };
+            Some(world.source(module.file_id()?).ok()?.root().span())
+        }
+        _ => None,
+    }
+}
+
+/// Find the definition of the item an identifier refers to.
+fn ident_definition(world: &dyn World, leaf: &LinkedNode) -> Option<Span> {
+    let name = leaf.text();
+    let parent = leaf.parent()?;
+
+    // The field of a module: `utils.add`.
+    if parent.kind() == SyntaxKind::FieldAccess && leaf.index() > 0 {
+        let target = parent.children().next()?;
+
+        // Try to find the imported file syntactically before resorting to
+        // the much more expensive expression analysis.
+        if matches!(target.kind(), SyntaxKind::Ident | SyntaxKind::MathIdent) {
+            if let Some(id) = ident_definition(world, &target)
+                .and_then(|span| file_of_root(world, span))
+            {
+                return file_definition(world, id, name)?;
+            }
+        }
+
+        return analyze_expr(world, &target).into_iter().find_map(|(value, _)| {
+            let Value::Module(module) = value else { return None };
+            module_definition(world, &module, name)?
+        });
+    }
+
+    // An item in an import list: `#import "utils.typ": add`.
+    if let Some(import) = import_of_item(leaf) {
+        let source = import.children().find(|child| child.is::<ast::Expr>())?;
+        if let Some(Value::Module(module)) = analyze_import(world, &source) {
+            if let Some(found) = module_definition(world, &module, name) {
+                return found;
+            }
+        }
+    }
+
+    // The identifier binds the item itself.
+    if is_binding(leaf) {
+        return Some(leaf.span());
+    }
+
+    scope_definition(world, leaf, name)?
+}
+
+/// Find the import an identifier is the original name of an imported item in.
+fn import_of_item<'a>(leaf: &LinkedNode<'a>) -> Option<LinkedNode<'a>> {
+    let mut parent = leaf.parent()?;
+    if parent.kind() == SyntaxKind::RenamedImportItem {
+        if leaf.prev_sibling().is_some() {
+            return None;
+        }
+        parent = parent.parent()?;
+    }
+
+    if parent.kind() != SyntaxKind::ImportItems {
+        return None;
+    }
+
+    parent
+        .parent()
+        .filter(|import| import.kind() == SyntaxKind::ModuleImport)
+        .cloned()
+}
+
+/// Whether an identifier is bound by one of its ancestors.
+fn is_binding(leaf: &LinkedNode) -> bool {
+    let mut ancestor = leaf.parent();
+    while let Some(node) = ancestor {
+        let found = match node.kind() {
+            SyntaxKind::LetBinding
+            | SyntaxKind::ForLoop
+            | SyntaxKind::Closure
+            | SyntaxKind::ModuleImport => {
+                bindings(node).iter().any(|ident| ident.span() == leaf.span())
+            }
+            // Parameters and destructuring patterns can be nested, but only
+            // in these node kinds.
+            SyntaxKind::Params
+            | SyntaxKind::Named
+            | SyntaxKind::Spread
+            | SyntaxKind::Destructuring
+            | SyntaxKind::Parenthesized
+            | SyntaxKind::RenamedImportItem
+            | SyntaxKind::ImportItems => {
+                ancestor = node.parent();
+                continue;
+            }
+            _ => false,
+        };
+        return found;
+    }
+    false
+}
+
+/// The identifiers a binding node introduces.
+fn bindings<'a>(node: &LinkedNode<'a>) -> Vec<ast::Ident<'a>> {
+    if let Some(binding) = node.get().cast::<ast::LetBinding>() {
+        binding.kind().bindings()
+    } else if let Some(for_loop) = node.get().cast::<ast::ForLoop>() {
+        for_loop.pattern().bindings()
+    } else if let Some(closure) = node.get().cast::<ast::Closure>() {
+        let mut idents: Vec<_> = closure.name().into_iter().collect();
+        for param in closure.params().children() {
+            match param {
+                ast::Param::Pos(pattern) => idents.extend(pattern.bindings()),
+                ast::Param::Named(named) => idents.push(named.name()),
+                ast::Param::Spread(spread) => idents.extend(spread.sink_ident()),
+            }
+        }
+        idents
+    } else if let Some(import) = node.get().cast::<ast::ModuleImport>() {
+        let mut idents: Vec<_> = import.new_name().into_iter().collect();
+        if let Some(ast::Imports::Items(items)) = import.imports() {
+            idents.extend(items.iter().map(|item| item.bound_name()));
+        }
+        idents
+    } else {
+        vec![]
+    }
+}
+
+/// Find the binding of a name that is visible at a node.
+///
+/// Returns `Some(None)` if the name is bound, but the binding can't be
+/// located, e.g. because it comes from a built-in module.
+pub(crate) fn scope_definition(
+    world: &dyn World,
+    node: &LinkedNode,
+    name: &str,
+) -> Option<Option<Span>> {
+    let mut node = node.clone();
+    loop {
+        // Bindings that precede the node in the same block.
+        let mut sibling = node.prev_sibling();
+        while let Some(prev) = sibling {
+            if let Some(found) = binding_definition(world, &prev, name) {
+                return Some(found);
+            }
+            sibling = prev.prev_sibling();
+        }
+
+        let parent = node.parent()?.clone();
+
+        // Parameters are visible in the body of a closure.
+        if let Some(closure) = parent.cast::<ast::Closure>() {
+            if closure.body().span() == node.span() {
+                if let Some(span) = find_binding(&parent, name) {
+                    return Some(Some(span));
+                }
+            }
+        }
+
+        // The pattern is visible in the body of a for loop.
+        if let Some(for_loop) = parent.cast::<ast::ForLoop>() {
+            if for_loop.body().span() == node.span() {
+                if let Some(span) = find_binding(&parent, name) {
+                    return Some(Some(span));
+                }
+            }
+        }
+
+        node = parent;
+    }
+}
+
+/// Find the binding of a name in a node that precedes a usage.
+///
+/// Returns `Some(None)` if the node binds the name, but the binding can't be
+/// located.
+fn binding_definition(
+    world: &dyn World,
+    node: &LinkedNode,
+    name: &str,
+) -> Option<Option<Span>> {
+    if node.kind() == SyntaxKind::LetBinding {
+        return find_binding(node, name).map(Some);
+    }
+
+    let import = node.cast::<ast::ModuleImport>()?;
+    let module = || {
+        let source = node.children().find(|child| child.is::<ast::Expr>())?;
+        match analyze_import(world, &source)? {
+            Value::Module(module) => Some(module),
+            _ => None,
+        }
+    };
+
+    if let Some(new_name) = import.new_name() {
+        if new_name.get() == name {
+            return Some(Some(new_name.span()));
+        }
+    }
+
+    match import.imports() {
+        None => {
+            let module = module()?;
+            if import.new_name().is_some() || module.name() != name {
+                return None;
+            }
+            Some(
+                world
+                    .source(module.file_id()?)
+                    .ok()
+                    .map(|source| source.root().span()),
+            )
+        }
+        Some(ast::Imports::Wildcard) => {
+            let module = module()?;
+            module.scope().get(name)?;
+            Some(module_definition(world, &module, name).flatten())
+        }
+        Some(ast::Imports::Items(items)) => {
+            let item = items.iter().find(|item| item.bound_name().get() == name)?;
+            let original = item.original_name();
+            let found = module()
+                .and_then(|module| module_definition(world, &module, original.get()))
+                .flatten();
+            Some(found.or(Some(item.bound_name().span())))
+        }
+    }
+}
+
+/// The file whose root node has the given span, if any.
+pub(crate) fn file_of_root(world: &dyn World, span: Span) -> Option<FileId> {
+    let id = span.id()?;
+    (world.source(id).ok()?.root().span() == span).then_some(id)
+}
+
+/// Find the span of the identifier with the given name among the bindings of
+/// a node.
+fn find_binding(node: &LinkedNode, name: &str) -> Option<Span> {
+    bindings(node)
+        .into_iter()
+        .find(|ident| ident.get() == name)
+        .map(|ident| ident.span())
+}
+
+/// Find the top-level binding of a name in the file that defines a module.
+///
+/// Returns `None` if the module is not defined by a file or doesn't bind the
+/// name at the top-level.
+fn module_definition(
+    world: &dyn World,
+    module: &Module,
+    name: &str,
+) -> Option<Option<Span>> {
+    file_definition(world, module.file_id()?, name)
+}
+
+/// Find the top-level binding of a name in a file.
+///
+/// Returns `Some(None)` if the file binds the name, but the binding can't be
+/// located.
+pub(crate) fn file_definition(
+    world: &dyn World,
+    id: FileId,
+    name: &str,
+) -> Option<Option<Span>> {
+    let source = world.source(id).ok()?;
+    let root = LinkedNode::new(source.root());
+    let mut child = root.children().last();
+    while let Some(node) = child {
+        if let Some(found) = binding_definition(world, &node, name) {
+            return Some(found);
+        }
+        child = node.prev_sibling();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use typst::eval::Tracer;
+    use typst::WorldExt;
+
+    use super::definition;
+    use crate::tests::TestWorld;
+
+    #[track_caller]
+    fn test(world: &TestWorld, cursor: usize, expected: Option<(&str, Range<usize>)>) {
+        let doc = typst::compile(world, &mut Tracer::new()).ok();
+        let found = definition(world, doc.as_ref(), &world.main, cursor).map(|span| {
+            let path = span.id().unwrap().vpath().as_rootless_path();
+            (path.to_str().unwrap().to_owned(), world.range(span).unwrap())
+        });
+        let expected = expected.map(|(path, range)| (path.to_owned(), range));
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_definition_let() {
+        let world = TestWorld::new("#let x = 1\n#x");
+        test(&world, 13, Some(("main.typ", 5..6)));
+        test(&world, 6, Some(("main.typ", 5..6)));
+    }
+
+    #[test]
+    fn test_definition_shadowed() {
+        let world = TestWorld::new("#let x = 1\n#let x = x + 1\n#x");
+        test(&world, 20, Some(("main.typ", 5..6)));
+        test(&world, 27, Some(("main.typ", 16..17)));
+    }
+
+    #[test]
+    fn test_definition_params() {
+        let world =
+            TestWorld::new("#let f(a, b: 2, ..c) = a + b + c.len() + f((x => x)(a))");
+        test(&world, 24, Some(("main.typ", 7..8)));
+        test(&world, 28, Some(("main.typ", 10..11)));
+        test(&world, 32, Some(("main.typ", 18..19)));
+        test(&world, 42, Some(("main.typ", 5..6)));
+        test(&world, 50, Some(("main.typ", 44..45)));
+        test(&world, 53, Some(("main.typ", 7..8)));
+    }
+
+    #[test]
+    fn test_definition_for_loop() {
+        let world = TestWorld::new("#for (k, v) in (a: 1) { k + v }");
+        test(&world, 25, Some(("main.typ", 6..7)));
+        test(&world, 29, Some(("main.typ", 9..10)));
+    }
+
+    #[test]
+    fn test_definition_import() {
+        let world = TestWorld::new(
+            "#import \"lib.typ\": greet, x as y\n#import \"lib.typ\"\n#greet #y #lib.x",
+        )
+        .with_source("lib.typ", "#let greet = 1\n#let x = 2");
+        test(&world, 21, Some(("lib.typ", 5..10)));
+        test(&world, 27, Some(("lib.typ", 20..21)));
+        test(&world, 32, Some(("main.typ", 31..32)));
+        test(&world, 54, Some(("lib.typ", 5..10)));
+        test(&world, 60, Some(("lib.typ", 20..21)));
+        test(&world, 67, Some(("lib.typ", 20..21)));
+        test(&world, 10, Some(("lib.typ", 0..25)));
+    }
+
+    #[test]
+    fn test_definition_label() {
+        let world = TestWorld::new("#figure([A], caption: [B]) <a>\n@a");
+        test(&world, 33, Some(("main.typ", 1..26)));
+
+        let world = TestWorld::new(
+            "#set heading(numbering: \"1.\")\n= Intro <intro>\nSee @intro",
+        );
+        test(&world, 53, Some(("main.typ", 30..37)));
+    }
+
+    #[test]
+    fn test_definition_builtin() {
+        let world = TestWorld::new("#text(red)[A]");
+        test(&world, 3, None);
+    }
+}
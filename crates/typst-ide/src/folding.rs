@@ -0,0 +1,150 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use typst::syntax::{ast, LinkedNode, Source, SyntaxKind};
+
+/// Find the regions of a source file that can be folded.
+///
+/// Code and content blocks, block comments and runs of line comments, and
+/// heading sections can be folded. A heading's section extends until the next
+/// heading of the same or a lower depth. Only regions spanning multiple lines
+/// are returned.
+pub fn folding_ranges(source: &Source) -> Vec<FoldingRange> {
+    let mut ranges = vec![];
+    collect(source, &LinkedNode::new(source.root()), &mut ranges);
+    ranges.sort_by_key(|range| range.range.start);
+    ranges
+}
+
+/// A region of a source file that can be folded.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct FoldingRange {
+    /// What is folded.
+    pub kind: FoldingKind,
+    /// The byte range of the region.
+    pub range: Range<usize>,
+}
+
+/// A kind of foldable region.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FoldingKind {
+    /// A code or content block.
+    Block,
+    /// A heading and its section.
+    Heading,
+    /// A block comment or consecutive line comments.
+    Comment,
+}
+
+/// Collect the foldable regions in a node and its descendants.
+fn collect(source: &Source, node: &LinkedNode, ranges: &mut Vec<FoldingRange>) {
+    match node.kind() {
+        SyntaxKind::CodeBlock | SyntaxKind::ContentBlock => {
+            push(source, FoldingKind::Block, node.range(), ranges);
+        }
+        SyntaxKind::BlockComment => {
+            push(source, FoldingKind::Comment, node.range(), ranges);
+        }
+        SyntaxKind::Markup => sections(source, node, ranges),
+        _ => {}
+    }
+
+    // Line comments that directly follow each other fold together.
+    let mut run: Option<Range<usize>> = None;
+    for child in node.children() {
+        match child.kind() {
+            SyntaxKind::LineComment => {
+                let start = run.map_or(child.offset(), |run| run.start);
+                run = Some(start..child.range().end);
+            }
+            SyntaxKind::Space if child.text().matches('\n').count() <= 1 => {}
+            _ => {
+                if let Some(run) = run.take() {
+                    push(source, FoldingKind::Comment, run, ranges);
+                }
+            }
+        }
+        collect(source, &child, ranges);
+    }
+
+    if let Some(run) = run {
+        push(source, FoldingKind::Comment, run, ranges);
+    }
+}
+
+/// Collect the sections of the headings in a markup node.
+fn sections(source: &Source, node: &LinkedNode, ranges: &mut Vec<FoldingRange>) {
+    let headings: Vec<_> = node
+        .children()
+        .filter_map(|child| {
+            let heading = child.cast::<ast::Heading>()?;
+            Some((heading.depth().get(), child.offset()))
+        })
+        .collect();
+
+    for (i, &(depth, start)) in headings.iter().enumerate() {
+        let end = headings[i + 1..]
+            .iter()
+            .find(|&&(next, _)| next <= depth)
+            .map_or(node.range().end, |&(_, offset)| offset);
+
+        // The whitespace before the next heading doesn't belong to the section.
+        let end = source.text()[..end].trim_end().len().max(start);
+        push(source, FoldingKind::Heading, start..end, ranges);
+    }
+}
+
+/// Add a region if it spans multiple lines.
+fn push(
+    source: &Source,
+    kind: FoldingKind,
+    range: Range<usize>,
+    ranges: &mut Vec<FoldingRange>,
+) {
+    if source.byte_to_line(range.start) != source.byte_to_line(range.end) {
+        ranges.push(FoldingRange { kind, range });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use typst::syntax::Source;
+
+    use super::{folding_ranges, FoldingKind};
+
+    #[track_caller]
+    fn test(text: &str, expected: &[(FoldingKind, Range<usize>)]) {
+        let source = Source::detached(text);
+        let ranges: Vec<_> = folding_ranges(&source)
+            .into_iter()
+            .map(|range| (range.kind, range.range))
+            .collect();
+        assert_eq!(ranges, expected);
+    }
+
+    #[test]
+    fn test_folding_sections() {
+        use FoldingKind::*;
+        test(
+            "= A\nText\n== B\nMore\n\n= C\n== D",
+            &[(Heading, 0..18), (Heading, 9..18), (Heading, 20..28)],
+        );
+        test(
+            "#[\n= A\nx\n]\n= B\ny",
+            &[(Block, 1..10), (Heading, 3..8), (Heading, 11..16)],
+        );
+    }
+
+    #[test]
+    fn test_folding_blocks_and_comments() {
+        use FoldingKind::*;
+        test(
+            "#{\n  1 // x\n}\n/* a\nb */\n// c\n// d\n\n// e",
+            &[(Block, 1..13), (Comment, 14..23), (Comment, 24..33)],
+        );
+        test("#{ 1 }\n#[a]", &[]);
+    }
+}
@@ -0,0 +1,458 @@
+use typst::diag::{bail, StrResult};
+use typst::syntax::{parse, SyntaxKind, SyntaxNode};
+
+/// The column after which the formatter breaks lists of arguments and items.
+const MAX_WIDTH: usize = 80;
+
+/// The text with which nested code is indented.
+const INDENT: &str = "  ";
+
+/// Format the source code of a Typst file.
+///
+/// Only code is formatted: Its indentation, the spacing around operators and
+/// in argument lists, and the line breaks in lists of arguments and items.
+/// Lists are broken into one item per line if they don't fit into a line or
+/// if the author already broke them, and broken lists always end with a
+/// trailing comma. Markup and comments stay exactly as written.
+///
+/// Formatting is idempotent. Fails if the file has syntax errors.
+pub fn format(text: &str) -> StrResult<String> {
+    let root = parse(text);
+    if root.erroneous() {
+        bail!("cannot format a file with syntax errors");
+    }
+
+    let mut p = Printer::default();
+    p.markup(&root);
+    Ok(p.buf)
+}
+
+/// Writes formatted source code.
+#[derive(Default)]
+struct Printer {
+    /// The output.
+    buf: String,
+}
+
+/// An entry in a list of items or statements.
+enum Entry<'a> {
+    /// An item or a statement.
+    Item(&'a SyntaxNode),
+    /// A comment that follows an item on the same line.
+    TrailingComment(&'a SyntaxNode),
+    /// A comment on its own line.
+    Comment(&'a SyntaxNode),
+    /// An empty line.
+    Blank,
+}
+
+impl Printer {
+    /// The column at which the next character is written.
+    fn column(&self) -> usize {
+        let line = self.buf.rfind('\n').map_or(0, |i| i + 1);
+        self.buf[line..].chars().count()
+    }
+
+    /// The indentation of the line that is currently written.
+    fn indent(&self) -> String {
+        let line = self.buf.rfind('\n').map_or(0, |i| i + 1);
+        let rest = &self.buf[line..];
+        rest[..rest.len() - rest.trim_start().len()].into()
+    }
+
+    /// Write a node verbatim.
+    fn verbatim(&mut self, node: &SyntaxNode) {
+        self.buf.push_str(&node.clone().into_text());
+    }
+
+    /// Write markup, formatting only the embedded code.
+    fn markup(&mut self, node: &SyntaxNode) {
+        if node.children().next().is_none() {
+            self.buf.push_str(node.text());
+            return;
+        }
+
+        let mut embedded = false;
+        for child in node.children() {
+            if embedded {
+                self.code(child, false);
+                embedded = false;
+                continue;
+            }
+
+            match child.kind() {
+                SyntaxKind::Hash => {
+                    self.buf.push('#');
+                    embedded = true;
+                }
+                SyntaxKind::ContentBlock => self.code(child, false),
+                SyntaxKind::Equation | SyntaxKind::Raw => self.verbatim(child),
+                _ => self.markup(child),
+            }
+        }
+    }
+
+    /// Write a piece of code.
+    ///
+    /// If `flat` is true, lists are kept on one line unless the author broke
+    /// them.
+    fn code(&mut self, node: &SyntaxNode, flat: bool) {
+        match node.kind() {
+            SyntaxKind::ContentBlock => {
+                for child in node.children() {
+                    match child.kind() {
+                        SyntaxKind::Markup => self.markup(child),
+                        _ => self.verbatim(child),
+                    }
+                }
+            }
+            SyntaxKind::CodeBlock => self.code_block(node, flat),
+            SyntaxKind::Args
+            | SyntaxKind::Array
+            | SyntaxKind::Dict
+            | SyntaxKind::Params
+            | SyntaxKind::Destructuring => self.list(node, flat),
+            SyntaxKind::ImportItems => self.import_items(node, flat),
+            SyntaxKind::Parenthesized
+            | SyntaxKind::FieldAccess
+            | SyntaxKind::FuncCall
+            | SyntaxKind::Spread
+            | SyntaxKind::Unary => self.joined(node, flat, false),
+            SyntaxKind::Named
+            | SyntaxKind::Keyed
+            | SyntaxKind::Binary
+            | SyntaxKind::Closure
+            | SyntaxKind::LetBinding
+            | SyntaxKind::SetRule
+            | SyntaxKind::ShowRule
+            | SyntaxKind::Contextual
+            | SyntaxKind::Conditional
+            | SyntaxKind::WhileLoop
+            | SyntaxKind::ForLoop
+            | SyntaxKind::ModuleImport
+            | SyntaxKind::RenamedImportItem
+            | SyntaxKind::ModuleInclude
+            | SyntaxKind::LoopBreak
+            | SyntaxKind::LoopContinue
+            | SyntaxKind::FuncReturn
+            | SyntaxKind::DestructAssignment => self.joined(node, flat, true),
+            _ => self.verbatim(node),
+        }
+    }
+
+    /// Write the children of a node after each other, separated by single
+    /// spaces if `spaced` is true.
+    ///
+    /// Nodes with comments are written verbatim as we can't know where the
+    /// comments belong.
+    fn joined(&mut self, node: &SyntaxNode, flat: bool, spaced: bool) {
+        if node.children().any(is_comment) {
+            self.verbatim(node);
+            return;
+        }
+
+        let mut prev: Option<&SyntaxNode> = None;
+        for child in node.children().filter(|child| !child.kind().is_trivia()) {
+            if let Some(prev) = prev {
+                let space = if spaced {
+                    // The parameters of a named closure and the arguments of
+                    // a set rule directly follow the name.
+                    !matches!(
+                        (prev.kind(), child.kind()),
+                        (_, SyntaxKind::Colon | SyntaxKind::Comma | SyntaxKind::Args)
+                            | (SyntaxKind::Ident, SyntaxKind::Params)
+                    )
+                } else {
+                    // Keep signs apart so that they aren't merged into
+                    // another token.
+                    prev.kind() == SyntaxKind::Not
+                        || (matches!(prev.kind(), SyntaxKind::Plus | SyntaxKind::Minus)
+                            && child.clone().into_text().starts_with(['+', '-']))
+                };
+                if space {
+                    self.buf.push(' ');
+                }
+            }
+
+            self.code(child, flat);
+            prev = Some(child);
+        }
+    }
+
+    /// Write a parenthesized list of arguments, items, or parameters.
+    fn list(&mut self, node: &SyntaxNode, flat: bool) {
+        let children: Vec<_> = node.children().collect();
+        let Some(open) = children
+            .iter()
+            .position(|child| child.kind() == SyntaxKind::LeftParen)
+        else {
+            // Closure parameters without parentheses and arguments that only
+            // consist of trailing content blocks.
+            self.joined(node, flat, false);
+            return;
+        };
+
+        let close = children
+            .iter()
+            .rposition(|child| child.kind() == SyntaxKind::RightParen)
+            .unwrap_or(children.len());
+
+        let inner = &children[open + 1..close];
+        let entries =
+            entries(inner.iter().copied(), &[SyntaxKind::Comma, SyntaxKind::Colon]);
+        let items: Vec<_> = entries
+            .iter()
+            .filter_map(|entry| match entry {
+                Entry::Item(item) => Some(*item),
+                _ => None,
+            })
+            .collect();
+
+        let trailing = inner
+            .iter()
+            .rev()
+            .find(|child| !child.kind().is_trivia())
+            .is_some_and(|child| child.kind() == SyntaxKind::Comma);
+        let broken = inner.iter().any(|child| breaks(child) || is_comment(child));
+
+        let mut flat_printer = Printer::default();
+        flat_printer.buf.push('(');
+        if node.kind() == SyntaxKind::Dict && items.is_empty() {
+            flat_printer.buf.push(':');
+        }
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                flat_printer.buf.push_str(", ");
+            }
+            flat_printer.code(item, true);
+        }
+        if items.len() == 1 && trailing {
+            flat_printer.buf.push(',');
+        }
+        flat_printer.buf.push(')');
+
+        if !broken && self.fits(&flat_printer.buf, flat) {
+            self.buf.push_str(&flat_printer.buf);
+        } else {
+            let indent = self.indent();
+            self.buf.push('(');
+            self.entries(&entries, &indent, ",");
+            self.buf.push('\n');
+            self.buf.push_str(&indent);
+            self.buf.push(')');
+        }
+
+        // Trailing content blocks.
+        for child in &children[close..] {
+            if child.kind() != SyntaxKind::RightParen && !child.kind().is_trivia() {
+                self.code(child, flat);
+            }
+        }
+    }
+
+    /// Write the items of an import.
+    fn import_items(&mut self, node: &SyntaxNode, flat: bool) {
+        if node.children().any(is_comment) {
+            self.verbatim(node);
+            return;
+        }
+
+        let items = node.children().filter(|child| {
+            !child.kind().is_trivia() && child.kind() != SyntaxKind::Comma
+        });
+        for (i, item) in items.enumerate() {
+            if i > 0 {
+                self.buf.push_str(", ");
+            }
+            self.code(item, flat);
+        }
+    }
+
+    /// Write a code block.
+    fn code_block(&mut self, node: &SyntaxNode, flat: bool) {
+        // The whitespace and comments around the statements may belong to the
+        // block or to the code inside of it.
+        let inner: Vec<_> = node
+            .children()
+            .flat_map(|child| match child.kind() {
+                SyntaxKind::Code => child.children().collect(),
+                SyntaxKind::LeftBrace | SyntaxKind::RightBrace => vec![],
+                _ => vec![child],
+            })
+            .collect();
+
+        let entries = entries(inner.iter().copied(), &[SyntaxKind::Semicolon]);
+        let stmts: Vec<_> = entries
+            .iter()
+            .filter_map(|entry| match entry {
+                Entry::Item(item) => Some(*item),
+                _ => None,
+            })
+            .collect();
+
+        let broken = inner.iter().any(|child| breaks(child) || is_comment(child));
+
+        let mut flat_printer = Printer::default();
+        flat_printer.buf.push('{');
+        for (i, stmt) in stmts.iter().enumerate() {
+            flat_printer.buf.push_str(if i > 0 { "; " } else { " " });
+            flat_printer.code(stmt, true);
+        }
+        if !stmts.is_empty() {
+            flat_printer.buf.push(' ');
+        }
+        flat_printer.buf.push('}');
+
+        if !broken && self.fits(&flat_printer.buf, flat) {
+            self.buf.push_str(&flat_printer.buf);
+        } else {
+            let indent = self.indent();
+            self.buf.push('{');
+            self.entries(&entries, &indent, "");
+            self.buf.push('\n');
+            self.buf.push_str(&indent);
+            self.buf.push('}');
+        }
+    }
+
+    /// Write list entries on separate lines, one level deeper than `indent`.
+    fn entries(&mut self, entries: &[Entry], indent: &str, separator: &str) {
+        for entry in entries {
+            match entry {
+                Entry::Item(item) => {
+                    self.newline(indent);
+                    self.code(item, false);
+                    self.buf.push_str(separator);
+                }
+                Entry::TrailingComment(comment) => {
+                    self.buf.push(' ');
+                    self.verbatim(comment);
+                }
+                Entry::Comment(comment) => {
+                    self.newline(indent);
+                    self.verbatim(comment);
+                }
+                Entry::Blank => self.buf.push('\n'),
+            }
+        }
+    }
+
+    /// Start a new line that is indented one level deeper than `indent`.
+    fn newline(&mut self, indent: &str) {
+        self.buf.push('\n');
+        self.buf.push_str(indent);
+        self.buf.push_str(INDENT);
+    }
+
+    /// Whether flat text fits onto the current line. Always true if `flat` is
+    /// true, as then the caller decides.
+    fn fits(&self, text: &str, flat: bool) -> bool {
+        !text.contains('\n')
+            && (flat || self.column() + text.chars().count() <= MAX_WIDTH)
+    }
+}
+
+/// Split the contents of a list or block into entries.
+///
+/// Separators are dropped and empty lines between entries are kept.
+fn entries<'a>(
+    children: impl Iterator<Item = &'a SyntaxNode>,
+    separators: &[SyntaxKind],
+) -> Vec<Entry<'a>> {
+    let mut entries = vec![];
+    let mut newlines = 0;
+    for child in children {
+        let kind = child.kind();
+        if kind == SyntaxKind::Space {
+            newlines += child.text().matches('\n').count();
+            continue;
+        } else if separators.contains(&kind) {
+            continue;
+        }
+
+        let first = entries.is_empty();
+        if newlines >= 2 && !first {
+            entries.push(Entry::Blank);
+        }
+
+        if is_comment(child) {
+            let after_item = matches!(
+                entries.last(),
+                Some(Entry::Item(_) | Entry::TrailingComment(_))
+            );
+            if newlines == 0 && after_item {
+                entries.push(Entry::TrailingComment(child));
+            } else {
+                entries.push(Entry::Comment(child));
+            }
+        } else {
+            entries.push(Entry::Item(child));
+        }
+
+        newlines = 0;
+    }
+    entries
+}
+
+/// Whether a node is a comment.
+fn is_comment(node: &SyntaxNode) -> bool {
+    matches!(node.kind(), SyntaxKind::LineComment | SyntaxKind::BlockComment)
+}
+
+/// Whether a node is whitespace that contains a line break.
+fn breaks(node: &SyntaxNode) -> bool {
+    node.kind() == SyntaxKind::Space && node.text().contains('\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format;
+
+    #[track_caller]
+    fn test(text: &str, expected: &str) {
+        let formatted = format(text).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(format(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_format_spacing() {
+        test("#let x=1+2", "#let x = 1 + 2");
+        test("#f( a,b:  1 ,..c )[*A*]", "#f(a, b: 1, ..c)[*A*]");
+        test("#let f(x)=(x,)", "#let f(x) = (x,)");
+        test("#show heading:it=>{it.body}", "#show heading: it => { it.body }");
+        test("#set text( red ) if  not  x", "#set text(red) if not x");
+    }
+
+    #[test]
+    fn test_format_markup_untouched() {
+        let text = "= Title\n\nSome   *strong*   text,  _emph_ and $x+y$.\n  - item\n";
+        test(text, text);
+        test("Hi #box[  a  #f( x ) ] there", "Hi #box[  a  #f(x) ] there");
+    }
+
+    #[test]
+    fn test_format_code_blocks() {
+        test(
+            "#{\nlet x = 1\n      let y = 2;  x\n\n\n  // Done.\n  y // Value.\n}",
+            "#{\n  let x = 1\n  let y = 2\n  x\n\n  // Done.\n  y // Value.\n}",
+        );
+        test("#{let x = 1;x}", "#{ let x = 1; x }");
+    }
+
+    #[test]
+    fn test_format_lists() {
+        test("#let d = (a: 1,\n  b: (1, 2))", "#let d = (\n  a: 1,\n  b: (1, 2),\n)");
+        test(
+            "#table(columns: (1fr, 2fr, 3fr), [A very long cell], [Another long cell], [C], [D])",
+            "#table(\n  columns: (1fr, 2fr, 3fr),\n  [A very long cell],\n  [Another long cell],\n  [C],\n  [D],\n)",
+        );
+        test("#let x = (:)\n#let y = ()", "#let x = (:)\n#let y = ()");
+    }
+
+    #[test]
+    fn test_format_errors() {
+        let message = "cannot format a file with syntax errors";
+        assert_eq!(format("#let x = (").unwrap_err().as_str(), message);
+    }
+}
@@ -0,0 +1,251 @@
+use std::ops::Range;
+
+use ecow::EcoString;
+use serde::{Deserialize, Serialize};
+use typst::foundations::{Repr, Value};
+use typst::model::Document;
+use typst::syntax::ast::AstNode;
+use typst::syntax::{ast, LinkedNode, Source, SyntaxKind};
+use typst::World;
+
+use crate::analyze::analyze_expr;
+use crate::complete::resolve_global_callee;
+
+/// The maximum length of a value hint's text.
+const MAX_VALUE_LEN: usize = 40;
+
+/// The maximum number of bindings that are evaluated for value hints.
+const MAX_VALUE_HINTS: usize = 10;
+
+/// Find inlay hints in a byte range of a source file.
+///
+/// Produces hints with parameter names for the positional arguments of calls
+/// to global functions and hints with the values of simple `let` bindings.
+///
+/// Value hints require compiling the document once per binding, so they are
+/// only produced when a `document` from a previous compilation is passed and
+/// only for the first few bindings in the range. The range should thus be
+/// restricted to what's visible in the editor.
+pub fn inlay_hints(
+    world: &dyn World,
+    document: Option<&Document>,
+    source: &Source,
+    range: Range<usize>,
+) -> Vec<InlayHint> {
+    let mut budget = if document.is_some() { MAX_VALUE_HINTS } else { 0 };
+    let mut hints = vec![];
+    collect(world, &LinkedNode::new(source.root()), &range, &mut budget, &mut hints);
+    hints
+}
+
+/// A hint that is shown inline in the source code.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct InlayHint {
+    /// The kind of the hint.
+    pub kind: InlayHintKind,
+    /// The byte offset at which the hint is shown.
+    pub offset: usize,
+    /// The text of the hint: The name of a parameter or the repr of a value.
+    pub label: EcoString,
+}
+
+/// A kind of inlay hint.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum InlayHintKind {
+    /// The name of the parameter an argument is passed to. Shown before the
+    /// argument.
+    Param,
+    /// The value of a `let` binding. Shown after the binding.
+    Value,
+}
+
+/// Collect the hints of a node and its descendants within the range.
+///
+/// The budget is the number of bindings that may still be evaluated.
+fn collect(
+    world: &dyn World,
+    node: &LinkedNode,
+    range: &Range<usize>,
+    budget: &mut usize,
+    hints: &mut Vec<InlayHint>,
+) {
+    if node.kind() == SyntaxKind::Args {
+        param_hints(world, node, range, hints);
+    } else if node.kind() == SyntaxKind::LetBinding {
+        value_hint(world, node, range, budget, hints);
+    }
+
+    for child in node.children() {
+        let child_range = child.range();
+        if child_range.start <= range.end && range.start <= child_range.end {
+            collect(world, &child, range, budget, hints);
+        }
+    }
+}
+
+/// Add hints with parameter names for the positional arguments in the
+/// parentheses of a function call or set rule.
+fn param_hints(
+    world: &dyn World,
+    node: &LinkedNode,
+    range: &Range<usize>,
+    hints: &mut Vec<InlayHint>,
+) {
+    let Some(parent) = node.parent() else { return };
+    let callee = match parent.cast::<ast::Expr>() {
+        Some(ast::Expr::FuncCall(call)) => call.callee(),
+        Some(ast::Expr::Set(set)) => set.target(),
+        _ => return,
+    };
+
+    let global = world.library().global.scope();
+    let Some(func) = resolve_global_callee(global, callee) else { return };
+    let Some(params) = func.params() else { return };
+
+    let mut positional = params.iter().filter(|param| param.positional);
+    let mut current = positional.next();
+    let mut first = true;
+    let mut in_parens = false;
+
+    for child in node.children() {
+        match child.kind() {
+            SyntaxKind::LeftParen => in_parens = true,
+            SyntaxKind::RightParen => in_parens = false,
+            _ => {}
+        }
+
+        let expr = match child.get().cast::<ast::Arg>() {
+            Some(ast::Arg::Pos(expr)) => expr,
+            Some(ast::Arg::Named(_)) | None => continue,
+            // We can't know how many arguments a spread produces.
+            Some(ast::Arg::Spread(_)) => break,
+        };
+
+        let Some(param) = current else { break };
+
+        // Only the first argument that is passed to a variadic parameter
+        // receives a hint.
+        let hinted = first;
+        if param.variadic {
+            first = false;
+        } else {
+            current = positional.next();
+        }
+
+        // Trailing content blocks and arguments that already carry the
+        // parameter's name don't need a hint.
+        let redundant =
+            matches!(expr, ast::Expr::Ident(ident) if ident.as_str() == param.name);
+        if !hinted || !in_parens || redundant || !range.contains(&child.offset()) {
+            continue;
+        }
+
+        hints.push(InlayHint {
+            kind: InlayHintKind::Param,
+            offset: child.offset(),
+            label: param.name.into(),
+        });
+    }
+}
+
+/// Add a hint with the value of a binding of the form `let x = expr`.
+fn value_hint(
+    world: &dyn World,
+    node: &LinkedNode,
+    range: &Range<usize>,
+    budget: &mut usize,
+    hints: &mut Vec<InlayHint>,
+) {
+    let Some(binding) = node.cast::<ast::LetBinding>() else { return };
+    let ast::LetBindingKind::Normal(ast::Pattern::Normal(ast::Expr::Ident(ident))) =
+        binding.kind()
+    else {
+        return;
+    };
+
+    // Literals and functions show their values already.
+    match binding.init() {
+        Some(ast::Expr::Closure(_)) | None => return,
+        Some(init) if init.is_literal() => return,
+        Some(_) => {}
+    }
+
+    let end = node.range().end;
+    if end < range.start || end > range.end || *budget == 0 {
+        return;
+    }
+
+    let Some(ident) = node.find(ident.span()) else { return };
+    *budget -= 1;
+    let values = analyze_expr(world, &ident);
+    let [(value, _)] = values.as_slice() else { return };
+    if matches!(value, Value::Content(_) | Value::Func(_) | Value::Module(_)) {
+        return;
+    }
+
+    let repr = value.repr();
+    if repr.chars().count() > MAX_VALUE_LEN {
+        return;
+    }
+
+    hints.push(InlayHint {
+        kind: InlayHintKind::Value,
+        offset: end,
+        label: repr,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use typst::eval::Tracer;
+
+    use super::{inlay_hints, InlayHintKind, MAX_VALUE_HINTS};
+    use crate::tests::TestWorld;
+
+    #[track_caller]
+    fn test(text: &str, range: Range<usize>, expected: &[(InlayHintKind, usize, &str)]) {
+        let world = TestWorld::new(text);
+        let doc = typst::compile(&world, &mut Tracer::new()).ok();
+        let hints: Vec<_> = inlay_hints(&world, doc.as_ref(), &world.main, range)
+            .into_iter()
+            .map(|hint| (hint.kind, hint.offset, hint.label))
+            .collect();
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|&(kind, offset, label)| (kind, offset, label.into()))
+            .collect();
+        assert_eq!(hints, expected);
+    }
+
+    #[test]
+    fn test_inlay_hints_params() {
+        use InlayHintKind::*;
+        test("#calc.pow(2, 3)", 0..15, &[(Param, 10, "base"), (Param, 13, "exponent")]);
+        test("#calc.pow(2, 3)", 12..15, &[(Param, 13, "exponent")]);
+        test("#let base = 2\n#calc.pow(base, 3)", 0..32, &[(Param, 30, "exponent")]);
+        test("#grid(columns: 2, [a], [b])", 0..27, &[(Param, 18, "children")]);
+        test("#strong[a]", 0..10, &[]);
+    }
+
+    #[test]
+    fn test_inlay_hints_values() {
+        use InlayHintKind::*;
+        let text = "#let x = 1 + 2\n#let y = x\n#let z = [a]\n#let f(a) = a";
+        test(text, 0..text.len(), &[(Value, 14, "3"), (Value, 25, "3")]);
+        test(text, 20..text.len(), &[(Value, 25, "3")]);
+
+        // Only the first bindings are evaluated.
+        let text = "#let x = 1 + 2\n".repeat(MAX_VALUE_HINTS + 2);
+        let world = TestWorld::new(&text);
+        let doc = typst::compile(&world, &mut Tracer::new()).ok();
+        let hints = inlay_hints(&world, doc.as_ref(), &world.main, 0..text.len());
+        assert_eq!(hints.len(), MAX_VALUE_HINTS);
+
+        // Without a document, no bindings are evaluated.
+        let hints = inlay_hints(&world, None, &world.main, 0..text.len());
+        assert!(hints.is_empty());
+    }
+}
@@ -1,13 +1,38 @@
 //! Capabilities for Typst IDE support.
 
+mod actions;
 mod analyze;
 mod complete;
+mod definition;
+mod folding;
+mod format;
+mod inlay;
 mod jump;
+mod lint;
+mod references;
+mod selection;
+mod signature;
+mod symbols;
+mod tokens;
 mod tooltip;
 
+pub use self::actions::{code_actions, CodeAction};
 pub use self::analyze::analyze_labels;
 pub use self::complete::{autocomplete, Completion, CompletionKind};
+pub use self::definition::definition;
+pub use self::folding::{folding_ranges, FoldingKind, FoldingRange};
+pub use self::format::format;
+pub use self::inlay::{inlay_hints, InlayHint, InlayHintKind};
 pub use self::jump::{jump_from_click, jump_from_cursor, Jump};
+pub use self::lint::lint;
+pub use self::references::{references, rename};
+pub use self::selection::selection_ranges;
+pub use self::signature::{signature_help, SignatureHelp, SignatureParam};
+pub use self::symbols::{document_symbols, DocumentSymbol, SymbolKind};
+pub use self::tokens::{
+    diff_tokens, encode_tokens, semantic_tokens, SemanticToken, SemanticTokens,
+    TokenModifier, TokensEdit,
+};
 pub use self::tooltip::{tooltip, Tooltip};
 
 use std::fmt::Write;
@@ -96,7 +121,7 @@ mod tests {
     use once_cell::sync::Lazy;
     use typst::diag::{FileError, FileResult};
     use typst::foundations::{Bytes, Datetime};
-    use typst::syntax::{FileId, Source};
+    use typst::syntax::{FileId, Source, VirtualPath};
     use typst::text::{Font, FontBook};
     use typst::utils::LazyHash;
     use typst::{Library, World};
@@ -104,6 +129,7 @@ mod tests {
     /// A world for IDE testing.
     pub struct TestWorld {
         pub main: Source,
+        sources: Vec<Source>,
         base: &'static TestBase,
     }
 
@@ -115,7 +141,14 @@ mod tests {
         pub fn new(text: &str) -> Self {
             static BASE: Lazy<TestBase> = Lazy::new(TestBase::default);
             let main = Source::detached(text);
-            Self { main, base: &*BASE }
+            Self { main, sources: vec![], base: &*BASE }
+        }
+
+        /// Add another source file at the given path.
+        pub fn with_source(mut self, path: &str, text: &str) -> Self {
+            let id = FileId::new(None, VirtualPath::new(path));
+            self.sources.push(Source::new(id, text.into()));
+            self
         }
     }
 
@@ -133,11 +166,11 @@ mod tests {
         }
 
         fn source(&self, id: FileId) -> FileResult<Source> {
-            if id == self.main.id() {
-                Ok(self.main.clone())
-            } else {
-                Err(FileError::NotFound(id.vpath().as_rootless_path().into()))
-            }
+            std::iter::once(&self.main)
+                .chain(&self.sources)
+                .find(|source| source.id() == id)
+                .cloned()
+                .ok_or_else(|| FileError::NotFound(id.vpath().as_rootless_path().into()))
         }
 
         fn file(&self, id: FileId) -> FileResult<Bytes> {
@@ -0,0 +1,480 @@
+use std::collections::HashSet;
+
+use ecow::{eco_format, EcoString};
+use typst::diag::SourceDiagnostic;
+use typst::eval::Tracer;
+use typst::syntax::ast::AstNode;
+use typst::syntax::{ast, FileId, LinkedNode, Source, Span, SyntaxKind, SyntaxNode};
+use typst::World;
+
+use crate::analyze::analyze_import;
+
+/// Check the main file and the files it imports for likely mistakes.
+///
+/// Warns about unused bindings and imports, bindings that shadow a
+/// parameter, and unreachable code after `return`, `break`, and `continue`.
+/// Top-level bindings of imported files are exported and thus never unused.
+/// A `// lint-allow: <lint>, ..` comment suppresses the listed lints on its
+/// own and the following line.
+pub fn lint(world: &dyn World, tracer: &mut Tracer) {
+    let mut seen = HashSet::new();
+    let mut queue = vec![(world.main().id(), true)];
+    while let Some((id, main)) = queue.pop() {
+        if !seen.insert(id) {
+            continue;
+        }
+
+        let Ok(source) = world.source(id) else { continue };
+        for warning in lint_source(world, &source, main) {
+            tracer.warn(warning);
+        }
+
+        queue.extend(file_imports(&source).into_iter().map(|id| (id, false)));
+    }
+}
+
+/// A kind of warning reported by [`lint`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+enum Lint {
+    UnusedBinding,
+    UnusedImport,
+    ShadowedParameter,
+    UnreachableCode,
+}
+
+impl Lint {
+    /// The list of all lints.
+    const LIST: &'static [Self] = &[
+        Self::UnusedBinding,
+        Self::UnusedImport,
+        Self::ShadowedParameter,
+        Self::UnreachableCode,
+    ];
+
+    /// The name by which the lint is suppressed.
+    fn name(self) -> &'static str {
+        match self {
+            Self::UnusedBinding => "unused-binding",
+            Self::UnusedImport => "unused-import",
+            Self::ShadowedParameter => "shadowed-parameter",
+            Self::UnreachableCode => "unreachable-code",
+        }
+    }
+}
+
+/// Lint a single file, dropping the warnings suppressed by pragmas.
+fn lint_source(world: &dyn World, source: &Source, main: bool) -> Vec<SourceDiagnostic> {
+    let mut linter = Linter {
+        world,
+        root: LinkedNode::new(source.root()),
+        exported: !main,
+        scopes: vec![vec![]],
+        wildcards: vec![],
+        warnings: vec![],
+    };
+
+    linter.visit(source.root());
+    linter.exit();
+    for (span, used, reported) in std::mem::take(&mut linter.wildcards) {
+        if !used && reported {
+            linter.warn(Lint::UnusedImport, span, "unused wildcard import".into());
+        }
+    }
+
+    let allowed = pragmas(source);
+    linter
+        .warnings
+        .into_iter()
+        .filter(|(lint, warning)| {
+            let line =
+                source.range(warning.span).and_then(|r| source.byte_to_line(r.start));
+            !line.is_some_and(|line| allowed.contains(&(line, *lint)))
+        })
+        .map(|(_, warning)| warning)
+        .collect()
+}
+
+/// Find the lints suppressed on each line.
+fn pragmas(source: &Source) -> HashSet<(usize, Lint)> {
+    let mut allowed = HashSet::new();
+    let mut comments = vec![];
+    collect(&LinkedNode::new(source.root()), SyntaxKind::LineComment, &mut comments);
+    for comment in comments {
+        let text = comment.text().trim_start_matches('/').trim();
+        let Some(lints) = text.strip_prefix("lint-allow:") else { continue };
+        let Some(line) = source.byte_to_line(comment.offset()) else { continue };
+        for name in lints.split(',').map(str::trim) {
+            let Some(&lint) = Lint::LIST.iter().find(|lint| lint.name() == name) else {
+                continue;
+            };
+            allowed.insert((line, lint));
+            allowed.insert((line + 1, lint));
+        }
+    }
+    allowed
+}
+
+/// Find the files that are imported or included by a source file through
+/// relative or absolute paths.
+fn file_imports(source: &Source) -> Vec<FileId> {
+    let mut nodes = vec![];
+    collect(&LinkedNode::new(source.root()), SyntaxKind::ModuleImport, &mut nodes);
+    collect(&LinkedNode::new(source.root()), SyntaxKind::ModuleInclude, &mut nodes);
+    nodes
+        .into_iter()
+        .filter_map(|node| {
+            let expr = match node.cast::<ast::Expr>()? {
+                ast::Expr::Import(import) => import.source(),
+                ast::Expr::Include(include) => include.source(),
+                _ => return None,
+            };
+            match expr {
+                ast::Expr::Str(path) if !path.get().starts_with('@') => {
+                    Some(source.id().join(&path.get()))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Collect a node and its descendants that are of a specific kind.
+fn collect<'a>(node: &LinkedNode<'a>, kind: SyntaxKind, nodes: &mut Vec<LinkedNode<'a>>) {
+    if node.kind() == kind {
+        nodes.push(node.clone());
+    }
+    for child in node.children() {
+        collect(&child, kind, nodes);
+    }
+}
+
+/// Walks a file while tracking the bindings in scope.
+struct Linter<'a> {
+    world: &'a dyn World,
+    root: LinkedNode<'a>,
+    /// Whether top-level bindings are exported to importing files.
+    exported: bool,
+    scopes: Vec<Vec<Binding>>,
+    /// The wildcard imports, whether one of their names was used, and
+    /// whether they should be reported if not.
+    wildcards: Vec<(Span, bool, bool)>,
+    warnings: Vec<(Lint, SourceDiagnostic)>,
+}
+
+/// A name bound in a scope.
+struct Binding {
+    name: EcoString,
+    span: Span,
+    kind: BindingKind,
+    used: bool,
+}
+
+/// How a name was bound.
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum BindingKind {
+    /// By a let binding.
+    Let,
+    /// By an import of the module or one of its items.
+    Import,
+    /// By a wildcard import, with the index of the import.
+    Wildcard(usize),
+    /// By a closure parameter.
+    Param,
+    /// By a for loop or as the name of a closure within its body.
+    Other,
+}
+
+impl Linter<'_> {
+    /// Visit a node and its descendants.
+    fn visit(&mut self, node: &SyntaxNode) {
+        match node.cast() {
+            Some(ast::Expr::Ident(ident)) => self.mark(&ident),
+            Some(ast::Expr::MathIdent(ident)) => self.mark(&ident),
+
+            // Code and content blocks create a scope.
+            Some(ast::Expr::Code(_) | ast::Expr::Content(_)) => {
+                self.enter();
+                for child in node.children() {
+                    self.visit(child);
+                }
+                self.exit();
+            }
+
+            // The field of a field access isn't a variable.
+            Some(ast::Expr::FieldAccess(access)) => {
+                self.visit(access.target().to_untyped());
+            }
+
+            // Default values are evaluated outside of the closure's scope.
+            Some(ast::Expr::Closure(closure)) => {
+                for param in closure.params().children() {
+                    if let ast::Param::Named(named) = param {
+                        self.visit(named.expr().to_untyped());
+                    }
+                }
+
+                self.enter();
+                if let Some(name) = closure.name() {
+                    self.bind(name, BindingKind::Other);
+                }
+
+                for param in closure.params().children() {
+                    let idents = match param {
+                        ast::Param::Pos(pattern) => pattern.bindings(),
+                        ast::Param::Named(named) => vec![named.name()],
+                        ast::Param::Spread(spread) => {
+                            spread.sink_ident().into_iter().collect()
+                        }
+                    };
+                    for ident in idents {
+                        self.bind(ident, BindingKind::Param);
+                    }
+                }
+
+                self.visit(closure.body().to_untyped());
+                self.exit();
+            }
+
+            // The binding is only active after the initializer is evaluated.
+            Some(ast::Expr::Let(binding)) => {
+                if let Some(init) = binding.init() {
+                    self.visit(init.to_untyped());
+                }
+                for ident in binding.kind().bindings() {
+                    self.bind(ident, BindingKind::Let);
+                }
+            }
+
+            Some(ast::Expr::For(for_loop)) => {
+                self.visit(for_loop.iterable().to_untyped());
+                self.enter();
+                for ident in for_loop.pattern().bindings() {
+                    self.bind(ident, BindingKind::Other);
+                }
+                self.visit(for_loop.body().to_untyped());
+                self.exit();
+            }
+
+            Some(ast::Expr::Import(import)) => {
+                self.visit(import.source().to_untyped());
+                self.import(node, import);
+            }
+
+            _ => {
+                // The name of a named pair isn't a variable.
+                if let Some(named) = node.cast::<ast::Named>() {
+                    self.visit(named.expr().to_untyped());
+                    return;
+                }
+
+                if let Some(code) = node.cast::<ast::Code>() {
+                    self.unreachable(code.exprs());
+                } else if let Some(markup) = node.cast::<ast::Markup>() {
+                    self.unreachable(markup.exprs());
+                }
+
+                for child in node.children() {
+                    self.visit(child);
+                }
+            }
+        }
+    }
+
+    /// Bind the names of a module import.
+    fn import(&mut self, node: &SyntaxNode, import: ast::ModuleImport) {
+        if let Some(new_name) = import.new_name() {
+            self.bind(new_name, BindingKind::Import);
+        }
+
+        let module = || {
+            let source = self.root.find(import.source().span())?;
+            analyze_import(self.world, &source)
+        };
+
+        match import.imports() {
+            None => {
+                if import.new_name().is_some()
+                    || !matches!(import.source(), ast::Expr::Str(_))
+                {
+                    return;
+                }
+                let Some(name) =
+                    module().and_then(|module| module.name().map(EcoString::from))
+                else {
+                    return;
+                };
+                self.define(name, import.source().span(), BindingKind::Import);
+            }
+            Some(ast::Imports::Wildcard) => {
+                let Some(module) = module() else { return };
+                let Some(scope) = module.scope() else { return };
+                let index = self.wildcards.len();
+                let reported = !(self.exported && self.scopes.len() == 1);
+                self.wildcards.push((node.span(), false, reported));
+                for (name, _) in scope.iter() {
+                    self.define(name.clone(), node.span(), BindingKind::Wildcard(index));
+                }
+            }
+            Some(ast::Imports::Items(items)) => {
+                for item in items.iter() {
+                    self.bind(item.bound_name(), BindingKind::Import);
+                }
+            }
+        }
+    }
+
+    /// Warn about the first expression after one that always jumps away.
+    fn unreachable<'b>(&mut self, exprs: impl Iterator<Item = ast::Expr<'b>>) {
+        let mut jumped = false;
+        for expr in exprs {
+            if matches!(expr, ast::Expr::Space(_) | ast::Expr::Parbreak(_)) {
+                continue;
+            }
+            if jumped {
+                self.warn(Lint::UnreachableCode, expr.span(), "unreachable code".into());
+                return;
+            }
+            jumped = matches!(
+                expr,
+                ast::Expr::Return(_) | ast::Expr::Break(_) | ast::Expr::Continue(_)
+            );
+        }
+    }
+
+    /// Bind an identifier in the innermost scope.
+    fn bind(&mut self, ident: ast::Ident, kind: BindingKind) {
+        if kind != BindingKind::Param
+            && self
+                .lookup(&ident)
+                .is_some_and(|binding| binding.kind == BindingKind::Param)
+        {
+            let message = eco_format!("binding shadows parameter: {}", ident.as_str());
+            self.warn(Lint::ShadowedParameter, ident.span(), message);
+        }
+        self.define(ident.get().clone(), ident.span(), kind);
+    }
+
+    /// Define a name in the innermost scope.
+    fn define(&mut self, name: EcoString, span: Span, kind: BindingKind) {
+        let binding = Binding { name, span, kind, used: false };
+        self.scopes.last_mut().unwrap().push(binding);
+    }
+
+    /// Find the binding a name refers to.
+    fn lookup(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .flat_map(|scope| scope.iter_mut().rev())
+            .find(|binding| binding.name == name)
+    }
+
+    /// Mark the binding a name refers to as used.
+    fn mark(&mut self, name: &str) {
+        let Some(binding) = self.lookup(name) else { return };
+        binding.used = true;
+        if let BindingKind::Wildcard(index) = binding.kind {
+            self.wildcards[index].1 = true;
+        }
+    }
+
+    /// Enter a new scope.
+    fn enter(&mut self) {
+        self.scopes.push(vec![]);
+    }
+
+    /// Exit the innermost scope and warn about its unused bindings.
+    fn exit(&mut self) {
+        let scope = self.scopes.pop().unwrap();
+        if self.exported && self.scopes.is_empty() {
+            return;
+        }
+
+        for binding in scope {
+            if binding.used || binding.name.starts_with('_') {
+                continue;
+            }
+            let (lint, message) = match binding.kind {
+                BindingKind::Let => (Lint::UnusedBinding, "unused variable"),
+                BindingKind::Import => (Lint::UnusedImport, "unused import"),
+                _ => continue,
+            };
+            let message = eco_format!("{message}: {}", binding.name);
+            self.warn(lint, binding.span, message);
+        }
+    }
+
+    /// Add a warning.
+    fn warn(&mut self, lint: Lint, span: Span, message: EcoString) {
+        let hint =
+            eco_format!("add `// lint-allow: {}` to suppress this warning", lint.name());
+        self.warnings
+            .push((lint, SourceDiagnostic::warning(span, message).with_hint(hint)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::eval::Tracer;
+    use typst::World;
+
+    use super::lint;
+    use crate::tests::TestWorld;
+
+    #[track_caller]
+    fn test(world: &TestWorld, expected: &[(&str, &str)]) {
+        let mut tracer = Tracer::new();
+        lint(world, &mut tracer);
+        let warnings: Vec<_> = tracer
+            .warnings()
+            .iter()
+            .map(|warning| {
+                let source = world.source(warning.span.id().unwrap()).unwrap();
+                let text = source.get(source.range(warning.span).unwrap()).unwrap();
+                (warning.message.to_string(), text.to_string())
+            })
+            .collect();
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|&(message, text)| (message.to_string(), text.to_string()))
+            .collect();
+        assert_eq!(warnings, expected);
+    }
+
+    #[test]
+    fn test_lint_unused() {
+        let world =
+            TestWorld::new("#import \"lib.typ\": a, b\n#let x = 1\n#let _y = 2\n#a")
+                .with_source("lib.typ", "#let a = 1\n#let b = 2");
+        test(&world, &[("unused import: b", "b"), ("unused variable: x", "x")]);
+
+        let world = TestWorld::new("#import \"lib.typ\": *\n#import \"lib.typ\"")
+            .with_source("lib.typ", "#let a = 1\n#let b = { let c = 2; 3 }");
+        test(
+            &world,
+            &[
+                ("unused import: lib", "\"lib.typ\""),
+                ("unused wildcard import", "import \"lib.typ\": *"),
+                ("unused variable: c", "c"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_lint_shadowed_and_unreachable() {
+        let world = TestWorld::new(
+            "#let f(x) = { let x = x + 1; x }\n#let g() = { return 1; 2 }\n#f(1) #g()",
+        );
+        test(&world, &[("binding shadows parameter: x", "x"), ("unreachable code", "2")]);
+    }
+
+    #[test]
+    fn test_lint_pragmas() {
+        test(&TestWorld::new("// lint-allow: unused-binding\n#let x = 1"), &[]);
+        test(&TestWorld::new("#let x = 1 // lint-allow: unused-binding"), &[]);
+        test(
+            &TestWorld::new("// lint-allow: unused-import\n#let x = 1"),
+            &[("unused variable: x", "x")],
+        );
+    }
+}
@@ -0,0 +1,390 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use ecow::{eco_format, EcoString};
+use typst::diag::{bail, StrResult};
+use typst::syntax::{
+    ast, is_id_continue, is_ident, FileId, LinkedNode, Source, Span, SyntaxKind,
+};
+use typst::World;
+
+use crate::definition::{file_definition, find_definition, leaf_at, scope_definition};
+
+/// Find all references to the item under the cursor.
+///
+/// Returns the ranges of all references, including the definition itself,
+/// grouped by file. The files are discovered by following the imports and
+/// includes of the main file and of `source`. For labels, the ranges only
+/// span the name, without the `@` or angle brackets.
+pub fn references(
+    world: &dyn World,
+    source: &Source,
+    cursor: usize,
+) -> Vec<(FileId, Vec<Range<usize>>)> {
+    let Some(target) = leaf_at(source, cursor).and_then(|leaf| target(world, &leaf))
+    else {
+        return vec![];
+    };
+
+    find_references(world, source, &target)
+        .into_iter()
+        .map(|(id, nodes)| (id, nodes.into_iter().map(|node| node.range).collect()))
+        .collect()
+}
+
+/// Rename the item under the cursor.
+///
+/// Returns the ranges that need to be replaced with the new name, grouped by
+/// file. Fails if there is nothing to rename at the cursor, if the new name is
+/// invalid, or if it would shadow or be shadowed by another definition.
+pub fn rename(
+    world: &dyn World,
+    source: &Source,
+    cursor: usize,
+    new_name: &str,
+) -> StrResult<Vec<(FileId, Vec<Range<usize>>)>> {
+    let Some(leaf) = leaf_at(source, cursor) else {
+        bail!("there is nothing to rename here");
+    };
+    let Some(target) = target(world, &leaf) else {
+        bail!("this cannot be renamed");
+    };
+
+    let references = find_references(world, source, &target);
+    match &target {
+        Target::Ident { name, span } => {
+            if span.id().is_some_and(|id| id.package().is_some()) {
+                bail!("cannot rename definitions in packages");
+            }
+            check_ident_rename(world, &references, name, *span, new_name)?;
+        }
+        Target::Label(name) => {
+            if name == new_name {
+                return Ok(vec![]);
+            }
+            check_label_rename(world, source, new_name)?;
+        }
+    }
+
+    Ok(references
+        .into_iter()
+        .map(|(id, nodes)| (id, nodes.into_iter().map(|node| node.range).collect()))
+        .collect())
+}
+
+/// The item references are searched for.
+enum Target {
+    /// A definition bound to an identifier.
+    Ident { name: EcoString, span: Span },
+    /// A label.
+    Label(EcoString),
+}
+
+/// A single found reference.
+struct Reference {
+    /// The range of the name in the file.
+    range: Range<usize>,
+    /// The span of the referencing node.
+    span: Span,
+    /// The kind of the referencing node.
+    kind: SyntaxKind,
+    /// Whether the reference is the field of a field access or an item in an
+    /// import list, i.e. whether it is not resolved in the local scope.
+    external: bool,
+}
+
+/// Determine what a leaf refers to.
+fn target(world: &dyn World, leaf: &LinkedNode) -> Option<Target> {
+    match leaf.kind() {
+        SyntaxKind::Ident | SyntaxKind::MathIdent => {
+            let span = find_definition(world, None, leaf)?;
+            let source = world.source(span.id()?).ok()?;
+            let node = source.find(span)?;
+            if !matches!(node.kind(), SyntaxKind::Ident | SyntaxKind::MathIdent) {
+                return None;
+            }
+            Some(Target::Ident { name: node.text().clone(), span })
+        }
+        SyntaxKind::RefMarker | SyntaxKind::Label => {
+            Some(Target::Label(label_name(leaf.text()).into()))
+        }
+        _ => None,
+    }
+}
+
+/// Find all references to a target.
+fn find_references(
+    world: &dyn World,
+    source: &Source,
+    target: &Target,
+) -> Vec<(FileId, Vec<Reference>)> {
+    let mut output = vec![];
+    for source in workspace(world, source) {
+        let mut found = vec![];
+        let root = LinkedNode::new(source.root());
+        visit(&root, &mut |node| match (node.kind(), target) {
+            (SyntaxKind::Ident | SyntaxKind::MathIdent, Target::Ident { name, span })
+                if node.text() == name
+                    && find_definition(world, None, node) == Some(*span) =>
+            {
+                found.push(Reference {
+                    range: node.range(),
+                    span: node.span(),
+                    kind: node.kind(),
+                    external: is_external(node),
+                });
+            }
+            (SyntaxKind::RefMarker | SyntaxKind::Label, Target::Label(name))
+                if label_name(node.text()) == name =>
+            {
+                let range = node.range();
+                let end = range.end - usize::from(node.kind() == SyntaxKind::Label);
+                found.push(Reference {
+                    range: range.start + 1..end,
+                    span: node.span(),
+                    kind: node.kind(),
+                    external: false,
+                });
+            }
+            _ => {}
+        });
+
+        if !found.is_empty() {
+            output.push((source.id(), found));
+        }
+    }
+    output
+}
+
+/// Ensure that renaming an identifier doesn't change the meaning of the
+/// program.
+fn check_ident_rename(
+    world: &dyn World,
+    references: &[(FileId, Vec<Reference>)],
+    name: &str,
+    span: Span,
+    new_name: &str,
+) -> StrResult<()> {
+    if !is_ident(new_name) {
+        bail!("`{new_name}` is not a valid identifier");
+    }
+
+    if name == new_name {
+        return Ok(());
+    }
+
+    let library = world.library();
+    for (id, references) in references {
+        let source = world.source(*id).map_err(|err| eco_format!("{err}"))?;
+        let root = LinkedNode::new(source.root());
+        for reference in references {
+            let math = reference.kind == SyntaxKind::MathIdent;
+            if math && !is_math_ident(new_name) {
+                bail!("`{new_name}` cannot be used as an identifier in math");
+            }
+
+            // Fields and imported items are resolved in the file that
+            // defines the item, which is checked below.
+            if reference.external {
+                continue;
+            }
+
+            let Some(node) = root.find(reference.span) else { continue };
+            if scope_definition(world, &node, new_name).is_some() {
+                bail!("`{new_name}` is already defined");
+            }
+
+            let scope = if math { library.math.scope() } else { library.global.scope() };
+            if scope.get(new_name).is_some() {
+                bail!("`{new_name}` would shadow a built-in definition");
+            }
+        }
+    }
+
+    // The new name must not already be defined at the top-level of the file
+    // the item is imported from elsewhere.
+    let external = references
+        .iter()
+        .flat_map(|(_, references)| references)
+        .any(|r| r.external);
+    if let Some(id) = span.id().filter(|_| external) {
+        if file_definition(world, id, new_name).is_some() {
+            bail!("`{new_name}` is already defined");
+        }
+    }
+
+    Ok(())
+}
+
+/// Ensure that renaming a label doesn't clash with an existing label.
+fn check_label_rename(
+    world: &dyn World,
+    source: &Source,
+    new_name: &str,
+) -> StrResult<()> {
+    if new_name.is_empty()
+        || new_name.ends_with(['.', ':'])
+        || !new_name.chars().all(|c| is_id_continue(c) || matches!(c, ':' | '.'))
+    {
+        bail!("`{new_name}` is not a valid label");
+    }
+
+    let existing = find_references(world, source, &Target::Label(new_name.into()));
+    if existing
+        .iter()
+        .flat_map(|(_, references)| references)
+        .any(|reference| reference.kind == SyntaxKind::Label)
+    {
+        bail!("label `<{new_name}>` already exists");
+    }
+
+    Ok(())
+}
+
+/// Whether a string is a valid identifier in math.
+fn is_math_ident(string: &str) -> bool {
+    is_ident(string) && string.chars().count() > 1 && !string.contains(['_', '-'])
+}
+
+/// Whether an identifier is resolved outside of the local scope.
+fn is_external(node: &LinkedNode) -> bool {
+    match node.parent_kind() {
+        Some(SyntaxKind::FieldAccess) => node.index() > 0,
+        Some(SyntaxKind::ImportItems) => true,
+        Some(SyntaxKind::RenamedImportItem) => node.prev_sibling().is_none(),
+        _ => false,
+    }
+}
+
+/// The name of a label or reference.
+fn label_name(text: &str) -> &str {
+    text.trim_start_matches(['@', '<']).trim_end_matches('>')
+}
+
+/// Call a function for all descendants of a node.
+fn visit<'a>(node: &LinkedNode<'a>, f: &mut impl FnMut(&LinkedNode<'a>)) {
+    f(node);
+    for child in node.children() {
+        visit(&child, f);
+    }
+}
+
+/// Find all files that are reachable from the main file and the given source
+/// through imports and includes of paths.
+fn workspace(world: &dyn World, source: &Source) -> Vec<Source> {
+    let mut seen = HashSet::new();
+    let mut queue = vec![world.main(), source.clone()];
+    let mut output = vec![];
+
+    while let Some(source) = queue.pop() {
+        if !seen.insert(source.id()) {
+            continue;
+        }
+
+        let root = LinkedNode::new(source.root());
+        visit(&root, &mut |node| {
+            let path = match node.cast::<ast::Expr>() {
+                Some(ast::Expr::Import(import)) => import.source(),
+                Some(ast::Expr::Include(include)) => include.source(),
+                _ => return,
+            };
+            let ast::Expr::Str(path) = path else { return };
+            let path = path.get();
+            if path.starts_with('@') {
+                return;
+            }
+            if let Some(source) = node
+                .span()
+                .resolve_path(&path)
+                .ok()
+                .and_then(|id| world.source(id).ok())
+            {
+                queue.push(source);
+            }
+        });
+
+        output.push(source);
+    }
+
+    output.sort_by_key(|source| source.id());
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use typst::syntax::FileId;
+
+    use super::{references, rename};
+    use crate::tests::TestWorld;
+
+    type Found = Vec<(&'static str, Vec<Range<usize>>)>;
+
+    fn paths(found: Vec<(FileId, Vec<Range<usize>>)>) -> Found {
+        found
+            .into_iter()
+            .map(|(id, ranges)| (id.vpath().as_rootless_path().to_str().unwrap(), ranges))
+            .collect()
+    }
+
+    #[track_caller]
+    fn test(world: &TestWorld, cursor: usize, expected: Found) {
+        assert_eq!(paths(references(world, &world.main, cursor)), expected);
+    }
+
+    #[track_caller]
+    fn test_rename(
+        world: &TestWorld,
+        cursor: usize,
+        new_name: &str,
+        expected: Result<Found, &str>,
+    ) {
+        let found = rename(world, &world.main, cursor, new_name).map(paths);
+        assert_eq!(found, expected.map_err(Into::into));
+    }
+
+    #[test]
+    fn test_references_local() {
+        let world = TestWorld::new("#let x = 1\n#let f(x) = x\n#x #f(x)");
+        test(&world, 6, vec![("main.typ", vec![5..6, 26..27, 31..32])]);
+        test(&world, 19, vec![("main.typ", vec![18..19, 23..24])]);
+    }
+
+    #[test]
+    fn test_references_imports() {
+        let world =
+            TestWorld::new("#import \"lib.typ\": f\n#import \"lib.typ\"\n#f #lib.f")
+                .with_source("lib.typ", "#let f = 1\n#f");
+        let expected = vec![
+            ("main.typ", vec![19..20, 40..41, 47..48]),
+            ("lib.typ", vec![5..6, 12..13]),
+        ];
+        test(&world, 41, expected.clone());
+        test_rename(&world, 41, "g", Ok(expected));
+    }
+
+    #[test]
+    fn test_references_labels() {
+        let world = TestWorld::new("= A <a>\n@a @b\n#include \"lib.typ\"")
+            .with_source("lib.typ", "See @a and @a.");
+        let expected =
+            vec![("main.typ", vec![5..6, 9..10]), ("lib.typ", vec![5..6, 12..13])];
+        test(&world, 10, expected.clone());
+        test_rename(&world, 10, "intro", Ok(expected));
+
+        let world = TestWorld::new("= A <a>\n= B <b>\n@a");
+        test_rename(&world, 5, "b", Err("label `<b>` already exists"));
+        test_rename(&world, 5, "b:c.", Err("`b:c.` is not a valid label"));
+    }
+
+    #[test]
+    fn test_rename_errors() {
+        let world = TestWorld::new("#let x = 1\n#let y = 2\n#x");
+        test_rename(&world, 6, "y", Err("`y` is already defined"));
+        test_rename(&world, 6, "a b", Err("`a b` is not a valid identifier"));
+        test_rename(&world, 6, "text", Err("`text` would shadow a built-in definition"));
+        test_rename(&world, 6, "z", Ok(vec![("main.typ", vec![5..6, 23..24])]));
+        test_rename(&world, 3, "z", Err("there is nothing to rename here"));
+    }
+}
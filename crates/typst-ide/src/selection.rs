@@ -0,0 +1,60 @@
+use std::ops::Range;
+
+use typst::syntax::{LinkedNode, Side, Source};
+
+/// Find the ranges a selection can be expanded to from each cursor.
+///
+/// For each cursor, returns the byte ranges of the syntax node at the cursor
+/// and all of its ancestors, from the innermost to the outermost. Ranges that
+/// are equal to the previous one are skipped.
+pub fn selection_ranges(source: &Source, cursors: &[usize]) -> Vec<Vec<Range<usize>>> {
+    let root = LinkedNode::new(source.root());
+    cursors
+        .iter()
+        .map(|&cursor| {
+            // Prefer the leaf after the cursor unless it's trivia and there is
+            // a meaningful one before the cursor.
+            let after = root.leaf_at(cursor, Side::After);
+            let leaf = match after {
+                Some(leaf) if !leaf.kind().is_trivia() => Some(leaf),
+                _ => root
+                    .leaf_at(cursor, Side::Before)
+                    .filter(|leaf| !leaf.kind().is_trivia())
+                    .or(after),
+            };
+
+            let mut ranges: Vec<Range<usize>> = vec![];
+            let mut node = leaf;
+            while let Some(current) = node {
+                let range = current.range();
+                if ranges.last() != Some(&range) {
+                    ranges.push(range);
+                }
+                node = current.parent().cloned();
+            }
+            ranges
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use typst::syntax::Source;
+
+    use super::selection_ranges;
+
+    #[track_caller]
+    fn test(text: &str, cursor: usize, expected: &[Range<usize>]) {
+        let source = Source::detached(text);
+        assert_eq!(selection_ranges(&source, &[cursor]), [expected.to_vec()]);
+    }
+
+    #[test]
+    fn test_selection_ranges() {
+        test("#f(x + 1)", 3, &[3..4, 3..8, 2..9, 1..9, 0..9]);
+        test("#f(x + 1)", 4, &[3..4, 3..8, 2..9, 1..9, 0..9]);
+        test("= A *b*", 5, &[5..6, 4..7, 2..7, 0..7]);
+    }
+}
@@ -0,0 +1,334 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+use ecow::{eco_format, EcoString};
+use serde::{Deserialize, Serialize};
+use typst::foundations::{CastInfo, Func, ParamInfo, Type, Value};
+use typst::model::Document;
+use typst::syntax::ast::AstNode;
+use typst::syntax::{ast, LinkedNode, Side, Source, SyntaxKind};
+use typst::World;
+
+use crate::analyze::analyze_expr;
+use crate::complete::resolve_global_callee;
+use crate::plain_docs_sentence;
+
+/// Describe the signature of the function call the cursor is in.
+///
+/// Works for the arguments of function calls and set rules.
+///
+/// Passing a `document` (from a previous compilation) is optional, but
+/// enables help for user-defined closures and other functions that aren't in
+/// the global scope. These are resolved through expression analysis, which
+/// compiles the document again. Without a document or if the analysis fails,
+/// the callee is looked up in the global scope.
+pub fn signature_help(
+    world: &dyn World,
+    document: Option<&Document>,
+    source: &Source,
+    cursor: usize,
+) -> Option<SignatureHelp> {
+    let leaf = LinkedNode::new(source.root()).leaf_at(cursor, Side::Before)?;
+    let (callee, args) = find_call(&leaf, cursor)?;
+
+    let analyzed = document.and_then(|_| {
+        analyze_expr(world, &callee)
+            .into_iter()
+            .find_map(|(value, _)| match value {
+                Value::Func(func) => Some(func),
+                _ => None,
+            })
+    });
+
+    let func = match analyzed {
+        Some(func) => func,
+        None => {
+            let global = world.library().global.scope();
+            resolve_global_callee(global, callee.cast()?)?.clone()
+        }
+    };
+
+    let params = match func.params() {
+        Some(params) => params.iter().map(native_param).collect(),
+        None => closure_params(world, &func)?,
+    };
+
+    let mut label = callee.get().clone().into_text();
+    label.push('(');
+    let mut ranges = vec![];
+    for (i, param) in params.iter().enumerate() {
+        if i > 0 {
+            label.push_str(", ");
+        }
+        let start = label.len();
+        label.push_str(&param.label);
+        ranges.push(start..label.len());
+    }
+    label.push(')');
+
+    if let Some(returns) = func.returns() {
+        write!(label, " -> {}", describe(returns)).unwrap();
+    }
+
+    let active = active_param(&params, &args, cursor);
+    Some(SignatureHelp {
+        label,
+        docs: func.docs().map(plain_docs_sentence),
+        params: params
+            .into_iter()
+            .zip(ranges)
+            .map(|(param, label)| SignatureParam { label, docs: param.docs })
+            .collect(),
+        active,
+    })
+}
+
+/// The signature of a function with the parameter the cursor is on.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SignatureHelp {
+    /// The rendered signature, e.g. `lower(text: str | content) -> str | content`.
+    pub label: EcoString,
+    /// The first sentence of the function's documentation, if any.
+    pub docs: Option<EcoString>,
+    /// The function's parameters.
+    pub params: Vec<SignatureParam>,
+    /// The index of the parameter the cursor is on, if it can be determined.
+    pub active: Option<usize>,
+}
+
+/// A parameter in a [signature](SignatureHelp).
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SignatureParam {
+    /// The byte range of the parameter in the signature's label.
+    pub label: Range<usize>,
+    /// The first sentence of the parameter's documentation, if any.
+    pub docs: Option<EcoString>,
+}
+
+/// A parameter as it is matched against the arguments of a call.
+struct Param {
+    /// How the parameter is shown in the signature.
+    label: EcoString,
+    /// The parameter's documentation.
+    docs: Option<EcoString>,
+    /// The name with which the parameter can be given as a named argument.
+    named: Option<EcoString>,
+    /// Whether the parameter can be given positionally.
+    positional: bool,
+    /// Whether the parameter takes any number of positional arguments.
+    variadic: bool,
+}
+
+/// Find the callee and arguments of the innermost call whose parentheses
+/// contain the cursor.
+fn find_call<'a>(
+    leaf: &LinkedNode<'a>,
+    cursor: usize,
+) -> Option<(LinkedNode<'a>, LinkedNode<'a>)> {
+    let mut node = leaf.clone();
+    loop {
+        let parent = node.parent()?.clone();
+        if parent.kind() == SyntaxKind::Args && in_parens(&parent, cursor) {
+            let grand = parent.parent()?;
+            let callee = match grand.cast::<ast::Expr>() {
+                Some(ast::Expr::FuncCall(call)) => Some(call.callee()),
+                Some(ast::Expr::Set(set)) => Some(set.target()),
+                _ => None,
+            };
+
+            if let Some(callee) = callee.and_then(|callee| grand.find(callee.span())) {
+                return Some((callee, parent));
+            }
+        }
+        node = parent;
+    }
+}
+
+/// Whether the cursor is between the parentheses of an argument list.
+fn in_parens(args: &LinkedNode, cursor: usize) -> bool {
+    let mut children = args.children();
+    let Some(open) = children.find(|child| child.kind() == SyntaxKind::LeftParen) else {
+        return false;
+    };
+
+    let close = children.find(|child| child.kind() == SyntaxKind::RightParen);
+    open.range().end <= cursor && close.map_or(true, |close| cursor <= close.offset())
+}
+
+/// Determine the index of the parameter the argument at the cursor is matched
+/// to.
+fn active_param(params: &[Param], args: &LinkedNode, cursor: usize) -> Option<usize> {
+    // Find the argument at the cursor and count the positional ones before it.
+    let mut positional = 0;
+    let mut current = None;
+    for child in args.children() {
+        if child.kind() == SyntaxKind::RightParen || child.offset() >= cursor {
+            break;
+        }
+
+        match child.kind() {
+            SyntaxKind::Comma => {
+                if matches!(current, Some(ast::Arg::Pos(_))) {
+                    positional += 1;
+                }
+                current = None;
+            }
+            _ => {
+                if let Some(arg) = child.get().cast::<ast::Arg>() {
+                    current = Some(arg);
+                }
+            }
+        }
+    }
+
+    match current {
+        Some(ast::Arg::Named(named)) => params
+            .iter()
+            .position(|param| param.named.as_deref() == Some(named.name().as_str())),
+        Some(ast::Arg::Spread(_)) => None,
+        Some(ast::Arg::Pos(_)) | None => {
+            for (i, param) in params.iter().enumerate() {
+                if !param.positional {
+                    continue;
+                }
+                if positional == 0 || param.variadic {
+                    return Some(i);
+                }
+                positional -= 1;
+            }
+            None
+        }
+    }
+}
+
+/// Describe a parameter of a built-in function.
+fn native_param(param: &ParamInfo) -> Param {
+    let mut label = EcoString::new();
+    if param.variadic {
+        label.push_str("..");
+    }
+    write!(label, "{}: {}", param.name, describe(&param.input)).unwrap();
+
+    Param {
+        label,
+        docs: Some(plain_docs_sentence(param.docs)),
+        named: param.named.then(|| param.name.into()),
+        positional: param.positional,
+        variadic: param.variadic,
+    }
+}
+
+/// Describe the parameters of a closure from its syntax.
+fn closure_params(world: &dyn World, func: &Func) -> Option<Vec<Param>> {
+    let span = func.span();
+    let source = world.source(span.id()?).ok()?;
+    let node = source.find(span)?;
+    let closure = node.parent()?.cast::<ast::Closure>()?;
+
+    let params = closure
+        .params()
+        .children()
+        .map(|param| match param {
+            ast::Param::Pos(pattern) => Param {
+                label: pattern.to_untyped().clone().into_text(),
+                docs: None,
+                named: None,
+                positional: true,
+                variadic: false,
+            },
+            ast::Param::Named(named) => Param {
+                label: eco_format!(
+                    "{}: {}",
+                    named.name().as_str(),
+                    named.expr().to_untyped().clone().into_text()
+                ),
+                docs: None,
+                named: Some(named.name().get().clone()),
+                positional: false,
+                variadic: false,
+            },
+            ast::Param::Spread(spread) => Param {
+                label: spread.to_untyped().clone().into_text(),
+                docs: None,
+                named: None,
+                positional: true,
+                variadic: true,
+            },
+        })
+        .collect();
+
+    Some(params)
+}
+
+/// Describe the values a parameter accepts or a function returns by their
+/// types, e.g. `length | auto`.
+fn describe(info: &CastInfo) -> EcoString {
+    let mut types: Vec<Option<Type>> = vec![];
+    info.walk(|info| {
+        let ty = match info {
+            CastInfo::Any => None,
+            CastInfo::Value(value, _) => Some(value.ty()),
+            CastInfo::Type(ty) => Some(*ty),
+            CastInfo::Union(_) => return,
+        };
+        if !types.contains(&ty) {
+            types.push(ty);
+        }
+    });
+
+    let parts: Vec<&str> = types
+        .into_iter()
+        .map(|ty| ty.map_or("any", |ty| ty.short_name()))
+        .collect();
+    parts.join(" | ").into()
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::eval::Tracer;
+
+    use super::signature_help;
+    use crate::tests::TestWorld;
+
+    #[track_caller]
+    fn test(text: &str, cursor: usize, expected: Option<(&str, Option<usize>)>) {
+        let world = TestWorld::new(text);
+        let doc = typst::compile(&world, &mut Tracer::new()).ok();
+        let help = signature_help(&world, doc.as_ref(), &world.main, cursor);
+        let found = help.as_ref().map(|help| (help.label.as_str(), help.active));
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_signature_builtin() {
+        let label = "calc.pow(base: int | float, exponent: int | float) -> int | float";
+        test("#calc.pow(2, 3)", 10, Some((label, Some(0))));
+        test("#calc.pow(2, 3)", 13, Some((label, Some(1))));
+        test("#calc.pow(2, 3)", 15, None);
+    }
+
+    #[test]
+    fn test_signature_param_labels() {
+        let world = TestWorld::new("#lower(\"A\")");
+        let help = signature_help(&world, None, &world.main, 7).unwrap();
+        assert_eq!(help.active, Some(0));
+        assert_eq!(&help.label[help.params[0].label.clone()], "text: str | content");
+
+        let world = TestWorld::new("#rect(fill: red, )");
+        let help = signature_help(&world, None, &world.main, 12).unwrap();
+        let active = &help.label[help.params[help.active.unwrap()].label.clone()];
+        assert!(active.starts_with("fill: "));
+    }
+
+    #[test]
+    fn test_signature_closure() {
+        let text = "#let f(a, b: 1, ..c) = a\n#f(1, 2, b: 3)";
+        test(text, 28, Some(("f(a, b: 1, ..c)", Some(0))));
+        test(text, 31, Some(("f(a, b: 1, ..c)", Some(2))));
+        test(text, 35, Some(("f(a, b: 1, ..c)", Some(1))));
+
+        // Closures are only resolved with a document.
+        let world = TestWorld::new(text);
+        assert_eq!(signature_help(&world, None, &world.main, 28), None);
+    }
+}
@@ -0,0 +1,344 @@
+use std::ops::Range;
+
+use ecow::EcoString;
+use serde::{Deserialize, Serialize};
+use typst::syntax::ast::AstNode;
+use typst::syntax::{ast, LinkedNode, Source, SyntaxKind, SyntaxNode};
+
+/// Create a hierarchical outline of a source file.
+///
+/// Finds headings, `let` bindings, labels, and figures. Headings contain
+/// everything up to the next heading of the same or a lower depth and the
+/// other symbols contain the symbols in their bodies. This works purely
+/// syntactically and thus doesn't require compiling the document.
+pub fn document_symbols(source: &Source) -> Vec<DocumentSymbol> {
+    let mut items = vec![];
+    collect(&LinkedNode::new(source.root()), &mut items);
+    nest(items)
+}
+
+/// A symbol in the outline of a source file.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DocumentSymbol {
+    /// The kind of the symbol.
+    pub kind: SymbolKind,
+    /// The name of the symbol. May be empty for empty headings.
+    pub name: EcoString,
+    /// The byte range of the whole symbol, including its children.
+    pub range: Range<usize>,
+    /// The byte range of the symbol's name, e.g. of the identifier of a
+    /// binding.
+    pub selection_range: Range<usize>,
+    /// The symbols nested in this one.
+    pub children: Vec<DocumentSymbol>,
+}
+
+/// A kind of symbol in a source file.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SymbolKind {
+    /// A section heading.
+    Heading,
+    /// A function bound with `let`.
+    Func,
+    /// A variable bound with `let`.
+    Variable,
+    /// A label.
+    Label,
+    /// A figure.
+    Figure,
+}
+
+/// A symbol together with its heading depth, which is zero for all symbols
+/// that aren't headings.
+type Item = (usize, DocumentSymbol);
+
+/// Collect the symbols in the children of a node.
+fn collect(node: &LinkedNode, items: &mut Vec<Item>) {
+    for child in node.children() {
+        visit(&child, items);
+    }
+}
+
+/// Collect the symbols of a node, descending into it if it isn't a symbol
+/// itself.
+fn visit(node: &LinkedNode, items: &mut Vec<Item>) {
+    if let Some(heading) = node.cast::<ast::Heading>() {
+        let mut children = vec![];
+        collect(node, &mut children);
+        items.push((
+            heading.depth().get(),
+            DocumentSymbol {
+                kind: SymbolKind::Heading,
+                name: plain_text(heading.body().to_untyped()),
+                range: node.range(),
+                selection_range: node.range(),
+                children: nest(children),
+            },
+        ));
+    } else if let Some(binding) = node.cast::<ast::LetBinding>() {
+        visit_binding(node, binding, items);
+    } else if let Some(label) = node.cast::<ast::Label>() {
+        items.push((
+            0,
+            DocumentSymbol {
+                kind: SymbolKind::Label,
+                name: label.get().into(),
+                range: node.range(),
+                selection_range: node.range(),
+                children: vec![],
+            },
+        ));
+    } else if let Some(call) = node.cast::<ast::FuncCall>().filter(is_figure) {
+        let caption = call.args().items().find_map(|arg| match arg {
+            ast::Arg::Named(named) if named.name().as_str() == "caption" => {
+                Some(plain_text(named.expr().to_untyped()))
+            }
+            _ => None,
+        });
+
+        let mut children = vec![];
+        collect(node, &mut children);
+        items.push((
+            0,
+            DocumentSymbol {
+                kind: SymbolKind::Figure,
+                name: caption
+                    .filter(|caption| !caption.is_empty())
+                    .unwrap_or("figure".into()),
+                range: node.range(),
+                selection_range: node.range(),
+                children: nest(children),
+            },
+        ));
+    } else {
+        collect(node, items);
+    }
+}
+
+/// Collect the symbols of a let binding.
+fn visit_binding(node: &LinkedNode, binding: ast::LetBinding, items: &mut Vec<Item>) {
+    let idents = binding.kind().bindings();
+    let init = binding.init();
+
+    // The body of a function or the initializer of a single variable belongs
+    // to the binding. With destructuring, it belongs to none of the bound
+    // variables in particular.
+    let mut children = vec![];
+    let body = match init {
+        Some(ast::Expr::Closure(closure)) => Some(closure.body().to_untyped()),
+        Some(expr) => Some(expr.to_untyped()),
+        None => None,
+    };
+    if let Some(body) = body.and_then(|body| node.find(body.span())) {
+        visit(&body, &mut children);
+    }
+
+    let kind = match init {
+        Some(ast::Expr::Closure(_)) => SymbolKind::Func,
+        _ => SymbolKind::Variable,
+    };
+
+    if let [ident] = idents.as_slice() {
+        let Some(ident) = node.find(ident.span()) else { return };
+        items.push((
+            0,
+            DocumentSymbol {
+                kind,
+                name: ident.text().clone(),
+                range: node.range(),
+                selection_range: ident.range(),
+                children: nest(children),
+            },
+        ));
+        return;
+    }
+
+    for ident in idents {
+        let Some(ident) = node.find(ident.span()) else { continue };
+        items.push((
+            0,
+            DocumentSymbol {
+                kind,
+                name: ident.text().clone(),
+                range: ident.range(),
+                selection_range: ident.range(),
+                children: vec![],
+            },
+        ));
+    }
+
+    items.extend(children);
+}
+
+/// Whether a function call creates a figure.
+fn is_figure(call: &ast::FuncCall) -> bool {
+    matches!(call.callee(), ast::Expr::Ident(ident) if ident.as_str() == "figure")
+}
+
+/// Nest symbols below the headings that precede them.
+fn nest(items: Vec<Item>) -> Vec<DocumentSymbol> {
+    let mut output = vec![];
+    let mut stack: Vec<Item> = vec![];
+
+    for (depth, symbol) in items {
+        if depth > 0 {
+            while stack.last().is_some_and(|&(prev, _)| prev >= depth) {
+                close(&mut stack, &mut output);
+            }
+            stack.push((depth, symbol));
+        } else if let Some((_, parent)) = stack.last_mut() {
+            parent.children.push(symbol);
+        } else {
+            output.push(symbol);
+        }
+    }
+
+    while !stack.is_empty() {
+        close(&mut stack, &mut output);
+    }
+
+    output
+}
+
+/// Close the innermost open heading, extending its range over its children.
+fn close(stack: &mut Vec<Item>, output: &mut Vec<DocumentSymbol>) {
+    let Some((_, mut symbol)) = stack.pop() else { return };
+    if let Some(last) = symbol.children.last() {
+        symbol.range.end = symbol.range.end.max(last.range.end);
+    }
+
+    match stack.last_mut() {
+        Some((_, parent)) => parent.children.push(symbol),
+        None => output.push(symbol),
+    }
+}
+
+/// Extract the plain text of a piece of markup, ignoring labels and
+/// collapsing whitespace.
+fn plain_text(node: &SyntaxNode) -> EcoString {
+    fn walk(node: &SyntaxNode, buf: &mut String) {
+        match node.kind() {
+            SyntaxKind::Str => buf.push_str(node.text().trim_matches('"')),
+            SyntaxKind::Text
+            | SyntaxKind::Escape
+            | SyntaxKind::Shorthand
+            | SyntaxKind::SmartQuote => buf.push_str(node.text()),
+            SyntaxKind::Space | SyntaxKind::Linebreak | SyntaxKind::Parbreak => {
+                buf.push(' ')
+            }
+            SyntaxKind::Label => {}
+            _ => {
+                for child in node.children() {
+                    walk(child, buf);
+                }
+            }
+        }
+    }
+
+    let mut buf = String::new();
+    walk(node, &mut buf);
+    buf.split_whitespace().collect::<Vec<_>>().join(" ").into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use typst::syntax::Source;
+
+    use super::{document_symbols, DocumentSymbol, SymbolKind};
+
+    /// A symbol as `(kind, name, range, children)`.
+    #[derive(Debug, Eq, PartialEq)]
+    struct Outline(SymbolKind, String, Range<usize>, Vec<Outline>);
+
+    fn outline(symbols: Vec<DocumentSymbol>) -> Vec<Outline> {
+        symbols
+            .into_iter()
+            .map(|symbol| {
+                Outline(
+                    symbol.kind,
+                    symbol.name.to_string(),
+                    symbol.range,
+                    outline(symbol.children),
+                )
+            })
+            .collect()
+    }
+
+    #[track_caller]
+    fn test(text: &str, expected: Vec<Outline>) {
+        let source = Source::detached(text);
+        assert_eq!(outline(document_symbols(&source)), expected);
+    }
+
+    fn sym(
+        kind: SymbolKind,
+        name: &str,
+        range: Range<usize>,
+        children: Vec<Outline>,
+    ) -> Outline {
+        Outline(kind, name.into(), range, children)
+    }
+
+    #[test]
+    fn test_symbols_headings() {
+        use SymbolKind::*;
+        test(
+            "= A\n== B <b>\n=== C\n== D\n= _E_",
+            vec![
+                sym(
+                    Heading,
+                    "A",
+                    0..23,
+                    vec![
+                        sym(
+                            Heading,
+                            "B",
+                            4..18,
+                            vec![
+                                sym(Label, "b", 9..12, vec![]),
+                                sym(Heading, "C", 13..18, vec![]),
+                            ],
+                        ),
+                        sym(Heading, "D", 19..23, vec![]),
+                    ],
+                ),
+                sym(Heading, "E", 24..29, vec![]),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_symbols_bindings() {
+        use SymbolKind::*;
+        test(
+            "#let f(x) = { let y = x; y }\n#let (a, b) = (1, 2)\n#let g = x => x",
+            vec![
+                sym(Func, "f", 1..28, vec![sym(Variable, "y", 14..23, vec![])]),
+                sym(Variable, "a", 35..36, vec![]),
+                sym(Variable, "b", 38..39, vec![]),
+                sym(Func, "g", 51..65, vec![]),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_symbols_figures() {
+        use SymbolKind::*;
+        test(
+            "= Results\n#figure([A], caption: [The *first* one]) <fig>\n#figure[B]",
+            vec![sym(
+                Heading,
+                "Results",
+                0..67,
+                vec![
+                    sym(Figure, "The first one", 11..50, vec![]),
+                    sym(Label, "fig", 51..56, vec![]),
+                    sym(Figure, "figure", 58..67, vec![]),
+                ],
+            )],
+        );
+    }
+}
@@ -0,0 +1,352 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use typst::syntax::{highlight, is_newline, LinkedNode, Source, SyntaxKind, Tag};
+
+/// Find the semantic tokens on the lines that overlap with a byte range of a
+/// source file.
+///
+/// Tokens are flattened from the [highlighting tags](highlight) of the
+/// syntax tree: Each piece of text gets the tag of its innermost highlighted
+/// node and tokens never span multiple lines.
+pub fn semantic_tokens(source: &Source, range: Range<usize>) -> Vec<SemanticToken> {
+    let start = line_start(source, range.start);
+    let end = line_end(source, range.end.max(start));
+    let mut tokens = vec![];
+    collect(source, &LinkedNode::new(source.root()), &(start..end), 0, None, &mut tokens);
+    tokens
+}
+
+/// The semantic tokens of a source file, which can be updated incrementally
+/// as the file is edited.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct SemanticTokens {
+    tokens: Vec<SemanticToken>,
+}
+
+impl SemanticTokens {
+    /// Find all semantic tokens in a source file.
+    pub fn new(source: &Source) -> Self {
+        Self {
+            tokens: semantic_tokens(source, 0..source.len_bytes()),
+        }
+    }
+
+    /// The tokens, in the order they appear in the file.
+    pub fn tokens(&self) -> &[SemanticToken] {
+        &self.tokens
+    }
+
+    /// Edit the source file and update the tokens.
+    ///
+    /// Only the tokens on the lines that were reparsed are recomputed, the
+    /// ones after them are merely shifted. Returns the range in the new source
+    /// that was ultimately reparsed, just like [`Source::edit`].
+    ///
+    /// The method panics if the `replace` range is out of bounds.
+    #[track_caller]
+    pub fn edit(
+        &mut self,
+        source: &mut Source,
+        replace: Range<usize>,
+        with: &str,
+    ) -> Range<usize> {
+        let old_len = source.len_bytes();
+        let old_line = source.byte_to_line(replace.end).unwrap();
+        let reparsed = source.edit(replace.clone(), with);
+
+        let end = replace.start + with.len();
+        let new_line = source.byte_to_line(end).unwrap();
+        let region = line_start(source, reparsed.start.min(replace.start))
+            ..line_end(source, reparsed.end.max(end));
+
+        // The text after the region is the same as before the edit.
+        let old_region_end = region.end + old_len - source.len_bytes();
+        let first = self.tokens.partition_point(|token| token.range.start < region.start);
+        let last = self
+            .tokens
+            .partition_point(|token| token.range.start < old_region_end);
+
+        for token in &mut self.tokens[last..] {
+            token.range.start = token.range.start + source.len_bytes() - old_len;
+            token.range.end = token.range.end + source.len_bytes() - old_len;
+            token.line = token.line + new_line - old_line;
+        }
+
+        let fresh = semantic_tokens(source, region);
+        self.tokens.splice(first..last, fresh);
+        reparsed
+    }
+}
+
+/// A piece of text that is highlighted by the editor.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct SemanticToken {
+    /// The byte range of the token in the source file.
+    pub range: Range<usize>,
+    /// The zero-based line the token is on.
+    pub line: usize,
+    /// The column at which the token starts, in UTF-16 code units.
+    pub column: usize,
+    /// The length of the token in UTF-16 code units.
+    pub length: usize,
+    /// How the token is highlighted.
+    pub tag: Tag,
+    /// The bit set of [modifiers](TokenModifier) that apply to the token.
+    pub modifiers: u32,
+}
+
+/// A modifier that applies to a semantic token in addition to its tag.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenModifier {
+    /// The token is within strong markup.
+    Strong,
+    /// The token is within emphasized markup.
+    Emph,
+    /// The token is within an equation.
+    Math,
+}
+
+impl TokenModifier {
+    /// The list of all modifiers, in the same order as they are defined.
+    pub const LIST: &'static [Self] = &[Self::Strong, Self::Emph, Self::Math];
+
+    /// The modifier's bit in a token's set of modifiers.
+    pub fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// Encode tokens relative to each other as the Language Server Protocol
+/// expects them.
+///
+/// Each token is encoded as five integers: The difference to the previous
+/// token's line, its column (relative to the previous token's column if on
+/// the same line), its length, the index of its tag in [`Tag::LIST`], and its
+/// modifiers.
+pub fn encode_tokens(tokens: &[SemanticToken]) -> Vec<u32> {
+    let mut data = Vec::with_capacity(5 * tokens.len());
+    let mut prev = (0, 0);
+    for token in tokens {
+        let column =
+            if token.line == prev.0 { token.column - prev.1 } else { token.column };
+        data.extend([
+            (token.line - prev.0) as u32,
+            column as u32,
+            token.length as u32,
+            token.tag as u32,
+            token.modifiers,
+        ]);
+        prev = (token.line, token.column);
+    }
+    data
+}
+
+/// Find the single edit that turns one list of encoded tokens into another.
+///
+/// Returns `None` if the lists are equal.
+pub fn diff_tokens(old: &[u32], new: &[u32]) -> Option<TokensEdit> {
+    if old == new {
+        return None;
+    }
+
+    // Compare whole tokens so that the edit doesn't split one.
+    let prefix = old.chunks(5).zip(new.chunks(5)).take_while(|(a, b)| a == b).count();
+    let suffix = old[5 * prefix..]
+        .rchunks(5)
+        .zip(new[5 * prefix..].rchunks(5))
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    Some(TokensEdit {
+        start: 5 * prefix,
+        delete: old.len() - 5 * (prefix + suffix),
+        data: new[5 * prefix..new.len() - 5 * suffix].to_vec(),
+    })
+}
+
+/// An edit of a list of encoded tokens.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct TokensEdit {
+    /// The index of the first integer to replace.
+    pub start: usize,
+    /// How many integers to remove.
+    pub delete: usize,
+    /// The integers to insert instead.
+    pub data: Vec<u32>,
+}
+
+/// Collect the tokens of a node and its descendants within the line-aligned
+/// region.
+fn collect(
+    source: &Source,
+    node: &LinkedNode,
+    region: &Range<usize>,
+    mut modifiers: u32,
+    mut tag: Option<Tag>,
+    tokens: &mut Vec<SemanticToken>,
+) {
+    if let Some(own) = highlight(node) {
+        tag = Some(own);
+        match own {
+            Tag::Strong => modifiers |= TokenModifier::Strong.bit(),
+            Tag::Emph => modifiers |= TokenModifier::Emph.bit(),
+            _ => {}
+        }
+    }
+
+    if node.kind() == SyntaxKind::Equation {
+        modifiers |= TokenModifier::Math.bit();
+    }
+
+    if node.text().is_empty() {
+        for child in node.children() {
+            let range = child.range();
+            if range.start < region.end && region.start < range.end {
+                collect(source, &child, region, modifiers, tag, tokens);
+            }
+        }
+        return;
+    }
+
+    let Some(tag) = tag else { return };
+
+    // Split the leaf at line breaks.
+    let mut start = node.offset();
+    for (i, c) in node.text().char_indices().chain([(node.len(), '\n')]) {
+        let end = node.offset() + i;
+        if is_newline(c) {
+            if start < end && region.start <= start && start < region.end {
+                push(source, start..end, tag, modifiers, tokens);
+            }
+            start = end + c.len_utf8();
+        }
+    }
+}
+
+/// Add a token, merging it with the previous one if they are adjacent and
+/// highlighted in the same way.
+fn push(
+    source: &Source,
+    range: Range<usize>,
+    tag: Tag,
+    modifiers: u32,
+    tokens: &mut Vec<SemanticToken>,
+) {
+    let length = source.text()[range.clone()].encode_utf16().count();
+    if let Some(prev) = tokens.last_mut() {
+        if prev.range.end == range.start && prev.tag == tag && prev.modifiers == modifiers
+        {
+            prev.range.end = range.end;
+            prev.length += length;
+            return;
+        }
+    }
+
+    let line = source.byte_to_line(range.start).unwrap();
+    let line_utf16 = source.byte_to_utf16(source.line_to_byte(line).unwrap()).unwrap();
+    let column = source.byte_to_utf16(range.start).unwrap() - line_utf16;
+    tokens.push(SemanticToken { range, line, column, length, tag, modifiers });
+}
+
+/// The byte index at which the line containing the byte index starts.
+fn line_start(source: &Source, byte: usize) -> usize {
+    let byte = byte.min(source.len_bytes());
+    source.line_to_byte(source.byte_to_line(byte).unwrap()).unwrap()
+}
+
+/// The byte index at which the line after the one containing the byte index
+/// starts, or the end of the file.
+fn line_end(source: &Source, byte: usize) -> usize {
+    let byte = byte.min(source.len_bytes());
+    source.line_to_range(source.byte_to_line(byte).unwrap()).unwrap().end
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use typst::syntax::{Source, Tag};
+
+    use super::{
+        diff_tokens, encode_tokens, semantic_tokens, SemanticTokens, TokenModifier,
+    };
+
+    #[track_caller]
+    fn test(
+        text: &str,
+        range: Range<usize>,
+        expected: &[(usize, usize, usize, Tag, u32)],
+    ) {
+        let source = Source::detached(text);
+        let tokens: Vec<_> = semantic_tokens(&source, range)
+            .into_iter()
+            .map(|token| {
+                (token.line, token.column, token.length, token.tag, token.modifiers)
+            })
+            .collect();
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn test_tokens_full() {
+        use Tag::*;
+        let strong = TokenModifier::Strong.bit();
+        let text = "#let x = 1\n= Hi *a*";
+        test(
+            text,
+            0..text.len(),
+            &[
+                (0, 0, 4, Keyword, 0),
+                (0, 7, 1, Operator, 0),
+                (0, 9, 1, Number, 0),
+                (1, 0, 5, Heading, 0),
+                (1, 5, 3, Strong, strong),
+            ],
+        );
+        test(text, 12..14, &[(1, 0, 5, Heading, 0), (1, 5, 3, Strong, strong)]);
+    }
+
+    #[test]
+    fn test_tokens_utf16_and_lines() {
+        use Tag::*;
+        test("ä 😀 #1", 0..10, &[(0, 5, 2, Number, 0)]);
+        test(
+            "```\na\n```",
+            0..9,
+            &[(0, 0, 3, Raw, 0), (1, 0, 1, Raw, 0), (2, 0, 3, Raw, 0)],
+        );
+    }
+
+    #[test]
+    fn test_tokens_incremental() {
+        let mut source = Source::detached("= A\n#let x = 1\n#f(x)\n\n_b_ $x + 1$");
+        let mut tokens = SemanticTokens::new(&source);
+        for (replace, with) in [
+            (4..5, ""),
+            (12..13, "\"long\"\n"),
+            (0..0, "#{\n"),
+            (20..22, "/* c */"),
+            (3..18, ""),
+        ] {
+            tokens.edit(&mut source, replace, with);
+            assert_eq!(tokens, SemanticTokens::new(&source), "{:?}", source.text());
+        }
+    }
+
+    #[test]
+    fn test_tokens_diff() {
+        let old = Source::detached("#let x = 1\n#x");
+        let new = Source::detached("#let x = 12\n#x");
+        let old = encode_tokens(SemanticTokens::new(&old).tokens());
+        let new = encode_tokens(SemanticTokens::new(&new).tokens());
+        assert_eq!(diff_tokens(&old, &old), None);
+
+        let edit = diff_tokens(&old, &new).unwrap();
+        assert_eq!((edit.start, edit.delete, edit.data.len()), (10, 5, 5));
+        let mut patched = old.clone();
+        patched.splice(edit.start..edit.start + edit.delete, edit.data);
+        assert_eq!(patched, new);
+    }
+}
@@ -276,6 +276,7 @@ fn create(element: &Elem) -> Result<TokenStream> {
     let fields_impl = create_fields_impl(element);
     let repr_impl = element.cannot("Repr").then(|| create_repr_impl(element));
     let locatable_impl = element.can("Locatable").then(|| create_locatable_impl(element));
+    let tagged_impl = element.can("Tagged").then(|| create_tagged_impl(element));
     let into_value_impl = create_into_value_impl(element);
 
     // We use a const block to create an anonymous scope, as to not leak any
@@ -295,6 +296,7 @@ fn create(element: &Elem) -> Result<TokenStream> {
             #partial_eq_impl
             #repr_impl
             #locatable_impl
+            #tagged_impl
             #into_value_impl
         };
     })
@@ -1002,6 +1004,12 @@ fn create_locatable_impl(element: &Elem) -> TokenStream {
     quote! { impl ::typst::introspection::Locatable for #foundations::Packed<#ident> {} }
 }
 
+/// Creates the element's `Tagged` implementation.
+fn create_tagged_impl(element: &Elem) -> TokenStream {
+    let ident = &element.ident;
+    quote! { impl ::typst::introspection::Tagged for #foundations::Packed<#ident> {} }
+}
+
 /// Creates the element's `IntoValue` implementation.
 fn create_into_value_impl(element: &Elem) -> TokenStream {
     let Elem { ident, .. } = element;
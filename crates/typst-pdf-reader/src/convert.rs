@@ -0,0 +1,548 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io::Cursor;
+
+use base64::Engine;
+use ecow::{eco_format, EcoString};
+
+use crate::parse::{Document, Parser};
+use crate::{
+    bail, PdfDict, PdfObject, PdfPage, PdfStream, StrResult, MAX_DASHES, MAX_DEPTH,
+    MAX_OPERATIONS,
+};
+
+/// Converts a PDF page into an SVG.
+pub(crate) struct Converter<'a> {
+    page: &'a PdfPage,
+    /// How many more operators may be interpreted.
+    budget: usize,
+    /// Whether the page contains text, which is left out.
+    text: bool,
+    svg: String,
+    defs: String,
+    clips: usize,
+    /// The IDs of the images that were already defined, keyed by the address
+    /// of their stream. `None` if an image can't be displayed.
+    images: HashMap<*const PdfStream, Option<usize>>,
+    state: State,
+    stack: Vec<State>,
+    path: String,
+    current: (f64, f64),
+    start: (f64, f64),
+    clip: Option<bool>,
+}
+
+/// The graphics state relevant for the conversion.
+#[derive(Clone)]
+struct State {
+    ctm: [f64; 6],
+    fill: [f64; 3],
+    stroke: [f64; 3],
+    fill_alpha: f64,
+    stroke_alpha: f64,
+    line_width: f64,
+    line_cap: i64,
+    line_join: i64,
+    miter_limit: f64,
+    dash: Option<(Vec<f64>, f64)>,
+    /// How many clipping groups were opened in this state.
+    groups: usize,
+}
+
+impl<'a> Converter<'a> {
+    /// Convert the page and return the SVG and whether text was left out.
+    pub(crate) fn convert(page: &'a PdfPage) -> StrResult<(String, bool)> {
+        let (w, h) = (page.width(), page.height());
+        let to_svg = [w, 0.0, 0.0, -h, 0.0, h];
+        let mut converter = Self {
+            page,
+            budget: MAX_OPERATIONS,
+            text: false,
+            svg: String::new(),
+            defs: String::new(),
+            clips: 0,
+            images: HashMap::new(),
+            state: State {
+                ctm: concat(page.matrix(), to_svg),
+                fill: [0.0; 3],
+                stroke: [0.0; 3],
+                fill_alpha: 1.0,
+                stroke_alpha: 1.0,
+                line_width: 1.0,
+                line_cap: 0,
+                line_join: 0,
+                miter_limit: 10.0,
+                dash: None,
+                groups: 0,
+            },
+            stack: vec![],
+            path: String::new(),
+            current: (0.0, 0.0),
+            start: (0.0, 0.0),
+            clip: None,
+        };
+
+        let resources = page.resources().and_then(|r| converter.resolve(r).as_dict());
+        converter.run(page.content(), resources, 0)?;
+        while converter.restore() {}
+
+        let svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" \
+             xmlns:xlink=\"http://www.w3.org/1999/xlink\" \
+             width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\
+             <defs>{}</defs>{}</svg>",
+            converter.defs, converter.svg,
+        );
+        Ok((svg, converter.text))
+    }
+
+    fn resolve(&self, object: &'a PdfObject) -> &'a PdfObject {
+        self.page.document.resolve(object)
+    }
+
+    /// Look up a named resource of the given category.
+    fn resource(
+        &self,
+        resources: Option<&'a PdfDict>,
+        category: &[u8],
+        name: &PdfObject,
+    ) -> Option<&'a PdfObject> {
+        let category = self.resolve(resources?.get(category)?).as_dict()?;
+        Some(self.resolve(category.get(name.as_name()?)?))
+    }
+
+    /// Interpret a content stream.
+    fn run(
+        &mut self,
+        content: &[u8],
+        resources: Option<&'a PdfDict>,
+        depth: usize,
+    ) -> StrResult<()> {
+        let mut parser = Parser::content(content);
+        let mut operands = vec![];
+        while let Some(operator) = parser.parse_operation(&mut operands) {
+            let Some(budget) = self.budget.checked_sub(1) else {
+                bail!("PDF page is too complex to convert");
+            };
+            self.budget = budget;
+
+            let nums: Vec<f64> = operands.iter().filter_map(PdfObject::as_f64).collect();
+            let num = |i: usize| nums.get(i).copied().unwrap_or(0.0);
+            match operator {
+                b"q" => self.save(),
+                b"Q" => {
+                    self.restore();
+                }
+                b"cm" if nums.len() == 6 => {
+                    let m = [num(0), num(1), num(2), num(3), num(4), num(5)];
+                    self.state.ctm = concat(m, self.state.ctm);
+                }
+                b"w" => self.state.line_width = num(0),
+                b"J" => self.state.line_cap = num(0) as i64,
+                b"j" => self.state.line_join = num(0) as i64,
+                b"M" => self.state.miter_limit = num(0),
+                b"d" => {
+                    let array = operands.first().and_then(PdfObject::as_array);
+                    let dashes: Vec<f64> = array
+                        .unwrap_or_default()
+                        .iter()
+                        .filter_map(PdfObject::as_f64)
+                        .take(MAX_DASHES)
+                        .collect();
+                    let phase = operands.get(1).and_then(PdfObject::as_f64);
+                    self.state.dash =
+                        (!dashes.is_empty()).then(|| (dashes, phase.unwrap_or(0.0)));
+                }
+                b"m" => {
+                    self.current = (num(0), num(1));
+                    self.start = self.current;
+                    self.segment('M', &[num(0), num(1)]);
+                }
+                b"l" => {
+                    self.current = (num(0), num(1));
+                    self.segment('L', &[num(0), num(1)]);
+                }
+                b"c" => {
+                    self.current = (num(4), num(5));
+                    self.segment('C', &nums);
+                }
+                b"v" => {
+                    let (x, y) = self.current;
+                    self.current = (num(2), num(3));
+                    self.segment('C', &[x, y, num(0), num(1), num(2), num(3)]);
+                }
+                b"y" => {
+                    self.current = (num(2), num(3));
+                    self.segment('C', &[num(0), num(1), num(2), num(3), num(2), num(3)]);
+                }
+                b"h" => {
+                    self.current = self.start;
+                    self.path.push_str("Z ");
+                }
+                b"re" => {
+                    let (x, y, w, h) = (num(0), num(1), num(2), num(3));
+                    self.current = (x, y);
+                    self.start = self.current;
+                    self.segment('M', &[x, y]);
+                    self.segment('L', &[x + w, y]);
+                    self.segment('L', &[x + w, y + h]);
+                    self.segment('L', &[x, y + h]);
+                    self.path.push_str("Z ");
+                }
+                b"S" => self.paint(None, true),
+                b"s" => {
+                    self.path.push_str("Z ");
+                    self.paint(None, true);
+                }
+                b"f" | b"F" => self.paint(Some(false), false),
+                b"f*" => self.paint(Some(true), false),
+                b"B" => self.paint(Some(false), true),
+                b"B*" => self.paint(Some(true), true),
+                b"b" => {
+                    self.path.push_str("Z ");
+                    self.paint(Some(false), true);
+                }
+                b"b*" => {
+                    self.path.push_str("Z ");
+                    self.paint(Some(true), true);
+                }
+                b"n" => self.paint(None, false),
+                b"BT" => self.text = true,
+                b"W" => self.clip = Some(false),
+                b"W*" => self.clip = Some(true),
+                b"g" => self.state.fill = color(&nums),
+                b"G" => self.state.stroke = color(&nums),
+                b"rg" | b"k" | b"sc" | b"scn" => self.state.fill = color(&nums),
+                b"RG" | b"K" | b"SC" | b"SCN" => self.state.stroke = color(&nums),
+                b"cs" => self.state.fill = [0.0; 3],
+                b"CS" => self.state.stroke = [0.0; 3],
+                b"gs" => {
+                    let Some(name) = operands.first() else { continue };
+                    let Some(gs) = self
+                        .resource(resources, b"ExtGState", name)
+                        .and_then(PdfObject::as_dict)
+                    else {
+                        continue;
+                    };
+                    let document = &self.page.document;
+                    let alpha = |key: &[u8]| document.get(gs, key)?.as_f64();
+                    if let Some(ca) = alpha(b"ca") {
+                        self.state.fill_alpha = ca;
+                    }
+                    if let Some(ca) = alpha(b"CA") {
+                        self.state.stroke_alpha = ca;
+                    }
+                    if let Some(lw) = alpha(b"LW") {
+                        self.state.line_width = lw;
+                    }
+                }
+                b"Do" => {
+                    let Some(name) = operands.first() else { continue };
+                    let Some(xobject) = self
+                        .resource(resources, b"XObject", name)
+                        .and_then(PdfObject::as_stream)
+                    else {
+                        continue;
+                    };
+                    self.draw_xobject(xobject, depth)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Draw an image or form XObject.
+    fn draw_xobject(&mut self, xobject: &'a PdfStream, depth: usize) -> StrResult<()> {
+        let document = &self.page.document;
+        match document.get(&xobject.dict, b"Subtype").and_then(PdfObject::as_name) {
+            Some(b"Form") if depth < MAX_DEPTH => {
+                let content = document.decode(xobject)?;
+                let resources = document
+                    .get(&xobject.dict, b"Resources")
+                    .and_then(PdfObject::as_dict);
+                self.save();
+                if let Some(m) = document
+                    .get(&xobject.dict, b"Matrix")
+                    .and_then(PdfObject::as_array)
+                    .filter(|m| m.len() == 6)
+                {
+                    let m: Vec<f64> = m
+                        .iter()
+                        .map(|v| self.resolve(v).as_f64().unwrap_or(0.0))
+                        .collect();
+                    self.state.ctm =
+                        concat([m[0], m[1], m[2], m[3], m[4], m[5]], self.state.ctm);
+                }
+                self.run(&content, resources, depth + 1)?;
+                self.restore();
+            }
+            Some(b"Image") => {
+                // Each image is only defined once, no matter how often it is
+                // drawn.
+                let next = self.images.len();
+                let id = *self.images.entry(xobject as *const _).or_insert_with(|| {
+                    let url = image_url(document, xobject)?;
+                    write!(
+                        self.defs,
+                        "<image id=\"i{next}\" width=\"1\" height=\"1\" \
+                         preserveAspectRatio=\"none\" xlink:href=\"{url}\"/>"
+                    )
+                    .unwrap();
+                    Some(next)
+                });
+                let Some(id) = id else { return Ok(()) };
+
+                // Images are drawn into the unit square with their top row at
+                // the top, whereas SVG images start at the origin.
+                let flip = [1.0, 0.0, 0.0, -1.0, 0.0, 1.0];
+                let transform = matrix_attr(concat(flip, self.state.ctm));
+                write!(
+                    self.svg,
+                    "<use xlink:href=\"#i{id}\" transform=\"{transform}\"/>"
+                )
+                .unwrap();
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn save(&mut self) {
+        let mut state = self.state.clone();
+        state.groups = 0;
+        self.stack.push(std::mem::replace(&mut self.state, state));
+    }
+
+    fn restore(&mut self) -> bool {
+        for _ in 0..self.state.groups {
+            self.svg.push_str("</g>");
+        }
+        match self.stack.pop() {
+            Some(state) => {
+                self.state = state;
+                true
+            }
+            None => {
+                self.state.groups = 0;
+                false
+            }
+        }
+    }
+
+    /// Append a path segment.
+    fn segment(&mut self, command: char, coords: &[f64]) {
+        self.path.push(command);
+        for &v in coords {
+            write!(self.path, " {}", round(v)).unwrap();
+        }
+        self.path.push(' ');
+    }
+
+    /// Paint the current path and apply a pending clip.
+    fn paint(&mut self, fill: Option<bool>, stroke: bool) {
+        let path = std::mem::take(&mut self.path);
+        let transform = matrix_attr(self.state.ctm);
+
+        if (fill.is_some() || stroke) && !path.is_empty() {
+            write!(self.svg, "<path d=\"{path}\" transform=\"{transform}\"").unwrap();
+            match fill {
+                Some(even_odd) => {
+                    write!(self.svg, " fill=\"{}\"", hex(self.state.fill)).unwrap();
+                    if even_odd {
+                        self.svg.push_str(" fill-rule=\"evenodd\"");
+                    }
+                    if self.state.fill_alpha < 1.0 {
+                        write!(self.svg, " fill-opacity=\"{}\"", self.state.fill_alpha)
+                            .unwrap();
+                    }
+                }
+                None => self.svg.push_str(" fill=\"none\""),
+            }
+            if stroke {
+                self.write_stroke();
+            }
+            self.svg.push_str("/>");
+        }
+
+        if let Some(even_odd) = self.clip.take() {
+            let id = self.clips;
+            self.clips += 1;
+            let rule = if even_odd { "evenodd" } else { "nonzero" };
+            write!(
+                self.defs,
+                "<clipPath id=\"c{id}\"><path d=\"{path}\" transform=\"{transform}\" \
+                 clip-rule=\"{rule}\"/></clipPath>"
+            )
+            .unwrap();
+            write!(self.svg, "<g clip-path=\"url(#c{id})\">").unwrap();
+            self.state.groups += 1;
+        }
+    }
+
+    /// Write the stroke attributes of the current state.
+    fn write_stroke(&mut self) {
+        let state = &self.state;
+        // A width of zero denotes the thinnest line that can be rendered.
+        let width = if state.line_width > 0.0 { state.line_width } else { 0.1 };
+        write!(self.svg, " stroke=\"{}\" stroke-width=\"{}\"", hex(state.stroke), width)
+            .unwrap();
+        if state.stroke_alpha < 1.0 {
+            write!(self.svg, " stroke-opacity=\"{}\"", state.stroke_alpha).unwrap();
+        }
+        match state.line_cap {
+            1 => self.svg.push_str(" stroke-linecap=\"round\""),
+            2 => self.svg.push_str(" stroke-linecap=\"square\""),
+            _ => {}
+        }
+        match state.line_join {
+            1 => self.svg.push_str(" stroke-linejoin=\"round\""),
+            2 => self.svg.push_str(" stroke-linejoin=\"bevel\""),
+            _ => {
+                write!(self.svg, " stroke-miterlimit=\"{}\"", state.miter_limit.max(1.0))
+                    .unwrap()
+            }
+        }
+        if let Some((dashes, phase)) = &state.dash {
+            let dashes: Vec<_> = dashes.iter().map(|d| round(*d).to_string()).collect();
+            write!(
+                self.svg,
+                " stroke-dasharray=\"{}\" stroke-dashoffset=\"{}\"",
+                dashes.join(" "),
+                round(*phase)
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// Encode an image XObject as a data URL, if its format is supported.
+fn image_url(document: &Document, stream: &PdfStream) -> Option<EcoString> {
+    let filter = document.get(&stream.dict, b"Filter");
+    let filter = match filter {
+        Some(PdfObject::Array(array)) if array.len() == 1 => Some(&array[0]),
+        other => other,
+    };
+
+    let (mime, data) = if filter.and_then(PdfObject::as_name) == Some(b"DCTDecode") {
+        ("jpeg", stream.data.clone())
+    } else {
+        let width = u32::try_from(document.int(&stream.dict, b"Width")?).ok()?;
+        let height = u32::try_from(document.int(&stream.dict, b"Height")?).ok()?;
+        if document.int(&stream.dict, b"BitsPerComponent") != Some(8) {
+            return None;
+        }
+
+        let components = match document.get(&stream.dict, b"ColorSpace")? {
+            PdfObject::Name(name) => match name.as_slice() {
+                b"DeviceGray" | b"CalGray" => 1,
+                b"DeviceRGB" | b"CalRGB" => 3,
+                _ => return None,
+            },
+            PdfObject::Array(array) => {
+                let (kind, rest) = array.split_first()?;
+                if document.resolve(kind).as_name() != Some(b"ICCBased") {
+                    return None;
+                }
+                let profile = document.resolve(rest.first()?).as_dict()?;
+                document.int(profile, b"N")?
+            }
+            _ => return None,
+        };
+
+        let pixels = document.decode(stream).ok()?;
+        let dynamic = match components {
+            1 => image::GrayImage::from_raw(width, height, pixels)?.into(),
+            3 => image::RgbImage::from_raw(width, height, pixels)?.into(),
+            _ => return None,
+        };
+
+        let mut png = Cursor::new(vec![]);
+        image::DynamicImage::write_to(&dynamic, &mut png, image::ImageFormat::Png)
+            .ok()?;
+        ("png", png.into_inner())
+    };
+
+    let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+    Some(eco_format!("data:image/{mime};base64,{encoded}"))
+}
+
+/// Convert color components to RGB based on their count.
+fn color(nums: &[f64]) -> [f64; 3] {
+    match *nums {
+        [g] => [g; 3],
+        [r, g, b] => [r, g, b],
+        [c, m, y, k] => {
+            [(1.0 - c) * (1.0 - k), (1.0 - m) * (1.0 - k), (1.0 - y) * (1.0 - k)]
+        }
+        _ => [0.0; 3],
+    }
+}
+
+/// Format an RGB color as a hex string.
+fn hex([r, g, b]: [f64; 3]) -> String {
+    let to_u8 = |v: f64| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", to_u8(r), to_u8(g), to_u8(b))
+}
+
+/// Format a matrix as an SVG transform attribute.
+fn matrix_attr(m: [f64; 6]) -> String {
+    format!(
+        "matrix({} {} {} {} {} {})",
+        round(m[0]),
+        round(m[1]),
+        round(m[2]),
+        round(m[3]),
+        round(m[4]),
+        round(m[5])
+    )
+}
+
+/// Round a coordinate to keep the SVG small.
+fn round(v: f64) -> f64 {
+    (v * 10000.0).round() / 10000.0
+}
+
+/// Concatenate two transformation matrices, applying `first` before `second`.
+pub(crate) fn concat(first: [f64; 6], second: [f64; 6]) -> [f64; 6] {
+    let [a1, b1, c1, d1, e1, f1] = first;
+    let [a2, b2, c2, d2, e2, f2] = second;
+    [
+        a1 * a2 + b1 * c2,
+        a1 * b2 + b1 * d2,
+        c1 * a2 + d1 * c2,
+        c1 * b2 + d1 * d2,
+        e1 * a2 + f1 * c2 + e2,
+        e1 * b2 + f1 * d2 + f2,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_with_negative_size() {
+        let entries: [(&[u8], PdfObject); 4] = [
+            (b"Width", PdfObject::Int(-1)),
+            (b"Height", PdfObject::Int(1)),
+            (b"BitsPerComponent", PdfObject::Int(8)),
+            (b"ColorSpace", PdfObject::Name(b"DeviceGray".to_vec())),
+        ];
+        let stream = PdfStream {
+            dict: PdfDict(entries.into_iter().map(|(k, v)| (k.to_vec(), v)).collect()),
+            data: vec![0],
+        };
+        let document = Document { objects: HashMap::new(), root: PdfDict::default() };
+        assert_eq!(image_url(&document, &stream), None);
+    }
+
+    #[test]
+    fn test_concat() {
+        let translate = [1.0, 0.0, 0.0, 1.0, 5.0, 0.0];
+        let scale = [2.0, 0.0, 0.0, 2.0, 0.0, 0.0];
+        assert_eq!(concat(translate, scale), [2.0, 0.0, 0.0, 2.0, 10.0, 0.0]);
+    }
+}
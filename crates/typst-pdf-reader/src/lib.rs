@@ -0,0 +1,275 @@
+//! Reading pages of PDF files for Typst.
+//!
+//! This crate parses just enough of a PDF file to embed one of its pages into
+//! another PDF and to convert the page into an SVG for other exporters. The
+//! input is untrusted, so all parsing is bounded: malformed files produce an
+//! error instead of a panic, and cyclic or overly large structures are
+//! rejected.
+
+mod convert;
+mod object;
+mod parse;
+
+pub use self::object::{PdfDict, PdfObject, PdfRef, PdfStream};
+
+use ecow::EcoString;
+
+use self::convert::{concat, Converter};
+use self::parse::Document;
+
+/// The maximum depth to which references, page trees and nested form
+/// XObjects are followed. Protects against cyclic documents.
+const MAX_DEPTH: usize = 64;
+
+/// The maximum size of a decoded stream in bytes. Protects against
+/// decompression bombs.
+const MAX_STREAM_SIZE: usize = 1 << 28;
+
+/// The maximum number of nodes in the page tree that are visited. Protects
+/// against page trees that reference the same nodes over and over.
+const MAX_PAGE_NODES: usize = 1 << 16;
+
+/// The maximum number of content stream operators that are interpreted when
+/// converting a page to SVG. Protects against form XObjects that draw each
+/// other over and over.
+const MAX_OPERATIONS: usize = 1_000_000;
+
+/// The maximum number of entries in a dash pattern that are kept, as they are
+/// repeated for every stroke.
+const MAX_DASHES: usize = 64;
+
+/// A result type with a string error message.
+pub type StrResult<T> = Result<T, EcoString>;
+
+/// Return early with a formatted error message.
+macro_rules! bail {
+    ($($tts:tt)*) => {
+        return Err(ecow::eco_format!($($tts)*))
+    };
+}
+
+use bail;
+
+/// A single page of a PDF document.
+///
+/// Only the parts of the document that are needed to embed the page are
+/// parsed: the object table, the page tree and the page's content streams.
+pub struct PdfPage {
+    index: usize,
+    page_count: usize,
+    document: Document,
+    resources: Option<PdfObject>,
+    bbox: [f64; 4],
+    rotate: i32,
+    content: Vec<u8>,
+}
+
+impl PdfPage {
+    /// Parse a PDF file and select the page with the given zero-based index.
+    pub fn parse(data: &[u8], index: usize) -> StrResult<Self> {
+        let document = Document::parse(data)?;
+        let pages = document.pages()?;
+        let page_count = pages.len();
+        let Some(selected) = pages.into_iter().nth(index) else {
+            bail!(
+                "page {} does not exist (the PDF has {} page{})",
+                index + 1,
+                page_count,
+                if page_count == 1 { "" } else { "s" },
+            );
+        };
+
+        let bbox = selected
+            .attr(&document, b"CropBox")
+            .or_else(|| selected.attr(&document, b"MediaBox"))
+            .and_then(|obj| document.rect(obj))
+            .unwrap_or([0.0, 0.0, 612.0, 792.0]);
+        if bbox[2] - bbox[0] <= 0.0 || bbox[3] - bbox[1] <= 0.0 {
+            bail!("page {} of the PDF is empty", index + 1);
+        }
+
+        let rotate = selected
+            .attr(&document, b"Rotate")
+            .and_then(|obj| document.resolve(obj).as_f64())
+            .map_or(0, |v| (v as i32).rem_euclid(360) / 90 * 90);
+
+        let resources = selected.attr(&document, b"Resources").cloned();
+        let content = document.page_content(&selected.dict)?;
+
+        Ok(Self {
+            index,
+            page_count,
+            document,
+            resources,
+            bbox,
+            rotate,
+            content,
+        })
+    }
+
+    /// The zero-based index of the page.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The number of pages in the document.
+    pub fn page_count(&self) -> usize {
+        self.page_count
+    }
+
+    /// The displayed width of the page in points.
+    pub fn width(&self) -> f64 {
+        let [x0, y0, x1, y1] = self.bbox;
+        if self.rotate % 180 == 0 {
+            x1 - x0
+        } else {
+            y1 - y0
+        }
+    }
+
+    /// The displayed height of the page in points.
+    pub fn height(&self) -> f64 {
+        let [x0, y0, x1, y1] = self.bbox;
+        if self.rotate % 180 == 0 {
+            y1 - y0
+        } else {
+            x1 - x0
+        }
+    }
+
+    /// The visible region of the page in its own coordinate system, as
+    /// `[x0, y0, x1, y1]`.
+    pub fn bbox(&self) -> [f64; 4] {
+        self.bbox
+    }
+
+    /// The transformation that maps the page's coordinate system onto the unit
+    /// square, taking the page's rotation into account.
+    pub fn matrix(&self) -> [f64; 6] {
+        let [x0, y0, x1, y1] = self.bbox;
+        let (w, h) = (x1 - x0, y1 - y0);
+        let normalize = [1.0 / w, 0.0, 0.0, 1.0 / h, -x0 / w, -y0 / h];
+        let rotate = match self.rotate {
+            90 => [0.0, -1.0, 1.0, 0.0, 0.0, 1.0],
+            180 => [-1.0, 0.0, 0.0, -1.0, 1.0, 1.0],
+            270 => [0.0, 1.0, -1.0, 0.0, 1.0, 0.0],
+            _ => [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+        };
+        concat(normalize, rotate)
+    }
+
+    /// The page's resource dictionary (or a reference to it), if any.
+    pub fn resources(&self) -> Option<&PdfObject> {
+        self.resources.as_ref()
+    }
+
+    /// The page's decoded and concatenated content streams.
+    pub fn content(&self) -> &[u8] {
+        &self.content
+    }
+
+    /// Look up an indirect object in the document.
+    pub fn get(&self, reference: PdfRef) -> Option<&PdfObject> {
+        self.document.objects.get(&reference)
+    }
+
+    /// Convert the page into an SVG and return its source code together with
+    /// whether text on the page was left out.
+    ///
+    /// The conversion supports vector graphics and images, but text is
+    /// currently omitted.
+    pub fn to_svg(&self) -> StrResult<(String, bool)> {
+        Converter::convert(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small PDF whose page tree lives in a compressed object stream with a
+    /// PNG predictor and whose cross-reference table is garbage.
+    fn sample(kids: &str) -> Vec<u8> {
+        let objects = format!(
+            "<< /Type /Pages /Kids [{kids}] /Count 1 /MediaBox [0 0 10 10] >>\n\
+             << /Type /Page /Parent 2 0 R /Contents 4 0 R >>\n"
+        );
+        let header = format!("2 0 3 {} ", objects.find('\n').unwrap() + 1);
+        let plain = format!("{header}{objects}");
+
+        // Apply the "None" PNG predictor with four columns.
+        let mut predicted = vec![];
+        for row in plain.as_bytes().chunks(4) {
+            predicted.push(0);
+            predicted.extend_from_slice(row);
+        }
+
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, &predicted).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut data =
+            b"%PDF-1.5\n1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n".to_vec();
+        data.extend(
+            format!(
+                "5 0 obj\n<< /Type /ObjStm /N 2 /First {} /Length {} \
+                 /Filter /FlateDecode /DecodeParms << /Predictor 12 /Columns 4 >> \
+                 >>\nstream\n",
+                header.len(),
+                compressed.len(),
+            )
+            .bytes(),
+        );
+        data.extend(compressed);
+        data.extend(b"\nendstream\nendobj\n");
+        data.extend(
+            b"4 0 obj\n<< /Length 14 >>\nstream\n0 0 5 5 re f\nendstream\nendobj\n",
+        );
+        data.extend(b"xref\n0 6\n0000000000 65535 f\n9999999999 00000 n\nxx\n");
+        data.extend(b"trailer\n<< /Size 6 /Root 1 0 R >>\nstartxref\n123456\n%%EOF\n");
+        data
+    }
+
+    /// Parse a PDF and convert its first page, like an image would be.
+    fn load(data: Vec<u8>) -> StrResult<PdfPage> {
+        let page = PdfPage::parse(&data, 0)?;
+        page.to_svg()?;
+        Ok(page)
+    }
+
+    #[test]
+    fn test_corpus_valid() {
+        let page = load(sample("3 0 R")).unwrap();
+        assert_eq!(page.page_count(), 1);
+        assert_eq!(page.width(), 10.0);
+        assert_eq!(page.content(), b"0 0 5 5 re f\n");
+    }
+
+    #[test]
+    fn test_corpus_page_tree_cycle() {
+        // A node that is its own kid.
+        let err = load(sample("2 0 R")).err().unwrap();
+        assert_eq!(err, "failed to parse PDF (page tree references a node twice)");
+
+        // A page that is referenced twice.
+        let err = load(sample("3 0 R 3 0 R")).err().unwrap();
+        assert_eq!(err, "failed to parse PDF (page tree references a node twice)");
+    }
+
+    #[test]
+    fn test_corpus_malformed() {
+        // Truncated files and corrupted bytes must fail gracefully, no
+        // matter where in the object stream or cross-reference table they
+        // are.
+        let data = sample("3 0 R");
+        for i in 0..data.len() {
+            load(data[..i].to_vec()).ok();
+            for c in [b'[', b'(', b'<', b'0', b'R', 0xFF] {
+                let mut corrupted = data.clone();
+                corrupted[i] = c;
+                load(corrupted).ok();
+            }
+        }
+    }
+}
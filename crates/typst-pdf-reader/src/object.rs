@@ -0,0 +1,110 @@
+/// An object in a PDF file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PdfObject {
+    /// The null object.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// An integer.
+    Int(i64),
+    /// A real number.
+    Real(f64),
+    /// A string, as raw bytes.
+    Str(Vec<u8>),
+    /// A name, without the leading slash.
+    Name(Vec<u8>),
+    /// An array of objects.
+    Array(Vec<PdfObject>),
+    /// A dictionary.
+    Dict(PdfDict),
+    /// A stream with its dictionary and still encoded data.
+    Stream(PdfStream),
+    /// A reference to an indirect object.
+    Ref(PdfRef),
+}
+
+impl PdfObject {
+    /// The object as a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Self::Int(v) => Some(v as f64),
+            Self::Real(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// The object as an integer.
+    pub fn as_int(&self) -> Option<i64> {
+        match *self {
+            Self::Int(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// The object as a name.
+    pub fn as_name(&self) -> Option<&[u8]> {
+        match self {
+            Self::Name(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The object as an array.
+    pub fn as_array(&self) -> Option<&[PdfObject]> {
+        match self {
+            Self::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    /// The object as a dictionary. Also returns the dictionary of streams.
+    pub fn as_dict(&self) -> Option<&PdfDict> {
+        match self {
+            Self::Dict(dict) => Some(dict),
+            Self::Stream(stream) => Some(&stream.dict),
+            _ => None,
+        }
+    }
+
+    /// The object as a stream.
+    pub fn as_stream(&self) -> Option<&PdfStream> {
+        match self {
+            Self::Stream(stream) => Some(stream),
+            _ => None,
+        }
+    }
+}
+
+/// A PDF dictionary.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PdfDict(pub Vec<(Vec<u8>, PdfObject)>);
+
+impl PdfDict {
+    /// Get the value for a key.
+    pub fn get(&self, key: &[u8]) -> Option<&PdfObject> {
+        self.0.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Iterate over the key-value pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &PdfObject)> {
+        self.0.iter().map(|(k, v)| (k.as_slice(), v))
+    }
+}
+
+/// A PDF stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfStream {
+    /// The stream's dictionary.
+    pub dict: PdfDict,
+    /// The stream's data, with all filters still applied.
+    pub data: Vec<u8>,
+}
+
+/// A reference to an indirect PDF object.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct PdfRef {
+    /// The object number.
+    pub id: u32,
+    /// The generation number.
+    pub generation: u16,
+}
@@ -0,0 +1,962 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+
+use crate::{
+    bail, PdfDict, PdfObject, PdfRef, PdfStream, StrResult, MAX_DEPTH, MAX_PAGE_NODES,
+    MAX_STREAM_SIZE,
+};
+
+/// The parsed objects of a PDF file.
+pub(crate) struct Document {
+    pub(crate) objects: HashMap<PdfRef, PdfObject>,
+    pub(crate) root: PdfDict,
+}
+
+/// A leaf of the page tree together with its inherited attributes.
+pub(crate) struct Page {
+    pub(crate) dict: PdfDict,
+    inherited: Vec<(&'static [u8], PdfObject)>,
+}
+
+impl Page {
+    /// Look up a possibly inherited page attribute.
+    pub(crate) fn attr<'a>(
+        &'a self,
+        document: &'a Document,
+        key: &[u8],
+    ) -> Option<&'a PdfObject> {
+        self.dict
+            .get(key)
+            .or_else(|| {
+                self.inherited.iter().rev().find(|(k, _)| *k == key).map(|(_, v)| v)
+            })
+            .map(|obj| document.resolve(obj))
+    }
+}
+
+impl Document {
+    /// Parse all objects in a PDF file.
+    ///
+    /// Instead of trusting the cross-reference table, which is frequently
+    /// broken in practice, this scans the file for object definitions. Later
+    /// definitions win, which matches the semantics of incremental updates.
+    pub(crate) fn parse(data: &[u8]) -> StrResult<Self> {
+        if find(data, b"%PDF-", 0).map_or(true, |i| i > 1024) {
+            bail!("failed to parse PDF (missing header)");
+        }
+
+        let mut objects = HashMap::new();
+        let mut trailers = vec![];
+        let mut object_streams = vec![];
+
+        let mut pos = 0;
+        while let Some(found) = find(data, b"obj", pos) {
+            pos = found + 3;
+            let Some(reference) = object_header(data, found) else { continue };
+            let mut parser = Parser::new(data, pos);
+            let Some(object) = parser.parse_object() else { continue };
+            pos = parser.pos;
+
+            if let PdfObject::Stream(stream) = &object {
+                match stream.dict.get(b"Type").and_then(PdfObject::as_name) {
+                    Some(b"XRef") => trailers.push((found, stream.dict.clone())),
+                    Some(b"ObjStm") => object_streams.push(stream.clone()),
+                    _ => {}
+                }
+            }
+
+            objects.insert(reference, object);
+        }
+
+        let mut pos = 0;
+        while let Some(found) = find(data, b"trailer", pos) {
+            pos = found + 7;
+            let mut parser = Parser::new(data, pos);
+            if let Some(PdfObject::Dict(dict)) = parser.parse_object() {
+                trailers.push((found, dict));
+            }
+        }
+
+        let mut document = Self { objects, root: PdfDict::default() };
+
+        // Objects in object streams never override directly defined ones.
+        for stream in object_streams {
+            for (reference, object) in document.unpack_object_stream(&stream)? {
+                document.objects.entry(reference).or_insert(object);
+            }
+        }
+
+        trailers.sort_by_key(|(pos, _)| *pos);
+        if trailers.iter().any(|(_, trailer)| trailer.get(b"Encrypt").is_some()) {
+            bail!("encrypted PDFs are not supported");
+        }
+
+        let root = trailers
+            .iter()
+            .rev()
+            .find_map(|(_, trailer)| trailer.get(b"Root"))
+            .map(|root| document.resolve(root))
+            .and_then(PdfObject::as_dict)
+            .or_else(|| {
+                document.objects.values().filter_map(PdfObject::as_dict).find(|dict| {
+                    dict.get(b"Type").and_then(PdfObject::as_name) == Some(b"Catalog")
+                })
+            })
+            .cloned();
+
+        let Some(root) = root else {
+            bail!("failed to parse PDF (missing document catalog)");
+        };
+
+        document.root = root;
+        Ok(document)
+    }
+
+    /// Extract the objects stored in an object stream.
+    fn unpack_object_stream(
+        &self,
+        stream: &PdfStream,
+    ) -> StrResult<Vec<(PdfRef, PdfObject)>> {
+        let count = self.int(&stream.dict, b"N").unwrap_or(0);
+        let first = self.int(&stream.dict, b"First").unwrap_or(0);
+        let Ok(first) = usize::try_from(first) else {
+            bail!("failed to parse PDF (invalid object stream)");
+        };
+
+        let data = self.decode(stream)?;
+        let Some(header) = data.get(..first) else { return Ok(vec![]) };
+
+        let mut objects = vec![];
+        let mut parser = Parser::new(header, 0);
+        for _ in 0..count {
+            let (Some(PdfObject::Int(id)), Some(PdfObject::Int(offset))) =
+                (parser.parse_object(), parser.parse_object())
+            else {
+                break;
+            };
+
+            let (Ok(id), Some(pos)) = (
+                u32::try_from(id),
+                usize::try_from(offset)
+                    .ok()
+                    .and_then(|offset| first.checked_add(offset)),
+            ) else {
+                bail!("failed to parse PDF (invalid object stream)");
+            };
+
+            let mut parser = Parser::new(&data, pos);
+            if let Some(object) = parser.parse_object() {
+                let reference = PdfRef { id, generation: 0 };
+                objects.push((reference, object));
+            }
+        }
+
+        Ok(objects)
+    }
+
+    /// Follow references until a direct object is reached.
+    pub(crate) fn resolve<'a>(&'a self, mut object: &'a PdfObject) -> &'a PdfObject {
+        for _ in 0..MAX_DEPTH {
+            let PdfObject::Ref(reference) = object else { break };
+            object = self.objects.get(reference).unwrap_or(&PdfObject::Null);
+        }
+        object
+    }
+
+    /// Look up and resolve a dictionary entry.
+    pub(crate) fn get<'a>(
+        &'a self,
+        dict: &'a PdfDict,
+        key: &[u8],
+    ) -> Option<&'a PdfObject> {
+        dict.get(key).map(|obj| self.resolve(obj))
+    }
+
+    /// Look up an integer in a dictionary.
+    pub(crate) fn int(&self, dict: &PdfDict, key: &[u8]) -> Option<i64> {
+        self.get(dict, key).and_then(PdfObject::as_int)
+    }
+
+    /// Interpret an object as a normalized rectangle.
+    pub(crate) fn rect(&self, object: &PdfObject) -> Option<[f64; 4]> {
+        let array = self.resolve(object).as_array()?;
+        let mut nums = array.iter().map(|v| self.resolve(v).as_f64());
+        let (x0, y0, x1, y1) =
+            (nums.next()??, nums.next()??, nums.next()??, nums.next()??);
+        Some([x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)])
+    }
+
+    /// Collect the leaves of the page tree in order.
+    pub(crate) fn pages(&self) -> StrResult<Vec<Page>> {
+        let mut pages = vec![];
+        if let Some(root) = self.root.get(b"Pages") {
+            let mut visited = HashSet::new();
+            self.collect_pages(root, &[], &mut pages, &mut visited, 0)?;
+        }
+        if pages.is_empty() {
+            bail!("failed to parse PDF (document has no pages)");
+        }
+        Ok(pages)
+    }
+
+    /// Recursively collect the pages below a node of the page tree.
+    ///
+    /// Fails if the tree references a node twice or is too large, as such
+    /// trees would take forever to traverse.
+    fn collect_pages(
+        &self,
+        node: &PdfObject,
+        inherited: &[(&'static [u8], PdfObject)],
+        pages: &mut Vec<Page>,
+        visited: &mut HashSet<PdfRef>,
+        depth: usize,
+    ) -> StrResult<()> {
+        if let PdfObject::Ref(reference) = node {
+            if !visited.insert(*reference) {
+                bail!("failed to parse PDF (page tree references a node twice)");
+            }
+            if visited.len() > MAX_PAGE_NODES {
+                bail!("failed to parse PDF (page tree is too large)");
+            }
+        }
+
+        let Some(dict) = self.resolve(node).as_dict() else { return Ok(()) };
+        if depth > MAX_DEPTH {
+            bail!("failed to parse PDF (page tree is too deep)");
+        }
+
+        match self.get(dict, b"Kids").and_then(PdfObject::as_array) {
+            Some(kids) => {
+                let mut inherited = inherited.to_vec();
+                for key in [&b"Resources"[..], b"MediaBox", b"CropBox", b"Rotate"] {
+                    if let Some(value) = dict.get(key) {
+                        inherited.push((key, value.clone()));
+                    }
+                }
+                for kid in kids {
+                    self.collect_pages(kid, &inherited, pages, visited, depth + 1)?;
+                }
+            }
+            None => {
+                pages.push(Page { dict: dict.clone(), inherited: inherited.to_vec() })
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decode and concatenate the content streams of a page.
+    pub(crate) fn page_content(&self, page: &PdfDict) -> StrResult<Vec<u8>> {
+        let mut content = vec![];
+        let streams = match self.get(page, b"Contents") {
+            Some(PdfObject::Array(array)) => array.iter().collect(),
+            Some(object) => vec![object],
+            None => vec![],
+        };
+
+        for stream in streams {
+            if let Some(stream) = self.resolve(stream).as_stream() {
+                content.extend(self.decode(stream)?);
+                content.push(b'\n');
+            }
+        }
+
+        Ok(content)
+    }
+
+    /// Apply a stream's filters to its data.
+    pub(crate) fn decode(&self, stream: &PdfStream) -> StrResult<Vec<u8>> {
+        let mut data = stream.data.clone();
+        let filters = match self.get(&stream.dict, b"Filter") {
+            Some(PdfObject::Array(array)) => array.iter().collect(),
+            Some(object) => vec![object],
+            None => vec![],
+        };
+        let params = match self.get(&stream.dict, b"DecodeParms") {
+            Some(PdfObject::Array(array)) => array.iter().collect(),
+            Some(object) => vec![object],
+            None => vec![],
+        };
+
+        for (i, filter) in filters.into_iter().enumerate() {
+            let params = params
+                .get(i)
+                .and_then(|params| self.resolve(params).as_dict())
+                .cloned()
+                .unwrap_or_default();
+
+            data = match self.resolve(filter).as_name() {
+                Some(b"FlateDecode" | b"Fl") => {
+                    let inflated = inflate(&data, MAX_STREAM_SIZE)?;
+                    self.unpredict(inflated, &params)?
+                }
+                Some(b"ASCIIHexDecode" | b"AHx") => decode_hex(&data),
+                Some(b"ASCII85Decode" | b"A85") => decode_ascii85(&data)?,
+                Some(name) => bail!(
+                    "PDF uses the unsupported filter {}",
+                    String::from_utf8_lossy(name)
+                ),
+                None => bail!("failed to parse PDF (invalid stream filter)"),
+            };
+        }
+
+        Ok(data)
+    }
+
+    /// Undo a PNG predictor that was applied before compression.
+    fn unpredict(&self, data: Vec<u8>, params: &PdfDict) -> StrResult<Vec<u8>> {
+        let predictor = self.int(params, b"Predictor").unwrap_or(1);
+        if predictor < 10 {
+            return Ok(data);
+        }
+
+        let colors = self.int(params, b"Colors").unwrap_or(1);
+        let bits = self.int(params, b"BitsPerComponent").unwrap_or(8);
+        let columns = self.int(params, b"Columns").unwrap_or(1);
+        if !(1..=32).contains(&colors) || ![1, 2, 4, 8, 16].contains(&bits) {
+            bail!("failed to parse PDF (invalid predictor parameters)");
+        }
+
+        // Both values are small, so the product can't overflow.
+        let pixel_bits = (colors * bits) as usize;
+        let bpp = pixel_bits.div_ceil(8);
+        let stride = usize::try_from(columns)
+            .ok()
+            .filter(|&columns| columns > 0)
+            .and_then(|columns| columns.checked_mul(pixel_bits))
+            .map(|bits| bits.div_ceil(8))
+            .filter(|&stride| stride <= MAX_STREAM_SIZE);
+        let Some(stride) = stride else {
+            bail!("failed to parse PDF (invalid predictor parameters)");
+        };
+
+        // A truncated last row is kept as is instead of being padded, so that
+        // the output is never larger than the input, no matter how large the
+        // stride is.
+        let mut out = Vec::with_capacity(data.len());
+        let mut prev: Vec<u8> = vec![];
+        for row in data.chunks(stride.saturating_add(1)) {
+            let (&kind, row) = row.split_first().unwrap();
+            let mut cur = row.to_vec();
+            for i in 0..cur.len() {
+                let left = if i >= bpp { cur[i - bpp] } else { 0 };
+                let up = prev.get(i).copied().unwrap_or(0);
+                let up_left =
+                    if i >= bpp { prev.get(i - bpp).copied().unwrap_or(0) } else { 0 };
+                let add = match kind {
+                    0 => 0,
+                    1 => left,
+                    2 => up,
+                    3 => ((left as u16 + up as u16) / 2) as u8,
+                    4 => paeth(left, up, up_left),
+                    _ => bail!("failed to parse PDF (invalid predictor)"),
+                };
+                cur[i] = cur[i].wrapping_add(add);
+            }
+            out.extend_from_slice(&cur);
+            prev = cur;
+        }
+
+        Ok(out)
+    }
+}
+
+/// Check whether the `obj` keyword at the given position is preceded by an
+/// object and generation number and return them.
+fn object_header(data: &[u8], obj: usize) -> Option<PdfRef> {
+    if data.get(obj + 3).is_some_and(|&c| is_regular(c)) {
+        return None;
+    }
+
+    let mut i = obj;
+    let skip_ws = |i: &mut usize| {
+        let start = *i;
+        while *i > 0 && is_whitespace(data[*i - 1]) {
+            *i -= 1;
+        }
+        *i < start
+    };
+    let digits = |i: &mut usize| {
+        let end = *i;
+        while *i > 0 && data[*i - 1].is_ascii_digit() {
+            *i -= 1;
+        }
+        std::str::from_utf8(&data[*i..end]).ok()?.parse::<u64>().ok()
+    };
+
+    if !skip_ws(&mut i) {
+        return None;
+    }
+    let generation = digits(&mut i)?;
+    if !skip_ws(&mut i) {
+        return None;
+    }
+    let id = digits(&mut i)?;
+    if i > 0 && is_regular(data[i - 1]) {
+        return None;
+    }
+
+    Some(PdfRef {
+        id: u32::try_from(id).ok()?,
+        generation: u16::try_from(generation).ok()?,
+    })
+}
+
+/// A parser for PDF objects and content streams.
+pub(crate) struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+    /// Whether `n g R` sequences are parsed as references. This is disabled
+    /// for content streams, which don't contain references.
+    refs: bool,
+    /// How deeply the arrays and dictionaries that are currently being parsed
+    /// are nested.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(data: &'a [u8], pos: usize) -> Self {
+        Self { data, pos, refs: true, depth: 0 }
+    }
+
+    /// Create a parser for a content stream.
+    pub(crate) fn content(data: &'a [u8]) -> Self {
+        Self { refs: false, ..Self::new(data, 0) }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if is_whitespace(c) {
+                self.pos += 1;
+            } else if c == b'%' {
+                while self.peek().is_some_and(|c| c != b'\n' && c != b'\r') {
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Read a run of regular characters.
+    fn keyword(&mut self) -> &'a [u8] {
+        let start = self.pos;
+        while self.peek().is_some_and(is_regular) {
+            self.pos += 1;
+        }
+        &self.data[start..self.pos]
+    }
+
+    /// Eat a keyword if it comes next.
+    fn eat_keyword(&mut self, keyword: &[u8]) -> bool {
+        let prev = self.pos;
+        self.skip_whitespace();
+        if self.keyword() == keyword {
+            return true;
+        }
+        self.pos = prev;
+        false
+    }
+
+    fn parse_object(&mut self) -> Option<PdfObject> {
+        self.skip_whitespace();
+        Some(match self.peek()? {
+            b'/' => {
+                self.pos += 1;
+                PdfObject::Name(decode_name(self.keyword()))
+            }
+            b'(' => PdfObject::Str(self.parse_literal_string()?),
+            b'<' if self.data.get(self.pos + 1) == Some(&b'<') => {
+                let dict = self.nested(Self::parse_dict)?;
+                self.parse_stream(dict)
+            }
+            b'<' => PdfObject::Str(self.parse_hex_string()?),
+            b'[' => PdfObject::Array(self.nested(Self::parse_array)?),
+            b'0'..=b'9' | b'+' | b'-' | b'.' => self.parse_number()?,
+            _ => match self.keyword() {
+                b"true" => PdfObject::Bool(true),
+                b"false" => PdfObject::Bool(false),
+                b"null" => PdfObject::Null,
+                _ => return None,
+            },
+        })
+    }
+
+    fn parse_number(&mut self) -> Option<PdfObject> {
+        let text = self.keyword();
+        let text = std::str::from_utf8(text).ok()?;
+        if text.contains('.') {
+            return text.parse().ok().map(PdfObject::Real);
+        }
+
+        let value = text.parse().ok()?;
+        if self.refs && value >= 0 {
+            let prev = self.pos;
+            self.skip_whitespace();
+            let generation = self.keyword();
+            let generation =
+                std::str::from_utf8(generation).ok().and_then(|g| g.parse().ok());
+            if let Some(generation) = generation {
+                if self.eat_keyword(b"R") {
+                    return Some(PdfObject::Ref(PdfRef {
+                        id: u32::try_from(value).ok()?,
+                        generation,
+                    }));
+                }
+            }
+            self.pos = prev;
+        }
+
+        Some(PdfObject::Int(value))
+    }
+
+    /// Parse an array or dictionary with the given function, giving up if
+    /// it is nested too deeply. Protects against stack overflows.
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        if self.depth >= MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_array(&mut self) -> Option<Vec<PdfObject>> {
+        self.pos += 1;
+        let mut items = vec![];
+        loop {
+            self.skip_whitespace();
+            match self.peek()? {
+                b']' => break,
+                _ => items.push(self.parse_object()?),
+            }
+        }
+        self.pos += 1;
+        Some(items)
+    }
+
+    fn parse_dict(&mut self) -> Option<PdfDict> {
+        self.pos += 2;
+        let mut pairs = vec![];
+        loop {
+            self.skip_whitespace();
+            match self.peek()? {
+                b'>' => break,
+                _ => {
+                    let PdfObject::Name(key) = self.parse_object()? else { return None };
+                    let value = self.parse_object()?;
+                    pairs.push((key, value));
+                }
+            }
+        }
+        if !self.data[self.pos..].starts_with(b">>") {
+            return None;
+        }
+        self.pos += 2;
+        Some(PdfDict(pairs))
+    }
+
+    /// Parse the data of a stream if the dictionary is followed by one.
+    fn parse_stream(&mut self, dict: PdfDict) -> PdfObject {
+        let prev = self.pos;
+        if !self.refs || !self.eat_keyword(b"stream") {
+            self.pos = prev;
+            return PdfObject::Dict(dict);
+        }
+
+        // The keyword is followed by CRLF or LF.
+        if self.peek() == Some(b'\r') {
+            self.pos += 1;
+        }
+        if self.peek() == Some(b'\n') {
+            self.pos += 1;
+        }
+
+        // Trust the length if it is direct and consistent, otherwise search
+        // for the end of the stream.
+        let start = self.pos;
+        if let Some(len) = dict.get(b"Length").and_then(PdfObject::as_int) {
+            let end = start.saturating_add(len.max(0) as usize);
+            if end <= self.data.len() {
+                self.pos = end;
+                if self.eat_keyword(b"endstream") {
+                    let data = self.data[start..end].to_vec();
+                    return PdfObject::Stream(PdfStream { dict, data });
+                }
+            }
+        }
+
+        let end = find(self.data, b"endstream", start).unwrap_or(self.data.len());
+        self.pos = (end + 9).min(self.data.len());
+        let mut data = &self.data[start..end];
+        if let Some(rest) = data.strip_suffix(b"\n") {
+            data = rest;
+        }
+        if let Some(rest) = data.strip_suffix(b"\r") {
+            data = rest;
+        }
+
+        PdfObject::Stream(PdfStream { dict, data: data.to_vec() })
+    }
+
+    fn parse_literal_string(&mut self) -> Option<Vec<u8>> {
+        self.pos += 1;
+        let mut out = vec![];
+        let mut depth = 0;
+        loop {
+            let c = self.peek()?;
+            self.pos += 1;
+            match c {
+                b'(' => {
+                    depth += 1;
+                    out.push(c);
+                }
+                b')' if depth == 0 => break,
+                b')' => {
+                    depth -= 1;
+                    out.push(c);
+                }
+                b'\\' => {
+                    let c = self.peek()?;
+                    self.pos += 1;
+                    match c {
+                        b'n' => out.push(b'\n'),
+                        b'r' => out.push(b'\r'),
+                        b't' => out.push(b'\t'),
+                        b'b' => out.push(8),
+                        b'f' => out.push(12),
+                        b'\r' => {
+                            if self.peek() == Some(b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        b'\n' => {}
+                        b'0'..=b'7' => {
+                            let mut value = (c - b'0') as u32;
+                            for _ in 0..2 {
+                                match self.peek() {
+                                    Some(d @ b'0'..=b'7') => {
+                                        value = value * 8 + (d - b'0') as u32;
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            out.push(value as u8);
+                        }
+                        _ => out.push(c),
+                    }
+                }
+                _ => out.push(c),
+            }
+        }
+        Some(out)
+    }
+
+    fn parse_hex_string(&mut self) -> Option<Vec<u8>> {
+        self.pos += 1;
+        let end = self.pos + self.data[self.pos..].iter().position(|&c| c == b'>')?;
+        let out = decode_hex(&self.data[self.pos..end]);
+        self.pos = end + 1;
+        Some(out)
+    }
+
+    /// Parse the next operator of a content stream and collect its operands.
+    pub(crate) fn parse_operation(
+        &mut self,
+        operands: &mut Vec<PdfObject>,
+    ) -> Option<&'a [u8]> {
+        operands.clear();
+        loop {
+            self.skip_whitespace();
+            let c = self.peek()?;
+            if is_regular(c) && !matches!(c, b'0'..=b'9' | b'+' | b'-' | b'.') {
+                let operator = self.keyword();
+                match operator {
+                    b"true" => operands.push(PdfObject::Bool(true)),
+                    b"false" => operands.push(PdfObject::Bool(false)),
+                    b"null" => operands.push(PdfObject::Null),
+                    b"BI" => {
+                        self.skip_inline_image();
+                        operands.clear();
+                    }
+                    _ => return Some(operator),
+                }
+            } else if let Some(object) = self.parse_object() {
+                operands.push(object);
+            } else {
+                // Skip over garbage.
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// Skip the dictionary and data of an inline image.
+    fn skip_inline_image(&mut self) {
+        let Some(start) = find(self.data, b"ID", self.pos) else {
+            self.pos = self.data.len();
+            return;
+        };
+
+        let mut pos = start + 2;
+        while let Some(end) = find(self.data, b"EI", pos) {
+            pos = end + 2;
+            let before = end.checked_sub(1).map(|i| self.data[i]);
+            let after = self.data.get(end + 2).copied();
+            if before.is_some_and(is_whitespace) && after.map_or(true, |c| !is_regular(c))
+            {
+                break;
+            }
+        }
+
+        self.pos = pos.min(self.data.len());
+    }
+}
+
+/// Decompress zlib-compressed data that may be at most `limit` bytes large.
+///
+/// Truncated streams are common in the wild, so whatever could be decoded
+/// is returned even if the stream ends early.
+fn inflate(data: &[u8], limit: usize) -> StrResult<Vec<u8>> {
+    let mut out = vec![];
+    let decoder = flate2::read::ZlibDecoder::new(data);
+    let result = decoder.take(limit as u64 + 1).read_to_end(&mut out);
+    if out.len() > limit {
+        bail!("failed to decompress PDF stream (stream is too large)");
+    }
+    if let Err(err) = result {
+        if out.is_empty() {
+            bail!("failed to decompress PDF stream ({err})");
+        }
+    }
+    Ok(out)
+}
+
+/// Decode hexadecimal data, ignoring whitespace.
+fn decode_hex(data: &[u8]) -> Vec<u8> {
+    let digits: Vec<u8> = data
+        .iter()
+        .take_while(|&&c| c != b'>')
+        .filter_map(|&c| (c as char).to_digit(16))
+        .map(|d| d as u8)
+        .collect();
+    digits
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0))
+        .collect()
+}
+
+/// Decode ASCII base-85 data.
+fn decode_ascii85(data: &[u8]) -> StrResult<Vec<u8>> {
+    let mut out = vec![];
+    let mut group = [0u8; 5];
+    let mut len = 0;
+
+    let flush = |group: &[u8; 5], len: usize, out: &mut Vec<u8>| {
+        let value = group.iter().fold(0u64, |acc, &d| acc * 85 + d as u64);
+        out.extend_from_slice(&(value as u32).to_be_bytes()[..len - 1]);
+    };
+
+    for &c in data {
+        match c {
+            b'~' => break,
+            b'z' if len == 0 => out.extend_from_slice(&[0; 4]),
+            b'!'..=b'u' => {
+                group[len] = c - b'!';
+                len += 1;
+                if len == 5 {
+                    flush(&group, 5, &mut out);
+                    len = 0;
+                }
+            }
+            c if is_whitespace(c) => {}
+            _ => bail!("failed to parse PDF (invalid ASCII85 data)"),
+        }
+    }
+
+    if len > 1 {
+        group[len..].fill(b'u' - b'!');
+        flush(&group, len, &mut out);
+    }
+
+    Ok(out)
+}
+
+/// The Paeth predictor function from the PNG specification.
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Decode the `#xx` escapes in a name.
+fn decode_name(name: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(name.len());
+    let mut i = 0;
+    while i < name.len() {
+        if name[i] == b'#' && i + 2 < name.len() {
+            if let Ok(byte) = u8::from_str_radix(
+                std::str::from_utf8(&name[i + 1..i + 3]).unwrap_or_default(),
+                16,
+            ) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(name[i]);
+        i += 1;
+    }
+    out
+}
+
+/// Find the first occurrence of a needle at or after the given position.
+fn find(data: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    data.get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|i| from + i)
+}
+
+/// Whether a byte is PDF whitespace.
+fn is_whitespace(c: u8) -> bool {
+    matches!(c, b'\0' | b'\t' | b'\n' | b'\x0C' | b'\r' | b' ')
+}
+
+/// Whether a byte is neither whitespace nor a delimiter.
+fn is_regular(c: u8) -> bool {
+    !is_whitespace(c)
+        && !matches!(
+            c,
+            b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_objects() {
+        let mut parser = Parser::new(
+            b"<< /Type /Page /Kids [1 0 R 2 0 R] /N -3.5 /S (a\\(b\\)) /H <4142> >>",
+            0,
+        );
+        let dict = parser.parse_object().unwrap();
+        let dict = dict.as_dict().unwrap();
+        assert_eq!(dict.get(b"Type"), Some(&PdfObject::Name(b"Page".to_vec())));
+        assert_eq!(
+            dict.get(b"Kids"),
+            Some(&PdfObject::Array(vec![
+                PdfObject::Ref(PdfRef { id: 1, generation: 0 }),
+                PdfObject::Ref(PdfRef { id: 2, generation: 0 }),
+            ]))
+        );
+        assert_eq!(dict.get(b"N"), Some(&PdfObject::Real(-3.5)));
+        assert_eq!(dict.get(b"S"), Some(&PdfObject::Str(b"a(b)".to_vec())));
+        assert_eq!(dict.get(b"H"), Some(&PdfObject::Str(b"AB".to_vec())));
+    }
+
+    #[test]
+    fn test_parse_deeply_nested() {
+        let nested = |depth| {
+            let mut data = vec![b'['; depth];
+            data.extend(vec![b']'; depth]);
+            data
+        };
+        assert!(Parser::new(&nested(MAX_DEPTH), 0).parse_object().is_some());
+        assert!(Parser::new(&nested(MAX_DEPTH + 1), 0).parse_object().is_none());
+        assert!(Parser::new(&vec![b'['; 1 << 20], 0).parse_object().is_none());
+        assert!(Parser::new(&b"<<".repeat(1 << 20), 0).parse_object().is_none());
+    }
+
+    #[test]
+    fn test_object_header() {
+        let data = b"12 0 obj\nendobj";
+        assert_eq!(object_header(data, 5), Some(PdfRef { id: 12, generation: 0 }));
+        assert_eq!(object_header(data, 12), None);
+    }
+
+    #[test]
+    fn test_decode_filters() {
+        assert_eq!(decode_hex(b"48 65 6c6c 6f>"), b"Hello");
+        assert_eq!(decode_ascii85(b"87cURDZ~>").unwrap(), b"Hello");
+        assert_eq!(decode_name(b"A#20B"), b"A B");
+    }
+
+    /// Build a dictionary from key-value pairs.
+    fn dict<const N: usize>(pairs: [(&[u8], PdfObject); N]) -> PdfDict {
+        PdfDict(pairs.into_iter().map(|(k, v)| (k.to_vec(), v)).collect())
+    }
+
+    /// An empty document to resolve objects against.
+    fn document() -> Document {
+        Document { objects: HashMap::new(), root: PdfDict::default() }
+    }
+
+    #[test]
+    fn test_inflate_limit() {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, &[0; 1000]).unwrap();
+        let data = encoder.finish().unwrap();
+        assert_eq!(inflate(&data, 1000).unwrap().len(), 1000);
+        assert!(inflate(&data, 999).is_err());
+    }
+
+    #[test]
+    fn test_unpredict() {
+        let params = |columns| {
+            dict([
+                (b"Predictor", PdfObject::Int(12)),
+                (b"Columns", PdfObject::Int(columns)),
+            ])
+        };
+
+        // A row without a filter followed by one that adds the row above.
+        let data = vec![0, 1, 2, 2, 1, 1];
+        assert_eq!(document().unpredict(data, &params(2)).unwrap(), [1, 2, 2, 3]);
+
+        for columns in [0, -1, i64::MAX] {
+            assert!(document().unpredict(vec![0; 8], &params(columns)).is_err());
+        }
+
+        let colors =
+            dict([(b"Predictor", PdfObject::Int(12)), (b"Colors", PdfObject::Int(-1))]);
+        assert!(document().unpredict(vec![0; 8], &colors).is_err());
+
+        // A truncated row is not padded, so a huge stride doesn't allocate.
+        let data = vec![2, 1, 2];
+        assert_eq!(document().unpredict(data, &params(1 << 28)).unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn test_object_stream_offsets() {
+        let stream = |data: &[u8]| PdfStream {
+            dict: dict([(b"N", PdfObject::Int(1)), (b"First", PdfObject::Int(15))]),
+            data: data.to_vec(),
+        };
+
+        let objects = document()
+            .unpack_object_stream(&stream(b"7 0            true"))
+            .unwrap();
+        assert_eq!(objects, [(PdfRef { id: 7, generation: 0 }, PdfObject::Bool(true))]);
+        assert!(document()
+            .unpack_object_stream(&stream(b"7 -1           true"))
+            .is_err());
+        assert!(document()
+            .unpack_object_stream(&stream(b"4294967296 0   true"))
+            .is_err());
+    }
+}
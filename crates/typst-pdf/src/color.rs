@@ -1,6 +1,11 @@
+use ecow::EcoString;
 use once_cell::sync::Lazy;
 use pdf_writer::types::DeviceNSubtype;
 use pdf_writer::{writers, Chunk, Dict, Filter, Name, Ref};
+use typst::diag::{bail, SourceResult};
+use typst::foundations::{Bytes, NativeElement, StyleChain};
+use typst::model::Document;
+use typst::pdf::OutputIntentElem;
 use typst::visualize::{Color, ColorSpace, Paint};
 
 use crate::deflate;
@@ -34,6 +39,7 @@ pub struct ColorSpaces {
     srgb: Option<Ref>,
     d65_gray: Option<Ref>,
     use_linear_rgb: bool,
+    output_intent: Option<(OutputIntent, Ref)>,
 }
 
 impl ColorSpaces {
@@ -61,6 +67,17 @@ impl ColorSpaces {
         self.use_linear_rgb = true;
     }
 
+    /// Set the document's output intent, allocating a reference for its ICC
+    /// profile.
+    pub fn set_output_intent(&mut self, intent: OutputIntent, alloc: &mut Ref) {
+        self.output_intent = Some((intent, alloc.bump()));
+    }
+
+    /// Get the document's output intent and a reference to its ICC profile.
+    pub fn output_intent(&self) -> Option<(&OutputIntent, Ref)> {
+        self.output_intent.as_ref().map(|(intent, id)| (intent, *id))
+    }
+
     /// Write the color space on usage.
     pub fn write(
         &mut self,
@@ -141,6 +158,92 @@ impl ColorSpaces {
                 .range([0.0, 1.0])
                 .filter(Filter::FlateDecode);
         }
+
+        // Write the ICC profile of the output intent.
+        if let Some((intent, id)) = &self.output_intent {
+            let data = deflate(intent.profile.as_slice());
+            let mut stream = chunk.icc_profile(*id, &data);
+            stream.filter(Filter::FlateDecode);
+            stream.n(intent.n);
+            match intent.n {
+                1 => stream.alternate().d65_gray(),
+                3 => stream.alternate().srgb(),
+                _ => stream.alternate().device_cmyk(),
+            }
+        }
+    }
+}
+
+/// An ICC profile describing the device the document is intended to be output
+/// on.
+pub struct OutputIntent {
+    /// The raw ICC profile.
+    pub profile: Bytes,
+    /// The number of color components of the profile's color space.
+    pub n: i32,
+    /// The identifier of the output condition.
+    pub condition: EcoString,
+    /// A human-readable description of the output condition.
+    pub info: Option<EcoString>,
+}
+
+impl OutputIntent {
+    /// Whether the output device uses CMYK colors.
+    pub fn is_cmyk(&self) -> bool {
+        self.n == 4
+    }
+}
+
+/// Find the output intent of the document, if any, and check its profile.
+pub fn output_intent(document: &Document) -> SourceResult<Option<OutputIntent>> {
+    let elements = document.introspector.query(&OutputIntentElem::elem().select());
+    let Some(first) = elements.first() else { return Ok(None) };
+    if let Some(second) = elements.get(1) {
+        bail!(
+            second.span(),
+            "a document can only have a single output intent";
+            hint: "remove all but one of the `pdf.output-intent` elements"
+        );
+    }
+
+    let styles = StyleChain::default();
+    let elem = first.to_packed::<OutputIntentElem>().unwrap();
+    let profile = elem.data().clone();
+    let Some(n) = icc_components(&profile) else {
+        bail!(
+            elem.span(),
+            "failed to read ICC profile";
+            hint: "the profile must be for a gray, RGB, or CMYK color space"
+        );
+    };
+
+    // Only output device profiles can describe an output intent.
+    if !matches!(&profile[12..16], b"prtr" | b"mntr") {
+        bail!(elem.span(), "output intent must be a printer or monitor profile");
+    }
+
+    Ok(Some(OutputIntent {
+        profile,
+        n,
+        condition: elem.condition(styles).clone().unwrap_or_else(|| "Custom".into()),
+        info: elem.info(styles).clone(),
+    }))
+}
+
+/// The number of color components of an ICC profile's color space.
+///
+/// Returns `None` if the data is not an ICC profile or if its color space is
+/// neither gray, RGB nor CMYK.
+pub fn icc_components(profile: &[u8]) -> Option<i32> {
+    if profile.len() < 128 || &profile[36..40] != b"acsp" {
+        return None;
+    }
+
+    match &profile[16..20] {
+        b"GRAY" => Some(1),
+        b"RGB " => Some(3),
+        b"CMYK" => Some(4),
+        _ => None,
     }
 }
 
@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+
+use ecow::EcoString;
+use pdf_writer::{Filter, Finish, Name, Ref, Str, TextStr};
+use typst::diag::{bail, SourceResult};
+use typst::foundations::{NativeElement, Packed, StyleChain};
+use typst::pdf::{EmbedElem, EmbeddedFileRelationship};
+
+use crate::{deflate, PdfContext};
+
+/// Write all embedded files of the document.
+///
+/// The resulting file specifications are stored in the context, sorted by
+/// name, so that they can be added to the catalog's name dictionary.
+pub(crate) fn write_embedded_files(ctx: &mut PdfContext) -> SourceResult<()> {
+    let elements = ctx.document.introspector.query(&EmbedElem::elem().select());
+    let mut seen = HashSet::new();
+
+    for elem in elements.iter() {
+        let embed = elem.to_packed::<EmbedElem>().unwrap();
+        let name = embed.path().clone();
+        if !seen.insert(name.clone()) {
+            bail!(
+                embed.span(),
+                "the file {name:?} is embedded multiple times";
+                hint: "each embedded file must have a unique path"
+            );
+        }
+
+        let file_spec_ref = write_embedded_file(ctx, embed);
+        ctx.embedded_files.push((name, file_spec_ref));
+    }
+
+    // Name trees must be sorted by key.
+    ctx.embedded_files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    Ok(())
+}
+
+/// Write a single embedded file and its file specification.
+fn write_embedded_file(ctx: &mut PdfContext, embed: &Packed<EmbedElem>) -> Ref {
+    let styles = StyleChain::default();
+    let file_spec_ref = ctx.alloc.bump();
+    let embedded_file_ref = ctx.alloc.bump();
+
+    let data = embed.data();
+    let compressed = deflate(data.as_slice());
+    let mut embedded_file = ctx.pdf.embedded_file(embedded_file_ref, &compressed);
+    embedded_file.filter(Filter::FlateDecode);
+
+    // PDF/A-3 requires a MIME type, so we fall back to a generic one.
+    let mime_type = embed.mime_type(styles).or_else(|| {
+        ctx.standard
+            .is_pdfa()
+            .then(|| EcoString::from("application/octet-stream"))
+    });
+    if let Some(mime_type) = &mime_type {
+        embedded_file.subtype(Name(mime_type.as_bytes()));
+    }
+    embedded_file.params().size(data.len() as i32);
+    embedded_file.finish();
+
+    let path = embed.path();
+    let mut file_spec = ctx.pdf.file_spec(file_spec_ref);
+    file_spec.path(Str(path.as_bytes()));
+    file_spec.unic_file(TextStr(path));
+    file_spec
+        .insert(Name(b"EF"))
+        .dict()
+        .pair(Name(b"F"), embedded_file_ref)
+        .pair(Name(b"UF"), embedded_file_ref);
+
+    if let Some(description) = embed.description(styles) {
+        file_spec.description(TextStr(description.as_str()));
+    }
+
+    if ctx.standard.is_pdfa() {
+        let relationship = embed
+            .relationship(styles)
+            .unwrap_or(EmbeddedFileRelationship::Unspecified);
+        file_spec.pair(Name(b"AFRelationship"), relationship_name(relationship));
+    }
+
+    file_spec.finish();
+    file_spec_ref
+}
+
+/// The PDF name of an associated file relationship.
+fn relationship_name(relationship: EmbeddedFileRelationship) -> Name<'static> {
+    match relationship {
+        EmbeddedFileRelationship::Source => Name(b"Source"),
+        EmbeddedFileRelationship::Data => Name(b"Data"),
+        EmbeddedFileRelationship::Alternative => Name(b"Alternative"),
+        EmbeddedFileRelationship::Supplement => Name(b"Supplement"),
+        EmbeddedFileRelationship::Unspecified => Name(b"Unspecified"),
+    }
+}
@@ -1,13 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::Cursor;
 
 use image::{DynamicImage, GenericImageView, Rgba};
-use pdf_writer::{Chunk, Filter, Finish, Ref};
+use pdf_writer::{Chunk, Filter, Finish, Name, Obj, Rect, Ref, Str};
+use svg2pdf::usvg;
 use typst::utils::Deferred;
 use typst::visualize::{
-    ColorSpace, Image, ImageKind, RasterFormat, RasterImage, SvgImage,
+    ColorSpace, Image, ImageKind, PdfImage, PdfObject, PdfRef, PdfStream, RasterFormat,
+    RasterImage, SvgImage,
 };
 
+use crate::color::icc_components;
 use crate::{deflate, PdfContext};
 
 /// Creates a new PDF image from the given image.
@@ -19,15 +22,59 @@ pub fn deferred_image(image: Image) -> Deferred<EncodedImage> {
         ImageKind::Raster(raster) => {
             let raster = raster.clone();
             let (width, height) = (raster.width(), raster.height());
-            let (data, filter, has_color) = encode_raster_image(&raster);
-            let icc = raster.icc().map(deflate);
 
+            // JPEGs are embedded as is, which avoids a lossy re-encoding and
+            // keeps their CMYK data intact.
+            if let Some(jpeg) = jpeg_passthrough(&raster) {
+                return EncodedImage::Raster {
+                    data: raster.data().to_vec(),
+                    filter: Filter::DctDecode,
+                    space: jpeg.space,
+                    inverted: jpeg.inverted,
+                    bits: 8,
+                    width,
+                    height,
+                    icc: jpeg.icc.map(deflate),
+                    alpha: None,
+                };
+            }
+
+            // The image crate converts CMYK to RGB when decoding, so we read
+            // the samples of CMYK TIFFs ourselves to keep them and their ICC
+            // profile.
+            if let Some(data) = cmyk_tiff(&raster).and_then(read_cmyk_tiff) {
+                return EncodedImage::Raster {
+                    data: deflate(&data),
+                    filter: Filter::FlateDecode,
+                    space: RasterColorSpace::Cmyk,
+                    inverted: false,
+                    bits: 8,
+                    width,
+                    height,
+                    icc: matching_icc(&raster, RasterColorSpace::Cmyk).map(deflate),
+                    alpha: None,
+                };
+            }
+
+            let (data, filter, space, bits) = encode_raster_image(&raster);
+            let icc = matching_icc(&raster, space).map(deflate);
             let alpha =
                 raster.dynamic().color().has_alpha().then(|| encode_alpha(&raster));
 
-            EncodedImage::Raster { data, filter, has_color, width, height, icc, alpha }
+            EncodedImage::Raster {
+                data,
+                filter,
+                space,
+                inverted: false,
+                bits,
+                width,
+                height,
+                icc,
+                alpha,
+            }
         }
         ImageKind::Svg(svg) => EncodedImage::Svg(encode_svg(svg)),
+        ImageKind::Pdf(pdf) => EncodedImage::Pdf(encode_pdf(pdf)),
     })
 }
 
@@ -40,7 +87,9 @@ pub(crate) fn write_images(ctx: &mut PdfContext) {
             EncodedImage::Raster {
                 data,
                 filter,
-                has_color,
+                space,
+                inverted,
+                bits,
                 width,
                 height,
                 icc,
@@ -53,23 +102,30 @@ pub(crate) fn write_images(ctx: &mut PdfContext) {
                 image.filter(*filter);
                 image.width(*width as i32);
                 image.height(*height as i32);
-                image.bits_per_component(8);
+                image.bits_per_component(*bits as i32);
 
                 let mut icc_ref = None;
-                let space = image.color_space();
+                let writer = image.color_space();
                 if icc.is_some() {
                     let id = ctx.alloc.bump();
-                    space.icc_based(id);
+                    writer.icc_based(id);
                     icc_ref = Some(id);
-                } else if *has_color {
-                    ctx.colors.write(ColorSpace::Srgb, space, &mut ctx.alloc);
                 } else {
-                    ctx.colors.write(ColorSpace::D65Gray, space, &mut ctx.alloc);
+                    let color_space = match space {
+                        RasterColorSpace::Luma => ColorSpace::D65Gray,
+                        RasterColorSpace::Rgb => ColorSpace::Srgb,
+                        RasterColorSpace::Cmyk => ColorSpace::Cmyk,
+                    };
+                    ctx.colors.write(color_space, writer, &mut ctx.alloc);
+                }
+
+                if *inverted {
+                    image.decode([1.0, 0.0].repeat(space.components() as usize));
                 }
 
                 // Add a second gray-scale image containing the alpha values if
                 // this image has an alpha channel.
-                if let Some((alpha_data, alpha_filter)) = alpha {
+                if let Some((alpha_data, alpha_filter, alpha_bits)) = alpha {
                     let mask_ref = ctx.alloc.bump();
                     image.s_mask(mask_ref);
                     image.finish();
@@ -79,7 +135,7 @@ pub(crate) fn write_images(ctx: &mut PdfContext) {
                     mask.width(*width as i32);
                     mask.height(*height as i32);
                     mask.color_space().device_gray();
-                    mask.bits_per_component(8);
+                    mask.bits_per_component(*alpha_bits as i32);
                 } else {
                     image.finish();
                 }
@@ -87,16 +143,15 @@ pub(crate) fn write_images(ctx: &mut PdfContext) {
                 if let (Some(icc), Some(icc_ref)) = (icc, icc_ref) {
                     let mut stream = ctx.pdf.icc_profile(icc_ref, icc);
                     stream.filter(Filter::FlateDecode);
-                    if *has_color {
-                        stream.n(3);
-                        stream.alternate().srgb();
-                    } else {
-                        stream.n(1);
-                        stream.alternate().d65_gray();
+                    stream.n(space.components());
+                    match space {
+                        RasterColorSpace::Luma => stream.alternate().d65_gray(),
+                        RasterColorSpace::Rgb => stream.alternate().srgb(),
+                        RasterColorSpace::Cmyk => stream.alternate().device_cmyk(),
                     }
                 }
             }
-            EncodedImage::Svg(chunk) => {
+            EncodedImage::Svg(chunk) | EncodedImage::Pdf(chunk) => {
                 let mut map = HashMap::new();
                 chunk.renumber_into(&mut ctx.pdf, |old| {
                     *map.entry(old).or_insert_with(|| ctx.alloc.bump())
@@ -107,19 +162,142 @@ pub(crate) fn write_images(ctx: &mut PdfContext) {
     }
 }
 
-/// Encode an image with a suitable filter and return the data, filter and
-/// whether the image has color.
+/// A JPEG image whose data can be embedded into the PDF as is.
+pub(crate) struct JpegPassthrough<'a> {
+    /// The color space of the encoded data.
+    pub space: RasterColorSpace,
+    /// Whether the color components are stored inverted, as Adobe
+    /// applications do for CMYK JPEGs.
+    pub inverted: bool,
+    /// The image's ICC profile, if it matches the color space.
+    pub icc: Option<&'a [u8]>,
+}
+
+/// Check whether a raster image is a JPEG that the PDF's `DCTDecode` filter
+/// can read directly.
+///
+/// Images that were rotated according to their EXIF metadata must be
+/// re-encoded, as their encoded data doesn't match the rotated pixels.
+pub(crate) fn jpeg_passthrough(raster: &RasterImage) -> Option<JpegPassthrough<'_>> {
+    if raster.format() != RasterFormat::Jpg || raster.is_rotated() {
+        return None;
+    }
+
+    let (components, adobe) = parse_jpeg_header(raster.data())?;
+    let space = match components {
+        1 => RasterColorSpace::Luma,
+        3 => RasterColorSpace::Rgb,
+        4 => RasterColorSpace::Cmyk,
+        _ => return None,
+    };
+
+    Some(JpegPassthrough {
+        space,
+        inverted: adobe && space == RasterColorSpace::Cmyk,
+        icc: matching_icc(raster, space),
+    })
+}
+
+/// Parse the header of a baseline or progressive JPEG file with 8 bits per
+/// sample.
+///
+/// Returns the number of color components and whether the file has an Adobe
+/// marker segment.
+fn parse_jpeg_header(data: &[u8]) -> Option<(u8, bool)> {
+    let mut s = data.strip_prefix(&[0xFF, 0xD8])?;
+    let mut components = None;
+    let mut adobe = false;
+
+    loop {
+        // Markers may be preceded by any number of fill bytes.
+        s = s.strip_prefix(&[0xFF])?;
+        while let Some(rest) = s.strip_prefix(&[0xFF]) {
+            s = rest;
+        }
+
+        let (&marker, rest) = s.split_first()?;
+        let len = usize::from(u16::from_be_bytes([*rest.first()?, *rest.get(1)?]));
+        let segment = rest.get(2..len)?;
+        s = rest.get(len..)?;
+
+        match marker {
+            // Baseline, extended sequential and progressive DCT.
+            0xC0..=0xC2 => {
+                if segment.first() != Some(&8) {
+                    return None;
+                }
+                components = Some(*segment.get(5)?);
+            }
+            // Other frame types, like lossless or arithmetic coding, are not
+            // supported by all PDF readers.
+            0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => return None,
+            // Application segment with Adobe color transform information.
+            0xEE if segment.starts_with(b"Adobe") => adobe = true,
+            // Start of scan: The header is complete.
+            0xDA => return components.map(|components| (components, adobe)),
+            _ => {}
+        }
+    }
+}
+
+/// A decoder for a TIFF image with 8-bit CMYK samples.
+type CmykTiff<'a> = tiff::decoder::Decoder<Cursor<&'a [u8]>>;
+
+/// Check whether a raster image is a TIFF with 8-bit CMYK samples, which the
+/// PDF can store as is.
+///
+/// As for JPEGs, images that were rotated according to their EXIF metadata
+/// must be re-encoded.
+pub(crate) fn cmyk_tiff(raster: &RasterImage) -> Option<CmykTiff<'_>> {
+    if raster.format() != RasterFormat::Tiff || raster.is_rotated() {
+        return None;
+    }
+
+    let mut decoder =
+        tiff::decoder::Decoder::new(Cursor::new(raster.data().as_slice())).ok()?;
+    (decoder.colortype().ok()? == tiff::ColorType::CMYK(8)).then_some(decoder)
+}
+
+/// Read the samples of a CMYK TIFF.
+fn read_cmyk_tiff(mut decoder: CmykTiff<'_>) -> Option<Vec<u8>> {
+    match decoder.read_image().ok()? {
+        tiff::decoder::DecodingResult::U8(data) => Some(data),
+        _ => None,
+    }
+}
+
+/// The image's ICC profile, if it describes the given color space.
+pub(crate) fn matching_icc(
+    raster: &RasterImage,
+    space: RasterColorSpace,
+) -> Option<&[u8]> {
+    raster
+        .icc()
+        .filter(|icc| icc_components(icc) == Some(space.components()))
+}
+
+/// Encode an image with a suitable filter and return the data, filter,
+/// color space and the number of bits per component.
 ///
 /// Skips the alpha channel as that's encoded separately.
-fn encode_raster_image(image: &RasterImage) -> (Vec<u8>, Filter, bool) {
+fn encode_raster_image(image: &RasterImage) -> (Vec<u8>, Filter, RasterColorSpace, u8) {
     let dynamic = image.dynamic();
     let channel_count = dynamic.color().channel_count();
-    let has_color = channel_count > 2;
+    let space =
+        if channel_count > 2 { RasterColorSpace::Rgb } else { RasterColorSpace::Luma };
 
     if image.format() == RasterFormat::Jpg {
         let mut data = Cursor::new(vec![]);
         dynamic.write_to(&mut data, image::ImageFormat::Jpeg).unwrap();
-        (data.into_inner(), Filter::DctDecode, has_color)
+        (data.into_inner(), Filter::DctDecode, space, 8)
+    } else if is_16_bit(dynamic) {
+        // PDF expects 16-bit samples in big-endian byte order.
+        let samples: Vec<u16> = match space {
+            RasterColorSpace::Luma => dynamic.to_luma16().into_raw(),
+            _ => dynamic.to_rgb16().into_raw(),
+        };
+        let data: Vec<u8> = samples.iter().flat_map(|v| v.to_be_bytes()).collect();
+        (deflate(&data), Filter::FlateDecode, space, 16)
     } else {
         // TODO: Encode flate streams with PNG-predictor?
         let data = match (dynamic, channel_count) {
@@ -130,18 +308,35 @@ fn encode_raster_image(image: &RasterImage) -> (Vec<u8>, Filter, bool) {
             // Anything else
             _ => deflate(dynamic.to_rgb8().as_raw()),
         };
-        (data, Filter::FlateDecode, has_color)
+        (data, Filter::FlateDecode, space, 8)
     }
 }
 
-/// Encode an image's alpha channel if present.
-fn encode_alpha(raster: &RasterImage) -> (Vec<u8>, Filter) {
-    let pixels: Vec<_> = raster
-        .dynamic()
-        .pixels()
-        .map(|(_, _, Rgba([_, _, _, a]))| a)
-        .collect();
-    (deflate(&pixels), Filter::FlateDecode)
+/// Encode an image's alpha channel if present, keeping 16-bit precision.
+fn encode_alpha(raster: &RasterImage) -> (Vec<u8>, Filter, u8) {
+    let dynamic = raster.dynamic();
+    if is_16_bit(dynamic) {
+        let pixels: Vec<u8> = dynamic
+            .to_rgba16()
+            .pixels()
+            .flat_map(|&Rgba([_, _, _, a])| a.to_be_bytes())
+            .collect();
+        return (deflate(&pixels), Filter::FlateDecode, 16);
+    }
+
+    let pixels: Vec<_> = dynamic.pixels().map(|(_, _, Rgba([_, _, _, a]))| a).collect();
+    (deflate(&pixels), Filter::FlateDecode, 8)
+}
+
+/// Whether an image has 16 bits per channel.
+fn is_16_bit(dynamic: &DynamicImage) -> bool {
+    matches!(
+        dynamic,
+        DynamicImage::ImageLuma16(_)
+            | DynamicImage::ImageLumaA16(_)
+            | DynamicImage::ImageRgb16(_)
+            | DynamicImage::ImageRgba16(_)
+    )
 }
 
 /// Encode an SVG into a chunk of PDF objects.
@@ -166,6 +361,241 @@ fn encode_svg(svg: &SvgImage) -> Chunk {
     chunk
 }
 
+/// Encode a page of a PDF file into a chunk of PDF objects.
+///
+/// The page becomes a form XObject with ID 1 that maps the page onto the unit
+/// square. All objects reachable from the page's resources are copied over.
+fn encode_pdf(pdf: &PdfImage) -> Chunk {
+    let mut chunk = Chunk::new();
+    let mut copier = Copier {
+        map: HashMap::new(),
+        queue: VecDeque::new(),
+        next: 2,
+    };
+
+    let content = deflate(pdf.content());
+    let [x0, y0, x1, y1] = pdf.bbox();
+    let mut form = chunk.form_xobject(Ref::new(1), &content);
+    form.filter(Filter::FlateDecode);
+    form.bbox(Rect::new(x0 as f32, y0 as f32, x1 as f32, y1 as f32));
+    form.matrix(pdf.matrix().map(|v| v as f32));
+    if let Some(resources) = pdf.resources() {
+        copier.write(form.insert(Name(b"Resources")), resources);
+    }
+    form.finish();
+
+    while let Some((old, new)) = copier.queue.pop_front() {
+        match pdf.get(old) {
+            Some(PdfObject::Stream(stream)) => {
+                let mut writer = chunk.stream(new, &stream.data);
+                for (key, value) in stream.dict.iter() {
+                    // The length is written by the stream writer.
+                    if key != b"Length" {
+                        copier.write(writer.insert(Name(key)), value);
+                    }
+                }
+            }
+            Some(object) => copier.write(chunk.indirect(new), object),
+            None => chunk.indirect(new).primitive(pdf_writer::Null),
+        }
+    }
+
+    chunk
+}
+
+/// Copies objects from a PDF file into a chunk, renumbering references.
+struct Copier {
+    map: HashMap<PdfRef, Ref>,
+    queue: VecDeque<(PdfRef, Ref)>,
+    next: i32,
+}
+
+impl Copier {
+    /// Write an object, scheduling referenced objects for copying.
+    fn write(&mut self, obj: Obj, object: &PdfObject) {
+        match object {
+            PdfObject::Null => obj.primitive(pdf_writer::Null),
+            PdfObject::Bool(v) => obj.primitive(*v),
+            PdfObject::Int(v) => {
+                obj.primitive((*v).clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+            }
+            PdfObject::Real(v) => obj.primitive(*v as f32),
+            PdfObject::Str(v) => obj.primitive(Str(v)),
+            PdfObject::Name(v) => obj.primitive(Name(v)),
+            PdfObject::Array(items) => {
+                let mut array = obj.array();
+                for item in items {
+                    self.write(array.push(), item);
+                }
+            }
+            PdfObject::Dict(dict) => {
+                let mut writer = obj.dict();
+                for (key, value) in dict.iter() {
+                    self.write(writer.insert(Name(key)), value);
+                }
+            }
+            // Streams can only occur as indirect objects.
+            PdfObject::Stream(_) => obj.primitive(pdf_writer::Null),
+            PdfObject::Ref(reference) => {
+                let new = *self.map.entry(*reference).or_insert_with(|| {
+                    let new = Ref::new(self.next);
+                    self.next += 1;
+                    self.queue.push_back((*reference, new));
+                    new
+                });
+                obj.primitive(new);
+            }
+        }
+    }
+}
+
+/// Whether an image uses transparency when embedded, in which case the pages
+/// showing it need a transparency group.
+#[comemo::memoize]
+pub fn uses_transparency(image: Image) -> bool {
+    match image.kind() {
+        ImageKind::Raster(raster) => raster.dynamic().color().has_alpha(),
+        ImageKind::Svg(svg) => {
+            let mut transparent = false;
+            // Safety: We do not keep any references to tree nodes beyond the
+            // scope of `with`.
+            unsafe {
+                svg.with(|tree| transparent = svg_uses_transparency(&tree.root));
+            }
+            transparent
+        }
+        ImageKind::Pdf(pdf) => pdf_uses_transparency(pdf),
+    }
+}
+
+/// Whether svg2pdf converts a group of an SVG using transparency.
+fn svg_uses_transparency(group: &usvg::Group) -> bool {
+    if group.opacity.get() < 1.0
+        || group.blend_mode != usvg::BlendMode::Normal
+        || group.mask.is_some()
+        || !group.filters.is_empty()
+        || group
+            .clip_path
+            .as_ref()
+            .is_some_and(|clip| is_soft_clip(&clip.borrow()))
+    {
+        return true;
+    }
+
+    let paint = |paint: &usvg::Paint| match paint {
+        usvg::Paint::LinearGradient(gradient) => {
+            gradient.stops.iter().any(|stop| stop.opacity.get() < 1.0)
+        }
+        usvg::Paint::RadialGradient(gradient) => {
+            gradient.stops.iter().any(|stop| stop.opacity.get() < 1.0)
+        }
+        // The contents of patterns are checked as subroots.
+        usvg::Paint::Color(_) | usvg::Paint::Pattern(_) => false,
+    };
+
+    group.children.iter().any(|node| {
+        let mut transparent = match node {
+            usvg::Node::Group(group) => svg_uses_transparency(group),
+            usvg::Node::Path(path) => {
+                path.fill
+                    .as_ref()
+                    .is_some_and(|fill| fill.opacity.get() < 1.0 || paint(&fill.paint))
+                    || path.stroke.as_ref().is_some_and(|stroke| {
+                        stroke.opacity.get() < 1.0 || paint(&stroke.paint)
+                    })
+            }
+            usvg::Node::Image(image) => match &image.kind {
+                // Other raster images may have an alpha channel, which
+                // becomes a soft mask.
+                usvg::ImageKind::JPEG(_) => false,
+                usvg::ImageKind::PNG(_) | usvg::ImageKind::GIF(_) => true,
+                usvg::ImageKind::SVG(tree) => svg_uses_transparency(&tree.root),
+            },
+            usvg::Node::Text(text) => {
+                text.flattened.as_deref().is_some_and(svg_uses_transparency)
+            }
+        };
+        node.subroots(|root| transparent |= svg_uses_transparency(root));
+        transparent
+    })
+}
+
+/// Whether svg2pdf converts a clip path into a soft mask because PDF clipping
+/// paths can't express it.
+fn is_soft_clip(clip: &usvg::ClipPath) -> bool {
+    fn is_simple(group: &usvg::Group) -> bool {
+        group.children.iter().all(|node| match node {
+            usvg::Node::Group(group) => group.clip_path.is_none() && is_simple(group),
+            _ => true,
+        })
+    }
+
+    fn collect_rules(group: &usvg::Group, rules: &mut Vec<usvg::FillRule>) {
+        for node in &group.children {
+            match node {
+                usvg::Node::Path(path) => {
+                    rules.extend(path.fill.as_ref().map(|f| f.rule))
+                }
+                usvg::Node::Text(text) => {
+                    if let Some(group) = text.flattened.as_deref() {
+                        collect_rules(group, rules);
+                    }
+                }
+                usvg::Node::Group(group) => collect_rules(group, rules),
+                usvg::Node::Image(_) => {}
+            }
+        }
+    }
+
+    let mut rules = vec![];
+    collect_rules(&clip.root, &mut rules);
+    let native = is_simple(&clip.root)
+        && (rules.iter().all(|&rule| rule == usvg::FillRule::NonZero)
+            || (rules.len() == 1 && rules[0] == usvg::FillRule::EvenOdd));
+
+    !native
+        || clip
+            .clip_path
+            .as_ref()
+            .is_some_and(|clip| is_soft_clip(&clip.borrow()))
+}
+
+/// Whether any object reachable from the resources of a PDF page uses
+/// transparency, like the graphics states, soft masks and transparency groups
+/// that the copier carries over.
+fn pdf_uses_transparency(pdf: &PdfImage) -> bool {
+    let mut visited = HashSet::new();
+    let mut stack: Vec<&PdfObject> = pdf.resources().into_iter().collect();
+    while let Some(object) = stack.pop() {
+        match object {
+            PdfObject::Array(items) => stack.extend(items),
+            PdfObject::Dict(dict) | PdfObject::Stream(PdfStream { dict, .. }) => {
+                for (key, value) in dict.iter() {
+                    let transparent = match key {
+                        b"CA" | b"ca" => value.as_f64().is_some_and(|v| v < 1.0),
+                        b"SMask" => value.as_name() != Some(b"None"),
+                        b"SMaskInData" => value.as_int().is_some_and(|v| v > 0),
+                        b"BM" => {
+                            !matches!(value.as_name(), Some(b"Normal" | b"Compatible"))
+                        }
+                        b"S" => value.as_name() == Some(b"Transparency"),
+                        _ => false,
+                    };
+                    if transparent {
+                        return true;
+                    }
+                    stack.push(value);
+                }
+            }
+            PdfObject::Ref(reference) if visited.insert(*reference) => {
+                stack.extend(pdf.get(*reference));
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
 /// A pre-encoded image.
 pub enum EncodedImage {
     /// A pre-encoded rasterized image.
@@ -174,19 +604,207 @@ pub enum EncodedImage {
         data: Vec<u8>,
         /// The filter to use for the image.
         filter: Filter,
-        /// Whether the image has color.
-        has_color: bool,
+        /// The color space of the image data.
+        space: RasterColorSpace,
+        /// Whether the color components are stored inverted.
+        inverted: bool,
+        /// The number of bits per color component.
+        bits: u8,
         /// The image's width.
         width: u32,
         /// The image's height.
         height: u32,
         /// The image's ICC profile, pre-deflated, if any.
         icc: Option<Vec<u8>>,
-        /// The alpha channel of the image, pre-deflated, if any.
-        alpha: Option<(Vec<u8>, Filter)>,
+        /// The alpha channel of the image, pre-deflated, and its number of
+        /// bits per component, if any.
+        alpha: Option<(Vec<u8>, Filter, u8)>,
     },
     /// A vector graphic.
     ///
     /// The chunk is the SVG converted to PDF objects.
     Svg(Chunk),
+    /// A page of a PDF file.
+    ///
+    /// The chunk contains the page as a form XObject and all objects it
+    /// references.
+    Pdf(Chunk),
+}
+
+/// The color space of an encoded raster image.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RasterColorSpace {
+    /// Grayscale.
+    Luma,
+    /// RGB.
+    Rgb,
+    /// Device-dependent CMYK.
+    Cmyk,
+}
+
+impl RasterColorSpace {
+    /// The number of color components.
+    pub fn components(self) -> i32 {
+        match self {
+            Self::Luma => 1,
+            Self::Rgb => 3,
+            Self::Cmyk => 4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::foundations::Bytes;
+
+    use super::*;
+
+    /// The header of a JPEG file with the given frame marker and number of
+    /// components.
+    fn jpeg(marker: u8, components: u8, adobe: bool) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8];
+        if adobe {
+            data.extend([0xFF, 0xEE, 0x00, 0x0E]);
+            data.extend(b"Adobe");
+            data.extend([0x00, 0x64, 0x00, 0x00, 0x00, 0x00, 0x02]);
+        }
+
+        let len = 8 + 3 * u16::from(components);
+        data.extend([0xFF, marker]);
+        data.extend(len.to_be_bytes());
+        data.extend([8, 0x00, 0x01, 0x00, 0x01, components]);
+        for i in 0..components {
+            data.extend([i + 1, 0x11, 0x00]);
+        }
+
+        data.extend([0xFF, 0xDA, 0x00, 0x02]);
+        data
+    }
+
+    /// A minimal ICC profile header for the given color space.
+    fn icc(space: &[u8; 4]) -> Vec<u8> {
+        let mut profile = vec![0; 132];
+        profile[..4].copy_from_slice(&132u32.to_be_bytes());
+        profile[12..16].copy_from_slice(b"prtr");
+        profile[16..20].copy_from_slice(space);
+        profile[36..40].copy_from_slice(b"acsp");
+        profile
+    }
+
+    /// An uncompressed TIFF image consisting of a single pixel.
+    fn tiff(photometric: u16, pixel: &[u8], icc: &[u8]) -> RasterImage {
+        let samples = pixel.len() as u16;
+        let bits_offset = 8 + 4;
+        let icc_offset = bits_offset + 2 * u32::from(samples);
+        let ifd_offset = icc_offset + icc.len() as u32;
+
+        let mut data = b"II*\0".to_vec();
+        data.extend(ifd_offset.to_le_bytes());
+        data.extend(pixel);
+        data.resize(bits_offset as usize, 0);
+        for _ in 0..samples {
+            data.extend(8u16.to_le_bytes());
+        }
+        data.extend(icc);
+
+        let entries: [(u16, u16, u32, u32); 10] = [
+            (256, 3, 1, 1),
+            (257, 3, 1, 1),
+            (258, 3, samples.into(), bits_offset),
+            (259, 3, 1, 1),
+            (262, 3, 1, photometric.into()),
+            (273, 4, 1, 8),
+            (277, 3, 1, samples.into()),
+            (278, 3, 1, 1),
+            (279, 4, 1, samples.into()),
+            (34675, 7, icc.len() as u32, icc_offset),
+        ];
+        data.extend((entries.len() as u16).to_le_bytes());
+        for (tag, kind, count, value) in entries {
+            data.extend(tag.to_le_bytes());
+            data.extend(kind.to_le_bytes());
+            data.extend(count.to_le_bytes());
+            data.extend(value.to_le_bytes());
+        }
+        data.extend(0u32.to_le_bytes());
+
+        RasterImage::new(Bytes::from(data), RasterFormat::Tiff).unwrap()
+    }
+
+    #[test]
+    fn test_parse_jpeg_header() {
+        assert_eq!(parse_jpeg_header(&jpeg(0xC0, 1, false)), Some((1, false)));
+        assert_eq!(parse_jpeg_header(&jpeg(0xC0, 3, false)), Some((3, false)));
+    }
+
+    #[test]
+    fn test_parse_jpeg_header_progressive() {
+        assert_eq!(parse_jpeg_header(&jpeg(0xC2, 3, false)), Some((3, false)));
+        assert_eq!(parse_jpeg_header(&jpeg(0xC2, 4, true)), Some((4, true)));
+    }
+
+    #[test]
+    fn test_parse_jpeg_header_cmyk() {
+        assert_eq!(parse_jpeg_header(&jpeg(0xC0, 4, true)), Some((4, true)));
+        assert_eq!(parse_jpeg_header(&jpeg(0xC0, 4, false)), Some((4, false)));
+    }
+
+    #[test]
+    fn test_parse_jpeg_header_fill_bytes() {
+        let mut data = jpeg(0xC0, 3, false);
+        data.splice(2..2, [0xFF, 0xFF]);
+        assert_eq!(parse_jpeg_header(&data), Some((3, false)));
+    }
+
+    #[test]
+    fn test_parse_jpeg_header_unsupported() {
+        // Lossless and arithmetic coding.
+        assert_eq!(parse_jpeg_header(&jpeg(0xC3, 3, false)), None);
+        assert_eq!(parse_jpeg_header(&jpeg(0xC9, 3, false)), None);
+
+        // 12 bits per sample.
+        let mut data = jpeg(0xC0, 3, false);
+        data[6] = 12;
+        assert_eq!(parse_jpeg_header(&data), None);
+
+        // Truncated header and missing frame.
+        let data = jpeg(0xC0, 3, false);
+        assert_eq!(parse_jpeg_header(&data[..10]), None);
+        assert_eq!(parse_jpeg_header(&[0xFF, 0xD8, 0xFF, 0xDA, 0x00, 0x02]), None);
+        assert_eq!(parse_jpeg_header(b"\x89PNG"), None);
+    }
+
+    #[test]
+    fn test_matching_icc() {
+        let cmyk = icc(b"CMYK");
+        let raster = tiff(5, &[0, 0, 0, 255], &cmyk);
+        assert_eq!(matching_icc(&raster, RasterColorSpace::Cmyk), Some(&cmyk[..]));
+        assert_eq!(matching_icc(&raster, RasterColorSpace::Rgb), None);
+
+        let raster = tiff(2, &[255, 0, 0], &cmyk);
+        assert_eq!(matching_icc(&raster, RasterColorSpace::Rgb), None);
+
+        let rgb = icc(b"RGB ");
+        let raster = tiff(2, &[255, 0, 0], &rgb);
+        assert_eq!(matching_icc(&raster, RasterColorSpace::Rgb), Some(&rgb[..]));
+        assert_eq!(matching_icc(&raster, RasterColorSpace::Luma), None);
+    }
+
+    #[test]
+    fn test_matching_icc_invalid() {
+        let mut profile = icc(b"CMYK");
+        profile[36..40].copy_from_slice(b"xxxx");
+        let raster = tiff(5, &[0, 0, 0, 255], &profile);
+        assert_eq!(matching_icc(&raster, RasterColorSpace::Cmyk), None);
+    }
+
+    #[test]
+    fn test_cmyk_tiff() {
+        let raster = tiff(5, &[10, 20, 30, 40], &icc(b"CMYK"));
+        let data = cmyk_tiff(&raster).and_then(read_cmyk_tiff);
+        assert_eq!(data, Some(vec![10, 20, 30, 40]));
+
+        let raster = tiff(2, &[10, 20, 30], &icc(b"RGB "));
+        assert!(cmyk_tiff(&raster).is_none());
+    }
 }
@@ -1,6 +1,7 @@
 //! Exporting of Typst documents into PDFs.
 
 mod color;
+mod embed;
 mod extg;
 mod font;
 mod gradient;
@@ -8,17 +9,21 @@ mod image;
 mod outline;
 mod page;
 mod pattern;
+mod standard;
+mod tags;
 
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
 use std::hash::Hash;
 use std::sync::Arc;
 
 use base64::Engine;
 use ecow::{eco_format, EcoString};
 use indexmap::IndexMap;
-use pdf_writer::types::Direction;
-use pdf_writer::writers::Destination;
+use pdf_writer::types::{Direction, OutputIntentSubtype};
+use pdf_writer::writers::{self, Destination};
 use pdf_writer::{Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use typst::diag::SourceResult;
 use typst::foundations::{Datetime, Label, NativeElement, Smart};
 use typst::introspection::Location;
 use typst::layout::{Abs, Dir, Em, Frame, PageRanges, Transform};
@@ -29,43 +34,36 @@ use typst::utils::Deferred;
 use typst::visualize::Image;
 use xmp_writer::{DateTime, LangId, RenditionClass, Timezone, XmpWriter};
 
-use crate::color::ColorSpaces;
+use crate::color::{ColorSpaces, OutputIntent};
 use crate::extg::ExtGState;
 use crate::gradient::PdfGradient;
 use crate::image::EncodedImage;
 use crate::page::EncodedPage;
 use crate::pattern::PdfPattern;
+use crate::tags::Tags;
 
 /// Export a document into a PDF file.
 ///
-/// Returns the raw bytes making up the PDF file.
-///
-/// The `ident` parameter, if given, shall be a string that uniquely and stably
-/// identifies the document. It should not change between compilations of the
-/// same document.  **If you cannot provide such a stable identifier, just pass
-/// `Smart::Auto` rather than trying to come up with one.** The CLI, for
-/// example, does not have a well-defined notion of a long-lived project and as
-/// such just passes `Smart::Auto`.
-///
-/// If an `ident` is given, the hash of it will be used to create a PDF document
-/// identifier (the identifier itself is not leaked). If `ident` is `Auto`, a
-/// hash of the document's title and author is used instead (which is reasonably
-/// unique and stable).
-///
-/// The `timestamp`, if given, is expected to be the creation date of the
-/// document as a UTC datetime. It will only be used if `set document(date: ..)`
-/// is `auto`.
-///
-/// The `page_ranges` option specifies which ranges of pages should be exported
-/// in the PDF. When `None`, all pages should be exported.
+/// Returns the raw bytes making up the PDF file or an error if the document
+/// can't be exported with the given `options`, for example because it uses a
+/// feature that the requested [standard](PdfOptions::standard) doesn't allow.
 #[typst_macros::time(name = "pdf")]
-pub fn pdf(
-    document: &Document,
-    ident: Smart<&str>,
-    timestamp: Option<Datetime>,
-    page_ranges: Option<PageRanges>,
-) -> Vec<u8> {
-    let mut ctx = PdfContext::new(document, page_ranges);
+pub fn pdf(document: &Document, options: &PdfOptions) -> SourceResult<Vec<u8>> {
+    let output_intent = color::output_intent(document)?;
+    standard::validate(
+        document,
+        options.page_ranges.as_ref(),
+        options.standard,
+        output_intent.as_ref(),
+    )?;
+
+    let mut ctx = PdfContext::new(
+        document,
+        options.page_ranges.clone(),
+        options.standard,
+        options.tagged,
+        output_intent,
+    );
     page::construct_pages(&mut ctx, &document.pages);
     font::write_fonts(&mut ctx);
     image::write_images(&mut ctx);
@@ -73,16 +71,94 @@ pub fn pdf(
     extg::write_external_graphics_states(&mut ctx);
     pattern::write_patterns(&mut ctx);
     write_named_destinations(&mut ctx);
+    embed::write_embedded_files(&mut ctx)?;
     page::write_page_tree(&mut ctx);
     page::write_global_resources(&mut ctx);
-    write_catalog(&mut ctx, ident, timestamp);
-    ctx.pdf.finish()
+    write_catalog(&mut ctx, options.ident, options.timestamp);
+    Ok(ctx.pdf.finish())
+}
+
+/// Settings for PDF export.
+#[derive(Debug, Default, Clone)]
+pub struct PdfOptions<'a> {
+    /// If given, shall be a string that uniquely and stably identifies the
+    /// document. It should not change between compilations of the same
+    /// document.  **If you cannot provide such a stable identifier, just pass
+    /// `Smart::Auto` rather than trying to come up with one.** The CLI, for
+    /// example, does not have a well-defined notion of a long-lived project
+    /// and as such just passes `Smart::Auto`.
+    ///
+    /// If an `ident` is given, the hash of it will be used to create a PDF
+    /// document identifier (the identifier itself is not leaked). If `ident`
+    /// is `Auto`, a hash of the document's title and author is used instead
+    /// (which is reasonably unique and stable).
+    pub ident: Smart<&'a str>,
+    /// If given, is expected to be the creation date of the document as a UTC
+    /// datetime. It will only be used if `set document(date: ..)` is `auto`.
+    pub timestamp: Option<Datetime>,
+    /// Specifies which ranges of pages should be exported in the PDF. When
+    /// `None`, all pages should be exported.
+    pub page_ranges: Option<PageRanges>,
+    /// The standard the PDF should conform to. If the document uses a feature
+    /// that the standard does not allow, export fails with an error.
+    pub standard: PdfStandard,
+    /// Whether to write the document's logical structure, so that assistive
+    /// technology can make sense of it. This requires that the document was
+    /// compiled with a library that has [tagging](typst::Library::tagged)
+    /// enabled. Otherwise, the structure is limited to headings and similar
+    /// elements.
+    pub tagged: bool,
+}
+
+/// A standard that the exported PDF can conform to.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+#[allow(non_camel_case_types)]
+pub enum PdfStandard {
+    /// Plain PDF 1.7, without any additional restrictions.
+    #[default]
+    V_1_7,
+    /// PDF/A-2b, the archival standard ISO 19005-2 with basic conformance.
+    A_2b,
+    /// PDF/A-3b, the archival standard ISO 19005-3 with basic conformance.
+    ///
+    /// In contrast to PDF/A-2b, this also allows arbitrary embedded files.
+    A_3b,
+}
+
+impl PdfStandard {
+    /// The part of the PDF/A standard, if this is a PDF/A standard.
+    fn pdfa_part(self) -> Option<&'static str> {
+        match self {
+            Self::V_1_7 => None,
+            Self::A_2b => Some("2"),
+            Self::A_3b => Some("3"),
+        }
+    }
+
+    /// Whether this is a PDF/A standard.
+    fn is_pdfa(self) -> bool {
+        self.pdfa_part().is_some()
+    }
+}
+
+impl Display for PdfStandard {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.pad(match self {
+            Self::V_1_7 => "PDF 1.7",
+            Self::A_2b => "PDF/A-2b",
+            Self::A_3b => "PDF/A-3b",
+        })
+    }
 }
 
 /// Context for exporting a whole PDF document.
 struct PdfContext<'a> {
     /// The document that we're currently exporting.
     document: &'a Document,
+    /// The standard the PDF should conform to.
+    standard: PdfStandard,
+    /// Whether the document's logical structure is written.
+    tagged: bool,
     /// The writer we are writing the PDF into.
     pdf: Pdf,
     /// Content of exported pages.
@@ -147,16 +223,39 @@ struct PdfContext<'a> {
     dests: Vec<(Label, Ref)>,
     /// Maps from locations to named destinations that point to them.
     loc_to_dest: HashMap<Location, Label>,
+    /// A sorted list of all embedded files.
+    embedded_files: Vec<(EcoString, Ref)>,
+    /// The logical structure of the document.
+    tags: Tags,
 }
 
 impl<'a> PdfContext<'a> {
-    fn new(document: &'a Document, page_ranges: Option<PageRanges>) -> Self {
+    fn new(
+        document: &'a Document,
+        page_ranges: Option<PageRanges>,
+        standard: PdfStandard,
+        tagged: bool,
+        output_intent: Option<OutputIntent>,
+    ) -> Self {
         let mut alloc = Ref::new(1);
         let page_tree_ref = alloc.bump();
         let global_resources_ref = alloc.bump();
         let type3_font_resources_ref = alloc.bump();
+
+        // PDF/A requires an output intent, which references the sRGB profile
+        // unless the document specifies its own. Allocating the profile up
+        // front ensures that it is written out.
+        let mut colors = ColorSpaces::default();
+        if let Some(intent) = output_intent {
+            colors.set_output_intent(intent, &mut alloc);
+        } else if standard.is_pdfa() {
+            colors.srgb(&mut alloc);
+        }
+
         Self {
             document,
+            standard,
+            tagged,
             pdf: Pdf::new(),
             pages: vec![],
             exported_pages: page_ranges,
@@ -171,7 +270,7 @@ impl<'a> PdfContext<'a> {
             gradient_refs: vec![],
             pattern_refs: vec![],
             ext_gs_refs: vec![],
-            colors: ColorSpaces::default(),
+            colors,
             font_map: Remapper::new(),
             image_map: Remapper::new(),
             image_deferred_map: HashMap::default(),
@@ -181,6 +280,8 @@ impl<'a> PdfContext<'a> {
             color_font_map: ColorFontMap::new(),
             dests: vec![],
             loc_to_dest: HashMap::new(),
+            embedded_files: vec![],
+            tags: Tags::new(),
         }
     }
 }
@@ -201,6 +302,9 @@ fn write_catalog(ctx: &mut PdfContext, ident: Smart<&str>, timestamp: Option<Dat
     // Write the page labels.
     let page_labels = page::write_page_labels(ctx);
 
+    // Write the logical structure tree.
+    let struct_tree_root_id = tags::write_structure_tree(ctx);
+
     // Write the document information.
     let mut info = ctx.pdf.document_info(ctx.alloc.bump());
     let mut xmp = XmpWriter::new();
@@ -288,6 +392,11 @@ fn write_catalog(ctx: &mut PdfContext, ident: Smart<&str>, timestamp: Option<Dat
     xmp.rendition_class(RenditionClass::Proof);
     xmp.pdf_version("1.7");
 
+    if let Some(part) = ctx.standard.pdfa_part() {
+        xmp.pdfa_part(part);
+        xmp.pdfa_conformance("B");
+    }
+
     let xmp_buf = xmp.finish(None);
     let meta_ref = ctx.alloc.bump();
     ctx.pdf
@@ -301,6 +410,39 @@ fn write_catalog(ctx: &mut PdfContext, ident: Smart<&str>, timestamp: Option<Dat
     catalog.viewer_preferences().direction(dir);
     catalog.metadata(meta_ref);
 
+    // Write the document's output intent. Its subtype depends on the standard
+    // the document conforms to. PDF/A requires an intent, so we fall back to
+    // sRGB there, with the identifier it is registered under at the ICC.
+    let subtype = if ctx.standard.is_pdfa() {
+        OutputIntentSubtype::PDFA
+    } else {
+        OutputIntentSubtype::PDFX
+    };
+    if let Some((intent, profile)) = ctx.colors.output_intent() {
+        let mut intents = catalog.insert(Name(b"OutputIntents")).array();
+        let mut writer = intents.push().start::<writers::OutputIntent>();
+        writer.subtype(subtype);
+        writer.output_condition_identifier(TextStr(&intent.condition));
+        if let Some(info) = &intent.info {
+            writer.output_condition(TextStr(info));
+            writer.info(TextStr(info));
+        }
+        writer.dest_output_profile(profile);
+    } else if ctx.standard.is_pdfa() {
+        let profile = ctx.colors.srgb(&mut ctx.alloc);
+        catalog
+            .insert(Name(b"OutputIntents"))
+            .array()
+            .push()
+            .start::<writers::OutputIntent>()
+            .subtype(subtype)
+            .output_condition(TextStr("sRGB"))
+            .output_condition_identifier(TextStr("sRGB IEC61966-2.1"))
+            .registry_name(TextStr("http://www.color.org"))
+            .info(TextStr("sRGB IEC61966-2.1"))
+            .dest_output_profile(profile);
+    }
+
     // Write the named destination tree.
     let mut name_dict = catalog.names();
     let mut dests_name_tree = name_dict.destinations();
@@ -310,8 +452,26 @@ fn write_catalog(ctx: &mut PdfContext, ident: Smart<&str>, timestamp: Option<Dat
     }
     names.finish();
     dests_name_tree.finish();
+
+    // Write the embedded files name tree.
+    if !ctx.embedded_files.is_empty() {
+        let mut embedded_files = name_dict.embedded_files();
+        let mut names = embedded_files.names();
+        for (name, file_spec_ref) in &ctx.embedded_files {
+            names.insert(Str(name.as_bytes()), *file_spec_ref);
+        }
+    }
+
     name_dict.finish();
 
+    // In PDF/A-3, embedded files must be associated with the document.
+    if ctx.standard.is_pdfa() && !ctx.embedded_files.is_empty() {
+        catalog
+            .insert(Name(b"AF"))
+            .array()
+            .items(ctx.embedded_files.iter().map(|&(_, file_spec_ref)| file_spec_ref));
+    }
+
     // Insert the page labels.
     if !page_labels.is_empty() {
         let mut num_tree = catalog.page_labels();
@@ -325,6 +485,11 @@ fn write_catalog(ctx: &mut PdfContext, ident: Smart<&str>, timestamp: Option<Dat
         catalog.outlines(outline_root_id);
     }
 
+    if let Some(struct_tree_root_id) = struct_tree_root_id {
+        catalog.pair(Name(b"StructTreeRoot"), struct_tree_root_id);
+        catalog.mark_info().marked(true);
+    }
+
     if let Some(lang) = lang {
         catalog.lang(TextStr(lang.as_str()));
     }
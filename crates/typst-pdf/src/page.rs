@@ -3,14 +3,15 @@ use std::num::NonZeroUsize;
 
 use crate::color::PaintEncode;
 use crate::extg::ExtGState;
-use crate::image::deferred_image;
+use crate::image::{deferred_image, uses_transparency};
+use crate::tags::{self, Marked};
 use crate::{deflate_deferred, AbsExt, EmExt, PdfContext};
 use ecow::{eco_format, EcoString};
 use pdf_writer::types::{
     ActionType, AnnotationFlags, AnnotationType, ColorSpaceOperand, LineCapStyle,
     LineJoinStyle, NumberingStyle, TextRenderingMode,
 };
-use pdf_writer::writers::{PageLabel, Resources};
+use pdf_writer::writers::{Annotation, PageLabel, Resources};
 use pdf_writer::{Content, Filter, Finish, Name, Rect, Ref, Str, TextStr};
 use typst::layout::{
     Abs, Em, Frame, FrameItem, GroupItem, Page, Point, Ratio, Size, Transform,
@@ -37,7 +38,7 @@ pub(crate) fn construct_pages(ctx: &mut PdfContext, pages: &[Page]) {
             ctx.pages.push(None);
             skipped_pages += 1;
         } else {
-            let mut encoded = construct_page(ctx, &page.frame);
+            let mut encoded = construct_page(ctx, &page.frame, ctx.tagged);
             encoded.label = page
                 .numbering
                 .as_ref()
@@ -58,12 +59,21 @@ pub(crate) fn construct_pages(ctx: &mut PdfContext, pages: &[Page]) {
 }
 
 /// Construct a page object.
+///
+/// If `tagged` is true, the page's contents become part of the document's
+/// logical structure.
 #[typst_macros::time(name = "construct page")]
-pub(crate) fn construct_page(ctx: &mut PdfContext, frame: &Frame) -> EncodedPage {
+pub(crate) fn construct_page(
+    ctx: &mut PdfContext,
+    frame: &Frame,
+    tagged: bool,
+) -> EncodedPage {
     let page_ref = ctx.alloc.bump();
+    let struct_parents = tagged.then(|| ctx.tags.start_page(page_ref));
 
     let size = frame.size();
     let mut ctx = PageContext::new(ctx, size);
+    ctx.tagged = tagged;
 
     // Make the coordinate system start at the top-left.
     ctx.bottom = size.y.to_f32();
@@ -78,6 +88,9 @@ pub(crate) fn construct_page(ctx: &mut PdfContext, frame: &Frame) -> EncodedPage
 
     // Encode the page into the content stream.
     write_frame(&mut ctx, frame);
+    if tagged {
+        ctx.parent.tags.end_page();
+    }
 
     EncodedPage {
         size,
@@ -85,6 +98,7 @@ pub(crate) fn construct_page(ctx: &mut PdfContext, frame: &Frame) -> EncodedPage
         id: page_ref,
         uses_opacities: ctx.uses_opacities,
         links: ctx.links,
+        struct_parents,
         label: None,
         resources: ctx.resources,
     }
@@ -216,12 +230,29 @@ fn write_page(ctx: &mut PdfContext, i: usize, refs: &mut Vec<Ref>) {
             .srgb();
     }
 
-    let mut annotations = page_writer.annotations();
-    for (dest, rect) in &page.links {
-        let mut annotation = annotations.push();
+    // Annotations are written as indirect objects so that the structure tree
+    // can refer to them.
+    let annotation_refs: Vec<Ref> = page.links.iter().map(|_| ctx.alloc.bump()).collect();
+    page_writer
+        .insert(Name(b"Annots"))
+        .array()
+        .items(annotation_refs.iter().copied());
+
+    if let Some(key) = page.struct_parents {
+        page_writer.struct_parents(key);
+    }
+
+    page_writer.finish();
+
+    for ((dest, rect, node), &annotation_ref) in page.links.iter().zip(&annotation_refs) {
+        let mut annotation = ctx.pdf.indirect(annotation_ref).start::<Annotation>();
         annotation.subtype(AnnotationType::Link).rect(*rect);
         annotation.border(0.0, 0.0, 0.0, None).flags(AnnotationFlags::PRINT);
 
+        if let Some(node) = *node {
+            annotation.struct_parent(ctx.tags.annotation(node, page.id, annotation_ref));
+        }
+
         let pos = match dest {
             Destination::Url(uri) => {
                 annotation
@@ -259,9 +290,6 @@ fn write_page(ctx: &mut PdfContext, i: usize, refs: &mut Vec<Ref>) {
         }
     }
 
-    annotations.finish();
-    page_writer.finish();
-
     ctx.pdf
         .stream(content_id, page.content.wait())
         .filter(Filter::FlateDecode);
@@ -423,8 +451,12 @@ pub struct EncodedPage {
     pub content: Deferred<Vec<u8>>,
     /// Whether the page uses opacities.
     pub uses_opacities: bool,
-    /// Links in the PDF coordinate system.
-    pub links: Vec<(Destination, Rect)>,
+    /// Links in the PDF coordinate system, along with the structure element
+    /// they belong to.
+    pub links: Vec<(Destination, Rect, Option<usize>)>,
+    /// The page's key in the parent tree of the structure tree, if it is
+    /// tagged.
+    pub struct_parents: Option<i32>,
     /// The page's used resources
     pub resources: HashMap<PageResource, usize>,
     /// The page's PDF label.
@@ -493,8 +525,13 @@ pub struct PageContext<'a, 'b> {
     state: State,
     saves: Vec<State>,
     pub bottom: f32,
-    uses_opacities: bool,
-    links: Vec<(Destination, Rect)>,
+    /// Whether the contents use transparency, in which case the page needs a
+    /// transparency group.
+    pub(crate) uses_opacities: bool,
+    links: Vec<(Destination, Rect, Option<usize>)>,
+    /// Whether the contents are tagged. This is only the case for actual
+    /// pages, but not for patterns or color glyphs.
+    pub(crate) tagged: bool,
     /// Keep track of the resources being used in the page.
     pub resources: HashMap<PageResource, usize>,
 }
@@ -509,6 +546,7 @@ impl<'a, 'b> PageContext<'a, 'b> {
             saves: vec![],
             bottom: 0.0,
             links: vec![],
+            tagged: false,
             resources: HashMap::default(),
         }
     }
@@ -745,11 +783,27 @@ pub(crate) fn write_frame(ctx: &mut PageContext, frame: &Frame) {
         let y = pos.y.to_f32();
         match item {
             FrameItem::Group(group) => write_group(ctx, pos, group),
-            FrameItem::Text(text) => write_text(ctx, pos, text),
-            FrameItem::Shape(shape, _) => write_shape(ctx, pos, shape),
-            FrameItem::Image(image, size, _) => write_image(ctx, x, y, image, *size),
+            FrameItem::Text(text) => {
+                tags::start_marked(ctx, Marked::Text(text.lang));
+                write_text(ctx, pos, text);
+                tags::end_marked(ctx);
+            }
+            FrameItem::Shape(shape, _) => {
+                tags::start_marked(ctx, Marked::Shape);
+                write_shape(ctx, pos, shape);
+                tags::end_marked(ctx);
+            }
+            FrameItem::Image(image, size, _) => {
+                tags::start_marked(ctx, Marked::Image);
+                write_image(ctx, x, y, image, *size);
+                tags::end_marked(ctx);
+            }
             FrameItem::Link(dest, size) => write_link(ctx, pos, dest, *size),
-            FrameItem::Tag(_) => {}
+            FrameItem::Tag(tag) => {
+                if ctx.tagged {
+                    ctx.parent.tags.handle(tag);
+                }
+            }
         }
     }
 }
@@ -1044,7 +1098,9 @@ fn write_image(ctx: &mut PageContext, x: f32, y: f32, image: &Image, size: Size)
     ctx.content.save_state();
     ctx.content.transform([w, 0.0, 0.0, -h, x, y + h]);
 
-    if let Some(alt) = image.alt() {
+    // In tagged pages, the alternative description is part of the image's
+    // structure element instead.
+    if let Some(alt) = image.alt().filter(|_| !ctx.tagged) {
         let mut image_span =
             ctx.content.begin_marked_content_with_properties(Name(b"Span"));
         let mut image_alt = image_span.properties();
@@ -1061,6 +1117,12 @@ fn write_image(ctx: &mut PageContext, x: f32, y: f32, image: &Image, size: Size)
     ctx.resources
         .insert(PageResource::new(ResourceKind::XObject, name.clone()), index);
     ctx.content.restore_state();
+
+    // Raster images with an alpha channel are drawn with a soft mask and
+    // vector images may contain transparency of their own.
+    if uses_transparency(image.clone()) {
+        ctx.uses_opacities = true;
+    }
 }
 
 /// Save a link for later writing in the annotations dictionary.
@@ -1090,7 +1152,8 @@ fn write_link(ctx: &mut PageContext, pos: Point, dest: &Destination, size: Size)
     let y2 = min_y.to_f32();
     let rect = Rect::new(x1, y1, x2, y2);
 
-    ctx.links.push((dest.clone(), rect));
+    let node = if ctx.tagged { ctx.parent.tags.current_link() } else { None };
+    ctx.links.push((dest.clone(), rect, node));
 }
 
 fn to_pdf_line_cap(cap: LineCap) -> LineCapStyle {
@@ -116,7 +116,10 @@ fn register_pattern(
     };
 
     // Render the body.
-    let content = construct_page(ctx.parent, pattern.frame());
+    let content = construct_page(ctx.parent, pattern.frame(), false);
+    if content.uses_opacities {
+        ctx.uses_opacities = true;
+    }
 
     let mut pdf_pattern = PdfPattern {
         transform,
@@ -0,0 +1,196 @@
+use typst::diag::{bail, SourceResult};
+use typst::foundations::NativeElement;
+use typst::layout::{Abs, Frame, FrameItem, PageRanges};
+use typst::model::Document;
+use typst::pdf::EmbedElem;
+use typst::syntax::Span;
+use typst::text::TextItem;
+use typst::visualize::{Color, ColorSpace, Image, ImageKind, Paint};
+
+use crate::color::OutputIntent;
+use crate::image::{cmyk_tiff, jpeg_passthrough, matching_icc, RasterColorSpace};
+use crate::PdfStandard;
+
+/// The smallest page dimension allowed by PDF/A, in points.
+const MIN_PAGE_SIZE: f64 = 3.0;
+
+/// The largest page dimension allowed by PDF/A, in points.
+const MAX_PAGE_SIZE: f64 = 14400.0;
+
+/// Ensure that the exported pages of the document only use features that are
+/// allowed by the given standard.
+#[typst_macros::time(name = "validate pdf")]
+pub(crate) fn validate(
+    document: &Document,
+    page_ranges: Option<&PageRanges>,
+    standard: PdfStandard,
+    output_intent: Option<&OutputIntent>,
+) -> SourceResult<()> {
+    if !standard.is_pdfa() {
+        return Ok(());
+    }
+
+    // Device-dependent CMYK colors are only allowed if the output intent
+    // tells how to interpret them.
+    let rules = Rules {
+        standard,
+        allow_cmyk: output_intent.is_some_and(OutputIntent::is_cmyk),
+    };
+
+    // PDF/A-2 only allows embedding other PDF/A files, which we can't verify.
+    if standard == PdfStandard::A_2b {
+        let embeds = document.introspector.query(&EmbedElem::elem().select());
+        if let Some(embed) = embeds.first() {
+            bail!(
+                embed.span(),
+                "{standard} does not allow embedded files";
+                hint: "export to PDF/A-3b instead"
+            );
+        }
+    }
+
+    for (i, page) in document.pages.iter().enumerate() {
+        if page_ranges.is_some_and(|ranges| !ranges.includes_page_index(i)) {
+            continue;
+        }
+
+        let size = page.frame.size();
+        let allowed = Abs::pt(MIN_PAGE_SIZE)..=Abs::pt(MAX_PAGE_SIZE);
+        if !allowed.contains(&size.x) || !allowed.contains(&size.y) {
+            bail!(
+                Span::detached(),
+                "page {} is {}pt × {}pt, which {standard} does not allow",
+                i + 1,
+                size.x.to_pt().round(),
+                size.y.to_pt().round();
+                hint: "pages must be between {MIN_PAGE_SIZE}pt and {MAX_PAGE_SIZE}pt in both dimensions"
+            );
+        }
+
+        validate_frame(&page.frame, rules)?;
+    }
+
+    Ok(())
+}
+
+/// The rules a document is validated against.
+#[derive(Copy, Clone)]
+struct Rules {
+    /// The standard the document must conform to.
+    standard: PdfStandard,
+    /// Whether device-dependent CMYK colors may be used.
+    allow_cmyk: bool,
+}
+
+/// Validate the contents of a frame.
+fn validate_frame(frame: &Frame, rules: Rules) -> SourceResult<()> {
+    for (_, item) in frame.items() {
+        match item {
+            FrameItem::Group(group) => validate_frame(&group.frame, rules)?,
+            FrameItem::Text(text) => validate_text(text, rules)?,
+            FrameItem::Shape(shape, span) => {
+                if let Some(fill) = &shape.fill {
+                    validate_paint(fill, *span, rules)?;
+                }
+                if let Some(stroke) = &shape.stroke {
+                    validate_paint(&stroke.paint, *span, rules)?;
+                }
+            }
+            FrameItem::Image(image, _, span) => validate_image(image, *span, rules)?,
+            FrameItem::Link(..) | FrameItem::Tag(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Validate a text run.
+fn validate_text(text: &TextItem, rules: Rules) -> SourceResult<()> {
+    let standard = rules.standard;
+    let span = text.glyphs.first().map_or(Span::detached(), |glyph| glyph.span.0);
+    validate_paint(&text.fill, span, rules)?;
+    if let Some(stroke) = &text.stroke {
+        validate_paint(&stroke.paint, span, rules)?;
+    }
+
+    // PDF/A forbids references to the `.notdef` glyph, which is what we use
+    // for characters that are missing from the font.
+    if let Some(glyph) = text.glyphs.iter().find(|glyph| glyph.id == 0) {
+        let missing = &text.text[glyph.range()];
+        bail!(
+            glyph.span.0,
+            "the text {missing:?} could not be displayed with any font";
+            hint: "{standard} does not allow glyphs that are missing from their font";
+            hint: "try using a font that contains this character"
+        );
+    }
+
+    Ok(())
+}
+
+/// Validate an image.
+fn validate_image(image: &Image, span: Span, rules: Rules) -> SourceResult<()> {
+    // Pages of other PDFs are copied as they are and may use fonts that
+    // aren't embedded, uncalibrated colors, or transparency groups, which we
+    // can't fix up.
+    if let ImageKind::Pdf(_) = image.kind() {
+        bail!(
+            span,
+            "{} does not allow embedding pages of other PDFs",
+            rules.standard;
+            hint: "the page may use fonts, colors, or transparency that {} forbids",
+            rules.standard;
+            hint: "convert the page to an SVG or raster image instead"
+        );
+    }
+
+    let ImageKind::Raster(raster) = image.kind() else { return Ok(()) };
+    let cmyk = match jpeg_passthrough(raster) {
+        Some(jpeg) => jpeg.space == RasterColorSpace::Cmyk,
+        None => cmyk_tiff(raster).is_some(),
+    };
+    let icc = matching_icc(raster, RasterColorSpace::Cmyk);
+    if cmyk && icc.is_none() && !rules.allow_cmyk {
+        bail!(
+            span,
+            "{} does not allow CMYK images without an ICC profile",
+            rules.standard;
+            hint: "embed an ICC profile into the image";
+            hint: "or specify a CMYK output intent with `pdf.output-intent`"
+        );
+    }
+    Ok(())
+}
+
+/// Validate a paint.
+fn validate_paint(paint: &Paint, span: Span, rules: Rules) -> SourceResult<()> {
+    if rules.allow_cmyk {
+        return match paint {
+            Paint::Pattern(pattern) => validate_frame(pattern.frame(), rules),
+            _ => Ok(()),
+        };
+    }
+
+    let standard = rules.standard;
+    let uses_cmyk = match paint {
+        Paint::Solid(color) => matches!(color, Color::Cmyk(_)),
+        Paint::Gradient(gradient) => {
+            gradient.space() == ColorSpace::Cmyk
+                || gradient
+                    .stops_ref()
+                    .iter()
+                    .any(|(color, _)| matches!(color, Color::Cmyk(_)))
+        }
+        Paint::Pattern(pattern) => return validate_frame(pattern.frame(), rules),
+    };
+
+    if uses_cmyk {
+        bail!(
+            span,
+            "{standard} does not allow CMYK colors";
+            hint: "convert the color to RGB with `rgb(..)` instead";
+            hint: "or specify a CMYK output intent with `pdf.output-intent`"
+        );
+    }
+
+    Ok(())
+}
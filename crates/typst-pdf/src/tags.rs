@@ -0,0 +1,497 @@
+use std::collections::HashSet;
+
+use pdf_writer::types::StructRole;
+use pdf_writer::writers::StructTreeRoot;
+use pdf_writer::{Finish, Name, Ref, TextStr};
+use typst::foundations::{Content, StyleChain};
+use typst::introspection::{Location, Tag};
+use typst::math::EquationElem;
+use typst::model::{
+    EnumElem, EnumItem, FigureCaption, FigureElem, HeadingElem, LinkElem, ListElem,
+    ListItem, ParElem, TableCell, TableElem, TermItem, TermsElem,
+};
+use typst::text::Lang;
+use typst::visualize::ImageElem;
+
+use crate::page::PageContext;
+use crate::PdfContext;
+
+/// Builds the logical structure tree of the document from the tags in the
+/// frames.
+///
+/// Every text run, image, and shape on a page is either wrapped in a
+/// marked-content sequence that belongs to a structure element or marked as
+/// an artifact.
+pub(crate) struct Tags {
+    /// All structure elements. The first one is the root document element.
+    nodes: Vec<StructNode>,
+    /// The elements that are currently open, along with the location of the
+    /// Typst element that opened them.
+    ///
+    /// An entry without a node marks a region whose content is an artifact.
+    /// Multiple entries can refer to the same node if an element is merged
+    /// into its parent.
+    stack: Vec<(Location, Option<usize>)>,
+    /// The locations of all elements we have seen a start tag for.
+    seen: HashSet<Location>,
+    /// The entries of the parent tree, indexed by their key.
+    parent_tree: Vec<ParentEntry>,
+    /// The page that is currently being constructed.
+    page: Option<TaggedPage>,
+}
+
+/// A node in the structure tree.
+struct StructNode {
+    /// The role of the element.
+    role: StructRole,
+    /// The index of the parent node.
+    parent: usize,
+    /// The contents of the element, in reading order.
+    children: Vec<StructChild>,
+    /// An alternate description of the element.
+    alt: Option<String>,
+    /// The language of the first text within the element.
+    lang: Option<Lang>,
+    /// For tables, the y coordinate and node of the row that was opened last.
+    row: Option<(usize, usize)>,
+    /// For tables, the location hashes of the cells that were tagged.
+    cells: HashSet<u128>,
+}
+
+/// A child of a structure element.
+enum StructChild {
+    /// Another structure element.
+    Node(usize),
+    /// A marked-content sequence on a page.
+    Content { page: Ref, mcid: i32 },
+    /// An annotation on a page.
+    Annotation { page: Ref, annot: Ref },
+}
+
+/// An entry in the parent tree.
+enum ParentEntry {
+    /// The structure elements of the marked-content sequences on a page,
+    /// indexed by their marked-content identifier.
+    Page(Vec<usize>),
+    /// The structure element of an annotation.
+    Annotation(usize),
+}
+
+/// The page that is currently being constructed.
+struct TaggedPage {
+    /// The ID of the page.
+    id: Ref,
+    /// The page's key in the parent tree.
+    key: usize,
+}
+
+/// A kind of item that can be written into a marked-content sequence.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Marked {
+    /// A text run in the given language.
+    Text(Lang),
+    /// An image.
+    Image,
+    /// A geometric shape.
+    Shape,
+}
+
+impl Tags {
+    /// Create a structure tree with only the root document element.
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![StructNode::new(StructRole::Document, 0)],
+            stack: vec![],
+            seen: HashSet::new(),
+            parent_tree: vec![],
+            page: None,
+        }
+    }
+
+    /// Start tagging the contents of a page and return its key in the parent
+    /// tree.
+    pub fn start_page(&mut self, id: Ref) -> i32 {
+        let key = self.parent_tree.len();
+        self.parent_tree.push(ParentEntry::Page(vec![]));
+        self.page = Some(TaggedPage { id, key });
+        key as i32
+    }
+
+    /// Stop tagging the contents of the current page.
+    pub fn end_page(&mut self) {
+        self.page = None;
+    }
+
+    /// Open or close a structure element.
+    pub fn handle(&mut self, tag: &Tag) {
+        match tag {
+            Tag::Start(elem) => self.start(elem),
+            Tag::End(loc) => {
+                // Elements may end out of order, e.g. when a list item's body
+                // ends within a paragraph. In this case, we close everything
+                // that was opened within the element, too.
+                if let Some(i) = self.stack.iter().position(|&(l, _)| l == *loc) {
+                    self.stack.truncate(i);
+                }
+            }
+        }
+    }
+
+    /// Open a structure element for the given Typst element, if it has one.
+    fn start(&mut self, elem: &Content) {
+        let Some(loc) = elem.location() else { return };
+        let Some(role) = role(elem) else { return };
+
+        // Elements that are laid out multiple times (like repeated table
+        // headers) are only tagged the first time.
+        if !self.seen.insert(loc) {
+            self.stack.push((loc, None));
+            return;
+        }
+
+        let Some(parent) = self.current() else {
+            self.stack.push((loc, None));
+            return;
+        };
+
+        // Paragraphs within elements that only contain inline content are
+        // merged into their parent.
+        let parent_role = self.nodes[parent].role;
+        if role == StructRole::P
+            && matches!(
+                parent_role,
+                StructRole::H1
+                    | StructRole::H2
+                    | StructRole::H3
+                    | StructRole::H4
+                    | StructRole::H5
+                    | StructRole::H6
+                    | StructRole::P
+                    | StructRole::Caption
+                    | StructRole::Link
+                    | StructRole::Formula
+            )
+        {
+            self.stack.push((loc, Some(parent)));
+            return;
+        }
+
+        // Paragraphs directly within lists hold the item markers.
+        if role == StructRole::P && parent_role == StructRole::L {
+            self.stack.push((loc, None));
+            return;
+        }
+
+        // Table cells are grouped into rows, which we synthesize from the
+        // cells' positions.
+        let (role, parent) = match elem.to_packed::<TableCell>() {
+            Some(cell) if parent_role == StructRole::Table => {
+                // Repeated headers and footers are laid out anew on each
+                // page, so their cells get fresh locations with the same
+                // hash.
+                let table = &mut self.nodes[parent];
+                if !table.cells.insert(loc.hash) {
+                    self.stack.push((loc, None));
+                    return;
+                }
+
+                let styles = StyleChain::default();
+                let y = cell.y(styles).custom().unwrap_or(0);
+                let role = if cell.header() == Some(&true) {
+                    StructRole::TH
+                } else {
+                    StructRole::TD
+                };
+                let row = match table.row {
+                    Some((row_y, row)) if row_y == y => row,
+                    _ => {
+                        let row = self.push(StructRole::TR, parent);
+                        self.nodes[parent].row = Some((y, row));
+                        row
+                    }
+                };
+                (role, row)
+            }
+            _ => (role, parent),
+        };
+
+        let node = self.push(role, parent);
+        self.stack.push((loc, Some(node)));
+
+        if let Some(image) = elem.to_packed::<ImageElem>() {
+            let alt = image.alt(StyleChain::default());
+            self.nodes[node].alt = alt.map(Into::into);
+        } else if role == StructRole::LI {
+            // The contents of list items go into a list body.
+            let body = self.push(StructRole::LBody, node);
+            self.stack.push((loc, Some(body)));
+        }
+    }
+
+    /// Add a new structure element as the last child of `parent`.
+    fn push(&mut self, role: StructRole, parent: usize) -> usize {
+        let node = self.nodes.len();
+        self.nodes.push(StructNode::new(role, parent));
+        self.nodes[parent].children.push(StructChild::Node(node));
+        node
+    }
+
+    /// The structure element that content is currently added to. Returns
+    /// `None` if content is currently an artifact.
+    fn current(&self) -> Option<usize> {
+        match self.stack.last() {
+            Some(&(_, node)) => node,
+            None => Some(0),
+        }
+    }
+
+    /// The link that is currently open, if any.
+    pub fn current_link(&self) -> Option<usize> {
+        let node = self.current()?;
+        (self.nodes[node].role == StructRole::Link).then_some(node)
+    }
+
+    /// Determine the marked-content identifier for an item that is added to
+    /// the current structure element. Returns `None` and the item should be
+    /// marked as an artifact, if it does not belong to any element.
+    fn mark(&mut self, marked: Marked) -> Option<(StructRole, i32)> {
+        let page = self.page.as_ref()?;
+        let node = self.current()?;
+        let role = self.nodes[node].role;
+
+        // Content directly within grouping elements is decoration, like list
+        // markers or table strokes. Shapes are only meaningful in figures and
+        // formulas.
+        let grouping = matches!(
+            role,
+            StructRole::Document
+                | StructRole::L
+                | StructRole::LI
+                | StructRole::Table
+                | StructRole::TR
+        );
+        let graphical = matches!(role, StructRole::Figure | StructRole::Formula);
+        match marked {
+            Marked::Text(_) | Marked::Image if grouping => return None,
+            Marked::Shape if !graphical => return None,
+            _ => {}
+        }
+
+        let ParentEntry::Page(mcids) = &mut self.parent_tree[page.key] else {
+            unreachable!("page key must refer to a page entry");
+        };
+        let mcid = mcids.len() as i32;
+        mcids.push(node);
+
+        let page = page.id;
+        let node = &mut self.nodes[node];
+        node.children.push(StructChild::Content { page, mcid });
+        if let Marked::Text(lang) = marked {
+            node.lang.get_or_insert(lang);
+        }
+
+        Some((role, mcid))
+    }
+
+    /// Attach an annotation to a structure element and return the
+    /// annotation's key in the parent tree.
+    pub fn annotation(&mut self, node: usize, page: Ref, annot: Ref) -> i32 {
+        let key = self.parent_tree.len();
+        self.parent_tree.push(ParentEntry::Annotation(node));
+        self.nodes[node]
+            .children
+            .push(StructChild::Annotation { page, annot });
+        key as i32
+    }
+}
+
+impl StructNode {
+    fn new(role: StructRole, parent: usize) -> Self {
+        Self {
+            role,
+            parent,
+            children: vec![],
+            alt: None,
+            lang: None,
+            row: None,
+            cells: HashSet::new(),
+        }
+    }
+}
+
+/// Start a marked-content sequence for an item.
+///
+/// Must be followed by a call to [`end_marked`] once the item was written.
+pub(crate) fn start_marked(ctx: &mut PageContext, marked: Marked) {
+    if !ctx.tagged {
+        return;
+    }
+
+    match ctx.parent.tags.mark(marked) {
+        Some((role, mcid)) => {
+            ctx.content
+                .begin_marked_content_with_properties(tag_name(role))
+                .properties()
+                .identify(mcid);
+        }
+        None => {
+            ctx.content.begin_marked_content(Name(b"Artifact"));
+        }
+    }
+}
+
+/// End a marked-content sequence started with [`start_marked`].
+pub(crate) fn end_marked(ctx: &mut PageContext) {
+    if ctx.tagged {
+        ctx.content.end_marked_content();
+    }
+}
+
+/// Write the structure tree and return the ID of its root.
+///
+/// Returns `None` if the document has no tagged content.
+pub(crate) fn write_structure_tree(ctx: &mut PdfContext) -> Option<Ref> {
+    let tags = &ctx.tags;
+
+    // Determine which elements actually have content. Children always come
+    // after their parents, so one backwards pass suffices.
+    let mut used = vec![false; tags.nodes.len()];
+    for (i, node) in tags.nodes.iter().enumerate().rev() {
+        if node.children.iter().any(|child| match child {
+            StructChild::Node(child) => used[*child],
+            _ => true,
+        }) {
+            used[i] = true;
+        }
+    }
+
+    if !used[0] {
+        return None;
+    }
+
+    let root_ref = ctx.alloc.bump();
+    let refs: Vec<Ref> = tags.nodes.iter().map(|_| ctx.alloc.bump()).collect();
+    let doc_lang = ctx.languages.iter().max_by_key(|(_, &count)| count).map(|(&l, _)| l);
+
+    for (i, node) in tags.nodes.iter().enumerate() {
+        if !used[i] {
+            continue;
+        }
+
+        let mut elem = ctx.pdf.struct_element(refs[i]);
+        elem.kind(node.role);
+        elem.parent(if i == 0 { root_ref } else { refs[node.parent] });
+
+        if let Some(alt) = &node.alt {
+            elem.alt(TextStr(alt));
+        }
+
+        if let Some(lang) = node.lang.filter(|&lang| Some(lang) != doc_lang) {
+            elem.lang(TextStr(lang.as_str()));
+        }
+
+        let mut children = elem.children();
+        for child in &node.children {
+            match *child {
+                StructChild::Node(child) => {
+                    if used[child] {
+                        children.struct_element(refs[child]);
+                    }
+                }
+                StructChild::Content { page, mcid } => {
+                    children.marked_content_ref().page(page).marked_content_id(mcid);
+                }
+                StructChild::Annotation { page, annot } => {
+                    children.object_ref().page(page).object(annot);
+                }
+            }
+        }
+    }
+
+    // Write the arrays of the parent tree, which map marked-content sequences
+    // on a page back to their structure elements.
+    let mut parents = Vec::with_capacity(tags.parent_tree.len());
+    for entry in &tags.parent_tree {
+        match entry {
+            ParentEntry::Page(mcids) => {
+                let array_ref = ctx.alloc.bump();
+                ctx.pdf
+                    .indirect(array_ref)
+                    .array()
+                    .items(mcids.iter().map(|&node| refs[node]));
+                parents.push(array_ref);
+            }
+            ParentEntry::Annotation(node) => parents.push(refs[*node]),
+        }
+    }
+
+    let mut root = ctx.pdf.indirect(root_ref).start::<StructTreeRoot>();
+    root.children().item(refs[0]);
+    let mut tree = root.parent_tree();
+    let mut nums = tree.nums();
+    for (key, parent) in parents.into_iter().enumerate() {
+        nums.insert(key as i32, parent);
+    }
+    nums.finish();
+    tree.finish();
+    root.parent_tree_next_key(tags.parent_tree.len() as i32);
+    root.finish();
+
+    Some(root_ref)
+}
+
+/// The tag of a marked-content sequence that belongs to an element with the
+/// given role.
+fn tag_name(role: StructRole) -> Name<'static> {
+    Name(match role {
+        StructRole::P => b"P",
+        StructRole::H1 => b"H1",
+        StructRole::H2 => b"H2",
+        StructRole::H3 => b"H3",
+        StructRole::H4 => b"H4",
+        StructRole::H5 => b"H5",
+        StructRole::H6 => b"H6",
+        StructRole::LBody => b"LBody",
+        StructRole::TH => b"TH",
+        StructRole::TD => b"TD",
+        StructRole::Figure => b"Figure",
+        StructRole::Caption => b"Caption",
+        StructRole::Link => b"Link",
+        StructRole::Formula => b"Formula",
+        _ => b"Span",
+    })
+}
+
+/// The structure role of a Typst element, if it has one.
+fn role(elem: &Content) -> Option<StructRole> {
+    Some(if let Some(heading) = elem.to_packed::<HeadingElem>() {
+        match heading.resolve_level(StyleChain::default()).get() {
+            1 => StructRole::H1,
+            2 => StructRole::H2,
+            3 => StructRole::H3,
+            4 => StructRole::H4,
+            5 => StructRole::H5,
+            _ => StructRole::H6,
+        }
+    } else if elem.is::<ParElem>() {
+        StructRole::P
+    } else if elem.is::<ListElem>() || elem.is::<EnumElem>() || elem.is::<TermsElem>() {
+        StructRole::L
+    } else if elem.is::<ListItem>() || elem.is::<EnumItem>() || elem.is::<TermItem>() {
+        StructRole::LI
+    } else if elem.is::<TableElem>() {
+        StructRole::Table
+    } else if elem.is::<TableCell>() {
+        StructRole::TD
+    } else if elem.is::<FigureElem>() || elem.is::<ImageElem>() {
+        StructRole::Figure
+    } else if elem.is::<FigureCaption>() {
+        StructRole::Caption
+    } else if elem.is::<LinkElem>() {
+        StructRole::Link
+    } else if elem.is::<EquationElem>() {
+        StructRole::Formula
+    } else {
+        return None;
+    })
+}
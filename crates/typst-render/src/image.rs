@@ -4,7 +4,7 @@ use image::imageops::FilterType;
 use image::{GenericImageView, Rgba};
 use tiny_skia as sk;
 use typst::layout::Size;
-use typst::visualize::{Image, ImageKind};
+use typst::visualize::{Image, ImageKind, SvgImage};
 
 use crate::{AbsExt, State};
 
@@ -70,17 +70,27 @@ fn scaled_texture(image: &Image, w: u32, h: u32) -> Option<Arc<sk::Pixmap>> {
                 *dest = sk::ColorU8::from_rgba(r, g, b, a).premultiply();
             }
         }
-        // Safety: We do not keep any references to tree nodes beyond the scope
-        // of `with`.
-        ImageKind::Svg(svg) => unsafe {
-            svg.with(|tree| {
-                let ts = tiny_skia::Transform::from_scale(
-                    w as f32 / tree.size.width(),
-                    h as f32 / tree.size.height(),
-                );
-                resvg::render(tree, ts, &mut pixmap.as_mut())
-            });
-        },
+        ImageKind::Svg(svg) => render_svg(svg, &mut pixmap),
+        // Pages of PDF files are rendered through a conversion to SVG. If it
+        // fails, the image stays blank. Callers can report this with
+        // `check_pdf_images`.
+        ImageKind::Pdf(pdf) => render_svg(&pdf.svg().ok()?, &mut pixmap),
     }
     Some(Arc::new(pixmap))
 }
+
+/// Render an SVG to fill the whole pixmap.
+fn render_svg(svg: &SvgImage, pixmap: &mut sk::Pixmap) {
+    let (w, h) = (pixmap.width(), pixmap.height());
+    // Safety: We do not keep any references to tree nodes beyond the scope of
+    // `with`.
+    unsafe {
+        svg.with(|tree| {
+            let ts = tiny_skia::Transform::from_scale(
+                w as f32 / tree.size.width(),
+                h as f32 / tree.size.height(),
+            );
+            resvg::render(tree, ts, &mut pixmap.as_mut())
+        });
+    }
+}
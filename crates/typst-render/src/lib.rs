@@ -16,6 +16,10 @@ use typst::visualize::Color;
 ///
 /// This renders the frame at the given number of pixels per point and returns
 /// the resulting `tiny-skia` pixel buffer.
+///
+/// PDF images are rendered through a conversion to SVG, which may leave parts
+/// of them out. Use [`check_pdf_images`](typst::visualize::check_pdf_images)
+/// to warn about that.
 #[typst_macros::time(name = "render")]
 pub fn render(frame: &Frame, pixel_per_pt: f32, fill: Color) -> sk::Pixmap {
     let size = frame.size();
@@ -1,7 +1,9 @@
+use std::io::Cursor;
+
 use base64::Engine;
 use ecow::{eco_format, EcoString};
 use typst::layout::{Abs, Axes};
-use typst::visualize::{Image, ImageFormat, RasterFormat, VectorFormat};
+use typst::visualize::{Image, ImageKind, RasterFormat};
 
 use crate::SVGRenderer;
 
@@ -22,19 +24,34 @@ impl SVGRenderer {
 /// `data:image/{format};base64,`.
 #[comemo::memoize]
 pub fn convert_image_to_base64_url(image: &Image) -> EcoString {
-    let format = match image.format() {
-        ImageFormat::Raster(f) => match f {
-            RasterFormat::Png => "png",
-            RasterFormat::Jpg => "jpeg",
-            RasterFormat::Gif => "gif",
-        },
-        ImageFormat::Vector(f) => match f {
-            VectorFormat::Svg => "svg+xml",
+    let mut buf = vec![];
+    let svg;
+    let (format, data): (&str, &[u8]) = match image.kind() {
+        ImageKind::Raster(raster) => match raster.format() {
+            RasterFormat::Png => ("png", raster.data()),
+            RasterFormat::Jpg => ("jpeg", raster.data()),
+            RasterFormat::Gif => ("gif", raster.data()),
+            RasterFormat::Webp => ("webp", raster.data()),
+            RasterFormat::Avif => ("avif", raster.data()),
+            // Browsers generally can't display TIFF images, so we convert
+            // them to PNG.
+            RasterFormat::Tiff => {
+                let mut cursor = Cursor::new(&mut buf);
+                raster.dynamic().write_to(&mut cursor, image::ImageFormat::Png).ok();
+                ("png", &buf)
+            }
         },
+        ImageKind::Svg(svg) => ("svg+xml", svg.data()),
+        // Pages of PDF files are converted to SVG. If that fails, the image
+        // stays blank. Callers can report this with `check_pdf_images`.
+        ImageKind::Pdf(pdf) => {
+            svg = pdf.svg().ok();
+            ("svg+xml", svg.as_ref().map_or(&[][..], |svg| svg.data()))
+        }
     };
 
     let mut url = eco_format!("data:image/{format};base64,");
-    let data = base64::engine::general_purpose::STANDARD.encode(image.data());
+    let data = base64::engine::general_purpose::STANDARD.encode(data);
     url.push_str(&data);
     url
 }
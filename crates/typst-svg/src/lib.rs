@@ -22,6 +22,10 @@ use crate::paint::{GradientRef, PatternRef, SVGSubGradient};
 use crate::text::RenderedGlyph;
 
 /// Export a frame into a SVG file.
+///
+/// PDF images are embedded through a conversion to SVG, which may leave parts
+/// of them out. Use [`check_pdf_images`](typst::visualize::check_pdf_images)
+/// to warn about that.
 #[typst_macros::time(name = "svg")]
 pub fn svg(frame: &Frame) -> String {
     let mut renderer = SVGRenderer::new();
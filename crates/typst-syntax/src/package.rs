@@ -1,5 +1,6 @@
-//! Package manifest parsing.
+//! Package and project manifest parsing.
 
+use std::collections::BTreeMap;
 use std::fmt::{self, Debug, Display, Formatter};
 use std::str::FromStr;
 
@@ -26,11 +27,14 @@ pub struct TemplateInfo {
     pub path: EcoString,
     /// The path of the entrypoint relative to the starting point's `path`.
     pub entrypoint: EcoString,
+    /// The path of a preview image of the template within the package.
+    pub thumbnail: Option<EcoString>,
 }
 
 /// The `[package]` key in the manifest.
 ///
-/// More fields are specified, but they are not relevant to the compiler.
+/// Only the name, version and entrypoint are required. The other fields are
+/// checked when publishing a package, but not relevant to the compiler.
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct PackageInfo {
     /// The name of the package within its namespace.
@@ -41,6 +45,29 @@ pub struct PackageInfo {
     pub entrypoint: EcoString,
     /// The minimum required compiler version for the package.
     pub compiler: Option<PackageVersion>,
+    /// The package's authors, optionally with a contact in angle brackets.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<EcoString>,
+    /// The package's license as an SPDX expression.
+    pub license: Option<EcoString>,
+    /// A short description of the package.
+    pub description: Option<EcoString>,
+    /// A link to the package's repository.
+    pub repository: Option<EcoString>,
+    /// A link to the package's homepage.
+    pub homepage: Option<EcoString>,
+    /// Search keywords for the package.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<EcoString>,
+    /// The categories the package is listed in.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<EcoString>,
+    /// The disciplines the package is targeted at.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disciplines: Vec<EcoString>,
+    /// Globs of files that are left out of the published package.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<EcoString>,
 }
 
 impl PackageManifest {
@@ -74,6 +101,88 @@ impl PackageManifest {
     }
 }
 
+/// A parsed project manifest, which describes how to compile a standalone
+/// document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectManifest {
+    /// Details about the project itself.
+    pub project: ProjectInfo,
+    /// Named build targets, which override the project's settings.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub targets: BTreeMap<EcoString, TargetInfo>,
+}
+
+/// The `[project]` key in the manifest.
+///
+/// Paths are relative to the directory containing the manifest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProjectInfo {
+    /// The root relative to which absolute paths are resolved.
+    pub root: Option<EcoString>,
+    /// Additional directories to search for fonts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub font_paths: Vec<EcoString>,
+    /// The target that is built if none is selected.
+    pub default_target: Option<EcoString>,
+    /// The settings shared by all targets.
+    #[serde(flatten)]
+    pub settings: TargetInfo,
+}
+
+/// The settings of a build target.
+///
+/// Paths are relative to the directory containing the manifest.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TargetInfo {
+    /// The path of the main file.
+    pub entrypoint: Option<EcoString>,
+    /// The path of the output file, which may contain a page number template.
+    pub output: Option<EcoString>,
+    /// The format of the output file.
+    pub format: Option<EcoString>,
+    /// The PDF standard the output should conform to.
+    pub pdf_standard: Option<EcoString>,
+    /// The pixels per inch of PNG output.
+    pub ppi: Option<f32>,
+    /// Key-value pairs visible through `sys.inputs`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub inputs: BTreeMap<EcoString, EcoString>,
+}
+
+impl ProjectManifest {
+    /// The settings of a target, combined with the project's settings.
+    ///
+    /// Without a name, the default target is used if there is one and the
+    /// project's settings otherwise.
+    pub fn target(&self, name: Option<&str>) -> Result<TargetInfo, EcoString> {
+        let base = &self.project.settings;
+        let Some(name) = name.or(self.project.default_target.as_deref()) else {
+            return Ok(base.clone());
+        };
+
+        let Some(target) = self.targets.get(name) else {
+            return Err(eco_format!("project manifest contains no target `{name}`"));
+        };
+
+        let mut inputs = base.inputs.clone();
+        inputs.extend(target.inputs.clone());
+
+        Ok(TargetInfo {
+            entrypoint: target.entrypoint.clone().or_else(|| base.entrypoint.clone()),
+            output: target.output.clone().or_else(|| base.output.clone()),
+            format: target.format.clone().or_else(|| base.format.clone()),
+            pdf_standard: target
+                .pdf_standard
+                .clone()
+                .or_else(|| base.pdf_standard.clone()),
+            ppi: target.ppi.or(base.ppi),
+            inputs,
+        })
+    }
+}
+
 /// Identifies a package.
 #[derive(Clone, Eq, PartialEq, Hash)]
 pub struct PackageSpec {
@@ -155,6 +264,62 @@ impl Display for VersionlessPackageSpec {
     }
 }
 
+/// Identifies a package and the versions of it that are acceptable, as in
+/// `@preview/example:^0.2`.
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct PackageRequirement {
+    /// The namespace the package lives in.
+    pub namespace: EcoString,
+    /// The name of the package within its namespace.
+    pub name: EcoString,
+    /// The acceptable versions.
+    pub version: VersionRequirement,
+}
+
+impl PackageRequirement {
+    /// The package without its version.
+    pub fn versionless(&self) -> VersionlessPackageSpec {
+        VersionlessPackageSpec {
+            namespace: self.namespace.clone(),
+            name: self.name.clone(),
+        }
+    }
+
+    /// The specified package if the requirement only accepts a single
+    /// version.
+    pub fn exact(&self) -> Option<PackageSpec> {
+        self.version.exact().map(|version| self.versionless().at(version))
+    }
+}
+
+impl FromStr for PackageRequirement {
+    type Err = EcoString;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut s = unscanny::Scanner::new(s);
+        let namespace = parse_namespace(&mut s)?.into();
+        let name = parse_name(&mut s)?.into();
+        s.eat_if(':');
+        let version = s.after();
+        if version.is_empty() {
+            Err("package specification is missing version")?;
+        }
+        Ok(Self { namespace, name, version: version.parse()? })
+    }
+}
+
+impl Debug for PackageRequirement {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl Display for PackageRequirement {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "@{}/{}:{}", self.namespace, self.name, self.version)
+    }
+}
+
 fn parse_namespace<'s>(s: &mut Scanner<'s>) -> Result<&'s str, EcoString> {
     if !s.eat_if('@') {
         Err("package specification must start with '@'")?;
@@ -253,6 +418,135 @@ impl Display for PackageVersion {
     }
 }
 
+/// A requirement on a package's version.
+///
+/// A full version like `0.2.1` only accepts itself, while a partial one like
+/// `0.2` accepts all versions starting with it. With `^`, all compatible
+/// versions are accepted, i.e. those that don't increment the first non-zero
+/// component. With `~`, only patch updates are accepted, or minor updates if
+/// only the major version is given.
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub struct VersionRequirement {
+    /// How the version is compared.
+    pub op: VersionOp,
+    /// The required major version.
+    pub major: u32,
+    /// The required minor version, if any.
+    pub minor: Option<u32>,
+    /// The required patch version, if any.
+    pub patch: Option<u32>,
+}
+
+/// How a [`VersionRequirement`] compares versions.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum VersionOp {
+    /// The given components must match exactly, as in `0.2` or `=0.2.1`.
+    Exact,
+    /// Compatible updates are accepted, as in `^0.2.1`.
+    Caret,
+    /// Patch updates are accepted, as in `~0.2.1`.
+    Tilde,
+}
+
+impl VersionRequirement {
+    /// The single version that is accepted, if the requirement is exact.
+    pub fn exact(&self) -> Option<PackageVersion> {
+        match (self.op, self.minor, self.patch) {
+            (VersionOp::Exact, Some(minor), Some(patch)) => {
+                Some(PackageVersion { major: self.major, minor, patch })
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether a version satisfies the requirement.
+    pub fn matches(&self, version: PackageVersion) -> bool {
+        let lowest = PackageVersion {
+            major: self.major,
+            minor: self.minor.unwrap_or(0),
+            patch: self.patch.unwrap_or(0),
+        };
+
+        let same_major = version.major == self.major;
+        let same_minor = self.minor.map_or(true, |minor| version.minor == minor);
+        let same_patch = self.patch.map_or(true, |patch| version.patch == patch);
+
+        match self.op {
+            VersionOp::Exact => same_major && same_minor && same_patch,
+            VersionOp::Tilde => version >= lowest && same_major && same_minor,
+            VersionOp::Caret => {
+                version >= lowest
+                    && same_major
+                    && (self.major != 0
+                        || (same_minor && (lowest.minor != 0 || same_patch)))
+            }
+        }
+    }
+}
+
+impl FromStr for VersionRequirement {
+    type Err = EcoString;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (op, rest) = if let Some(rest) = s.strip_prefix('^') {
+            (VersionOp::Caret, rest)
+        } else if let Some(rest) = s.strip_prefix('~') {
+            (VersionOp::Tilde, rest)
+        } else {
+            (VersionOp::Exact, s.strip_prefix('=').unwrap_or(s))
+        };
+
+        let mut parts = rest.split('.');
+        let mut next = |kind| {
+            parts
+                .next()
+                .map(|part| match part.parse::<u32>() {
+                    Ok(number) => Ok(number),
+                    Err(_) if part.is_empty() => {
+                        Err(eco_format!("version number is missing {kind} version"))
+                    }
+                    Err(_) => Err(eco_format!("`{part}` is not a valid {kind} version")),
+                })
+                .transpose()
+        };
+
+        // Splitting always yields at least one part, so the major version is
+        // never absent.
+        let major = next("major")?.unwrap_or_default();
+        let minor = next("minor")?;
+        let patch = next("patch")?;
+        if let Some(rest) = parts.next() {
+            Err(eco_format!("version number has unexpected fourth component: `{rest}`"))?;
+        }
+
+        Ok(Self { op, major, minor, patch })
+    }
+}
+
+impl Debug for VersionRequirement {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl Display for VersionRequirement {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.op {
+            VersionOp::Exact => {}
+            VersionOp::Caret => f.write_str("^")?,
+            VersionOp::Tilde => f.write_str("~")?,
+        }
+        write!(f, "{}", self.major)?;
+        if let Some(minor) = self.minor {
+            write!(f, ".{minor}")?;
+        }
+        if let Some(patch) = self.patch {
+            write!(f, ".{patch}")?;
+        }
+        Ok(())
+    }
+}
+
 impl Serialize for PackageVersion {
     fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
         s.collect_str(self)
@@ -265,3 +559,73 @@ impl<'de> Deserialize<'de> for PackageVersion {
         string.parse().map_err(serde::de::Error::custom)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Which of the given versions satisfy a requirement.
+    fn matching(requirement: &str, versions: &[&str]) -> Vec<String> {
+        let requirement: VersionRequirement = requirement.parse().unwrap();
+        versions
+            .iter()
+            .filter(|v| requirement.matches(v.parse().unwrap()))
+            .map(|v| v.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_version_requirement_parse() {
+        let requirement: VersionRequirement = "^0.2".parse().unwrap();
+        assert_eq!(requirement.op, VersionOp::Caret);
+        assert_eq!(
+            (requirement.major, requirement.minor, requirement.patch),
+            (0, Some(2), None)
+        );
+        assert_eq!(requirement.to_string(), "^0.2");
+        assert_eq!("=1.2.3".parse::<VersionRequirement>().unwrap().to_string(), "1.2.3");
+        assert_eq!(
+            "1.2.3".parse::<VersionRequirement>().unwrap().exact(),
+            Some(PackageVersion { major: 1, minor: 2, patch: 3 })
+        );
+        assert_eq!("1.2".parse::<VersionRequirement>().unwrap().exact(), None);
+        assert_eq!("^1.2.3".parse::<VersionRequirement>().unwrap().exact(), None);
+        assert_eq!(
+            "1.2.3.4".parse::<VersionRequirement>().unwrap_err(),
+            "version number has unexpected fourth component: `4`"
+        );
+        assert_eq!(
+            "^".parse::<VersionRequirement>().unwrap_err(),
+            "version number is missing major version"
+        );
+    }
+
+    #[test]
+    fn test_version_requirement_matches() {
+        let versions = [
+            "0.0.2", "0.0.3", "0.0.4", "0.1.0", "0.2.0", "0.2.3", "0.2.9", "0.3.0",
+            "1.0.0", "1.2.0", "1.2.3", "1.3.0", "2.0.0",
+        ];
+
+        // Exact requirements must match all given components.
+        assert_eq!(matching("1.2.3", &versions), ["1.2.3"]);
+        assert_eq!(matching("=1.2", &versions), ["1.2.0", "1.2.3"]);
+        assert_eq!(matching("0", &versions).len(), 8);
+
+        // Caret requirements don't increment the first non-zero component.
+        assert_eq!(matching("^1.2.3", &versions), ["1.2.3", "1.3.0"]);
+        assert_eq!(matching("^1", &versions), ["1.0.0", "1.2.0", "1.2.3", "1.3.0"]);
+        assert_eq!(matching("^0.2.3", &versions), ["0.2.3", "0.2.9"]);
+        assert_eq!(matching("^0.2", &versions), ["0.2.0", "0.2.3", "0.2.9"]);
+        assert_eq!(matching("^0.0.3", &versions), ["0.0.3"]);
+        assert_eq!(matching("^0.0", &versions), ["0.0.2", "0.0.3", "0.0.4"]);
+        assert_eq!(matching("^0", &versions), matching("0", &versions));
+
+        // Tilde requirements accept patch updates, or minor updates if only
+        // the major version is given.
+        assert_eq!(matching("~1.2", &versions), ["1.2.0", "1.2.3"]);
+        assert_eq!(matching("~1.2.1", &versions), ["1.2.3"]);
+        assert_eq!(matching("~1", &versions), ["1.0.0", "1.2.0", "1.2.3", "1.3.0"]);
+        assert_eq!(matching("~0.0.3", &versions), ["0.0.3", "0.0.4"]);
+    }
+}
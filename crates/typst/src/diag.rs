@@ -9,7 +9,7 @@ use std::string::FromUtf8Error;
 use comemo::Tracked;
 use ecow::{eco_vec, EcoVec};
 
-use crate::syntax::package::PackageSpec;
+use crate::syntax::package::{PackageRequirement, PackageSpec};
 use crate::syntax::{Span, Spanned, SyntaxError};
 use crate::{World, WorldExt};
 
@@ -138,6 +138,8 @@ pub struct SourceDiagnostic {
     /// Additional hints to the user, indicating how this problem could be avoided
     /// or worked around.
     pub hints: EcoVec<EcoString>,
+    /// Edits that fix the problem and that tooling can apply automatically.
+    pub fixes: EcoVec<Fix>,
 }
 
 /// The severity of a [`SourceDiagnostic`].
@@ -158,6 +160,7 @@ impl SourceDiagnostic {
             trace: eco_vec![],
             message: message.into(),
             hints: eco_vec![],
+            fixes: eco_vec![],
         }
     }
 
@@ -169,6 +172,7 @@ impl SourceDiagnostic {
             trace: eco_vec![],
             message: message.into(),
             hints: eco_vec![],
+            fixes: eco_vec![],
         }
     }
 
@@ -188,6 +192,43 @@ impl SourceDiagnostic {
         self.hints.extend(hints);
         self
     }
+
+    /// Adds a single fix to the diagnostic.
+    pub fn fix(&mut self, fix: Fix) {
+        self.fixes.push(fix);
+    }
+
+    /// Adds a single fix to the diagnostic.
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix(fix);
+        self
+    }
+}
+
+/// An automatically applicable fix for a [`SourceDiagnostic`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Fix {
+    /// A short description of the fix, e.g. "wrap in quotes".
+    pub title: EcoString,
+    /// The span of the node whose text is replaced.
+    pub span: Span,
+    /// The text to replace the node's text with.
+    pub replacement: EcoString,
+}
+
+impl Fix {
+    /// Create a fix that replaces the text of a node.
+    pub fn new(
+        title: impl Into<EcoString>,
+        span: Span,
+        replacement: impl Into<EcoString>,
+    ) -> Self {
+        Self {
+            title: title.into(),
+            span,
+            replacement: replacement.into(),
+        }
+    }
 }
 
 impl From<SyntaxError> for SourceDiagnostic {
@@ -198,6 +239,7 @@ impl From<SyntaxError> for SourceDiagnostic {
             message: error.message,
             trace: eco_vec![],
             hints: error.hints,
+            fixes: eco_vec![],
         }
     }
 }
@@ -442,6 +484,8 @@ pub type PackageResult<T> = Result<T, PackageError>;
 pub enum PackageError {
     /// The specified package does not exist.
     NotFound(PackageSpec),
+    /// No version of the package satisfies the requirement.
+    VersionNotFound(PackageRequirement),
     /// Failed to retrieve the package through the network.
     NetworkFailed(Option<EcoString>),
     /// The package archive was malformed.
@@ -458,6 +502,9 @@ impl Display for PackageError {
             Self::NotFound(spec) => {
                 write!(f, "package not found (searched for {spec})",)
             }
+            Self::VersionNotFound(requirement) => {
+                write!(f, "package not found (no version matches {requirement})")
+            }
             Self::NetworkFailed(Some(err)) => {
                 write!(f, "failed to download package ({err})")
             }
@@ -1,6 +1,6 @@
 use ecow::{eco_vec, EcoVec};
 
-use crate::diag::{bail, error, At, SourceDiagnostic, SourceResult};
+use crate::diag::{bail, error, At, Fix, SourceDiagnostic, SourceResult};
 use crate::eval::{ops, CapturesVisitor, Eval, Vm};
 use crate::foundations::{
     Array, Capturer, Closure, Content, ContextElem, Dict, Func, NativeElement, Str, Value,
@@ -147,7 +147,22 @@ impl Eval for ast::Ident<'_> {
     type Output = Value;
 
     fn eval(self, vm: &mut Vm) -> SourceResult<Self::Output> {
-        vm.scopes.get(&self).cloned().at(self.span())
+        vm.scopes.get(&self).cloned().at(self.span()).map_err(|mut errors| {
+            // Identifiers may contain hyphens, but they are likely meant as
+            // a subtraction.
+            let parts: Vec<_> = self.split('-').collect();
+            if parts.len() > 1 && parts.iter().all(|part| !part.is_empty()) {
+                let spaced = parts.join(" - ");
+                for error in errors.make_mut() {
+                    error.fix(Fix::new(
+                        "add spaces around the minus sign",
+                        self.span(),
+                        spaced.as_str(),
+                    ));
+                }
+            }
+            errors
+        })
     }
 }
 
@@ -5,7 +5,7 @@ use crate::diag::{bail, error, warning, At, FileError, SourceResult, Trace, Trac
 use crate::eval::{eval, Eval, Vm};
 use crate::foundations::{Content, Module, Value};
 use crate::syntax::ast::{self, AstNode};
-use crate::syntax::package::{PackageManifest, PackageSpec};
+use crate::syntax::package::{PackageManifest, PackageRequirement, PackageSpec};
 use crate::syntax::{FileId, Span, VirtualPath};
 use crate::World;
 
@@ -122,7 +122,25 @@ pub fn import(
     // Handle package and file imports.
     let path = path.as_str();
     if path.starts_with('@') {
-        let spec = path.parse::<PackageSpec>().at(span)?;
+        let requirement = path.parse::<PackageRequirement>().at(span)?;
+        let Some(spec) = requirement.exact() else {
+            // Let the world pick a version and report it, also in all errors,
+            // so that it's clear which one was used.
+            let spec = vm.world().resolve_package(&requirement).at(span)?;
+            let message =
+                eco_format!("{requirement} resolved to version {}", spec.version);
+            let module = import_package(vm, spec, span).map_err(|mut errors| {
+                for error in errors.make_mut() {
+                    error.hint(message.clone());
+                }
+                errors
+            })?;
+            vm.engine.tracer.warn(warning!(
+                span, "{message}";
+                hint: "specify an exact version to always import the same one"
+            ));
+            return Ok(module);
+        };
         import_package(vm, spec, span)
     } else {
         import_file(vm, path, span)
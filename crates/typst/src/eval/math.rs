@@ -1,6 +1,6 @@
 use ecow::eco_format;
 
-use crate::diag::{At, SourceResult};
+use crate::diag::{At, Fix, SourceResult};
 use crate::eval::{Eval, Vm};
 use crate::foundations::{Content, NativeElement, Value};
 use crate::math::{AlignPointElem, AttachElem, FracElem, LrElem, PrimesElem, RootElem};
@@ -22,7 +22,18 @@ impl Eval for ast::MathIdent<'_> {
     type Output = Value;
 
     fn eval(self, vm: &mut Vm) -> SourceResult<Self::Output> {
-        vm.scopes.get_in_math(&self).cloned().at(self.span())
+        vm.scopes
+            .get_in_math(&self)
+            .cloned()
+            .at(self.span())
+            .map_err(|mut errors| {
+                // Multiple letters are likely meant to be displayed as text.
+                let quoted = eco_format!("\"{}\"", self.as_str());
+                for error in errors.make_mut() {
+                    error.fix(Fix::new("wrap in quotes", self.span(), quoted.clone()));
+                }
+                errors
+            })
     }
 }
 
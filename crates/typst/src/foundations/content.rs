@@ -18,7 +18,7 @@ use crate::foundations::{
     NativeElement, Recipe, RecipeIndex, Repr, Selector, Str, Style, StyleChain, Styles,
     Value,
 };
-use crate::introspection::{Location, TagElem};
+use crate::introspection::{introspectable, Location, Tag, TagElem};
 use crate::layout::{AlignElem, Alignment, Axes, Length, MoveElem, PadElem, Rel, Sides};
 use crate::model::{Destination, EmphElem, LinkElem, StrongElem};
 use crate::realize::{Behave, Behaviour};
@@ -153,6 +153,14 @@ impl Content {
         self.inner.lifecycle.contains(0)
     }
 
+    /// The location of the content.
+    ///
+    /// Unlike the location exposed to Typst code, this is also available for
+    /// elements that were only located to be tagged for exporters.
+    pub fn location(&self) -> Option<Location> {
+        self.inner.location
+    }
+
     /// Set the location of the content.
     pub fn set_location(&mut self, location: Location) {
         self.make_mut().location = Some(location);
@@ -481,7 +489,7 @@ impl Content {
     pub fn backlinked(self, loc: Location) -> Self {
         let mut backlink = Content::empty().spanned(self.span());
         backlink.set_location(loc);
-        TagElem::packed(backlink) + self
+        TagElem::packed(Tag::Start(backlink)) + self
     }
 
     /// Set alignments for this content.
@@ -573,9 +581,12 @@ impl Content {
     /// by [query] or provided by a [show rule]($reference/styling/#show-rules),
     /// for other content it will be `{none}`. The resulting location can be
     /// used with [counters]($counter), [state] and [queries]($query).
-    #[func]
-    pub fn location(&self) -> Option<Location> {
-        self.inner.location
+    ///
+    /// Elements that can't be queried, like paragraphs or list items, don't
+    /// have a location even in show rules.
+    #[func(name = "location")]
+    pub fn queryable_location(&self) -> Option<Location> {
+        self.location().filter(|_| introspectable(self))
     }
 }
 
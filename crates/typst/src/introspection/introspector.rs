@@ -10,7 +10,7 @@ use smallvec::SmallVec;
 
 use crate::diag::{bail, StrResult};
 use crate::foundations::{Content, Label, Repr, Selector};
-use crate::introspection::Location;
+use crate::introspection::{Locatable, Location, Tag, Tagged};
 use crate::layout::{Frame, FrameItem, Page, Point, Position, Transform};
 use crate::model::Numbering;
 use crate::utils::NonZeroExt;
@@ -61,8 +61,9 @@ impl Introspector {
                         .pre_concat(group.transform);
                     self.extract(&group.frame, page, ts);
                 }
-                FrameItem::Tag(elem)
-                    if !self.elems.contains_key(&elem.location().unwrap()) =>
+                FrameItem::Tag(Tag::Start(elem))
+                    if introspectable(elem)
+                        && !self.elems.contains_key(&elem.location().unwrap()) =>
                 {
                     let pos = pos.transform(ts);
                     let ret = self.elems.insert(
@@ -303,6 +304,15 @@ impl Debug for Introspector {
     }
 }
 
+/// Whether an element found in the frames is available for introspection.
+///
+/// Elements that are only tagged for exporters are skipped, so that tagging
+/// them doesn't change what queries return. For the same reason, show rules
+/// don't see the locations of such elements.
+pub(crate) fn introspectable(elem: &Content) -> bool {
+    elem.can::<dyn Locatable>() || elem.label().is_some() || !elem.can::<dyn Tagged>()
+}
+
 /// Caches queries.
 #[derive(Default)]
 struct QueryCache(RwLock<HashMap<u128, EcoVec<Content>>>);
@@ -99,3 +99,10 @@ impl Repr for Location {
 
 /// Makes this element locatable through `engine.locate`.
 pub trait Locatable {}
+
+/// Surrounds this element with start and end tags in the frames, so that
+/// exporters can reconstruct the document's logical structure.
+///
+/// Unlike for [`Locatable`] elements, this does not make the element available
+/// for introspection.
+pub trait Tagged {}
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 
 use comemo::{Track, Tracked, Validate};
 
-use crate::introspection::Location;
+use crate::introspection::{Location, Tag};
 use crate::layout::{Frame, FrameItem};
 
 /// Provides locations for elements in the document.
@@ -77,7 +77,7 @@ impl<'a> Locator<'a> {
         for (_, item) in frame.items() {
             match item {
                 FrameItem::Group(group) => self.visit_frame(&group.frame),
-                FrameItem::Tag(elem) => {
+                FrameItem::Tag(Tag::Start(elem)) => {
                     let hashes = self.hashes.get_mut();
                     let loc = elem.location().unwrap();
                     let entry = hashes.entry(loc.hash).or_default();
@@ -23,6 +23,8 @@ pub use self::metadata::*;
 pub use self::query_::*;
 pub use self::state::*;
 
+use std::fmt::{self, Debug, Formatter};
+
 use crate::diag::{bail, SourceResult};
 use crate::engine::Engine;
 use crate::foundations::NativeElement;
@@ -30,6 +32,8 @@ use crate::foundations::{
     category, elem, Args, Category, Construct, Content, Packed, Scope, Unlabellable,
 };
 use crate::realize::{Behave, Behaviour};
+use crate::syntax::Span;
+use crate::World;
 
 /// Interactions between document parts.
 ///
@@ -56,29 +60,59 @@ pub fn define(global: &mut Scope) {
     global.define_func::<locate>();
 }
 
-/// Holds a locatable element that was realized.
+/// Marks the start or end of a locatable element that was realized.
 ///
 /// The `TagElem` is handled by all layouters. The held element becomes
 /// available for introspection in the next compiler iteration.
 #[elem(Behave, Unlabellable, Construct)]
 pub struct TagElem {
-    /// The introspectible element.
+    /// The introspectible element or the end of one.
     #[required]
     #[internal]
-    pub elem: Content,
+    pub tag: Tag,
 }
 
 impl TagElem {
     /// Create a packed tag element.
-    pub fn packed(elem: Content) -> Content {
-        let span = elem.span();
-        let mut content = Self::new(elem).pack().spanned(span);
+    pub fn packed(tag: Tag) -> Content {
+        let span = match &tag {
+            Tag::Start(elem) => elem.span(),
+            Tag::End(_) => Span::detached(),
+        };
+        let mut content = Self::new(tag).pack().spanned(span);
         // We can skip preparation for the `TagElem`.
         content.mark_prepared();
         content
     }
 }
 
+impl TagElem {
+    /// Surrounds the `body` of the `index`-th item of a `parent` element with
+    /// start and end tags for the `item`.
+    ///
+    /// This is used for parts of elements that are never realized on their
+    /// own (like list items), but should still be discoverable in the frames.
+    /// The item is located at a well-known variant of the parent's location,
+    /// so that layout can tag it without touching the locator. Returns the
+    /// body as-is unless tagging is enabled and the parent has a location.
+    pub(crate) fn surround_item<T: NativeElement>(
+        engine: &Engine,
+        parent: Option<Location>,
+        index: usize,
+        item: &Packed<T>,
+        body: Content,
+    ) -> Content {
+        let Some(parent) = parent.filter(|_| engine.world.library().tagged) else {
+            return body;
+        };
+
+        let mut item = item.clone();
+        let location = item.location().unwrap_or_else(|| parent.variant(index + 1));
+        item.set_location(location);
+        Self::packed(Tag::Start(item.pack())) + body + Self::packed(Tag::End(location))
+    }
+}
+
 impl Construct for TagElem {
     fn construct(_: &mut Engine, args: &mut Args) -> SourceResult<Content> {
         bail!(args.span, "cannot be constructed manually")
@@ -92,3 +126,34 @@ impl Behave for Packed<TagElem> {
         Behaviour::Invisible
     }
 }
+
+/// Marks the start or end of a locatable element in the frames.
+#[derive(Clone, PartialEq, Hash)]
+pub enum Tag {
+    /// The stored element starts here.
+    ///
+    /// Content placed in a frame after this tag and before the matching end
+    /// tag belongs to the element.
+    Start(Content),
+    /// The element with the given location ends here.
+    End(Location),
+}
+
+impl Tag {
+    /// The location of the element this tag belongs to.
+    pub fn location(&self) -> Location {
+        match self {
+            Self::Start(elem) => elem.location().unwrap(),
+            Self::End(loc) => *loc,
+        }
+    }
+}
+
+impl Debug for Tag {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Start(elem) => write!(f, "Start({elem:?})"),
+            Self::End(loc) => write!(f, "End({loc:?})"),
+        }
+    }
+}
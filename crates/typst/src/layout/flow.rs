@@ -11,7 +11,7 @@ use crate::engine::Engine;
 use crate::foundations::{
     elem, Content, NativeElement, Packed, Resolve, Smart, StyleChain, StyledElem,
 };
-use crate::introspection::TagElem;
+use crate::introspection::{Tag, TagElem};
 use crate::layout::{
     Abs, AlignElem, Axes, BlockElem, ColbreakElem, ColumnsElem, FixedAlignment, Fr,
     Fragment, Frame, FrameItem, LayoutMultiple, LayoutSingle, PlaceElem, Point, Regions,
@@ -124,7 +124,7 @@ struct FlowLayouter<'a> {
     /// Spacing and layouted blocks for the current region.
     items: Vec<FlowItem>,
     /// A queue of tags that will be attached to the next frame.
-    pending_tags: Vec<Content>,
+    pending_tags: Vec<Tag>,
     /// A queue of floating elements.
     pending_floats: Vec<FlowItem>,
     /// Whether we have any footnotes in the current region.
@@ -229,7 +229,22 @@ impl<'a> FlowLayouter<'a> {
 
     /// Place explicit metadata into the flow.
     fn layout_tag(&mut self, tag: &Packed<TagElem>) {
-        self.pending_tags.push(tag.elem.clone());
+        // End tags are attached to the preceding frame if possible, so that an
+        // element ends in the region its last content ended up in.
+        if let Tag::End(_) = tag.tag {
+            if self.pending_tags.is_empty() {
+                let last = self.items.iter_mut().rev().find(|item| {
+                    !matches!(item, FlowItem::Absolute(..) | FlowItem::Fractional(_))
+                });
+                if let Some(FlowItem::Frame { frame, .. }) = last {
+                    let pos = Point::with_y(frame.height());
+                    frame.push(pos, FrameItem::Tag(tag.tag.clone()));
+                    return;
+                }
+            }
+        }
+
+        self.pending_tags.push(tag.tag.clone());
     }
 
     /// Layout vertical spacing.
@@ -425,7 +440,7 @@ impl<'a> FlowLayouter<'a> {
             frame.prepend_multiple(
                 self.pending_tags
                     .drain(..)
-                    .map(|elem| (Point::zero(), FrameItem::Tag(elem))),
+                    .map(|tag| (Point::zero(), FrameItem::Tag(tag))),
             );
         }
     }
@@ -665,7 +680,7 @@ impl<'a> FlowLayouter<'a> {
         if force && !self.pending_tags.is_empty() {
             let pos = Point::with_y(offset);
             output.push_multiple(
-                self.pending_tags.drain(..).map(|elem| (pos, FrameItem::Tag(elem))),
+                self.pending_tags.drain(..).map(|tag| (pos, FrameItem::Tag(tag))),
             );
         }
 
@@ -823,7 +838,7 @@ fn find_footnotes(notes: &mut Vec<Packed<FootnoteElem>>, frame: &Frame) {
     for (_, item) in frame.items() {
         match item {
             FrameItem::Group(group) => find_footnotes(notes, &group.frame),
-            FrameItem::Tag(elem)
+            FrameItem::Tag(Tag::Start(elem))
                 if !notes.iter().any(|note| note.location() == elem.location()) =>
             {
                 let Some(footnote) = elem.to_packed::<FootnoteElem>() else {
@@ -6,7 +6,8 @@ use std::sync::Arc;
 
 use smallvec::SmallVec;
 
-use crate::foundations::{cast, dict, Content, Dict, StyleChain, Value};
+use crate::foundations::{cast, dict, Dict, StyleChain, Value};
+use crate::introspection::Tag;
 use crate::layout::{
     Abs, Axes, Corners, FixedAlignment, HideElem, Length, Point, Rel, Sides, Size,
     Transform,
@@ -508,8 +509,9 @@ pub enum FrameItem {
     Image(Image, Size, Span),
     /// An internal or external link to a destination.
     Link(Destination, Size),
-    /// An introspectable element that produced something within this frame.
-    Tag(Content),
+    /// The start or end of an introspectable element that produced something
+    /// within this frame.
+    Tag(Tag),
 }
 
 impl Debug for FrameItem {
@@ -520,7 +522,7 @@ impl Debug for FrameItem {
             Self::Shape(shape, _) => write!(f, "{shape:?}"),
             Self::Image(image, _, _) => write!(f, "{image:?}"),
             Self::Link(dest, _) => write!(f, "Link({dest:?})"),
-            Self::Tag(elem) => write!(f, "Tag({elem:?})"),
+            Self::Tag(tag) => write!(f, "Tag({tag:?})"),
         }
     }
 }
@@ -311,6 +311,9 @@ pub trait ResolvableCell {
 
     /// The cell's span, for errors.
     fn span(&self) -> Span;
+
+    /// Marks the body of a resolved cell as part of the grid's header.
+    fn mark_header(_body: &mut Content) {}
 }
 
 /// A grid of cells, including the columns, rows, and cell data.
@@ -871,7 +874,7 @@ impl CellGrid {
         // creation, ensure the header expands enough to accommodate them
         // across all of their spanned rows. Same for the footer.
         // 4. If any cells before the footer try to span it, error.
-        let resolved_cells = resolved_cells
+        let mut resolved_cells = resolved_cells
             .into_iter()
             .chain(std::iter::repeat_with(|| None).take(missing_cells))
             .enumerate()
@@ -944,6 +947,16 @@ impl CellGrid {
             })
             .collect::<SourceResult<Vec<Entry>>>()?;
 
+        // Now that the header's extent is final, let the cells in its rows
+        // know that they are part of it.
+        if let Some(header) = &header {
+            for entry in resolved_cells.iter_mut().take(header.end * c) {
+                if let Entry::Cell(cell) = entry {
+                    T::mark_header(&mut cell.body);
+                }
+            }
+        }
+
         // Populate the final lists of lines.
         // For each line type (horizontal or vertical), we keep a vector for
         // every group of lines with the same index.
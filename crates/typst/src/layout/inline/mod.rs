@@ -1429,7 +1429,7 @@ fn commit(
             }
             Item::Tag(tag) => {
                 let mut frame = Frame::soft(Size::zero());
-                frame.push(Point::zero(), FrameItem::Tag(tag.elem.clone()));
+                frame.push(Point::zero(), FrameItem::Tag(tag.tag.clone()));
                 frames.push((offset, frame));
             }
             Item::Skip(_) => {}
@@ -46,6 +46,7 @@ pub mod layout;
 pub mod loading;
 pub mod math;
 pub mod model;
+pub mod pdf;
 pub mod realize;
 pub mod symbols;
 pub mod text;
@@ -60,10 +61,12 @@ use std::collections::HashSet;
 use std::ops::{Deref, Range};
 
 use comemo::{Track, Tracked, Validate};
-use ecow::{EcoString, EcoVec};
+use ecow::{eco_format, EcoString, EcoVec};
 use typst_timing::{timed, TimingScope};
 
-use crate::diag::{warning, FileResult, SourceDiagnostic, SourceResult};
+use crate::diag::{
+    warning, FileResult, PackageError, PackageResult, SourceDiagnostic, SourceResult,
+};
 use crate::engine::{Engine, Route};
 use crate::eval::Tracer;
 use crate::foundations::{
@@ -72,7 +75,7 @@ use crate::foundations::{
 use crate::introspection::{Introspector, Locator};
 use crate::layout::{Alignment, Dir, LayoutRoot};
 use crate::model::Document;
-use crate::syntax::package::PackageSpec;
+use crate::syntax::package::{PackageRequirement, PackageSpec};
 use crate::syntax::{FileId, Source, Span};
 use crate::text::{Font, FontBook};
 use crate::utils::LazyHash;
@@ -230,6 +233,21 @@ pub trait World: Send + Sync {
     fn packages(&self) -> &[(PackageSpec, Option<EcoString>)] {
         &[]
     }
+
+    /// Choose a version of a package that satisfies a version requirement,
+    /// as in `@preview/example:^0.2`.
+    ///
+    /// This function is optional to implement. It is only called for
+    /// requirements that accept more than one version. By default, such
+    /// requirements are rejected.
+    fn resolve_package(
+        &self,
+        requirement: &PackageRequirement,
+    ) -> PackageResult<PackageSpec> {
+        Err(PackageError::Other(Some(eco_format!(
+            "cannot resolve {requirement}, please specify an exact version"
+        ))))
+    }
 }
 
 macro_rules! delegate_for_ptr {
@@ -266,6 +284,13 @@ macro_rules! delegate_for_ptr {
             fn packages(&self) -> &[(PackageSpec, Option<EcoString>)] {
                 self.deref().packages()
             }
+
+            fn resolve_package(
+                &self,
+                requirement: &PackageRequirement,
+            ) -> PackageResult<PackageSpec> {
+                self.deref().resolve_package(requirement)
+            }
         }
     };
 }
@@ -301,6 +326,11 @@ pub struct Library {
     /// The standard library as a value.
     /// Used to provide the `std` variable.
     pub std: Value,
+    /// Whether realization surrounds elements like paragraphs, lists, and
+    /// tables with tags, so that exporters can reconstruct the document's
+    /// logical structure. This has some overhead, so it is disabled by
+    /// default and should only be enabled for tagged PDF export.
+    pub tagged: bool,
 }
 
 impl Library {
@@ -323,6 +353,7 @@ impl Default for Library {
 #[derive(Debug, Clone, Default)]
 pub struct LibraryBuilder {
     inputs: Option<Dict>,
+    tagged: bool,
 }
 
 impl LibraryBuilder {
@@ -332,13 +363,26 @@ impl LibraryBuilder {
         self
     }
 
+    /// Configure whether elements are tagged for exporters, see
+    /// [`Library::tagged`].
+    pub fn with_tagging(mut self, tagged: bool) -> Self {
+        self.tagged = tagged;
+        self
+    }
+
     /// Consumes the builder and returns a `Library`.
     pub fn build(self) -> Library {
         let math = math::module();
         let inputs = self.inputs.unwrap_or_default();
         let global = global(math.clone(), inputs);
         let std = Value::Module(global.clone());
-        Library { global, math, styles: Styles::new(), std }
+        Library {
+            global,
+            math,
+            styles: Styles::new(),
+            std,
+            tagged: self.tagged,
+        }
     }
 }
 
@@ -355,6 +399,7 @@ fn global(math: Module, inputs: Dict) -> Module {
     self::introspection::define(&mut global);
     self::loading::define(&mut global);
     self::symbols::define(&mut global);
+    self::pdf::define(&mut global);
     prelude(&mut global);
     Module::new("global", global)
 }
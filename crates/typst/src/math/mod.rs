@@ -298,7 +298,7 @@ impl LayoutMath for Content {
 
         if let Some(tag) = self.to_packed::<TagElem>() {
             let mut frame = Frame::soft(Size::zero());
-            frame.push(Point::zero(), FrameItem::Tag(tag.elem.clone()));
+            frame.push(Point::zero(), FrameItem::Tag(tag.tag.clone()));
             ctx.push(FrameFragment::new(ctx, styles, frame));
             return Ok(());
         }
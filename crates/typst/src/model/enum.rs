@@ -8,6 +8,7 @@ use crate::engine::Engine;
 use crate::foundations::{
     cast, elem, scope, Array, Content, Context, Packed, Smart, StyleChain,
 };
+use crate::introspection::{TagElem, Tagged};
 use crate::layout::{
     Alignment, Axes, BlockElem, Cell, CellGrid, Em, Fragment, GridLayouter, HAlignment,
     LayoutMultiple, Length, Regions, Sizing, Spacing, VAlignment,
@@ -71,7 +72,7 @@ use crate::text::TextElem;
 /// Enumeration items can contain multiple paragraphs and other block-level
 /// content. All content that is indented more than an item's marker becomes
 /// part of that item.
-#[elem(scope, title = "Numbered List", LayoutMultiple)]
+#[elem(scope, title = "Numbered List", Tagged, LayoutMultiple)]
 pub struct EnumElem {
     /// If this is `{false}`, the items are spaced apart with
     /// [enum spacing]($enum.spacing). If it is `{true}`, they use normal
@@ -242,7 +243,7 @@ impl LayoutMultiple for Packed<EnumElem> {
         // relation to the item it refers to.
         let number_align = self.number_align(styles);
 
-        for item in self.children() {
+        for (i, item) in self.children().iter().enumerate() {
             number = item.number(styles).unwrap_or(number);
 
             let context = Context::new(None, Some(styles));
@@ -269,9 +270,10 @@ impl LayoutMultiple for Packed<EnumElem> {
             cells.push(Cell::from(Content::empty()));
             cells.push(Cell::from(resolved));
             cells.push(Cell::from(Content::empty()));
-            cells.push(Cell::from(
-                item.body().clone().styled(EnumElem::set_parents(smallvec![number])),
-            ));
+            let body =
+                item.body().clone().styled(EnumElem::set_parents(smallvec![number]));
+            let body = TagElem::surround_item(engine, self.location(), i, item, body);
+            cells.push(Cell::from(body));
             number = number.saturating_add(1);
         }
 
@@ -292,7 +294,7 @@ impl LayoutMultiple for Packed<EnumElem> {
 }
 
 /// An enumeration item.
-#[elem(name = "item", title = "Numbered List Item")]
+#[elem(name = "item", title = "Numbered List Item", Tagged)]
 pub struct EnumItem {
     /// The item's number.
     #[positional]
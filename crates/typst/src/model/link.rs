@@ -6,7 +6,7 @@ use crate::engine::Engine;
 use crate::foundations::{
     cast, elem, Content, Label, Packed, Repr, Show, Smart, StyleChain,
 };
-use crate::introspection::Location;
+use crate::introspection::{Location, Tagged};
 use crate::layout::Position;
 use crate::text::{Hyphenate, TextElem};
 
@@ -30,7 +30,7 @@ use crate::text::{Hyphenate, TextElem};
 /// # Syntax
 /// This function also has dedicated syntax: Text that starts with `http://` or
 /// `https://` is automatically turned into a link.
-#[elem(Show)]
+#[elem(Tagged, Show)]
 pub struct LinkElem {
     /// The destination the link points to.
     ///
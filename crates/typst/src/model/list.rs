@@ -6,6 +6,7 @@ use crate::foundations::{
     cast, elem, scope, Array, Content, Context, Depth, Func, Packed, Smart, StyleChain,
     Value,
 };
+use crate::introspection::{TagElem, Tagged};
 use crate::layout::{
     Axes, BlockElem, Cell, CellGrid, Em, Fragment, GridLayouter, HAlignment,
     LayoutMultiple, Length, Regions, Sizing, Spacing, VAlignment,
@@ -44,7 +45,7 @@ use crate::text::TextElem;
 /// followed by a space to create a list item. A list item can contain multiple
 /// paragraphs and other block-level content. All content that is indented
 /// more than an item's marker becomes part of that item.
-#[elem(scope, title = "Bullet List", LayoutMultiple)]
+#[elem(scope, title = "Bullet List", Tagged, LayoutMultiple)]
 pub struct ListElem {
     /// If this is `{false}`, the items are spaced apart with
     /// [list spacing]($list.spacing). If it is `{true}`, they use normal
@@ -162,13 +163,13 @@ impl LayoutMultiple for Packed<ListElem> {
             .aligned(HAlignment::Start + VAlignment::Top);
 
         let mut cells = vec![];
-        for item in self.children() {
+        for (i, item) in self.children().iter().enumerate() {
             cells.push(Cell::from(Content::empty()));
             cells.push(Cell::from(marker.clone()));
             cells.push(Cell::from(Content::empty()));
-            cells.push(Cell::from(
-                item.body().clone().styled(ListElem::set_depth(Depth(1))),
-            ));
+            let body = item.body().clone().styled(ListElem::set_depth(Depth(1)));
+            let body = TagElem::surround_item(engine, self.location(), i, item, body);
+            cells.push(Cell::from(body));
         }
 
         let grid = CellGrid::new(
@@ -188,7 +189,7 @@ impl LayoutMultiple for Packed<ListElem> {
 }
 
 /// A bullet list item.
-#[elem(name = "item", title = "Bullet List Item")]
+#[elem(name = "item", title = "Bullet List Item", Tagged)]
 pub struct ListItem {
     /// The item's body.
     #[required]
@@ -6,6 +6,7 @@ use crate::foundations::{
     elem, Args, Cast, Construct, Content, NativeElement, Packed, Set, Smart, StyleChain,
     Unlabellable,
 };
+use crate::introspection::Tagged;
 use crate::layout::{Em, Fragment, Length, Size};
 
 /// Arranges text, spacing and inline-level elements into a paragraph.
@@ -33,7 +34,7 @@ use crate::layout::{Em, Fragment, Length, Size};
 /// let $a$ be the smallest of the
 /// three integers. Then, we ...
 /// ```
-#[elem(title = "Paragraph", Debug, Construct)]
+#[elem(title = "Paragraph", Debug, Construct, Tagged)]
 pub struct ParElem {
     /// The spacing between lines.
     #[resolve]
@@ -8,6 +8,7 @@ use crate::engine::Engine;
 use crate::foundations::{
     cast, elem, scope, Content, Fold, Packed, Show, Smart, StyleChain,
 };
+use crate::introspection::Tagged;
 use crate::layout::{
     show_grid_cell, Abs, Alignment, Axes, Cell, CellGrid, Celled, Dir, Fragment,
     GridCell, GridFooter, GridHLine, GridHeader, GridLayouter, GridVLine, LayoutMultiple,
@@ -120,7 +121,7 @@ use crate::visualize::{Paint, Stroke};
 ///   [Robert], b, a, b,
 /// )
 /// ```
-#[elem(scope, LayoutMultiple, LocalName, Figurable)]
+#[elem(scope, Tagged, LayoutMultiple, LocalName, Figurable)]
 pub struct TableElem {
     /// The column sizes. See the [grid documentation]($grid) for more
     /// information on track sizing.
@@ -731,7 +732,7 @@ pub struct TableVLine {
 ///   [Vikram], [49], [Perseverance],
 /// )
 /// ```
-#[elem(name = "cell", title = "Table Cell", Show)]
+#[elem(name = "cell", title = "Table Cell", Tagged, Show)]
 pub struct TableCell {
     /// The cell's body.
     #[required]
@@ -751,7 +752,7 @@ pub struct TableCell {
 
     /// The amount of rows spanned by this cell.
     #[default(NonZeroUsize::ONE)]
-    rowspan: NonZeroUsize,
+    pub rowspan: NonZeroUsize,
 
     /// The cell's [fill]($table.fill) override.
     pub fill: Smart<Option<Paint>>,
@@ -772,6 +773,12 @@ pub struct TableCell {
     /// unbreakable, while a cell spanning at least one `{auto}`-sized row is
     /// breakable.
     pub breakable: Smart<bool>,
+
+    /// Whether the cell is in one of the rows of the table's header. This is
+    /// determined when the table's cells are resolved.
+    #[internal]
+    #[synthesized]
+    pub header: bool,
 }
 
 cast! {
@@ -876,6 +883,12 @@ impl ResolvableCell for Packed<TableCell> {
     fn span(&self) -> Span {
         Packed::span(self)
     }
+
+    fn mark_header(body: &mut Content) {
+        if let Some(cell) = body.to_packed_mut::<TableCell>() {
+            cell.push_header(true);
+        }
+    }
 }
 
 impl Show for Packed<TableCell> {
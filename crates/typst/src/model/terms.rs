@@ -3,6 +3,7 @@ use crate::engine::Engine;
 use crate::foundations::{
     cast, elem, scope, Array, Content, NativeElement, Packed, Smart, StyleChain,
 };
+use crate::introspection::{TagElem, Tagged};
 use crate::layout::{
     BlockElem, Dir, Em, Fragment, HElem, LayoutMultiple, Length, Regions, Sides, Spacing,
     StackChild, StackElem,
@@ -27,7 +28,7 @@ use crate::utils::Numeric;
 /// # Syntax
 /// This function also has dedicated syntax: Starting a line with a slash,
 /// followed by a term, a colon and a description creates a term list item.
-#[elem(scope, title = "Term List", LayoutMultiple)]
+#[elem(scope, title = "Term List", Tagged, LayoutMultiple)]
 pub struct TermsElem {
     /// If this is `{false}`, the items are spaced apart with
     /// [term list spacing]($terms.spacing). If it is `{true}`, they use normal
@@ -132,13 +133,20 @@ impl LayoutMultiple for Packed<TermsElem> {
             .then(|| HElem::new((-hanging_indent).into()).pack());
 
         let mut children = vec![];
-        for child in self.children().iter() {
+        for (i, child) in self.children().iter().enumerate() {
             let mut seq = vec![];
             seq.extend(unpad.clone());
             seq.push(child.term().clone().strong());
             seq.push((*separator).clone());
             seq.push(child.description().clone());
-            children.push(StackChild::Block(Content::sequence(seq)));
+            let body = Content::sequence(seq);
+            children.push(StackChild::Block(TagElem::surround_item(
+                engine,
+                self.location(),
+                i,
+                child,
+                body,
+            )));
         }
 
         let mut padding = Sides::default();
@@ -157,7 +165,7 @@ impl LayoutMultiple for Packed<TermsElem> {
 }
 
 /// A term list item.
-#[elem(name = "item", title = "Term List Item")]
+#[elem(name = "item", title = "Term List Item", Tagged)]
 pub struct TermItem {
     /// The term described by the list item.
     #[required]
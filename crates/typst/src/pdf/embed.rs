@@ -0,0 +1,85 @@
+use ecow::EcoString;
+
+use crate::diag::{At, SourceResult};
+use crate::engine::Engine;
+use crate::foundations::{elem, Bytes, Cast, Content, Packed, Show, StyleChain};
+use crate::introspection::Locatable;
+use crate::syntax::Spanned;
+use crate::World;
+
+/// A file that will be embedded into the output PDF.
+///
+/// This can be used to distribute additional files that are related to the
+/// PDF within it, like the data a report was generated from. PDF readers will
+/// display the files in a file listing.
+///
+/// The file is read relative to the current file, just like with
+/// [`read`]($read). The element itself does not produce any visible content.
+///
+/// # Example
+/// ```typ
+/// #pdf.embed(
+///   "experiment.csv",
+///   relationship: "supplement",
+///   mime-type: "text/csv",
+///   description: "Raw data of the experiment",
+/// )
+/// ```
+///
+/// Note that embedded files are not allowed in PDF/A-2b. When exporting to
+/// PDF/A-3b, the files become _associated files_ of the document, whose
+/// relationship to the document should be given.
+#[elem(Show, Locatable)]
+pub struct EmbedElem {
+    /// Path to the file to be embedded.
+    ///
+    /// This is also the name of the file in the PDF.
+    #[required]
+    #[parse(
+        let Spanned { v: path, span } =
+            args.expect::<Spanned<EcoString>>("path to the file to be embedded")?;
+        let id = span.resolve_path(&path).at(span)?;
+        let data = engine.world.file(id).at(span)?;
+        path
+    )]
+    #[borrowed]
+    pub path: EcoString,
+
+    /// The raw file data.
+    #[internal]
+    #[required]
+    #[parse(data)]
+    pub data: Bytes,
+
+    /// The relationship of the embedded file to the document.
+    ///
+    /// Only used when exporting to PDF/A-3b, which requires it.
+    pub relationship: Option<EmbeddedFileRelationship>,
+
+    /// The MIME type of the embedded file.
+    pub mime_type: Option<EcoString>,
+
+    /// A description of the embedded file.
+    pub description: Option<EcoString>,
+}
+
+impl Show for Packed<EmbedElem> {
+    fn show(&self, _: &mut Engine, _: StyleChain) -> SourceResult<Content> {
+        Ok(Content::empty())
+    }
+}
+
+/// The relationship of an embedded file to the document.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Cast)]
+pub enum EmbeddedFileRelationship {
+    /// The PDF document was created from the source file.
+    Source,
+    /// The file was used to derive a visual presentation in the PDF.
+    Data,
+    /// An alternative representation of the document.
+    Alternative,
+    /// Additional resources for the document.
+    Supplement,
+    /// There is no clear relationship or it is not known.
+    Unspecified,
+}
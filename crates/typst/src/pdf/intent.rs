@@ -0,0 +1,68 @@
+use ecow::EcoString;
+
+use crate::diag::{At, SourceResult};
+use crate::engine::Engine;
+use crate::foundations::{elem, Bytes, Content, Packed, Show, StyleChain};
+use crate::introspection::Locatable;
+use crate::syntax::Spanned;
+use crate::World;
+
+/// The device the output PDF is intended to be printed or displayed on.
+///
+/// The output intent is given as an ICC profile describing the output device,
+/// typically a printing condition like FOGRA39 or GRACoL that your print shop
+/// tells you to use. Print workflows use it to interpret CMYK colors and
+/// images that aren't tagged with an ICC profile of their own.
+///
+/// The profile is read relative to the current file, just like with
+/// [`read`]($read). The element itself does not produce any visible content
+/// and may only be used once per document.
+///
+/// # Example
+/// ```typ
+/// #pdf.output-intent(
+///   "coated-fogra39.icc",
+///   condition: "FOGRA39",
+///   info: "Coated FOGRA39 (ISO 12647-2:2004)",
+/// )
+/// ```
+///
+/// When exporting to PDF/A, the profile replaces the default sRGB output
+/// intent. A CMYK output intent then also allows the use of CMYK colors and
+/// images without an embedded ICC profile.
+#[elem(Show, Locatable)]
+pub struct OutputIntentElem {
+    /// Path to an ICC profile of a printer or monitor.
+    #[required]
+    #[parse(
+        let Spanned { v: path, span } =
+            args.expect::<Spanned<EcoString>>("path to the ICC profile")?;
+        let id = span.resolve_path(&path).at(span)?;
+        let data = engine.world.file(id).at(span)?;
+        path
+    )]
+    #[borrowed]
+    pub path: EcoString,
+
+    /// The raw profile data.
+    #[internal]
+    #[required]
+    #[parse(data)]
+    pub data: Bytes,
+
+    /// The identifier of the output condition.
+    ///
+    /// This is preferably the name of a standard printing condition from the
+    /// [ICC's registry](https://www.color.org/registry/), like `{"FOGRA39"}`.
+    /// If not given, the condition is marked as custom.
+    pub condition: Option<EcoString>,
+
+    /// A human-readable description of the output condition.
+    pub info: Option<EcoString>,
+}
+
+impl Show for Packed<OutputIntentElem> {
+    fn show(&self, _: &mut Engine, _: StyleChain) -> SourceResult<Content> {
+        Ok(Content::empty())
+    }
+}
@@ -0,0 +1,31 @@
+//! PDF-specific functionality.
+
+mod embed;
+mod intent;
+
+pub use self::embed::*;
+pub use self::intent::*;
+
+use crate::foundations::{category, Category, Module, Scope};
+
+/// PDF-specific functionality.
+///
+/// The definitions in this module only have an effect when exporting to PDF.
+/// Other export formats ignore them.
+#[category]
+pub static PDF: Category;
+
+/// Hook up the `pdf` module.
+pub(super) fn define(global: &mut Scope) {
+    global.category(PDF);
+    global.define_module(module());
+}
+
+/// Create a module with all PDF definitions.
+pub fn module() -> Module {
+    let mut pdf = Scope::deduplicating();
+    pdf.category(PDF);
+    pdf.define_elem::<EmbedElem>();
+    pdf.define_elem::<OutputIntentElem>();
+    Module::new("pdf", pdf)
+}
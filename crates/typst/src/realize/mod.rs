@@ -12,7 +12,7 @@ mod process;
 
 pub use self::arenas::Arenas;
 pub use self::behaviour::{Behave, BehavedBuilder, Behaviour};
-pub use self::process::process;
+pub use self::process::{process, process_without_builtin};
 
 use std::mem;
 
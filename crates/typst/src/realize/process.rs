@@ -8,9 +8,11 @@ use crate::foundations::{
     Content, Context, Packed, Recipe, RecipeIndex, Regex, Selector, Show, ShowSet, Style,
     StyleChain, Styles, Synthesize, Transformation,
 };
-use crate::introspection::{Locatable, TagElem};
+use crate::introspection::{introspectable, Locatable, Tag, TagElem, Tagged};
+use crate::model::{EnumItem, ListItem, TermItem};
 use crate::text::TextElem;
 use crate::utils::{hash128, SmallBitSet};
+use crate::World;
 
 /// What to do with an element when encountering it during realization.
 struct Verdict<'a> {
@@ -37,7 +39,32 @@ pub fn process(
     target: &Content,
     styles: StyleChain,
 ) -> SourceResult<Option<Content>> {
-    let Some(Verdict { prepared, mut map, step }) = verdict(engine, target, styles)
+    process_impl(engine, target, styles, true)
+}
+
+/// Processes the given `target` element like [`process`], but never applies
+/// the element's built-in show rule.
+///
+/// This is used by exporters that handle the semantics of built-in elements
+/// themselves (like HTML export). Once this returns `None`, the element is
+/// prepared and no user-defined show rule is left to apply.
+pub fn process_without_builtin(
+    engine: &mut Engine,
+    target: &Content,
+    styles: StyleChain,
+) -> SourceResult<Option<Content>> {
+    process_impl(engine, target, styles, false)
+}
+
+/// The shared implementation of [`process`] and [`process_without_builtin`].
+fn process_impl(
+    engine: &mut Engine,
+    target: &Content,
+    styles: StyleChain,
+    builtin: bool,
+) -> SourceResult<Option<Content>> {
+    let Some(Verdict { prepared, mut map, step }) =
+        verdict(engine, target, styles, builtin)
     else {
         return Ok(None);
     };
@@ -47,9 +74,12 @@ pub fn process(
 
     // If the element isn't yet prepared (we're seeing it for the first time),
     // prepare it.
-    let mut tag = None;
+    let mut tags = None;
     if !prepared {
-        tag = prepare(engine, &mut target, &mut map, styles)?;
+        if let Some(start) = prepare(engine, &mut target, &mut map, styles)? {
+            let end = TagElem::packed(Tag::End(target.location().unwrap()));
+            tags = Some((start, end));
+        }
     }
 
     // Apply a step, if there is one.
@@ -66,9 +96,10 @@ pub fn process(
         None => target,
     };
 
-    // If necessary, add the tag generated in the preparation.
-    if let Some(tag) = tag {
-        output = tag + output;
+    // If necessary, surround the output with the tag generated in the
+    // preparation and a matching end tag.
+    if let Some((start, end)) = tags {
+        output = start + output + end;
     }
 
     Ok(Some(output.styled_with_map(map)))
@@ -80,6 +111,7 @@ fn verdict<'a>(
     engine: &mut Engine,
     target: &'a Content,
     styles: StyleChain<'a>,
+    builtin: bool,
 ) -> Option<Verdict<'a>> {
     let mut target = target;
     let mut map = Styles::new();
@@ -158,7 +190,7 @@ fn verdict<'a>(
     }
 
     // If we found no user-defined rule, also consider the built-in show rule.
-    if step.is_none() && target.can::<dyn Show>() {
+    if builtin && step.is_none() && target.can::<dyn Show>() {
         step = Some(ShowStep::Builtin);
     }
 
@@ -169,6 +201,7 @@ fn verdict<'a>(
             target.label().is_none()
                 && !target.can::<dyn ShowSet>()
                 && !target.can::<dyn Locatable>()
+                && !tagged(engine, target)
                 && !target.can::<dyn Synthesize>()
         })
     {
@@ -187,12 +220,17 @@ fn prepare(
 ) -> SourceResult<Option<Content>> {
     // Generate a location for the element, which uniquely identifies it in
     // the document. This has some overhead, so we only do it for elements
-    // that are explicitly marked as locatable and labelled elements.
+    // that are explicitly marked as locatable, labelled elements, and elements
+    // marked as tagged if tagging is enabled.
     //
     // The element could already have a location even if it is not prepared
     // when it stems from a query.
     let mut located = target.location().is_some();
-    if !located && (target.can::<dyn Locatable>() || target.label().is_some()) {
+    if !located
+        && (target.can::<dyn Locatable>()
+            || target.label().is_some()
+            || tagged(engine, target))
+    {
         let location = engine.locator.locate(hash128(&target));
         target.set_location(location);
         located = true;
@@ -215,12 +253,12 @@ fn prepare(
     // available in rules.
     target.materialize(styles.chain(map));
 
-    // If the element is locatable, create a tag element to be able to find the
-    // element in the frames after layout. Do this after synthesis and
-    // materialization, so that it includes the synthesized fields. Do it before
-    // marking as prepared so that show-set rules will apply to this element
-    // when queried.
-    let tag = located.then(|| TagElem::packed(target.clone()));
+    // If the element is locatable or tagged, create a tag element to be able
+    // to find the element in the frames after layout. Do this after synthesis
+    // and materialization, so that it includes the synthesized fields. Do it
+    // before marking as prepared so that show-set rules will apply to this
+    // element when queried.
+    let tag = located.then(|| TagElem::packed(Tag::Start(target.clone())));
 
     // Ensure that this preparation only runs once by marking the element as
     // prepared.
@@ -229,6 +267,18 @@ fn prepare(
     Ok(tag)
 }
 
+/// Whether the element is tagged for exporters.
+///
+/// List items are tagged by their list during layout instead. Tagging them
+/// here would interrupt list building.
+fn tagged(engine: &Engine, target: &Content) -> bool {
+    target.can::<dyn Tagged>()
+        && !target.is::<ListItem>()
+        && !target.is::<EnumItem>()
+        && !target.is::<TermItem>()
+        && engine.world.library().tagged
+}
+
 /// Apply a step.
 fn show(
     engine: &mut Engine,
@@ -239,7 +289,11 @@ fn show(
     match step {
         // Apply a user-defined show rule.
         ShowStep::Recipe(recipe, guard) => {
-            let context = Context::new(target.location(), Some(styles));
+            // Elements that only have a location because they are tagged for
+            // exporters are hidden from introspection, so their location is
+            // hidden from show rules, too.
+            let location = target.location().filter(|_| introspectable(&target));
+            let context = Context::new(location, Some(styles));
             match &recipe.selector {
                 // If the selector is a regex, the `target` is guaranteed to be a
                 // text element. This invokes special regex handling.
@@ -1,13 +1,18 @@
 //! Image handling.
 
+mod pdf;
 mod raster;
 mod svg;
 
+pub use self::pdf::{check_pdf_images, PdfImage};
+#[cfg(feature = "pdf-images")]
+pub use self::pdf::{PdfDict, PdfObject, PdfRef, PdfStream};
 pub use self::raster::{RasterFormat, RasterImage};
 pub use self::svg::SvgImage;
 
 use std::ffi::OsStr;
 use std::fmt::{self, Debug, Formatter};
+use std::num::NonZeroUsize;
 use std::sync::Arc;
 
 use comemo::Tracked;
@@ -19,6 +24,7 @@ use crate::foundations::{
     cast, elem, func, scope, Bytes, Cast, Content, NativeElement, Packed, Resolve, Smart,
     StyleChain,
 };
+use crate::introspection::Tagged;
 use crate::layout::{
     Abs, Axes, FixedAlignment, Frame, FrameItem, LayoutSingle, Length, Point, Regions,
     Rel, Size,
@@ -27,18 +33,22 @@ use crate::loading::Readable;
 use crate::model::Figurable;
 use crate::syntax::{Span, Spanned};
 use crate::text::{families, LocalName};
-use crate::utils::LazyHash;
+use crate::utils::{LazyHash, NonZeroExt};
 use crate::visualize::Path;
 use crate::World;
 
 /// A raster or vector graphic.
 ///
-/// Supported formats are PNG, JPEG, GIF and SVG.
+/// Supported formats are PNG, JPEG, GIF, WebP, TIFF, SVG and PDF.
 ///
 /// _Note:_ Work on SVG export is ongoing and there might be visual inaccuracies
 /// in the resulting PDF. Make sure to double-check embedded SVG images. If you
 /// have an issue, also feel free to report it on [GitHub][gh-svg].
 ///
+/// _Note:_ Pages of PDF files are embedded as-is into PDF output. When
+/// exporting to PNG or SVG, they are converted instead, which currently omits
+/// any text on the page. PDF/A export does not support them.
+///
 /// # Example
 /// ```example
 /// #figure(
@@ -51,7 +61,7 @@ use crate::World;
 /// ```
 ///
 /// [gh-svg]: https://github.com/typst/typst/issues?q=is%3Aopen+is%3Aissue+label%3Asvg
-#[elem(scope, LayoutSingle, LocalName, Figurable)]
+#[elem(scope, Tagged, LayoutSingle, LocalName, Figurable)]
 pub struct ImageElem {
     /// Path to an image file.
     #[required]
@@ -74,6 +84,11 @@ pub struct ImageElem {
     /// The image's format. Detected automatically by default.
     pub format: Smart<ImageFormat>,
 
+    /// The page of a PDF file to use, starting at one. Has no effect for other
+    /// formats.
+    #[default(NonZeroUsize::ONE)]
+    pub page: NonZeroUsize,
+
     /// The width of the image.
     pub width: Smart<Rel<Length>>,
 
@@ -99,6 +114,7 @@ pub struct ImageElem {
 }
 
 #[scope]
+#[allow(clippy::too_many_arguments)]
 impl ImageElem {
     /// Decode a raster or vector graphic from bytes or a string.
     ///
@@ -121,6 +137,9 @@ impl ImageElem {
         /// The image's format. Detected automatically by default.
         #[named]
         format: Option<Smart<ImageFormat>>,
+        /// The page of a PDF file to use.
+        #[named]
+        page: Option<NonZeroUsize>,
         /// The width of the image.
         #[named]
         width: Option<Smart<Rel<Length>>>,
@@ -138,6 +157,9 @@ impl ImageElem {
         if let Some(format) = format {
             elem.push_format(format);
         }
+        if let Some(page) = page {
+            elem.push_page(page);
+        }
         if let Some(width) = width {
             elem.push_width(width);
         }
@@ -178,9 +200,16 @@ impl LayoutSingle for Packed<ImageElem> {
                     "png" => ImageFormat::Raster(RasterFormat::Png),
                     "jpg" | "jpeg" => ImageFormat::Raster(RasterFormat::Jpg),
                     "gif" => ImageFormat::Raster(RasterFormat::Gif),
+                    "webp" => ImageFormat::Raster(RasterFormat::Webp),
+                    "tif" | "tiff" => ImageFormat::Raster(RasterFormat::Tiff),
+                    "avif" => ImageFormat::Raster(RasterFormat::Avif),
                     "svg" | "svgz" => ImageFormat::Vector(VectorFormat::Svg),
+                    "pdf" => ImageFormat::Vector(VectorFormat::Pdf),
                     _ => match &data {
                         Readable::Str(_) => ImageFormat::Vector(VectorFormat::Svg),
+                        Readable::Bytes(bytes) if bytes.starts_with(b"%PDF-") => {
+                            ImageFormat::Vector(VectorFormat::Pdf)
+                        }
                         Readable::Bytes(bytes) => match RasterFormat::detect(bytes) {
                             Some(f) => ImageFormat::Raster(f),
                             None => bail!(self.span(), "unknown image format"),
@@ -190,13 +219,18 @@ impl LayoutSingle for Packed<ImageElem> {
             }
         };
 
-        let image = Image::with_fonts(
-            data.clone().into(),
-            format,
-            self.alt(styles),
-            engine.world,
-            &families(styles).map(|s| s.into()).collect::<Vec<_>>(),
-        )
+        let image = match format {
+            ImageFormat::Vector(VectorFormat::Pdf) => {
+                Image::pdf(data.clone().into(), self.page(styles), self.alt(styles))
+            }
+            _ => Image::with_fonts(
+                data.clone().into(),
+                format,
+                self.alt(styles),
+                engine.world,
+                &families(styles).map(|s| s.into()).collect::<Vec<_>>(),
+            ),
+        }
         .at(self.span())?;
 
         let sizing = Axes::new(self.width(styles), self.height(styles));
@@ -308,6 +342,8 @@ pub enum ImageKind {
     Raster(RasterImage),
     /// An SVG image.
     Svg(SvgImage),
+    /// A page of a PDF file.
+    Pdf(PdfImage),
 }
 
 impl Image {
@@ -330,6 +366,9 @@ impl Image {
             ImageFormat::Vector(VectorFormat::Svg) => {
                 ImageKind::Svg(SvgImage::new(data)?)
             }
+            ImageFormat::Vector(VectorFormat::Pdf) => {
+                ImageKind::Pdf(PdfImage::new(data, 0)?)
+            }
         };
 
         Ok(Self(Arc::new(LazyHash::new(Repr { kind, alt }))))
@@ -352,16 +391,32 @@ impl Image {
             ImageFormat::Vector(VectorFormat::Svg) => {
                 ImageKind::Svg(SvgImage::with_fonts(data, world, families)?)
             }
+            ImageFormat::Vector(VectorFormat::Pdf) => {
+                ImageKind::Pdf(PdfImage::new(data, 0)?)
+            }
         };
 
         Ok(Self(Arc::new(LazyHash::new(Repr { kind, alt }))))
     }
 
+    /// Create an image from a page of a PDF file.
+    #[comemo::memoize]
+    #[typst_macros::time(name = "load image")]
+    pub fn pdf(
+        data: Bytes,
+        page: NonZeroUsize,
+        alt: Option<EcoString>,
+    ) -> StrResult<Image> {
+        let kind = ImageKind::Pdf(PdfImage::new(data, page.get() - 1)?);
+        Ok(Self(Arc::new(LazyHash::new(Repr { kind, alt }))))
+    }
+
     /// The raw image data.
     pub fn data(&self) -> &Bytes {
         match &self.0.kind {
             ImageKind::Raster(raster) => raster.data(),
             ImageKind::Svg(svg) => svg.data(),
+            ImageKind::Pdf(pdf) => pdf.data(),
         }
     }
 
@@ -370,6 +425,7 @@ impl Image {
         match &self.0.kind {
             ImageKind::Raster(raster) => raster.format().into(),
             ImageKind::Svg(_) => VectorFormat::Svg.into(),
+            ImageKind::Pdf(_) => VectorFormat::Pdf.into(),
         }
     }
 
@@ -378,6 +434,7 @@ impl Image {
         match &self.0.kind {
             ImageKind::Raster(raster) => raster.width() as f64,
             ImageKind::Svg(svg) => svg.width(),
+            ImageKind::Pdf(pdf) => pdf.width(),
         }
     }
 
@@ -386,6 +443,7 @@ impl Image {
         match &self.0.kind {
             ImageKind::Raster(raster) => raster.height() as f64,
             ImageKind::Svg(svg) => svg.height(),
+            ImageKind::Pdf(pdf) => pdf.height(),
         }
     }
 
@@ -393,7 +451,7 @@ impl Image {
     pub fn dpi(&self) -> Option<f64> {
         match &self.0.kind {
             ImageKind::Raster(raster) => raster.dpi(),
-            ImageKind::Svg(_) => None,
+            ImageKind::Svg(_) | ImageKind::Pdf(_) => None,
         }
     }
 
@@ -433,6 +491,8 @@ pub enum ImageFormat {
 pub enum VectorFormat {
     /// The vector graphics format of the web.
     Svg,
+    /// The Portable Document Format. Only a single page is used.
+    Pdf,
 }
 
 impl From<RasterFormat> for ImageFormat {
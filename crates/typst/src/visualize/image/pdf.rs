@@ -0,0 +1,212 @@
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use ecow::EcoVec;
+use once_cell::sync::OnceCell;
+#[cfg(feature = "pdf-images")]
+use typst_pdf_reader::PdfPage;
+#[cfg(feature = "pdf-images")]
+pub use typst_pdf_reader::{PdfDict, PdfObject, PdfRef, PdfStream};
+
+use crate::diag::{warning, SourceDiagnostic, StrResult};
+use crate::foundations::Bytes;
+use crate::layout::{Frame, FrameItem};
+use crate::visualize::{ImageKind, SvgImage};
+
+/// A single page of a PDF document.
+///
+/// Parsing is implemented by the `typst-pdf-reader` crate, which is only
+/// included with the `pdf-images` feature. Without it, loading a PDF image
+/// fails.
+#[derive(Clone)]
+pub struct PdfImage(Arc<Repr>);
+
+/// The internal representation.
+struct Repr {
+    data: Bytes,
+    page: PdfPage,
+    svg: OnceCell<StrResult<(SvgImage, bool)>>,
+}
+
+impl PdfImage {
+    /// Parse a PDF file and select the page with the given zero-based index.
+    #[comemo::memoize]
+    pub fn new(data: Bytes, page: usize) -> StrResult<PdfImage> {
+        let page = PdfPage::parse(&data, page)?;
+        Ok(Self(Arc::new(Repr { data, page, svg: OnceCell::new() })))
+    }
+
+    /// The raw PDF file.
+    pub fn data(&self) -> &Bytes {
+        &self.0.data
+    }
+
+    /// The zero-based index of the selected page.
+    pub fn page(&self) -> usize {
+        self.0.page.index()
+    }
+
+    /// The number of pages in the document.
+    pub fn page_count(&self) -> usize {
+        self.0.page.page_count()
+    }
+
+    /// The displayed width of the page in points.
+    pub fn width(&self) -> f64 {
+        self.0.page.width()
+    }
+
+    /// The displayed height of the page in points.
+    pub fn height(&self) -> f64 {
+        self.0.page.height()
+    }
+
+    /// The visible region of the page in its own coordinate system, as
+    /// `[x0, y0, x1, y1]`.
+    pub fn bbox(&self) -> [f64; 4] {
+        self.0.page.bbox()
+    }
+
+    /// The transformation that maps the page's coordinate system onto the unit
+    /// square, taking the page's rotation into account.
+    pub fn matrix(&self) -> [f64; 6] {
+        self.0.page.matrix()
+    }
+
+    /// The page's resource dictionary (or a reference to it), if any.
+    #[cfg(feature = "pdf-images")]
+    pub fn resources(&self) -> Option<&PdfObject> {
+        self.0.page.resources()
+    }
+
+    /// The page's decoded and concatenated content streams.
+    #[cfg(feature = "pdf-images")]
+    pub fn content(&self) -> &[u8] {
+        self.0.page.content()
+    }
+
+    /// Look up an indirect object in the document.
+    #[cfg(feature = "pdf-images")]
+    pub fn get(&self, reference: PdfRef) -> Option<&PdfObject> {
+        self.0.page.get(reference)
+    }
+
+    /// Convert the page into an SVG.
+    ///
+    /// This is used by exporters that can't embed PDF content directly. The
+    /// conversion supports vector graphics and images, but text is currently
+    /// omitted. Use [`omits_text`](Self::omits_text) to find out whether that
+    /// is the case for this page.
+    pub fn svg(&self) -> StrResult<SvgImage> {
+        self.converted().map(|(svg, _)| svg)
+    }
+
+    /// Whether the page contains text that its [SVG conversion](Self::svg)
+    /// leaves out.
+    pub fn omits_text(&self) -> bool {
+        self.converted().is_ok_and(|(_, text)| text)
+    }
+
+    /// The SVG conversion of the page and whether it omits text.
+    fn converted(&self) -> StrResult<(SvgImage, bool)> {
+        self.0
+            .svg
+            .get_or_init(|| {
+                let (svg, text) = self.0.page.to_svg()?;
+                Ok((SvgImage::new(Bytes::from(svg.into_bytes()))?, text))
+            })
+            .clone()
+    }
+}
+
+impl Hash for PdfImage {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // The page is fully determined by the file and the page index.
+        self.0.data.hash(state);
+        self.page().hash(state);
+    }
+}
+
+/// Check for PDF images in a frame that can't be displayed fully when
+/// exporting to a format that shows them through a conversion to SVG, like
+/// PNG or SVG.
+///
+/// The `format` is the name of the export format, which the returned warnings
+/// mention.
+pub fn check_pdf_images(frame: &Frame, format: &str) -> EcoVec<SourceDiagnostic> {
+    let mut warnings = EcoVec::new();
+    check_pdf_images_impl(frame, format, &mut warnings);
+    warnings
+}
+
+/// Recursively check the PDF images in a frame.
+fn check_pdf_images_impl(
+    frame: &Frame,
+    format: &str,
+    warnings: &mut EcoVec<SourceDiagnostic>,
+) {
+    for (_, item) in frame.items() {
+        match item {
+            FrameItem::Group(group) => {
+                check_pdf_images_impl(&group.frame, format, warnings)
+            }
+            FrameItem::Image(image, _, span) => {
+                let ImageKind::Pdf(pdf) = image.kind() else { continue };
+                if let Err(err) = pdf.svg() {
+                    warnings.push(warning!(
+                        *span,
+                        "failed to convert PDF image for {format} export ({err})";
+                        hint: "the image will be left blank"
+                    ));
+                } else if pdf.omits_text() {
+                    warnings.push(warning!(
+                        *span,
+                        "text in PDF images is not displayed in {format} export";
+                        hint: "export to PDF to display it"
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Stands in for the reader when the `pdf-images` feature is disabled. It
+/// can't be constructed, so a `PdfImage` never exists in that case.
+#[cfg(not(feature = "pdf-images"))]
+enum PdfPage {}
+
+#[cfg(not(feature = "pdf-images"))]
+impl PdfPage {
+    fn parse(_: &[u8], _: usize) -> StrResult<Self> {
+        crate::diag::bail!("PDF images are not supported in this build of Typst");
+    }
+
+    fn index(&self) -> usize {
+        match *self {}
+    }
+
+    fn page_count(&self) -> usize {
+        match *self {}
+    }
+
+    fn width(&self) -> f64 {
+        match *self {}
+    }
+
+    fn height(&self) -> f64 {
+        match *self {}
+    }
+
+    fn bbox(&self) -> [f64; 4] {
+        match *self {}
+    }
+
+    fn matrix(&self) -> [f64; 6] {
+        match *self {}
+    }
+
+    fn to_svg(&self) -> StrResult<(String, bool)> {
+        match *self {}
+    }
+}
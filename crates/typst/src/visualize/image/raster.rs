@@ -7,6 +7,8 @@ use ecow::{eco_format, EcoString};
 use image::codecs::gif::GifDecoder;
 use image::codecs::jpeg::JpegDecoder;
 use image::codecs::png::PngDecoder;
+use image::codecs::tiff::TiffDecoder;
+use image::codecs::webp::WebPDecoder;
 use image::io::Limits;
 use image::{guess_format, DynamicImage, ImageDecoder, ImageResult};
 
@@ -24,6 +26,7 @@ struct Repr {
     dynamic: image::DynamicImage,
     icc: Option<Vec<u8>>,
     dpi: Option<f64>,
+    rotated: bool,
 }
 
 impl RasterImage {
@@ -45,6 +48,11 @@ impl RasterImage {
             RasterFormat::Jpg => decode_with(JpegDecoder::new(cursor)),
             RasterFormat::Png => decode_with(PngDecoder::new(cursor)),
             RasterFormat::Gif => decode_with(GifDecoder::new(cursor)),
+            RasterFormat::Webp => decode_with(WebPDecoder::new(cursor)),
+            RasterFormat::Tiff => decode_with(TiffDecoder::new(cursor)),
+            // Decoding AVIF requires the native dav1d library, which we don't
+            // want to depend on.
+            RasterFormat::Avif => bail!("AVIF images are not supported yet"),
         }
         .map_err(format_image_error)?;
 
@@ -53,14 +61,16 @@ impl RasterImage {
             .ok();
 
         // Apply rotation from EXIF metadata.
-        if let Some(rotation) = exif.as_ref().and_then(exif_rotation) {
+        let rotation = exif.as_ref().and_then(exif_rotation);
+        if let Some(rotation) = rotation {
             apply_rotation(&mut dynamic, rotation);
         }
+        let rotated = rotation.is_some_and(|rotation| (2..=8).contains(&rotation));
 
         // Extract pixel density.
         let dpi = determine_dpi(&data, exif.as_ref());
 
-        Ok(Self(Arc::new(Repr { data, format, dynamic, icc, dpi })))
+        Ok(Self(Arc::new(Repr { data, format, dynamic, icc, dpi, rotated })))
     }
 
     /// The raw image data.
@@ -97,6 +107,12 @@ impl RasterImage {
     pub fn icc(&self) -> Option<&[u8]> {
         self.0.icc.as_deref()
     }
+
+    /// Whether the pixels were flipped or rotated according to the image's
+    /// EXIF metadata, such that they don't match the encoded data anymore.
+    pub fn is_rotated(&self) -> bool {
+        self.0.rotated
+    }
 }
 
 impl Hash for Repr {
@@ -116,11 +132,27 @@ pub enum RasterFormat {
     Jpg,
     /// Raster format that is typically used for short animated clips.
     Gif,
+    /// Raster format for the web that supports lossy and lossless compression
+    /// as well as transparency.
+    Webp,
+    /// Raster format commonly used for scans and in print workflows.
+    Tiff,
+    /// Raster format based on the AV1 video codec. Recognized, but not
+    /// supported yet.
+    Avif,
 }
 
 impl RasterFormat {
     /// Try to detect the format of data in a buffer.
     pub fn detect(data: &[u8]) -> Option<Self> {
+        // The size of the `ftyp` box varies, so we don't rely on a fixed
+        // prefix for AVIF.
+        if data.get(4..8) == Some(b"ftyp")
+            && matches!(data.get(8..12), Some(b"avif" | b"avis"))
+        {
+            return Some(Self::Avif);
+        }
+
         guess_format(data).ok().and_then(|format| format.try_into().ok())
     }
 }
@@ -131,6 +163,9 @@ impl From<RasterFormat> for image::ImageFormat {
             RasterFormat::Png => image::ImageFormat::Png,
             RasterFormat::Jpg => image::ImageFormat::Jpeg,
             RasterFormat::Gif => image::ImageFormat::Gif,
+            RasterFormat::Webp => image::ImageFormat::WebP,
+            RasterFormat::Tiff => image::ImageFormat::Tiff,
+            RasterFormat::Avif => image::ImageFormat::Avif,
         }
     }
 }
@@ -143,6 +178,9 @@ impl TryFrom<image::ImageFormat> for RasterFormat {
             image::ImageFormat::Png => RasterFormat::Png,
             image::ImageFormat::Jpeg => RasterFormat::Jpg,
             image::ImageFormat::Gif => RasterFormat::Gif,
+            image::ImageFormat::WebP => RasterFormat::Webp,
+            image::ImageFormat::Tiff => RasterFormat::Tiff,
+            image::ImageFormat::Avif => RasterFormat::Avif,
             _ => bail!("Format not yet supported."),
         })
     }
@@ -27,6 +27,7 @@ use typst::loading::DATA_LOADING;
 use typst::math::MATH;
 use typst::model::Document;
 use typst::model::MODEL;
+use typst::pdf::PDF;
 use typst::symbols::SYMBOLS;
 use typst::text::{Font, FontBook, TEXT};
 use typst::utils::LazyHash;
@@ -167,6 +168,7 @@ fn reference_pages(resolver: &dyn Resolver) -> PageModel {
         category_page(resolver, VISUALIZE),
         category_page(resolver, INTROSPECTION),
         category_page(resolver, DATA_LOADING),
+        category_page(resolver, PDF),
     ];
     page
 }
@@ -212,6 +214,8 @@ fn category_page(resolver: &dyn Resolver, category: Category) -> PageModel {
 
     let (module, path): (&Module, &[&str]) = if category == MATH {
         (&LIBRARY.math, &["math"])
+    } else if category == PDF {
+        (get_module(&LIBRARY.global, "pdf").unwrap(), &["pdf"])
     } else {
         (&LIBRARY.global, &[])
     };
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use typst_pdf_reader::PdfPage;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(page) = PdfPage::parse(data, 0) {
+        _ = std::hint::black_box(page.to_svg());
+    }
+});
@@ -0,0 +1,415 @@
+//! Tests for the exporters that check properties of their output which the
+//! reference images of the test suite don't cover.
+
+#[allow(dead_code)]
+mod world;
+
+use std::path::Path;
+
+use typst::eval::Tracer;
+use typst::model::Document;
+use typst::syntax::{FileId, Source, VirtualPath};
+
+use crate::world::TestWorld;
+
+/// Create a world for a test document.
+fn world(text: &str) -> TestWorld {
+    // Make all paths relative to the workspace, like in the test suite.
+    std::env::set_current_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("..")).unwrap();
+
+    let id = FileId::new(None, VirtualPath::new("main.typ"));
+    TestWorld::new(Source::new(id, text.into()))
+}
+
+/// Compile a test document from source.
+fn compile(text: &str) -> (TestWorld, Tracer, Document) {
+    compile_in(world(text))
+}
+
+/// Compile the main file of the given world.
+fn compile_in(world: TestWorld) -> (TestWorld, Tracer, Document) {
+    let mut tracer = Tracer::new();
+    let document = typst::compile(&world, &mut tracer).unwrap();
+    (world, tracer, document)
+}
+
+mod html {
+    use super::*;
+
+    /// Export a test document to HTML.
+    fn html(text: &str) -> String {
+        let (world, mut tracer, document) = compile(text);
+        typst_html::html(&world, &mut tracer, &document).unwrap()
+    }
+
+    #[test]
+    fn test_html_heading_numbering() {
+        let html = html("#set heading(numbering: \"1.\")\n= A\n= B");
+        assert!(html.contains("<h1>1. A</h1>\n<h1>2. B</h1>\n"), "{html}");
+    }
+
+    #[test]
+    fn test_html_table_cell_positions() {
+        let html = html("#table(columns: 2, [a], table.cell(x: 0, y: 1)[b], [c])");
+        assert!(
+            html.contains(
+                "<tr><td>a</td><td>c</td></tr>\n<tr><td>b</td><td></td></tr>\n"
+            ),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn test_html_table_cell_overlap() {
+        std::env::set_current_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join(".."))
+            .unwrap();
+        let text = "#table(columns: 2, [a], table.cell(x: 1, y: 1)[b], \
+                    table.cell(x: 1, y: 1)[c])";
+        let id = FileId::new(None, VirtualPath::new("main.typ"));
+        let world = TestWorld::new(Source::new(id, text.into()));
+
+        // HTML export places cells like table layout and fails the same way.
+        let paged = typst::compile(&world, &mut Tracer::new()).unwrap_err();
+        let html = typst_html::html(&world, &mut Tracer::new(), &Document::default())
+            .unwrap_err();
+        assert_eq!(
+            paged[0].message,
+            "attempted to place a second cell at column 1, row 1"
+        );
+        assert_eq!(html[0].message, paged[0].message);
+        assert_eq!(html[0].span, paged[0].span);
+        assert_eq!(html[0].hints, paged[0].hints);
+    }
+
+    #[test]
+    fn test_html_table_header() {
+        let html = html(
+            "#table(columns: 2, \
+             table.header([x], table.cell(rowspan: 2)[y], [z]), [a], [b])",
+        );
+        assert!(html.contains("<thead><tr><th>x</th><th rowspan=\"2\">y</th></tr>\n"));
+        assert!(html.contains("<tr><th>z</th></tr>\n</thead>\n"), "{html}");
+        assert!(html.contains("<tbody><tr><td>a</td><td>b</td></tr>\n"), "{html}");
+    }
+
+    #[test]
+    fn test_html_image_alt() {
+        let html =
+            html("#set image(alt: \"A tiger\")\n#image(\"/assets/images/tiger.jpg\")");
+        assert!(html.contains("role=\"img\" aria-label=\"A tiger\""), "{html}");
+    }
+}
+
+mod image {
+    use typst::layout::{Frame, FrameItem};
+    use typst::visualize::{check_pdf_images, Color, ImageKind, PdfImage};
+
+    use super::*;
+
+    /// Compile a document that shows just the given PDF file.
+    fn page(path: &str) -> Frame {
+        let text = format!(
+            "#set page(width: auto, height: auto, margin: 0pt)\n\
+             #image(\"/tests/suite/visualize/pdf/{path}\")"
+        );
+        let (_, _, document) = compile(&text);
+        document.pages[0].frame.clone()
+    }
+
+    /// Find the first PDF image in a frame.
+    fn find_pdf(frame: &Frame) -> Option<PdfImage> {
+        frame.items().find_map(|(_, item)| match item {
+            FrameItem::Group(group) => find_pdf(&group.frame),
+            FrameItem::Image(image, _, _) => match image.kind() {
+                ImageKind::Pdf(pdf) => Some(pdf.clone()),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_pdf_image_render() {
+        let pixmap = typst_render::render(&page("rect.pdf"), 1.0, Color::WHITE);
+        assert_eq!((pixmap.width(), pixmap.height()), (40, 20));
+
+        let rgb = |x, y| {
+            let pixel = pixmap.pixel(x, y).unwrap();
+            (pixel.red(), pixel.green(), pixel.blue())
+        };
+        assert_eq!(rgb(10, 10), (255, 0, 0));
+        assert_eq!(rgb(30, 10), (0, 0, 255));
+    }
+
+    #[test]
+    fn test_pdf_image_svg() {
+        let pdf = find_pdf(&page("rect.pdf")).unwrap();
+        let svg = pdf.svg().unwrap();
+        let svg = std::str::from_utf8(svg.data()).unwrap();
+        assert!(svg.contains("fill=\"#ff0000\""));
+        assert!(svg.contains("fill=\"#0000ff\""));
+        assert!(!pdf.omits_text());
+    }
+
+    #[test]
+    fn test_pdf_image_text() {
+        let pdf = find_pdf(&page("text.pdf")).unwrap();
+        assert!(pdf.svg().is_ok());
+        assert!(pdf.omits_text());
+    }
+
+    #[test]
+    fn test_pdf_image_warnings() {
+        assert!(check_pdf_images(&page("rect.pdf"), "PNG").is_empty());
+        let warnings = check_pdf_images(&page("text.pdf"), "PNG");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].message,
+            "text in PDF images is not displayed in PNG export"
+        );
+    }
+}
+
+mod pdf {
+    use ecow::EcoString;
+    use typst::diag::SourceResult;
+    use typst::layout::{Frame, FrameItem};
+    use typst_pdf::{PdfOptions, PdfStandard};
+
+    use super::*;
+
+    /// Export a test document to PDF, conforming to the given standard.
+    fn export(text: &str, standard: PdfStandard) -> SourceResult<Vec<u8>> {
+        let (_, _, document) = compile(text);
+        typst_pdf::pdf(&document, &PdfOptions { standard, ..PdfOptions::default() })
+    }
+
+    /// Export a test document to a tagged PDF.
+    fn export_tagged(text: &str) -> Vec<u8> {
+        let (_, _, document) = compile_in(world(text).tagged());
+        typst_pdf::pdf(&document, &PdfOptions { tagged: true, ..PdfOptions::default() })
+            .unwrap()
+    }
+
+    /// The number of tags in the frames of a document.
+    fn tags(document: &Document) -> usize {
+        fn visit(frame: &Frame) -> usize {
+            frame
+                .items()
+                .map(|(_, item)| match item {
+                    FrameItem::Group(group) => visit(&group.frame),
+                    FrameItem::Tag(_) => 1,
+                    _ => 0,
+                })
+                .sum()
+        }
+        document.pages.iter().map(|page| visit(&page.frame)).sum()
+    }
+
+    /// The message of the first error when exporting a test document.
+    fn error(text: &str, standard: PdfStandard) -> EcoString {
+        export(text, standard).unwrap_err()[0].message.clone()
+    }
+
+    /// Whether the PDF contains the given string.
+    fn contains(pdf: &[u8], needle: &str) -> bool {
+        count(pdf, needle) > 0
+    }
+
+    /// How often the PDF contains the given string.
+    fn count(pdf: &[u8], needle: &str) -> usize {
+        pdf.windows(needle.len())
+            .filter(|&window| window == needle.as_bytes())
+            .count()
+    }
+
+    #[test]
+    fn test_pdf_standard_metadata() {
+        for (standard, part) in [(PdfStandard::A_2b, "2"), (PdfStandard::A_3b, "3")] {
+            let pdf = export("Hello", standard).unwrap();
+            assert!(contains(&pdf, &format!("<pdfaid:part>{part}</pdfaid:part>")));
+            assert!(contains(&pdf, "<pdfaid:conformance>B</pdfaid:conformance>"));
+            assert!(contains(&pdf, "/OutputIntents"));
+            assert!(contains(&pdf, "/S /GTS_PDFA1"));
+        }
+
+        let pdf = export("Hello", PdfStandard::V_1_7).unwrap();
+        assert!(!contains(&pdf, "pdfaid"));
+        assert!(!contains(&pdf, "/OutputIntents"));
+    }
+
+    #[test]
+    fn test_pdf_output_intent_srgb() {
+        let pdf = export("Hello", PdfStandard::A_2b).unwrap();
+        assert!(contains(&pdf, "/OutputConditionIdentifier (sRGB IEC61966-2.1)"));
+        assert!(contains(&pdf, "/RegistryName (http://www.color.org)"));
+        assert!(!contains(&pdf, "(Custom)"));
+    }
+
+    #[test]
+    fn test_pdf_output_intent_subtype() {
+        let text = "#pdf.output-intent(\"/tests/suite/visualize/icc/printer-cmyk.icc\")";
+
+        let pdf = export(text, PdfStandard::A_2b).unwrap();
+        assert_eq!(count(&pdf, "/S /GTS_PDFA1"), 1);
+        assert!(!contains(&pdf, "/S /GTS_PDFX"));
+
+        let pdf = export(text, PdfStandard::V_1_7).unwrap();
+        assert_eq!(count(&pdf, "/S /GTS_PDFX"), 1);
+        assert!(!contains(&pdf, "/S /GTS_PDFA1"));
+    }
+
+    #[test]
+    fn test_pdf_standard_cmyk() {
+        let text = "#text(fill: cmyk(0%, 0%, 0%, 100%))[Hello]";
+        assert!(export(text, PdfStandard::V_1_7).is_ok());
+        assert_eq!(error(text, PdfStandard::A_2b), "PDF/A-2b does not allow CMYK colors");
+    }
+
+    #[test]
+    fn test_pdf_standard_page_size() {
+        let text = "#set page(width: 1pt, height: 1pt, margin: 0pt)";
+        assert!(export(text, PdfStandard::V_1_7).is_ok());
+        assert_eq!(
+            error(text, PdfStandard::A_3b),
+            "page 1 is 1pt × 1pt, which PDF/A-3b does not allow"
+        );
+    }
+
+    #[test]
+    fn test_pdf_standard_pdf_image() {
+        let text = "#image.decode(\"%PDF-1.4 \
+                    1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj \
+                    2 0 obj << /Type /Pages /Kids [3 0 R] >> endobj \
+                    3 0 obj << /Type /Page /MediaBox [0 0 10 10] >> endobj\", \
+                    format: \"pdf\")";
+        assert!(export(text, PdfStandard::V_1_7).is_ok());
+        assert_eq!(
+            error(text, PdfStandard::A_2b),
+            "PDF/A-2b does not allow embedding pages of other PDFs"
+        );
+    }
+
+    #[test]
+    fn test_pdf_standard_transparency() {
+        for text in [
+            "#rect(fill: rgb(255, 0, 0, 50%))",
+            "#rect(fill: pattern(size: (5pt, 5pt), square(fill: rgb(0, 0, 0, 50%))))",
+        ] {
+            let pdf = export(text, PdfStandard::A_2b).unwrap();
+            assert!(contains(&pdf, "/Group"));
+            assert!(contains(&pdf, "/S /Transparency"));
+        }
+    }
+
+    #[test]
+    fn test_pdf_vector_image_transparency() {
+        let svg = |attrs: &str| {
+            format!(
+                "#image.decode(\"<svg xmlns='http://www.w3.org/2000/svg' \
+                 width='10' height='10'><rect width='10' height='10' {attrs}/></svg>\")"
+            )
+        };
+
+        // Only vector images that are actually transparent need a
+        // transparency group on their page.
+        let pdf = export(&svg("fill='red'"), PdfStandard::A_2b).unwrap();
+        assert!(!contains(&pdf, "/S /Transparency"));
+        for attrs in ["fill='red' fill-opacity='0.5'", "fill='red' opacity='0.5'"] {
+            let pdf = export(&svg(attrs), PdfStandard::A_2b).unwrap();
+            assert!(contains(&pdf, "/S /Transparency"), "{attrs}");
+        }
+    }
+
+    #[test]
+    fn test_pdf_cmyk_tiff() {
+        let text = "#image(\"/tests/suite/visualize/tiff/cmyk-icc.tiff\")";
+        let pdf = export(text, PdfStandard::A_2b).unwrap();
+        assert!(contains(&pdf, "/ICCBased"));
+        assert!(contains(&pdf, "/N 4"));
+        assert!(contains(&pdf, "/Alternate /DeviceCMYK"));
+
+        let text = "#image(\"/tests/suite/visualize/tiff/cmyk.tiff\")";
+        let pdf = export(text, PdfStandard::V_1_7).unwrap();
+        assert!(contains(&pdf, "/ColorSpace /DeviceCMYK"));
+        assert_eq!(
+            error(text, PdfStandard::A_2b),
+            "PDF/A-2b does not allow CMYK images without an ICC profile"
+        );
+    }
+
+    #[test]
+    fn test_pdf_embedded_files() {
+        let text = "#pdf.embed(\"/assets/data/zoo.csv\", mime-type: \"text/csv\")";
+
+        let pdf = export(text, PdfStandard::A_3b).unwrap();
+        assert!(contains(&pdf, "/EmbeddedFiles"));
+        assert!(contains(&pdf, "(/assets/data/zoo.csv)"));
+        assert!(contains(&pdf, "/AF ["));
+        assert!(contains(&pdf, "/Subtype /text#2Fcsv"));
+        assert!(contains(&pdf, "/AFRelationship /Unspecified"));
+
+        let pdf = export(text, PdfStandard::V_1_7).unwrap();
+        assert!(contains(&pdf, "/EmbeddedFiles"));
+        assert!(!contains(&pdf, "/AF ["));
+
+        assert_eq!(
+            error(text, PdfStandard::A_2b),
+            "PDF/A-2b does not allow embedded files"
+        );
+    }
+
+    #[test]
+    fn test_pdf_structure_tree() {
+        let text = "\
+            #table(columns: 2, table.header([A], [B], [C], [D]), [1], [2])\n\
+            - Item\n\
+            - Item";
+        let pdf = export_tagged(text);
+        assert!(contains(&pdf, "/StructTreeRoot"));
+        assert_eq!(count(&pdf, "/S /Table"), 1);
+        assert_eq!(count(&pdf, "/S /TR"), 3);
+        assert_eq!(count(&pdf, "/S /TH"), 4);
+        assert_eq!(count(&pdf, "/S /TD"), 2);
+        assert_eq!(count(&pdf, "/S /L\n"), 1);
+        assert_eq!(count(&pdf, "/S /LI"), 2);
+        assert_eq!(count(&pdf, "/S /LBody"), 2);
+    }
+
+    #[test]
+    fn test_pdf_structure_tree_untagged() {
+        // Without tagging, neither compilation nor export do any extra work.
+        let text = "#table(columns: 2, [1], [2])\n- Item";
+        let pdf = export(text, PdfStandard::V_1_7).unwrap();
+        assert!(!contains(&pdf, "/StructTreeRoot"));
+        assert!(!contains(&pdf, "/MarkInfo"));
+
+        let (_, _, document) = compile(text);
+        assert_eq!(tags(&document), 0);
+        let (_, _, document) = compile_in(world(text).tagged());
+        assert!(tags(&document) > 0);
+    }
+
+    #[test]
+    fn test_pdf_tagged_location() {
+        // Elements that are only tagged for exporters can't be queried, so
+        // their location isn't exposed in show rules either. Labelled ones
+        // can be queried and thus have a location.
+        let text = "\
+            #show par: it => { test(it.location(), none); it }\n\
+            #show list: it => { test(it.location() != none, it.has(\"label\")); it }\n\
+            #show list.item: it => { test(it.location(), none); it }\n\
+            #show table.cell: it => { test(it.location(), none); it }\n\
+            #show <labelled>: it => { test(type(it.location()), location); it }\n\
+            #context test(query(<labelled>).len(), 1)\n\
+            #table([A])\n\
+            #list[B] <labelled>\n\
+            - C\n\
+            \n\
+            D";
+        compile(text);
+        compile_in(world(text).tagged());
+        let pdf = export_tagged(text);
+        assert_eq!(count(&pdf, "/S /L\n"), 2);
+    }
+}
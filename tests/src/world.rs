@@ -7,9 +7,10 @@ use std::sync::OnceLock;
 
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
-use typst::diag::{bail, FileError, FileResult, StrResult};
+use typst::diag::{bail, FileError, FileResult, PackageError, PackageResult, StrResult};
 use typst::foundations::{func, Bytes, Datetime, NoneValue, Repr, Smart, Value};
 use typst::layout::{Abs, Margin, PageElem};
+use typst::syntax::package::{PackageRequirement, PackageSpec, PackageVersion};
 use typst::syntax::{FileId, Source};
 use typst::text::{Font, FontBook, TextElem, TextSize};
 use typst::utils::LazyHash;
@@ -21,6 +22,7 @@ use typst::{Library, World};
 pub struct TestWorld {
     main: Source,
     base: &'static TestBase,
+    tagged: bool,
 }
 
 impl TestWorld {
@@ -30,13 +32,25 @@ impl TestWorld {
     /// initialized just once.
     pub fn new(source: Source) -> Self {
         static BASE: Lazy<TestBase> = Lazy::new(TestBase::default);
-        Self { main: source, base: &*BASE }
+        Self { main: source, base: &*BASE, tagged: false }
+    }
+
+    /// Enable tagging of elements for tagged PDF export.
+    ///
+    /// Only the export tests use this.
+    #[allow(dead_code)]
+    pub fn tagged(self) -> Self {
+        Self { tagged: true, ..self }
     }
 }
 
 impl World for TestWorld {
     fn library(&self) -> &LazyHash<Library> {
-        &self.base.library
+        if self.tagged {
+            &self.base.tagged_library
+        } else {
+            &self.base.library
+        }
     }
 
     fn book(&self) -> &LazyHash<FontBook> {
@@ -66,6 +80,30 @@ impl World for TestWorld {
     fn today(&self, _: Option<i64>) -> Option<Datetime> {
         Some(Datetime::from_ymd(1970, 1, 1).unwrap())
     }
+
+    fn resolve_package(
+        &self,
+        requirement: &PackageRequirement,
+    ) -> PackageResult<PackageSpec> {
+        // All test packages live in the `@test` namespace.
+        if requirement.namespace != "test" {
+            return Err(PackageError::VersionNotFound(requirement.clone()));
+        }
+
+        let prefix = format!("{}-", requirement.name);
+        fs::read_dir("tests/packages")
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                name.strip_prefix(&prefix)?.parse::<PackageVersion>().ok()
+            })
+            .filter(|&version| requirement.version.matches(version))
+            .max()
+            .map(|version| requirement.versionless().at(version))
+            .ok_or_else(|| PackageError::VersionNotFound(requirement.clone()))
+    }
 }
 
 impl TestWorld {
@@ -82,6 +120,7 @@ impl TestWorld {
 /// Shared foundation of all test worlds.
 struct TestBase {
     library: LazyHash<Library>,
+    tagged_library: LazyHash<Library>,
     book: LazyHash<FontBook>,
     fonts: Vec<Font>,
     slots: Mutex<HashMap<FileId, FileSlot>>,
@@ -96,6 +135,7 @@ impl Default for TestBase {
 
         Self {
             library: LazyHash::new(library()),
+            tagged_library: LazyHash::new(Library { tagged: true, ..library() }),
             book: LazyHash::new(FontBook::from_fonts(&fonts)),
             fonts,
             slots: Mutex::new(HashMap::new()),
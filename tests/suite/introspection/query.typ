@@ -281,3 +281,23 @@
 // New show rules apply to this, but its location and the materialized fields
 // from the original are retained.
 #context query(heading).join()
+
+--- query-tagged-not-locatable ---
+// Elements that are only tagged for export can't be queried.
+// Error: 16-19 par is not locatable
+#context query(par)
+
+--- query-tagged-list-not-locatable ---
+// Error: 16-20 list is not locatable
+#context query(list)
+
+--- query-tagged-labelled ---
+// Tagged elements can still be queried through a label.
+#place(hide[
+  #table(columns: 2)[A][B] <tab>
+  #link("https://typst.app")[Typst] <link>
+  - Item
+])
+
+#context test(query(<tab>).map(it => it.func()), (table,))
+#context test(query(selector(<tab>).or(<link>)).len(), 2)
@@ -310,8 +310,8 @@ This is never reached.
 #import "@test/mypkg:": *
 
 --- import-from-package-version-missing-minor ---
-// Error: 9-24 version number is missing minor version
-#import "@test/mypkg:0": *
+// Error: 9-25 version number is missing minor version
+#import "@test/mypkg:0.": *
 
 --- import-from-package-version-major-invalid-1 ---
 // Error: 9-29 `latest` is not a valid major version
@@ -321,14 +321,40 @@ This is never reached.
 // Error: 9-29 `-3` is not a valid major version
 #import "@test/mypkg:-3.0.0": *
 
---- import-from-package-version-missing-patch-1 ---
-// Error: 9-26 version number is missing patch version
-#import "@test/mypkg:0.3": *
+--- import-from-package-version-requirement-caret ---
+// Test import with a version requirement.
+// Warning: 9-27 @test/adder:^0.1 resolved to version 0.1.0
+// Hint: 9-27 specify an exact version to always import the same one
+#import "@test/adder:^0.1": add
+#test(add(2, 8), 10)
 
---- import-from-package-version-missing-patch-2 ---
+--- import-from-package-version-requirement-partial ---
+// Test import with a partial version.
+// Warning: 9-26 @test/adder:0.1 resolved to version 0.1.0
+// Hint: 9-26 specify an exact version to always import the same one
+#import "@test/adder:0.1": add
+#test(add(2, 8), 10)
+
+--- import-from-package-version-requirement-unsatisfied ---
+// Error: 9-27 package not found (no version matches @test/adder:^0.2)
+#import "@test/adder:^0.2": *
+
+--- import-from-package-version-requirement-namespace ---
+// Error: 9-30 package not found (no version matches @preview/adder:^0.1)
+#import "@preview/adder:^0.1": *
+
+--- import-from-package-version-requirement-invalid ---
+// Error: 9-27 `x` is not a valid minor version
+#import "@test/mypkg:~0.x": *
+
+--- import-from-package-version-missing-patch-1 ---
 // Error: 9-27 version number is missing patch version
 #import "@test/mypkg:0.3.": *
 
+--- import-from-package-version-missing-patch-2 ---
+// Error: 9-28 version number is missing patch version
+#import "@test/mypkg:=0.3.": *
+
 --- import-from-file-package-lookalike ---
 // Error: 9-28 file not found (searched at tests/suite/scripting/#test/mypkg:1.0.0)
 #import "#test/mypkg:1.0.0": *
@@ -10,6 +10,13 @@
 #set page(height: 60pt)
 #image("/assets/images/tiger.jpg")
 
+--- image-tiff ---
+// Test loading LZW-compressed, 16-bit and CMYK TIFF images.
+#set page(width: 20pt, height: 30pt, margin: 0pt)
+#place(image("tiff/rgb-lzw.tiff", width: 20pt))
+#place(dy: 10pt, image("tiff/gray-16-bit.tiff", width: 20pt))
+#place(dy: 20pt, image("tiff/cmyk.tiff", width: 20pt))
+
 --- image-sizing ---
 // Test configuring the size and fitting behaviour of images.
 
@@ -104,6 +111,10 @@ A #box(image("/assets/images/tiger.jpg", height: 1cm, width: 80%)) B
 // Error: 2-91 failed to decode image (Format error decoding Png: Invalid PNG signature.)
 #image.decode(read("/assets/images/tiger.jpg", encoding: none), format: "png", width: 80%)
 
+--- image-decode-avif ---
+// Error: 2-56 AVIF images are not supported yet
+#image.decode(bytes((0, 0, 0, 24)) + bytes("ftypavif"))
+
 --- issue-870-image-rotation ---
 // Ensure that EXIF rotation is applied.
 // https://github.com/image-rs/image/issues/1045
@@ -120,3 +131,15 @@ A #box(image("/assets/images/tiger.jpg", height: 1cm, width: 80%)) B
 --- issue-2051-new-cm-svg ---
 #set text(font: "New Computer Modern")
 #image("/assets/images/diagram.svg")
+
+--- image-pdf-page-does-not-exist ---
+// Error: 2-32 page 2 does not exist (the PDF has 1 page)
+#image("pdf/rect.pdf", page: 2)
+
+--- image-pdf-invalid-predictor ---
+// Error: 2-28 failed to parse PDF (invalid predictor parameters)
+#image("pdf/predictor.pdf")
+
+--- image-pdf-cyclic-page-tree ---
+// Error: 2-138 failed to parse PDF (page tree references a node twice)
+#image.decode("%PDF-1.4 1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj 2 0 obj << /Type /Pages /Kids [2 0 R] >> endobj", format: "pdf")